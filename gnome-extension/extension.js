@@ -43,7 +43,9 @@ const DBUS_IFACE = `
 //  - Image blob methods (ReadBlob / WriteBlob) and BlobChanged signal
 //  - Rich-text format methods (WriteFormats) and FormatsChanged signal
 // Both sets ride in the same v4.0 release so the EGO submission only happens
-// once for the whole 0.3.0 cycle.
+// once for the whole 0.3.0 cycle. ReadFilesCut came later on the same terms:
+// FilesChanged keeps its (as) signature, and apps that find the method
+// missing treat every file copy as a copy.
 const CLIPBOARD_DBUS_IFACE = `
 <node>
   <interface name="app.clustercut.clustercut.Clipboard2">
@@ -59,6 +61,9 @@ const CLIPBOARD_DBUS_IFACE = `
     <method name="WriteFiles">
       <arg type="as" direction="in"/>
     </method>
+    <method name="ReadFilesCut">
+      <arg type="b" direction="out"/>
+    </method>
     <method name="GetMimetypes">
       <arg type="as" direction="out"/>
     </method>
@@ -642,6 +647,30 @@ export default class ClusterCutExtension extends Extension {
             .filter(l => l.length > 0 && !l.startsWith('#'));
     }
 
+    // Whether the files on the clipboard were cut rather than copied.
+    // Nautilus puts "cut" on the first line of x-special/gnome-copied-files;
+    // Dolphin offers application/x-kde-cutselection = "1".
+    _readFilesCut(callback) {
+        const clipboard = St.Clipboard.get_default();
+        const mimetypes = clipboard.get_mimetypes(St.ClipboardType.CLIPBOARD) || [];
+        const decode = bytes => {
+            const arr = bytes && bytes.get_data ? bytes.get_data() : bytes;
+            return arr && arr.length > 0 ? new TextDecoder('utf-8').decode(arr) : '';
+        };
+
+        if (mimetypes.includes('x-special/gnome-copied-files')) {
+            clipboard.get_content(St.ClipboardType.CLIPBOARD, 'x-special/gnome-copied-files', (cb, bytes) => {
+                callback(decode(bytes).split(/\r?\n/)[0].trim() === 'cut');
+            });
+        } else if (mimetypes.includes('application/x-kde-cutselection')) {
+            clipboard.get_content(St.ClipboardType.CLIPBOARD, 'application/x-kde-cutselection', (cb, bytes) => {
+                callback(decode(bytes).replace(/\0+$/, '').trim() === '1');
+            });
+        } else {
+            callback(false);
+        }
+    }
+
     _writeFiles(uris) {
         if (!uris || uris.length === 0) {
             return;
@@ -756,6 +785,10 @@ export default class ClusterCutExtension extends Extension {
             const [uris] = parameters.deep_unpack();
             this._writeFiles(uris);
             invocation.return_value(null);
+        } else if (methodName === 'ReadFilesCut') {
+            this._readFilesCut(cut => {
+                invocation.return_value(new GLib.Variant('(b)', [cut]));
+            });
         } else if (methodName === 'GetMimetypes') {
             // Synchronous — see _onClipboardOwnerChanged comment.
             const clipboard = St.Clipboard.get_default();
@@ -31,6 +31,25 @@ struct Args {
 
     #[arg(long)]
     theme: Option<String>,
+
+    /// Run an isolated profile with its own identity and storage (see
+    /// profile.rs). Falls back to `CLUSTERCUT_PROFILE`.
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+/// `--profile` when the full parse failed on some other argument.
+fn scan_profile_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--profile=") {
+            return Some(value.to_string());
+        }
+        if arg == "--profile" {
+            return args.next();
+        }
+    }
+    None
 }
 
 fn init_logging() -> Args {
@@ -39,10 +58,16 @@ fn init_logging() -> Args {
         Ok(a) => a,
         Err(_) => {
             // Keep default if parsing fails (e.g. extra args)
-            Args { log_level: "info".to_string(), debug: false, minimized: false, theme: None }
+            Args { log_level: "info".to_string(), debug: false, minimized: false, theme: None, profile: scan_profile_arg() }
         }
     };
 
+    // Before anything below resolves a path (the log dir, first of all).
+    if let Err(e) = crate::profile::init(args.profile.as_deref()) {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    }
+
     if let Some(theme) = &args.theme {
         std::env::set_var("CLUSTERCUT_THEME", theme);
     }
@@ -75,7 +100,7 @@ fn init_logging() -> Args {
     // "We need each log line to be timestamped, and include hostname."
 
     // Use temp_dir for logs to ensure we can write even if CWD is / (macOS Bundle)
-    let log_dir = std::env::temp_dir().join("ClusterCutLogs");
+    let log_dir = crate::profile::log_dir();
     let file_appender = tracing_appender::rolling::daily(&log_dir, "clustercut.log");
     let file_layer = tracing_subscriber::fmt::layer()
         .with_writer(file_appender)
@@ -105,6 +130,9 @@ fn init_logging() -> Args {
         .init();
 
     tracing::info!("Logging initialized. Level: {}, Hostname: {}", level, crate::get_hostname_internal());
+    if let Some(profile) = crate::profile::name() {
+        tracing::info!("Profile: {}", profile);
+    }
 
     if let Some(theme) = &args.theme {
         tracing::info!("Theme Override Active: {}", theme);
@@ -178,12 +206,10 @@ pub(crate) fn spawn_mdns_rescan(state: AppState, handle: tauri::AppHandle) {
             }
             match tokio::time::timeout(remaining, receiver.recv_async()).await {
                 Ok(Ok(mdns_sd::ServiceEvent::ServiceResolved(info))) => {
-                    let Some(ip_raw) = info.get_addresses().iter().next() else {
+                    let candidates = crate::paths::mdns_candidates(info.get_addresses().iter(), info.get_port());
+                    let Some(ip) = candidates.first().map(|a| a.addr.ip()) else {
                         continue;
                     };
-                    let ip: std::net::IpAddr = ip_raw.to_string().parse().unwrap_or(
-                        std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
-                    );
                     let id = info.get_property_val_str("id").unwrap_or("").to_string();
                     if id.is_empty() || id == local_id {
                         continue;
@@ -218,6 +244,12 @@ pub(crate) fn spawn_mdns_rescan(state: AppState, handle: tauri::AppHandle) {
                         signature: None,
                         fingerprint,
                         protocol_version: proto,
+                        addresses: candidates,
+                        reaches: Vec::new(),
+                        relay_via: None,
+                        status: None,
+                        device: None,
+                        link: Default::default(),
                     };
 
                     // Something we just re-resolved is clearly alive; cancel any
@@ -225,7 +257,7 @@ pub(crate) fn spawn_mdns_rescan(state: AppState, handle: tauri::AppHandle) {
                     {
                         state.pending_removals.lock().unwrap().remove(&id);
                     }
-                    state.add_peer(peer.clone());
+                    let peer = state.add_peer(peer);
                     let _ = handle.emit("peer-update", crate::peer::PeerView::from_peer(&peer));
                 }
                 Ok(Ok(_)) => {}                 // ignore ServiceRemoved etc. mid-rescan
@@ -458,7 +490,7 @@ fn apply_theme_change(app_handle: &tauri::AppHandle, theme: &str) {
 }
 
 fn clear_cache(app: &tauri::AppHandle) {
-    if let Ok(root_cache_dir) = app.path().app_cache_dir() {
+    if let Ok(root_cache_dir) = crate::profile::cache_dir(app) {
         // Use a subdirectory to avoid nuking Webview2/GTK cache
         let cache_dir = root_cache_dir.join("temp_downloads");
 
@@ -511,8 +543,10 @@ pub(crate) fn run() {
         builder = builder.plugin(tauri_plugin_deep_link::init());
     }
 
-    builder
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+    // The plugin is keyed on the app identifier, so it would stop a second
+    // profile too; named profiles lock their own directory instead.
+    if crate::profile::name().is_none() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             // Handle deep link activation from Toast
             let _ = app.emit("deep-link", args);
             // Always bring to front on activation
@@ -521,13 +555,29 @@ pub(crate) fn run() {
                  let _ = win.show();
                  let _ = win.set_focus();
              }
-        }))
-        // Pass --minimized to autostart args
-        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec!["--minimized"])))
+        }));
+    }
+
+    // Pass --minimized (and the profile) to autostart args
+    let mut autostart_args = vec!["--minimized"];
+    if let Some(profile) = crate::profile::name() {
+        autostart_args.extend(["--profile", profile]);
+    }
+
+    builder
+        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(autostart_args)))
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(crate::shortcuts::handle_shortcut).build())
         .manage(AppState::new())
         .setup(move |app| {
+            if let Err(e) = crate::profile::acquire_lock(app.handle()) {
+                tracing::error!("{}", e);
+                std::process::exit(1);
+            }
+            if let (Some(profile), Some(window)) = (crate::profile::name(), app.get_webview_window("main")) {
+                let _ = window.set_title(&format!("ClusterCut ({})", profile));
+            }
+
             #[cfg(not(target_os = "linux"))]
             {
                 use tauri_plugin_deep_link::DeepLinkExt;
@@ -572,15 +622,17 @@ pub(crate) fn run() {
                 }
             };
 
-            // Initialize QUIC Transport (Fixed Port 4654 for Discovery, or random fallback)
+            // Initialize QUIC Transport on the configured port (4654 by
+            // default), the next free one in its fallback range, or any.
+            // mDNS and PeerDiscovery advertise whichever we got.
+            // The beacon's port is left out of the range (see
+            // `listen_port_candidates`).
+            let (listen_port, listen_port_range, beacon_port) = {
+                let s = crate::storage::load_settings(app.handle());
+                (s.listen_port, s.listen_port_range, s.beacon_port)
+            };
             let transport = tauri::async_runtime::block_on(async {
-                match Transport::new(4654, cert_der.clone(), key_der.clone()) {
-                    Ok(t) => Ok(t),
-                    Err(e) => {
-                        tracing::warn!("Failed to bind port 4654 ({}). Falling back to random port.", e);
-                        Transport::new(0, cert_der, key_der)
-                    }
-                }
+                Transport::bind_in_range(listen_port, listen_port_range, beacon_port, cert_der, key_der)
             }).expect("Failed to create transport");
 
 
@@ -627,10 +679,12 @@ pub(crate) fn run() {
                 // Issue #18: skip auto-config when the user disabled it. Settings
                 // are loaded into state below (later in setup), but this block runs
                 // earlier, so read straight from disk here.
-                if crate::storage::load_settings(app_handle).configure_firewall {
+                let settings = crate::storage::load_settings(app_handle);
+                if settings.configure_firewall {
                     // Ensure firewall rule exists; checks first and only prompts UAC if needed.
-                    std::thread::spawn(|| {
-                        crate::net_util::configure_windows_firewall();
+                    let ports = crate::net_util::firewall_port_spec(settings.listen_port, settings.listen_port_range);
+                    std::thread::spawn(move || {
+                        crate::net_util::configure_windows_firewall(ports);
                     });
                 }
             }
@@ -668,6 +722,13 @@ pub(crate) fn run() {
                     *cid_lock = new_id;
                 }
 
+                // Outgoing clipboard sequence numbers start above the last
+                // run's (replay.rs).
+                state.clipboard_seq.store(
+                    crate::replay::first_seq(crate::storage::bump_seq_epoch(app_handle)),
+                    std::sync::atomic::Ordering::SeqCst,
+                );
+
                 // 2. Load Known Peers, then sweep for legacy entries that
                 //    pre-date mTLS — peers without a stored fingerprint can
                 //    no longer talk to us under the v0.3 strict-pinning model
@@ -676,6 +737,16 @@ pub(crate) fn run() {
                 {
                     let mut kp_lock = state.known_peers.lock().unwrap();
                     *kp_lock = load_known_peers(app_handle);
+                    *state.peer_policies.lock().unwrap() =
+                        crate::storage::load_peer_policies(app_handle);
+                    *state.revocations.lock().unwrap() =
+                        crate::storage::load_revocations(app_handle);
+                    *state.invite_tokens.lock().unwrap() =
+                        crate::storage::load_invite_tokens(app_handle);
+                    *state.join_restrictions.lock().unwrap() =
+                        crate::storage::load_join_restrictions(app_handle);
+                    *state.cluster_contexts.lock().unwrap() =
+                        crate::storage::load_cluster_contexts(app_handle);
 
                     let mut legacy = Vec::new();
                     for (id, peer) in kp_lock.iter_mut() {
@@ -765,23 +836,11 @@ pub(crate) fn run() {
 
                      // Retroactive Fix: If a peer is on a different subnet, mark it as manual.
                      let mut known_peers = state_owned.known_peers.lock().unwrap();
-                     let local_ip_obj = local_ip_address::local_ip().unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)));
                      let mut changed = false;
 
                      for peer in known_peers.values_mut() {
                          if !peer.is_manual {
-                              // If peer.ip is remote relative to local_ip_obj
-                              let is_remote = match (local_ip_obj, peer.ip) {
-                                 (std::net::IpAddr::V4(l), std::net::IpAddr::V4(r)) => {
-                                     // Compare first 3 octets
-                                     l.octets()[0..3] != r.octets()[0..3]
-                                 },
-                                 (std::net::IpAddr::V6(l), std::net::IpAddr::V6(r)) => {
-                                      // Compare first 4 segments
-                                      l.segments()[0..4] != r.segments()[0..4]
-                                 },
-                                 _ => true,
-                             };
+                             let is_remote = !crate::net_util::is_in_local_subnet(peer.ip);
 
                              if is_remote && !peer.ip.is_loopback() {
                                  tracing::info!("Startup: Auto-correcting peer {} to is_manual=true (Remote IP: {})", peer.id, peer.ip);
@@ -809,10 +868,12 @@ pub(crate) fn run() {
                 // discover peers; advertising (register) is gated on the
                 // mdns_advertising setting (issue #18).
                 let mut discovery = Discovery::new().expect("Failed to initialize discovery");
+                discovery.set_interface_exclude(state.settings.lock().unwrap().interface_exclude.clone());
                 if state.settings.lock().unwrap().mdns_advertising {
                     discovery
                         .register(&device_id, &network_name, port)
                         .expect("Failed to register service");
+                    crate::clusters::register_contexts(&mut discovery, &state, &device_id, port);
                 } else {
                     tracing::info!("mDNS advertising disabled by settings; browsing only.");
                 }
@@ -827,134 +888,32 @@ pub(crate) fn run() {
                     while let Ok(event) = receiver.recv_async().await {
                         match event {
                             mdns_sd::ServiceEvent::ServiceResolved(info) => {
-                                if let Some(ip) = info.get_addresses().iter().next() {
-                                    let id = info
-                                        .get_property_val_str("id")
-                                        .unwrap_or("unknown")
-                                        .to_string();
-
-                                    let local_id =
-                                        { d_state.local_device_id.lock().unwrap().clone() };
-                                    if id == local_id {
-                                        continue;
-                                    }
-
-                                    // DEBOUNCE: Cancel any pending removal for this peer
-                                    {
-                                        let mut pending = d_state.pending_removals.lock().unwrap();
-                                        if pending.remove(&id).is_some() {
-                                            tracing::debug!("[Discovery] Debounce: Cancelled pending removal for reappearing peer {}", id);
-                                        }
-                                    }
-
-                                    let network_name_prop = info
-                                        .get_property_val_str("n")
-                                        .map(|s| s.to_string());
-                                    let proto_prop = info
-                                        .get_property_val_str("proto")
-                                        .map(|s| s.to_string());
-
-                                    if let Some(n) = &network_name_prop {
-                                        tracing::debug!("Discovered peer {} with network name: {}", id, n);
-                                    } else {
-                                        tracing::warn!("Discovered peer {} WITHOUT network name (properties: {:?})", id, info.get_properties());
-                                    }
-
-                                    // Lock known_peers to prevent race with PairRequest.
-                                    // Trust requires a stored fingerprint under the v0.3
-                                    // strict-mTLS model — a known-but-unfingerprinted entry
-                                    // is a legacy peer and must re-pair.
-                                    let kp = d_state.known_peers.lock().unwrap();
-                                    let known_entry = kp.get(&id).cloned();
-                                    let is_trusted = known_entry
-                                        .as_ref()
-                                        .map(|p| p.fingerprint.is_some())
-                                        .unwrap_or(false);
-                                    let stored_fingerprint = known_entry
-                                        .and_then(|p| p.fingerprint);
-
-                                    // Extract hostname from property or fallback to mDNS hostname
-                                    let h_prop = info.get_property_val_str("h");
-                                    let hostname_prop = h_prop
-                                        .or_else(|| info.get_property_val_str("hostname"))
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_else(|| info.get_hostname().to_string());
-
-                                    tracing::info!("[Discovery] Peer {} resolved. 'h' prop: {:?}, Final hostname: {}", id, h_prop, hostname_prop);
-
-                                    let peer = Peer {
-                                        id: id.clone(),
-                                        ip: ip.to_string().parse().unwrap_or(std::net::IpAddr::V4(
-                                            std::net::Ipv4Addr::new(127, 0, 0, 1),
-                                        )),
-                                        port: info.get_port(),
-                                        hostname: hostname_prop,
-                                        last_seen: std::time::SystemTime::now()
-                                            .duration_since(std::time::UNIX_EPOCH)
-                                            .unwrap_or_default()
-                                            .as_secs(),
-                                        is_trusted,
-                                        is_manual: false, // Discovered via mDNS
-                                        network_name: network_name_prop,
-                                        signature: None,
-                                        // Carry the stored fingerprint forward into the
-                                        // runtime peer record so it shows up in the UI.
-                                        fingerprint: stored_fingerprint,
-                                        protocol_version: proto_prop,
-                                    };
-
-                                    // Check if peer is already active to prevent duplicate notifications
-                                    let is_new_peer = {
-                                        let peers = d_state.peers.lock().unwrap();
-                                        !peers.contains_key(&id)
-                                    };
-
-                                    d_state.add_peer(peer.clone());
-                                    let _ = d_handle.emit("peer-update", crate::peer::PeerView::from_peer(&peer));
-
-                                    // Trigger Notification (with Layer 2 ping verification)
-                                    {
-                                        let same_network = {
-                                            let local_net = d_state.network_name.lock().unwrap();
-                                            peer.network_name.as_ref().map_or(false, |rn| *rn == *local_net)
-                                        };
-
-                                        if same_network && is_new_peer
-                                            && d_state.settings.lock().unwrap().notifications.device_join
-                                            && d_state.should_notify()
-                                        {
-                                            // Ping-verify before notifying
-                                            let verify_state = d_state.clone();
-                                            let verify_handle = d_handle.clone();
-                                            let verify_peer = peer.clone();
-                                            tauri::async_runtime::spawn(async move {
-                                                let addr = std::net::SocketAddr::new(verify_peer.ip, verify_peer.port);
-                                                let transport_opt = { verify_state.transport.lock().unwrap().clone() };
-                                                let mut verified = false;
-
-                                                if let Some(transport) = transport_opt {
-                                                    if let Ok(ping_data) = serde_json::to_vec(&Message::Ping) {
-                                                        if let Ok(Ok(_)) = tokio::time::timeout(
-                                                            std::time::Duration::from_secs(3),
-                                                            transport.send_message(addr, &ping_data),
-                                                        ).await {
-                                                            verified = true;
-                                                        }
-                                                    }
-                                                }
-
-                                                if verified {
-                                                    tracing::info!("[Notification] Ping-verified 'Device Joined' for: {}", verify_peer.hostname);
-                                                    crate::send_notification(&verify_handle, "Device Joined", &format!("{} has joined your cluster", verify_peer.hostname), false, Some(1), "devices", crate::NotificationPayload::None);
-                                                } else {
-                                                    tracing::info!("[Notification] Deferring 'Device Joined' for {} (ping failed, will fire on heartbeat)", verify_peer.hostname);
-                                                    verify_state.pending_join_notifications.lock().unwrap().insert(verify_peer.id.clone());
-                                                }
-                                            });
-                                        }
-                                    }
+                                // Every address in the record is a candidate path; the
+                                // preferred one survives rediscovery (see paths.rs).
+                                let candidates = crate::paths::mdns_candidates(info.get_addresses().iter(), info.get_port());
+                                if candidates.is_empty() {
+                                    continue;
                                 }
-
+                                // Extract hostname from property or fallback to mDNS hostname
+                                let h_prop = info.get_property_val_str("h");
+                                let hostname = h_prop
+                                    .or_else(|| info.get_property_val_str("hostname"))
+                                    .map(|s| s.to_string())
+                                    .unwrap_or_else(|| info.get_hostname().to_string());
+                                tracing::debug!("[Discovery] mDNS record {} 'h' prop: {:?}", info.get_fullname(), h_prop);
+
+                                crate::discovery::resolve_advertisement(
+                                    &d_state,
+                                    &d_handle,
+                                    crate::discovery::Advertisement {
+                                        id: info.get_property_val_str("id").unwrap_or("unknown").to_string(),
+                                        network_name: info.get_property_val_str("n").map(|s| s.to_string()),
+                                        hostname,
+                                        proto: info.get_property_val_str("proto").map(|s| s.to_string()),
+                                        port: info.get_port(),
+                                        addresses: candidates,
+                                    },
+                                );
                             }
                             mdns_sd::ServiceEvent::ServiceRemoved(_ty, fullname) => {
                                 let id =
@@ -1017,6 +976,25 @@ pub(crate) fn run() {
                     resolver_state.knows_fingerprint(fp)
                 }));
             }
+            // Multi-homed peers: which of a peer's addresses to try, and
+            // how each attempt went (see paths.rs).
+            {
+                let resolver_state = listener_state.clone();
+                let reporter_state = listener_state.clone();
+                transport.set_path_resolver(
+                    std::sync::Arc::new(move |addr| crate::paths::candidates_for(&resolver_state, addr)),
+                    std::sync::Arc::new(move |addr, rtt| crate::paths::record_outcome(&reporter_state, addr, rtt)),
+                );
+            }
+            // Peers we can't reach directly: go through a member that can
+            // (see relay.rs).
+            {
+                let router_state = listener_state.clone();
+                let router_handle = listener_handle.clone();
+                transport.set_relay_router(std::sync::Arc::new(move |transport, addr, data| {
+                    crate::relay::route(&router_state, &router_handle, transport, addr, data)
+                }));
+            }
 
             {
                 let mut t_lock = listener_state.transport.lock().unwrap();
@@ -1031,8 +1009,9 @@ pub(crate) fn run() {
             //
             // The accept closure enforces three WIRE-PROTOCOL-0.3.1 hardening
             // requirements before spawning the handler:
-            //   §H1 — refuse outright while the responder is locked out
-            //         (drop the TCP socket immediately).
+            //   §H1 — refuse outright while the source is backing off or
+            //         the global failure ceiling has pairing paused (drop
+            //         the TCP socket immediately). See pairing/ratelimit.rs.
             //   §H6 — cap = 1 concurrent pairing; refuse anything else with
             //         no permit available.
             //   §H6 — wrap the handler in `PAIRING_PROTOCOL_TIMEOUT` so an
@@ -1054,13 +1033,34 @@ pub(crate) fn run() {
                         drop(stream);
                         return;
                     }
-                    if state.is_pairing_locked_out() {
-                        tracing::warn!(
-                            "Pairing TCP accept from {} refused: listener locked out (§H1).",
-                            peer_addr
-                        );
-                        drop(stream);
-                        return;
+                    match state.admit_pairing(peer_addr.ip()) {
+                        crate::pairing::ratelimit::Admission::Allowed => {}
+                        crate::pairing::ratelimit::Admission::GlobalPause(remaining) => {
+                            tracing::warn!(
+                                "Pairing TCP accept from {} refused: pairing paused for {:?} (§H1).",
+                                peer_addr,
+                                remaining
+                            );
+                            drop(stream);
+                            return;
+                        }
+                        crate::pairing::ratelimit::Admission::SourceBackoff(remaining) => {
+                            tracing::warn!(
+                                "Pairing TCP accept from {} refused: source backing off for {:?} (§H1).",
+                                peer_addr,
+                                remaining
+                            );
+                            crate::diagnostics::push_diagnostic(
+                                &state,
+                                &app,
+                                crate::diagnostics::DiagLevel::Detailed,
+                                "pairing",
+                                Some(peer_addr.ip().to_string()),
+                                format!("Pairing attempt refused: backing off for {}s", remaining.as_secs().max(1)),
+                            );
+                            drop(stream);
+                            return;
+                        }
                     }
                     let permit = match state.pairing_slot.clone().try_acquire_owned() {
                         Ok(p) => p,
@@ -1105,7 +1105,7 @@ pub(crate) fn run() {
             let conn_app = listener_handle.clone();
 
             transport.start_listening(
-                move |data, addr| {
+                move |data, addr, fingerprint| {
                     tracing::trace!("Received {} bytes from {}", data.len(), addr);
                     let listener_handle = listener_handle.clone();
                     let listener_state = listener_state.clone();
@@ -1114,7 +1114,7 @@ pub(crate) fn run() {
                     // ... Existing Message Handler Code ...
                     tauri::async_runtime::spawn(async move {
                          match serde_json::from_slice::<Message>(&data) {
-                             Ok(msg) => crate::handlers::handle_message(msg, addr, listener_state, listener_handle, transport_inside).await,
+                             Ok(msg) => crate::handlers::handle_message(msg, addr, fingerprint, listener_state, listener_handle, transport_inside).await,
                              Err(e) => tracing::error!("Failed to parse message: {}", e),
                          }
                     });
@@ -1159,6 +1159,7 @@ pub(crate) fn run() {
                     // confirm, so no per-peer left/joined spam. No-op unless the
                     // routed IP actually changed.
                     crate::netmon::check_and_handle_network_change(&hb_state, &hb_handle);
+                    crate::netmon::check_interface_change(&hb_state);
 
                     let peers: Vec<Peer> = {
                         // FIX: Heartbeat ALL runtime peers, not just known (connected) ones.
@@ -1174,6 +1175,7 @@ pub(crate) fn run() {
                     let network_name = hb_state.network_name.lock().unwrap().clone();
 
                     // Self Peer (for payload)
+                    let exclude = hb_state.settings.lock().unwrap().interface_exclude.clone();
                     let my_peer = Peer {
                         id: local_id,
                         ip: hb_transport.local_addr().unwrap().ip(),
@@ -1186,6 +1188,12 @@ pub(crate) fn run() {
                         signature: None,
                         fingerprint: Some(hb_transport.local_fingerprint()),
                         protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+                        addresses: crate::paths::local_candidates(&exclude, hb_transport.local_addr().unwrap().port()),
+                        reaches: crate::relay::direct_reach(&hb_state),
+                        relay_via: None,
+                        status: Some(crate::status::local(&hb_state)),
+                        device: None,
+                        link: Default::default(),
                     };
 
                     let msg = Message::PeerDiscovery(my_peer);
@@ -1217,6 +1225,42 @@ pub(crate) fn run() {
                 }
             });
 
+            // Background Task: Clock Probes (sender clock offsets for replay.rs)
+            let clock_state = (*app.state::<AppState>()).clone();
+            let clock_transport = transport.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(crate::replay::CLOCK_PROBE_INTERVAL).await;
+                    if crate::presence::presence_paused(&clock_state) {
+                        continue;
+                    }
+                    crate::replay::probe_clocks(&clock_state, &clock_transport).await;
+                }
+            });
+
+            // Background Tasks: discovery fallbacks for multicast-blocked
+            // networks (beacon.rs). Both idle until enabled in settings.
+            let beacon_state = (*app.state::<AppState>()).clone();
+            let beacon_handle = app.handle().clone();
+            tauri::async_runtime::spawn(crate::beacon::run(beacon_state.clone(), beacon_handle.clone()));
+            tauri::async_runtime::spawn(crate::beacon::run_sweeps(beacon_state, transport.clone(), beacon_handle));
+
+            // Background Task: per-network sync rules (netpolicy.rs)
+            let policy_state = (*app.state::<AppState>()).clone();
+            tauri::async_runtime::spawn(crate::netpolicy::run(policy_state, app.handle().clone()));
+
+            // Background Task: quiet hours and Do Not Disturb (quiet.rs)
+            let quiet_state = (*app.state::<AppState>()).clone();
+            tauri::async_runtime::spawn(crate::quiet::run(quiet_state, app.handle().clone()));
+
+            // Background Task: our activity status for heartbeats (status.rs)
+            let status_state = (*app.state::<AppState>()).clone();
+            tauri::async_runtime::spawn(crate::status::run(status_state));
+
+            // Background Task: re-send our DeviceInfo when it changes (device_info.rs)
+            let device_state = (*app.state::<AppState>()).clone();
+            tauri::async_runtime::spawn(crate::device_info::run(device_state, transport.clone()));
+
             // Background Task: Pruning (Remove Stale Untrusted Peers)
             let prune_handle = app.handle().clone();
             let prune_state = (*app.state::<AppState>()).clone();
@@ -1284,6 +1328,15 @@ pub(crate) fn run() {
             crate::commands::peers::add_manual_peer,
             crate::commands::peers::add_remote_peer,
             crate::pairing::start_pairing,
+            crate::pairing::invite::create_pairing_invite,
+            crate::pairing::invite::parse_pairing_invite,
+            crate::pairing::sas::confirm_pairing_sas,
+            crate::pairing::tokens::mint_invite_token,
+            crate::pairing::tokens::list_invite_tokens,
+            crate::clusters::list_clusters,
+            crate::clusters::set_cluster_route,
+            crate::clusters::leave_cluster,
+            crate::pairing::tokens::revoke_invite_token,
             crate::commands::peers::delete_peer,
             crate::commands::peers::leave_network,
             crate::commands::identity::get_network_name,
@@ -1300,11 +1353,16 @@ pub(crate) fn run() {
             crate::commands::diagnostics::get_diagnostic_events,
             crate::commands::diagnostics::clear_diagnostic_events,
             crate::commands::peers::get_known_peers,
+            crate::commands::peers::get_peer_policies,
+            crate::commands::peers::set_peer_policy,
             crate::commands::peers::expects_remote_manual_peers,
             crate::commands::system::log_frontend,
             crate::commands::settings::save_settings,
+            crate::commands::settings::get_network_identity,
+            crate::commands::settings::get_quiet_status,
             crate::commands::identity::set_network_identity,
             crate::commands::identity::regenerate_network_identity,
+            crate::commands::identity::rotate_device_cert,
             crate::commands::clipboard::send_clipboard,
             crate::commands::clipboard::set_local_clipboard,
             crate::commands::clipboard::set_local_clipboard_files,
@@ -1313,6 +1371,7 @@ pub(crate) fn run() {
             crate::commands::system::get_launch_args,
             crate::commands::system::exit_app,
             crate::commands::peers::retry_connection,
+            crate::commands::peers::test_connection,
             crate::commands::system::configure_autostart,
             crate::commands::system::get_autostart_state,
             crate::commands::peers::get_listening_port,
@@ -0,0 +1,294 @@
+//! Discovery without multicast.
+//!
+//! Networks that drop multicast (corporate and guest Wi-Fi, mostly) leave
+//! `Discovery::browse` empty. Two opt-in fallbacks cover them:
+//!
+//! - a UDP beacon carrying the same fields as our mDNS TXT record (`id`,
+//!   `n`, `h`, `proto`), broadcast on the local subnet and sent to any
+//!   configured unicast targets. A device that hears one answers the sender
+//!   directly, so a unicast target learns about us even if our broadcast
+//!   never reaches it. Beacons resolve through
+//!   `discovery::resolve_advertisement`, exactly like an mDNS record.
+//! - a periodic sweep of user-supplied CIDR ranges with `probe_ip`, whose
+//!   `PeerDiscovery` replies land in the usual handler path.
+//!
+//! Beacons are unauthenticated, like mDNS: they only create untrusted
+//! runtime entries, and trust still comes from the pinned fingerprint.
+//! They are IPv4 only since IPv6 has no broadcast.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::{Advertisement, CLUSTERCUT_PROTOCOL_VERSION};
+use crate::peer::PeerAddress;
+use crate::state::AppState;
+use crate::transport::Transport;
+
+pub(crate) const DEFAULT_BEACON_PORT: u16 = 4655;
+const BEACON_MAGIC: &str = "clustercut-beacon";
+const BEACON_INTERVAL: Duration = Duration::from_secs(10);
+/// While the beacon is off, how often to check whether it was turned on.
+const SETTINGS_POLL: Duration = Duration::from_secs(5);
+/// Larger datagrams are not beacons.
+const MAX_BEACON_LEN: usize = 1024;
+/// At most one direct answer per source in this window.
+const REPLY_INTERVAL: Duration = Duration::from_secs(5);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How often the sweep task checks for edited ranges.
+const SWEEP_POLL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Beacon {
+    magic: String,
+    id: String,
+    n: String,
+    h: String,
+    proto: String,
+    /// Our QUIC port, which the beacon port is not.
+    port: u16,
+    /// Set on periodic beacons; answers to them leave it unset so two
+    /// devices never reply to each other forever.
+    #[serde(default)]
+    ask: bool,
+}
+
+impl Beacon {
+    fn ours(state: &AppState, port: u16, ask: bool) -> Self {
+        Self {
+            magic: BEACON_MAGIC.to_string(),
+            id: state.local_device_id.lock().unwrap().clone(),
+            n: state.network_name.lock().unwrap().clone(),
+            h: hostname::get()
+                .map(|h| h.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "Unknown Device".to_string()),
+            proto: CLUSTERCUT_PROTOCOL_VERSION.to_string(),
+            port,
+            ask,
+        }
+    }
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() > MAX_BEACON_LEN {
+            return None;
+        }
+        let beacon: Beacon = serde_json::from_slice(data).ok()?;
+        (beacon.magic == BEACON_MAGIC && !beacon.id.is_empty()).then_some(beacon)
+    }
+
+    /// The advertisement this beacon stands for, heard from `from`.
+    fn into_advertisement(self, from: SocketAddr, now: u64) -> Advertisement {
+        let addr = SocketAddr::new(from.ip(), self.port);
+        Advertisement {
+            id: self.id,
+            network_name: Some(self.n).filter(|n| !n.is_empty()),
+            hostname: self.h,
+            proto: Some(self.proto).filter(|p| !p.is_empty()),
+            port: self.port,
+            addresses: vec![PeerAddress::new(addr, now)],
+        }
+    }
+}
+
+fn bind(port: u16) -> std::io::Result<tokio::net::UdpSocket> {
+    use socket2::{Domain, Socket, Type};
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, None)?;
+    // Other programs (or a second profile) may listen on the same port.
+    socket.set_reuse_address(true)?;
+    socket.set_broadcast(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from(([0, 0, 0, 0], port)).into())?;
+    tokio::net::UdpSocket::from_std(socket.into())
+}
+
+/// Where periodic beacons go: the limited broadcast address plus every
+/// configured unicast target (`ip` or `ip:port`, default the beacon port).
+fn destinations(targets: &[String], beacon_port: u16) -> Vec<SocketAddr> {
+    let mut out = vec![SocketAddr::from(([255, 255, 255, 255], beacon_port))];
+    for target in targets.iter().filter(|t| !t.trim().is_empty()) {
+        match crate::net_util::parse_peer_addr(target, beacon_port) {
+            Ok(addr) if addr.is_ipv4() => out.push(addr),
+            Ok(addr) => tracing::debug!("[Beacon] Skipping IPv6 target {}", addr),
+            Err(e) => tracing::warn!("[Beacon] Ignoring target '{}': {}", target, e),
+        }
+    }
+    out
+}
+
+fn quic_port(state: &AppState) -> Option<u16> {
+    let transport = state.transport.lock().unwrap().clone()?;
+    transport.local_addr().ok().map(|a| a.port())
+}
+
+/// Run the beacon for the life of the app, following the beacon settings:
+/// rebinds when the port changes and goes quiet when it is turned off.
+pub(crate) async fn run(state: AppState, app_handle: tauri::AppHandle) {
+    loop {
+        let (enabled, port) = {
+            let s = state.settings.lock().unwrap();
+            (s.beacon_enabled, s.beacon_port)
+        };
+        if !enabled {
+            tokio::time::sleep(SETTINGS_POLL).await;
+            continue;
+        }
+        // Sharing QUIC's port would split its datagrams between the sockets.
+        if quic_port(&state) == Some(port) {
+            tracing::warn!("[Beacon] UDP {} is the QUIC port; pick another beacon port", port);
+            tokio::time::sleep(SETTINGS_POLL).await;
+            continue;
+        }
+        match bind(port) {
+            Ok(socket) => {
+                tracing::info!("[Beacon] Listening on UDP {}", port);
+                serve(&state, &app_handle, &socket, port).await;
+                tracing::info!("[Beacon] Stopped on UDP {}", port);
+            }
+            Err(e) => {
+                tracing::warn!("[Beacon] Failed to bind UDP {}: {}", port, e);
+                tokio::time::sleep(BEACON_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Send and answer beacons on `socket` until the settings no longer match.
+async fn serve(state: &AppState, app_handle: &tauri::AppHandle, socket: &tokio::net::UdpSocket, port: u16) {
+    let mut tick = tokio::time::interval(BEACON_INTERVAL);
+    let mut buf = [0u8; MAX_BEACON_LEN + 1];
+    let mut replied: HashMap<IpAddr, Instant> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                let (enabled, current_port, advertising, targets) = {
+                    let s = state.settings.lock().unwrap();
+                    (s.beacon_enabled, s.beacon_port, s.mdns_advertising, s.beacon_targets.clone())
+                };
+                if !enabled || current_port != port {
+                    return;
+                }
+                replied.retain(|_, at| at.elapsed() < REPLY_INTERVAL);
+                // Staying hidden covers the beacon too; we still listen.
+                if !advertising {
+                    continue;
+                }
+                let Some(quic) = quic_port(state) else {
+                    continue;
+                };
+                let Ok(data) = serde_json::to_vec(&Beacon::ours(state, quic, true)) else {
+                    continue;
+                };
+                for dest in destinations(&targets, port) {
+                    if let Err(e) = socket.send_to(&data, dest).await {
+                        tracing::debug!("[Beacon] Send to {} failed: {}", dest, e);
+                    }
+                }
+            }
+            received = socket.recv_from(&mut buf) => {
+                let (len, from) = match received {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::debug!("[Beacon] Receive failed: {}", e);
+                        continue;
+                    }
+                };
+                let Some(beacon) = Beacon::parse(&buf[..len]) else {
+                    continue;
+                };
+                let local_id = state.local_device_id.lock().unwrap().clone();
+                if beacon.id == local_id {
+                    continue;
+                }
+                let ask = beacon.ask;
+                tracing::debug!("[Beacon] {} ({}) heard from {}", beacon.h, beacon.id, from);
+                crate::discovery::resolve_advertisement(state, app_handle, beacon.into_advertisement(from, crate::paths::now_secs()));
+
+                let due = replied.get(&from.ip()).map_or(true, |at| at.elapsed() >= REPLY_INTERVAL);
+                let advertising = state.settings.lock().unwrap().mdns_advertising;
+                if ask && due && advertising {
+                    if let Some(quic) = quic_port(state) {
+                        if let Ok(data) = serde_json::to_vec(&Beacon::ours(state, quic, false)) {
+                            let _ = socket.send_to(&data, from).await;
+                            replied.insert(from.ip(), Instant::now());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sweep the configured ranges every `SWEEP_INTERVAL`, and straight away
+/// whenever the list is edited.
+pub(crate) async fn run_sweeps(state: AppState, transport: Transport, app_handle: tauri::AppHandle) {
+    let mut last: Vec<String> = Vec::new();
+    let mut last_run: Option<Instant> = None;
+    loop {
+        tokio::time::sleep(SWEEP_POLL).await;
+        let ranges = state.settings.lock().unwrap().sweep_ranges.clone();
+        let due = last_run.map_or(true, |at| at.elapsed() >= SWEEP_INTERVAL);
+        if ranges.is_empty() || (ranges == last && !due) || crate::presence::presence_paused(&state) {
+            continue;
+        }
+        for range in &ranges {
+            let Ok(net) = range.trim().parse::<ipnetwork::IpNetwork>() else {
+                tracing::warn!("[Sweep] Ignoring invalid range '{}'", range);
+                continue;
+            };
+            let result = crate::net_util::sweep_range(net, state.clone(), transport.clone(), app_handle.clone(), false).await;
+            if let Err(e) = result {
+                tracing::warn!("[Sweep] {}: {}", range, e);
+            }
+        }
+        last = ranges;
+        last_run = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beacon(ask: bool) -> Beacon {
+        Beacon {
+            magic: BEACON_MAGIC.to_string(),
+            id: "dev-1".to_string(),
+            n: "home".to_string(),
+            h: "laptop".to_string(),
+            proto: CLUSTERCUT_PROTOCOL_VERSION.to_string(),
+            port: 4654,
+            ask,
+        }
+    }
+
+    #[test]
+    fn beacons_round_trip_and_reject_foreign_datagrams() {
+        let data = serde_json::to_vec(&beacon(true)).unwrap();
+        assert_eq!(Beacon::parse(&data), Some(beacon(true)));
+        assert_eq!(Beacon::parse(b"{\"magic\":\"other\"}"), None);
+        assert_eq!(Beacon::parse(b"\x00\x01garbage"), None);
+        let mut wrong = beacon(true);
+        wrong.magic = "something-else".to_string();
+        assert_eq!(Beacon::parse(&serde_json::to_vec(&wrong).unwrap()), None);
+        assert_eq!(Beacon::parse(&vec![b' '; MAX_BEACON_LEN + 1]), None);
+    }
+
+    #[test]
+    fn advertisement_uses_the_source_ip_and_quic_port() {
+        let from: SocketAddr = "192.168.1.40:4655".parse().unwrap();
+        let ad = beacon(false).into_advertisement(from, 100);
+        assert_eq!(ad.id, "dev-1");
+        assert_eq!(ad.network_name.as_deref(), Some("home"));
+        assert_eq!(ad.addresses[0].addr, "192.168.1.40:4654".parse::<SocketAddr>().unwrap());
+    }
+
+    #[test]
+    fn destinations_include_broadcast_and_ipv4_targets() {
+        let targets = vec!["10.0.0.5".to_string(), "10.0.0.6:9000".to_string(), "2001:db8::1".to_string(), "bogus".to_string()];
+        let dests = destinations(&targets, 4655);
+        let sa = |s: &str| s.parse::<SocketAddr>().unwrap();
+        assert_eq!(dests, vec![sa("255.255.255.255:4655"), sa("10.0.0.5:4655"), sa("10.0.0.6:9000")]);
+    }
+}
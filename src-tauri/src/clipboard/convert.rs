@@ -0,0 +1,276 @@
+//! Receiver-side conversion: extra representations derived from what
+//! arrived, so the destination app finds a format it reads whatever the
+//! sender's platform offered.
+//!
+//! Senders ship one representation per kind (a passthrough or PNG image,
+//! text plus HTML/RTF, a list of files). The backends call in here right
+//! before they write, and put the derived formats next to the original:
+//!
+//! - **Images** written verbatim get a PNG companion for apps that only
+//!   read PNG, and on wlroots a BMP one for legacy (Wine) apps that map
+//!   `CF_DIB` to `image/bmp`. Windows gets its DIB from arboard already.
+//!   The GNOME extension offers one MIME per selection and X11 writes only
+//!   arboard's PNG, so neither carries companions; both get PNG for
+//!   anything but SVG and GIF (`common::verbatim_image_mimes`).
+//! - **Rich text** that arrived without plain text gets it back from the
+//!   HTML, so plain-only destinations don't paste nothing.
+//! - **Files** become `file://` URIs for `text/uri-list`, and the paths
+//!   themselves for plain-text destinations.
+
+use std::io::Cursor;
+
+use super::common;
+use crate::protocol::{ClipboardBlob, ClipboardFormat};
+
+/// Images above this many pixels get no BMP companion: it is uncompressed
+/// and held in memory for as long as we own the selection.
+const MAX_BMP_PIXELS: u64 = 3840 * 2160;
+
+/// Images above this many pixels get no PNG companion either. Encoding one
+/// takes seconds and, held alongside the original, can cost more memory than
+/// the transfer itself; such an image still arrives in its own format.
+const MAX_PNG_PIXELS: u64 = 7680 * 4320;
+
+/// Which companions an image of `pixels` written as `mime` gets: (PNG, BMP).
+fn wanted_companions(mime: &str, pixels: u64, bmp: bool) -> (bool, bool) {
+    (
+        mime != "image/png" && pixels <= MAX_PNG_PIXELS,
+        bmp && pixels <= MAX_BMP_PIXELS,
+    )
+}
+
+/// Raster companions for an image about to be written as `blob.mime_type`:
+/// PNG unless it already is one, and BMP when `bmp` is set, each within its
+/// pixel cap. Empty for SVG and for anything that doesn't decode.
+pub(crate) fn image_companions(blob: &ClipboardBlob, bmp: bool) -> Vec<ClipboardFormat> {
+    let mime = blob.mime_type.as_str();
+    let Some(format) = common::image_format_for_mime(mime) else {
+        return Vec::new();
+    };
+    let Ok(bytes) = blob.raw_bytes() else {
+        return Vec::new();
+    };
+    // Sized from the header, so an oversized image is never decoded.
+    let pixels = match image::ImageReader::with_format(Cursor::new(&bytes), format).into_dimensions() {
+        Ok((w, h)) => u64::from(w) * u64::from(h),
+        Err(e) => {
+            tracing::debug!("No companions for {}: {}", mime, e);
+            return Vec::new();
+        }
+    };
+    let (want_png, want_bmp) = wanted_companions(mime, pixels, bmp);
+    if !want_png && !want_bmp {
+        return Vec::new();
+    }
+    let img = match image::load_from_memory_with_format(&bytes, format) {
+        Ok(i) => i,
+        Err(e) => {
+            tracing::debug!("No companions for {}: {}", mime, e);
+            return Vec::new();
+        }
+    };
+
+    let mut out = Vec::new();
+    if want_png {
+        let mut png = Vec::new();
+        if img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).is_ok() {
+            out.push(ClipboardFormat::from_bytes("image/png", &png));
+        }
+    }
+    if want_bmp {
+        // 24-bit: older readers choke on the alpha channel of 32-bit BMPs.
+        let rgb = image::DynamicImage::ImageRgb8(img.to_rgb8());
+        let mut out_bmp = Vec::new();
+        if rgb.write_to(&mut Cursor::new(&mut out_bmp), image::ImageFormat::Bmp).is_ok() {
+            out.push(ClipboardFormat::from_bytes("image/bmp", &out_bmp));
+        }
+    }
+    out
+}
+
+/// `text` unless it is blank, in which case the text of the `text/html`
+/// format, if any.
+pub(crate) fn plain_text_for(text: String, formats: &[ClipboardFormat]) -> String {
+    if !text.trim().is_empty() {
+        return text;
+    }
+    formats
+        .iter()
+        .find(|f| f.mime_type == "text/html")
+        .and_then(|f| f.raw_bytes().ok())
+        .and_then(|b| String::from_utf8(b).ok())
+        .map(|html| text_from_html(&html))
+        .filter(|t| !t.is_empty())
+        .unwrap_or(text)
+}
+
+/// Tags that start a new line.
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "table", "ul", "ol",
+];
+/// Tags whose content isn't text.
+const SKIPPED_TAGS: &[&str] = &["script", "style", "head", "title"];
+
+/// Readable text from an HTML fragment: tags dropped, block elements on
+/// their own lines, whitespace collapsed and the common entities decoded.
+/// Not a renderer — enough for a paste into a plain-text field.
+pub(crate) fn text_from_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut skipping: Option<String> = None;
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        if skipping.is_none() {
+            push_text(&mut out, &rest[..lt]);
+        }
+        let Some(gt) = rest[lt..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if let Some(skipped) = &skipping {
+            if closing && *skipped == name {
+                skipping = None;
+            }
+            continue;
+        }
+        if !closing && SKIPPED_TAGS.contains(&name.as_str()) && !tag.ends_with('/') {
+            skipping = Some(name);
+        } else if BLOCK_TAGS.contains(&name.as_str()) && !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    if skipping.is_none() {
+        push_text(&mut out, rest);
+    }
+    out.lines().map(str::trim).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+/// Append a run of HTML text: whitespace collapsed, entities decoded.
+fn push_text(out: &mut String, raw: &str) {
+    let mut rest = raw;
+    while !rest.is_empty() {
+        let ch = rest.chars().next().unwrap();
+        if ch.is_whitespace() {
+            if !out.is_empty() && !out.ends_with(' ') && !out.ends_with('\n') {
+                out.push(' ');
+            }
+            rest = &rest[ch.len_utf8()..];
+        } else if ch == '&' {
+            let entity = rest
+                .find(';')
+                .filter(|&end| end <= 10)
+                .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+            match entity {
+                Some((decoded, end)) => {
+                    out.push(decoded);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        } else {
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let n = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(n)
+        }
+    }
+}
+
+/// `file://` URIs for `paths`, relative ones resolved against the working
+/// directory. Paths that can't be made into a URI are dropped.
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+pub(crate) fn file_uris(paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .filter_map(|p| {
+            let path = std::path::Path::new(p);
+            let abs_path = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                std::env::current_dir().ok()?.join(path)
+            };
+            url::Url::from_file_path(abs_path).ok().map(|u| u.to_string())
+        })
+        .collect()
+}
+
+/// The paths as plain text, one per line, for destinations that only read
+/// text (terminals, editors).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn paths_as_text(paths: &[String]) -> String {
+    paths.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_becomes_readable_text() {
+        let html = "<html><head><style>p{color:red}</style></head><body>\
+                    <h1>Title</h1><p>Fish &amp; chips,\n   &lt;hot&gt;&#33;</p>\
+                    <ul><li>one</li><li>t&#x77;o</li></ul><script>x()</script>tail</body></html>";
+        assert_eq!(text_from_html(html), "Title\nFish & chips, <hot>!\none\ntwo\ntail");
+        assert_eq!(text_from_html("a &bogus; b & c"), "a &bogus; b & c");
+    }
+
+    #[test]
+    fn blank_text_is_filled_from_html_only() {
+        let html = vec![ClipboardFormat::from_text("text/html", "<b>bold</b> move")];
+        assert_eq!(plain_text_for(String::new(), &html), "bold move");
+        assert_eq!(plain_text_for("kept".to_string(), &html), "kept");
+        let rtf = vec![ClipboardFormat::from_text("text/rtf", r"{\rtf1 x}")];
+        assert_eq!(plain_text_for(" ".to_string(), &rtf), " ");
+    }
+
+    #[test]
+    fn jpeg_gets_png_and_bmp_companions() {
+        let img = image::DynamicImage::new_rgb8(4, 3);
+        let mut jpeg = Vec::new();
+        img.write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg).unwrap();
+        let blob = ClipboardBlob::from_bytes("image/jpeg", &jpeg, None, None);
+
+        let mimes: Vec<String> = image_companions(&blob, true).into_iter().map(|f| f.mime_type).collect();
+        assert_eq!(mimes, ["image/png", "image/bmp"]);
+        assert!(image_companions(&blob, false).iter().all(|f| f.mime_type == "image/png"));
+        let svg = ClipboardBlob::from_bytes("image/svg+xml", b"<svg/>", None, None);
+        assert!(image_companions(&svg, true).is_empty());
+    }
+
+    #[test]
+    fn large_images_lose_their_companions() {
+        assert_eq!(wanted_companions("image/jpeg", 1920 * 1080, true), (true, true));
+        assert_eq!(wanted_companions("image/jpeg", 5120 * 2880, true), (true, false));
+        assert_eq!(wanted_companions("image/jpeg", 10_000 * 10_000, true), (false, false));
+        assert_eq!(wanted_companions("image/png", 1920 * 1080, false), (false, false));
+    }
+}
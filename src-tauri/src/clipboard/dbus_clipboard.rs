@@ -169,31 +169,37 @@ fn write_formats_dbus(text: &str, formats: &[(String, Vec<u8>)]) -> Result<(), S
     Ok(())
 }
 
+/// Whether the files FilesChanged just reported were cut rather than
+/// copied. FilesChanged carries URIs only, so ask the extension to read the
+/// file manager's marker (`x-special/gnome-copied-files`,
+/// `application/x-kde-cutselection`). Extensions without ReadFilesCut
+/// report every file selection as a copy.
+async fn read_files_cut(proxy: &zbus::Proxy<'_>) -> bool {
+    match proxy.call::<_, _, bool>("ReadFilesCut", &()).await {
+        Ok(cut) => cut,
+        Err(e) => {
+            tracing::debug!("ReadFilesCut D-Bus call failed, treating files as copied: {}", e);
+            false
+        }
+    }
+}
+
 fn write_text(_app: &AppHandle, text: String) -> Result<(), String> {
     write_text_dbus(&text)
 }
 
+/// WriteBlob offers a single MIME, so there is no room for the companions
+/// the wlroots backend adds (`convert::image_companions`). Instead the blob
+/// arrives here already as the one representation worth offering: PNG for
+/// anything a PNG loses nothing of, SVG or GIF as they came
+/// (`common::verbatim_image_mimes`).
 fn write_image(_app: &AppHandle, blob: &ClipboardBlob) -> Result<(), String> {
     let bytes = blob.raw_bytes()?;
     write_blob_dbus(&blob.mime_type, &bytes)
 }
 
 fn write_files(_app: &AppHandle, files: Vec<String>) -> Result<(), String> {
-    // Convert paths to file:// URIs
-    let uris: Vec<String> = files
-        .into_iter()
-        .filter_map(|p| {
-            let path = std::path::Path::new(&p);
-            let abs_path = if path.is_absolute() {
-                path.to_path_buf()
-            } else {
-                std::env::current_dir().ok()?.join(path)
-            };
-            url::Url::from_file_path(abs_path)
-                .ok()
-                .map(|u| u.to_string())
-        })
-        .collect();
+    let uris = super::convert::file_uris(&files);
 
     if uris.is_empty() {
         return Err("No valid file paths".to_string());
@@ -359,7 +365,10 @@ pub fn start_monitor(
                 },
                 next = files_stream.next() => match next {
                     Some(msg) => match msg.body().deserialize::<Vec<String>>() {
-                        Ok(uris) if !uris.is_empty() => Some(ClipboardContent::Files(uris)),
+                        Ok(uris) if !uris.is_empty() => {
+                            let cut = read_files_cut(&proxy).await;
+                            Some(ClipboardContent::Files { paths: uris, cut })
+                        }
                         _ => None,
                     },
                     None => {
@@ -1,4 +1,5 @@
 pub mod common;
+mod convert;
 pub mod history_store;
 pub mod preview;
 mod plugin;
@@ -195,8 +196,10 @@ pub fn set_clipboard_paths(app: &AppHandle, paths: Vec<String>) {
 /// format it understands best. Wayland wlroots, GNOME-extension, and the
 /// Plugin backend (Windows / macOS) carry the rich formats end-to-end. X11
 /// (also Plugin) is intentionally out of scope — its set_clipboard_rich falls
-/// back to plain text via tauri-plugin-clipboard.
+/// back to plain text via tauri-plugin-clipboard. A payload that arrived
+/// without plain text gets it from its HTML first (`convert::plain_text_for`).
 pub fn set_clipboard_rich(app: &AppHandle, text: String, formats: Vec<ClipboardFormat>) {
+    let text = convert::plain_text_for(text, &formats);
     #[cfg(not(target_os = "linux"))]
     {
         plugin::set_clipboard_rich(app, text, formats);
@@ -223,11 +226,43 @@ pub fn set_clipboard_rich(app: &AppHandle, text: String, formats: Vec<ClipboardF
     }
 }
 
+/// Passthrough image MIMEs the active backend writes verbatim
+/// (`common::verbatim_image_mimes`).
+pub fn verbatim_image_mimes() -> &'static [&'static str] {
+    common::verbatim_image_mimes(std::env::consts::OS, crate::device_info::local_backend())
+}
+
+/// Convert a passthrough image the active backend can't write verbatim to
+/// PNG. Formats we can't decode (AVIF, HEIC) are written as they came; some
+/// app may still take them.
+fn localize_image_blob(blob: ClipboardBlob) -> ClipboardBlob {
+    let mime = blob.mime_type.as_str();
+    if !common::is_passthrough_image_mime(mime) || verbatim_image_mimes().contains(&mime) {
+        return blob;
+    }
+    let converted = blob
+        .raw_bytes()
+        .ok()
+        .and_then(|bytes| common::normalize_image_blob_from_bytes(bytes, mime));
+    match converted {
+        Some(png) => {
+            tracing::debug!("Converted received {} to image/png for this clipboard", mime);
+            png
+        }
+        None => {
+            tracing::warn!("Can't convert received {} to PNG; writing it verbatim", mime);
+            blob
+        }
+    }
+}
+
 /// Place an image blob (typically `image/png`) on the local clipboard so the
 /// user can paste it in any app. Wired up across all four backends; the GNOME
 /// extension path requires extension v4.0 or newer — older extensions return
-/// UnknownMethod and the write fails gracefully.
+/// UnknownMethod and the write fails gracefully. Passthrough formats this
+/// backend can't take are converted to PNG first.
 pub fn set_clipboard_image(app: &AppHandle, blob: ClipboardBlob) {
+    let blob = localize_image_blob(blob);
     #[cfg(not(target_os = "linux"))]
     {
         plugin::set_clipboard_image(app, blob);
@@ -170,9 +170,11 @@ fn read_clipboard_image_arboard(arboard: &mut arboard::Clipboard) -> Option<Clip
 /// CF_BITMAP + "PNG" atom (so Paint/Word/Photos can paste); the worker then
 /// also appends the source `image/jpeg` (or `image/gif`) registered atom
 /// without emptying the clipboard, so apps that prefer the original-format
-/// MIME (Chromium / Electron / image editors) still see it. macOS and Linux
-/// keep pure passthrough — their native MIME-based clipboards handle JPEG /
-/// GIF cleanly without a raster companion.
+/// MIME (Chromium / Electron / image editors) still see it. macOS writes the
+/// source bytes under their UTI with a PNG companion
+/// (`convert::image_companions`). X11 never gets here with a passthrough
+/// MIME: everything arrives converted to PNG, which is the one format
+/// arboard offers there, so it carries no BMP companion either.
 fn write_clipboard_image_arboard(_app: &AppHandle, blob: &ClipboardBlob) -> Result<(), String> {
     let bytes = blob.raw_bytes()?;
     let mime = blob.mime_type.as_str();
@@ -188,7 +190,7 @@ fn write_clipboard_image_arboard(_app: &AppHandle, blob: &ClipboardBlob) -> Resu
         }
         #[cfg(not(target_os = "windows"))]
         {
-            return rich::write_clipboard_passthrough_image(mime, &bytes);
+            return rich::write_clipboard_passthrough_image(mime, &bytes, &[]);
         }
     }
 
@@ -198,7 +200,8 @@ fn write_clipboard_image_arboard(_app: &AppHandle, blob: &ClipboardBlob) -> Resu
         && (mime == "image/jpeg" || mime == "image/gif");
 
     if super::common::is_passthrough_image_mime(mime) && !needs_windows_dual_write {
-        return rich::write_clipboard_passthrough_image(mime, &bytes);
+        let companions = super::convert::image_companions(blob, false);
+        return rich::write_clipboard_passthrough_image(mime, &bytes, &companions);
     }
 
     let format = match mime {
@@ -329,7 +332,7 @@ fn read_clipboard(
     match clip.read_files() {
         Ok(files) => {
             if !files.is_empty() {
-                return ClipboardContent::Files(files);
+                return ClipboardContent::Files { paths: files, cut: rich::clipboard_files_cut() };
             }
         }
         Err(_) => {}
@@ -496,24 +499,7 @@ fn write_files(app: &AppHandle, files: Vec<String>) -> Result<(), String> {
 
     #[cfg(not(target_os = "windows"))]
     {
-        let uris: Vec<String> = files
-            .into_iter()
-            .filter_map(|p| {
-                let path = std::path::Path::new(&p);
-                let abs_path = if path.is_absolute() {
-                    path.to_path_buf()
-                } else {
-                    match std::env::current_dir().ok() {
-                        Some(c) => c.join(path),
-                        None => return None,
-                    }
-                };
-
-                url::Url::from_file_path(abs_path)
-                    .ok()
-                    .map(|u| u.to_string())
-            })
-            .collect();
+        let uris = super::convert::file_uris(&files);
 
         if uris.is_empty() {
             return Err("No valid file paths convertible to URIs".to_string());
@@ -680,7 +666,7 @@ pub fn start_monitor(app_handle: AppHandle, state: AppState, transport: Transpor
                     bytes,
                     response,
                 } => {
-                    let result = rich::write_clipboard_passthrough_image(&mime, &bytes);
+                    let result = rich::write_clipboard_passthrough_image(&mime, &bytes, &[]);
                     let _ = response.send(result);
                 }
             }
@@ -52,6 +52,9 @@ pub struct ClipboardPreview {
     pub files: Option<Vec<crate::protocol::FileMetadata>>,
     /// Whether re-call is currently possible (content still in the store).
     pub has_backing: bool,
+    /// Self-destruct delay for ephemeral items (never stored, no preview
+    /// content). None for regular items.
+    pub ttl_secs: Option<u64>,
 }
 
 /// UTF-8-safe truncation to TEXT_PREVIEW_BYTES.
@@ -173,6 +176,30 @@ pub fn descriptor_preview(payload: &ClipboardPayload) -> (Option<String>, u64, O
     )
 }
 
+/// Build the redacted preview for an ephemeral payload: sizes and MIME only,
+/// no text snippet and no thumbnail, so the secret never reaches the UI.
+pub fn ephemeral_preview(payload: &ClipboardPayload) -> (Option<String>, u64, Option<BlobPreview>) {
+    if let Some(b) = payload.blob.as_ref() {
+        let size = b.total_size.unwrap_or(b.decoded_len() as u64);
+        if b.mime_type.starts_with("text/") {
+            return (None, size, None);
+        }
+        return (
+            None,
+            0,
+            Some(BlobPreview {
+                mime_type: b.mime_type.clone(),
+                width: b.width,
+                height: b.height,
+                size,
+                thumbnail: None,
+                descriptor: b.is_descriptor(),
+            }),
+        );
+    }
+    (None, payload.text.len() as u64, None)
+}
+
 /// Build the light format summary (no bytes).
 pub fn formats_preview(payload: &ClipboardPayload) -> Option<Vec<FormatPreview>> {
     payload.formats.as_ref().filter(|f| !f.is_empty()).map(|fs| {
@@ -69,6 +69,26 @@ pub fn clipboard_change_count() -> Option<i64> {
     }
 }
 
+/// Whether the files on the clipboard were cut rather than copied. Explorer
+/// says so with a "Preferred DropEffect" of DROPEFFECT_MOVE; X11 file
+/// managers with the same markers the wlroots backend reads
+/// (`x-special/gnome-copied-files`, `application/x-kde-cutselection`).
+/// macOS has no such marker, so it always reports a copy.
+pub fn clipboard_files_cut() -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        windows::files_cut()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        false
+    }
+    #[cfg(all(target_os = "linux", not(target_os = "windows")))]
+    {
+        x11::files_cut()
+    }
+}
+
 /// Read a passthrough image (SVG vector or animated GIF) from the OS
 /// clipboard if one is present. Returns `(mime, bytes)`. The plugin backend
 /// calls this *before* arboard's RGBA probe so passthrough representations
@@ -93,19 +113,26 @@ pub fn read_clipboard_passthrough_image() -> Option<(String, Vec<u8>)> {
 /// Write a passthrough image (SVG / animated GIF) to the OS clipboard
 /// verbatim under its source MIME. The plugin backend's
 /// `set_clipboard_image` branches on `blob.mime_type` and calls this for
-/// passthrough MIMEs; raster MIMEs continue through arboard.
-pub fn write_clipboard_passthrough_image(mime: &str, bytes: &[u8]) -> Result<(), String> {
+/// passthrough MIMEs; raster MIMEs continue through arboard. `companions`
+/// (a PNG derived from the image) go on the macOS pasteboard alongside;
+/// Windows gets its raster companion from the arboard dual-write instead.
+pub fn write_clipboard_passthrough_image(
+    mime: &str,
+    bytes: &[u8],
+    companions: &[ClipboardFormat],
+) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
+        let _ = companions;
         windows::write_passthrough_image(mime, bytes)
     }
     #[cfg(target_os = "macos")]
     {
-        macos::write_passthrough_image(mime, bytes)
+        macos::write_passthrough_image(mime, bytes, companions)
     }
     #[cfg(all(target_os = "linux", not(target_os = "windows")))]
     {
-        let _ = (mime, bytes);
+        let _ = (mime, bytes, companions);
         Err("passthrough-image clipboard write not supported on X11".to_string())
     }
 }
@@ -228,6 +255,73 @@ mod strip_html_doc_tests {
 
 // ── Windows ────────────────────────────────────────────────────────────────
 
+/// X11 selection reads for targets tauri-plugin-clipboard and arboard don't
+/// expose. Read-only: we never own the selection here, so none of the
+/// lazy-paste machinery the module doc warns about applies.
+#[cfg(all(target_os = "linux", not(target_os = "windows")))]
+mod x11 {
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, CreateWindowAux, WindowClass};
+    use x11rb::protocol::Event;
+
+    /// How long the selection owner gets to answer. The markers are a few
+    /// bytes, and the read happens on the clipboard worker's poll.
+    const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Nautilus and other GTK file managers put "cut" on the first line of
+    /// `x-special/gnome-copied-files`; Dolphin offers
+    /// `application/x-kde-cutselection` = "1".
+    pub fn files_cut() -> bool {
+        if let Some(gnome) = read_target("x-special/gnome-copied-files") {
+            return String::from_utf8_lossy(&gnome).lines().next().map(str::trim) == Some("cut");
+        }
+        read_target("application/x-kde-cutselection")
+            .map_or(false, |kde| String::from_utf8_lossy(&kde).trim_end_matches('\0').trim() == "1")
+    }
+
+    /// The CLIPBOARD selection converted to `target`, or `None` when there
+    /// is no X server, the owner doesn't offer `target` or doesn't answer
+    /// in time.
+    fn read_target(target: &str) -> Option<Vec<u8>> {
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots.get(screen_num)?.root;
+        let window = conn.generate_id().ok()?;
+        conn.create_window(0, window, root, 0, 0, 1, 1, 0, WindowClass::INPUT_ONLY, 0, &CreateWindowAux::new())
+            .ok()?;
+        let clipboard = intern(&conn, "CLIPBOARD")?;
+        let target_atom = intern(&conn, target)?;
+        let property = intern(&conn, "CLUSTERCUT_SELECTION")?;
+        conn.convert_selection(window, clipboard, target_atom, property, x11rb::CURRENT_TIME).ok()?;
+        conn.flush().ok()?;
+
+        let deadline = Instant::now() + READ_TIMEOUT;
+        while Instant::now() < deadline {
+            match conn.poll_for_event().ok()? {
+                Some(Event::SelectionNotify(e)) if e.requestor == window => {
+                    if e.property == x11rb::NONE {
+                        return None;
+                    }
+                    let reply = conn
+                        .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+                        .ok()?
+                        .reply()
+                        .ok()?;
+                    return Some(reply.value);
+                }
+                Some(_) => {}
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        tracing::debug!("X11 selection owner didn't answer for {}", target);
+        None
+    }
+
+    fn intern(conn: &impl Connection, name: &str) -> Option<u32> {
+        conn.intern_atom(false, name.as_bytes()).ok()?.reply().ok().map(|r| r.atom)
+    }
+}
+
 #[cfg(target_os = "windows")]
 mod windows {
     use crate::protocol::ClipboardFormat;
@@ -270,6 +364,33 @@ mod windows {
         raw::register_format("image/jpeg").map(|f| f.get())
     }
 
+    fn drop_effect_format_id() -> Option<u32> {
+        raw::register_format("Preferred DropEffect").map(|f| f.get())
+    }
+
+    /// DROPEFFECT_MOVE in the "Preferred DropEffect" DWORD.
+    const DROPEFFECT_MOVE: u32 = 2;
+
+    /// True when Explorer marked the current file selection as a cut.
+    pub fn files_cut() -> bool {
+        let Some(id) = drop_effect_format_id() else {
+            return false;
+        };
+        if !raw::is_format_avail(id) {
+            return false;
+        }
+        let Ok(_clip) = Clipboard::new_attempts(ATTEMPTS) else {
+            return false;
+        };
+        let mut buf: Vec<u8> = Vec::new();
+        match RawData(id).read_clipboard(&mut buf) {
+            Ok(_) if buf.len() >= 4 => {
+                (u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) & DROPEFFECT_MOVE) != 0
+            }
+            _ => false,
+        }
+    }
+
     /// Probe order for passthrough-image MIMEs on Windows. Each entry
     /// returns `(mime_label, format_id_resolver)`. SVG first since vector
     /// beats raster; GIF before JPEG so an animated GIF that also has a
@@ -740,16 +861,29 @@ mod macos {
             // public.jpeg: standard UTI for JPEG. Universally supported on
             // macOS since the dawn of time.
             "image/jpeg" => Some("public.jpeg"),
+            // WebP (Big Sur+), AVIF (Ventura+) and HEIC (High Sierra+) are
+            // read by Preview, Photos and WebKit under these UTIs.
+            "image/webp" => Some("org.webmproject.webp"),
+            "image/avif" => Some("public.avif"),
+            "image/heic" => Some("public.heic"),
+            // public.tiff: the pasteboard's own raster type.
+            "image/tiff" => Some("public.tiff"),
             _ => None,
         }
     }
 
     /// Probe order for passthrough-image MIMEs on macOS. SVG before raster
-    /// (vector beats raster); GIF before JPEG so an animated GIF that also
-    /// has a JPEG fallback keeps animation.
+    /// (vector beats raster); GIF and WebP before JPEG so an animated image
+    /// that also has a JPEG fallback keeps animation. TIFF is never probed:
+    /// AppKit adds public.tiff to every image copy, screenshots included, so
+    /// it would win over the PNG arboard reads. AVIF and HEIC aren't either:
+    /// arboard always has a PNG for them (from that TIFF), and receivers
+    /// that can't take them can't convert them. They are still written
+    /// verbatim when they arrive from another device.
     const PASSTHROUGH_IMAGE_PROBE: &[(&str, &str)] = &[
         ("image/svg+xml", "public.svg-image"),
         ("image/gif", "com.compuserve.gif"),
+        ("image/webp", "org.webmproject.webp"),
         ("image/jpeg", "public.jpeg"),
     ];
 
@@ -781,14 +915,21 @@ mod macos {
     }
 
     /// Write passthrough-image bytes verbatim under the appropriate macOS
-    /// UTI. Atomic single-format publish via `clearContents` +
-    /// `declareTypes:owner:` + `setData:forType:`.
-    pub fn write_passthrough_image(mime: &str, bytes: &[u8]) -> Result<(), String> {
+    /// UTI, with a `public.png` companion when one is given. Atomic publish
+    /// via `clearContents` + `declareTypes:owner:` + `setData:forType:`;
+    /// the source UTI is declared first so it stays the canonical type.
+    pub fn write_passthrough_image(mime: &str, bytes: &[u8], companions: &[ClipboardFormat]) -> Result<(), String> {
         let uti = passthrough_image_uti(mime)
             .ok_or_else(|| format!("unsupported passthrough MIME on macOS: {}", mime))?;
+        let png = companions.iter().find(|f| f.mime_type == "image/png");
         let pb = pasteboard();
         let uti_ns = NSString::from_str(uti);
-        let types_array = NSArray::from_retained_slice(&[uti_ns.clone()]);
+        let png_ns = NSString::from_str("public.png");
+        let mut types_vec = vec![uti_ns.clone()];
+        if png.is_some() {
+            types_vec.push(png_ns.clone());
+        }
+        let types_array = NSArray::from_retained_slice(&types_vec);
         unsafe {
             pb.clearContents();
             pb.declareTypes_owner(&types_array, None);
@@ -801,6 +942,12 @@ mod macos {
                 mime
             ));
         }
+        if let Some(png) = png {
+            let data = NSData::with_bytes(&png.raw_bytes()?);
+            if !pb.setData_forType(Some(&data), &png_ns) {
+                tracing::warn!("setData:forType: returned false for public.png companion");
+            }
+        }
         Ok(())
     }
 }
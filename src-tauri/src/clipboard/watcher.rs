@@ -9,7 +9,7 @@ use super::{dbus_clipboard, set_backend, ClipboardBackend};
 use crate::state::AppState;
 use crate::transport::Transport;
 use std::sync::Arc;
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 use tokio::sync::Notify;
 
 const EXTENSION_UUID: &str = "clustercut@keithvassallo.com";
@@ -24,8 +24,7 @@ const SHELL_EXT_IFACE: &str = "org.gnome.Shell.Extensions";
 // NOT on every launch (cold start often starts Degraded before the extension's
 // D-Bus is ready, then promotes — which must stay silent).
 fn announced_down_marker(app: &AppHandle) -> Option<std::path::PathBuf> {
-    app.path()
-        .app_config_dir()
+    crate::profile::config_dir(app)
         .ok()
         .map(|d| d.join("extension_sync_announced_down"))
 }
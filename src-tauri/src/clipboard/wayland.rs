@@ -3,6 +3,7 @@
 ///
 /// Uses polling with get_contents (not subprocess spawning), so no flickering.
 use super::common::{self, ClipboardContent};
+use super::convert;
 use crate::protocol::{ClipboardBlob, ClipboardFormat};
 use crate::state::AppState;
 use crate::transport::Transport;
@@ -26,25 +27,33 @@ const MAX_CLIPBOARD_IMAGE_READ_BYTES: u64 = 500 * 1024 * 1024;
 
 /// Raster image MIME types we know how to decode, in preference order.
 /// PNG first because it's lossless and the most commonly offered by browsers.
-/// `image/gif` and `image/jpeg` are intentionally absent — both go through
-/// the passthrough path below (GIF to preserve animation, JPEG to avoid
-/// the 5-30× wire-size inflation that PNG re-encoding of photo content
-/// causes).
-const IMAGE_MIME_PRIORITY: &[&str] = &[
-    "image/png",
-    "image/webp",
-    "image/bmp",
-    "image/x-bmp",
-    "image/tiff",
-];
+/// GIF, JPEG, WebP and TIFF are intentionally absent — they go through the
+/// passthrough path below (GIF and WebP to preserve animation, JPEG and
+/// WebP to avoid the 5-30× wire-size inflation that PNG re-encoding of
+/// photo content causes, TIFF to keep pages and DPI).
+const IMAGE_MIME_PRIORITY: &[&str] = &["image/png", "image/bmp", "image/x-bmp"];
 
 /// Passthrough image MIMEs — checked *before* the raster `IMAGE_MIME_PRIORITY`
 /// so a source app that offers both passthrough and raster representations
 /// (e.g. Inkscape: image/svg+xml + a rasterised image/png fallback) gives
 /// the higher-fidelity passthrough representation. Bytes go on the wire
-/// verbatim; receivers re-stock under the same MIME.
-const PASSTHROUGH_IMAGE_MIME_PRIORITY: &[&str] =
-    &["image/svg+xml", "image/gif", "image/jpeg"];
+/// verbatim; receivers re-stock under the same MIME or convert locally.
+const PASSTHROUGH_IMAGE_MIME_PRIORITY: &[&str] = &[
+    "image/svg+xml",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "image/heic",
+    "image/jpeg",
+    "image/tiff",
+];
+
+/// Passthrough MIMEs that lose to an offered `image/png`. GIMP and
+/// LibreOffice offer TIFF next to PNG for every copy; it's the PNG that is
+/// the image there, and the TIFF is several times larger. AVIF and HEIC
+/// can't be converted for receivers that don't take them, so a PNG, where
+/// there is one, reaches everyone.
+const PNG_PREFERRED_MIMES: &[&str] = &["image/tiff", "image/avif", "image/heic"];
 
 /// Rich-text MIME types we relay verbatim alongside the plain text. These
 /// carry formatted content (HTML/RTF) that destination apps can pick up
@@ -150,16 +159,36 @@ fn read_clipboard_files() -> Option<Vec<String>> {
     }
 }
 
-/// Probe for passthrough image formats (SVG, animated GIF) and pass the
-/// bytes through verbatim without raster decode/re-encode. Called from
-/// `read_clipboard_image` before the raster MIME loop, so passthrough
+/// Read a small text MIME in full, or None if it isn't offered.
+fn read_small_text(mime: &str) -> Option<String> {
+    let (pipe, _) = get_contents(ClipboardType::Regular, Seat::Unspecified, PasteMimeType::Specific(mime)).ok()?;
+    let mut data = String::new();
+    pipe.take(64 * 1024).read_to_string(&mut data).ok()?;
+    Some(data)
+}
+
+/// Whether the file selection is a cut: Nautilus and other GTK file
+/// managers put "cut" on the first line of `x-special/gnome-copied-files`,
+/// Dolphin offers `application/x-kde-cutselection` = "1".
+fn read_clipboard_files_cut() -> bool {
+    if let Some(gnome) = read_small_text("x-special/gnome-copied-files") {
+        return gnome.lines().next().map(str::trim) == Some("cut");
+    }
+    read_small_text("application/x-kde-cutselection").map_or(false, |kde| kde.trim_end_matches('\0').trim() == "1")
+}
+
+/// Probe for passthrough image formats (SVG, animated GIF, WebP, …) and
+/// pass the bytes through verbatim without raster decode/re-encode. Called
+/// from `read_clipboard_image` before the raster MIME loop, so passthrough
 /// representations win over rasterised companions when both are offered.
 fn read_clipboard_passthrough_image(
     offered: &std::collections::HashSet<String>,
 ) -> Option<ClipboardBlob> {
+    let has_png = offered.contains("image/png");
     let mime = PASSTHROUGH_IMAGE_MIME_PRIORITY
         .iter()
         .copied()
+        .filter(|m| !(has_png && PNG_PREFERRED_MIMES.contains(m)))
         .find(|m| offered.contains(*m))?;
 
     let mut pipe = match get_contents(
@@ -340,7 +369,7 @@ fn read_clipboard_rich() -> Option<(String, Vec<ClipboardFormat>)> {
 
 fn read_clipboard() -> ClipboardContent {
     if let Some(files) = read_clipboard_files() {
-        return ClipboardContent::Files(files);
+        return ClipboardContent::Files { paths: files, cut: read_clipboard_files_cut() };
     }
     if let Some(blob) = read_clipboard_image() {
         return ClipboardContent::Image(blob);
@@ -361,20 +390,7 @@ fn write_text(_app: &AppHandle, text: String) -> Result<(), String> {
 }
 
 fn write_files(_app: &AppHandle, files: Vec<String>) -> Result<(), String> {
-    let uris: Vec<String> = files
-        .into_iter()
-        .filter_map(|p| {
-            let path = std::path::Path::new(&p);
-            let abs_path = if path.is_absolute() {
-                path.to_path_buf()
-            } else {
-                std::env::current_dir().ok()?.join(path)
-            };
-            url::Url::from_file_path(abs_path)
-                .ok()
-                .map(|u| u.to_string())
-        })
-        .collect();
+    let uris = convert::file_uris(&files);
 
     if uris.is_empty() {
         return Err("No valid file paths".to_string());
@@ -383,7 +399,8 @@ fn write_files(_app: &AppHandle, files: Vec<String>) -> Result<(), String> {
     // Advertise both text/uri-list and x-special/gnome-copied-files so GTK file
     // managers (Nautilus) and others recognise a file paste rather than a text
     // paste. The `copy` field in x-special/gnome-copied-files distinguishes a
-    // copy from a cut — we always use "copy".
+    // copy from a cut — we always use "copy". The plain paths go last for
+    // terminals and editors, as Nautilus does.
     let uri_list = format!("{}\n", uris.join("\n"));
     let gnome_copied = format!("copy\n{}", uris.join("\n"));
 
@@ -396,6 +413,10 @@ fn write_files(_app: &AppHandle, files: Vec<String>) -> Result<(), String> {
             source: Source::Bytes(gnome_copied.into_bytes().into()),
             mime_type: CopyMimeType::Specific("x-special/gnome-copied-files".to_string()),
         },
+        MimeSource {
+            source: Source::Bytes(convert::paths_as_text(&files).into_bytes().into()),
+            mime_type: CopyMimeType::Text,
+        },
     ];
 
     let opts = CopyOptions::new();
@@ -403,14 +424,24 @@ fn write_files(_app: &AppHandle, files: Vec<String>) -> Result<(), String> {
         .map_err(|e| format!("wl-clipboard-rs copy files failed: {}", e))
 }
 
+/// Write the image under its own MIME, followed by the PNG/BMP companions
+/// `convert::image_companions` derives from it, so apps that don't read the
+/// original format still get a picture.
 fn write_image(_app: &AppHandle, blob: &ClipboardBlob) -> Result<(), String> {
     let bytes = blob.raw_bytes()?;
+    let mut sources = vec![MimeSource {
+        source: Source::Bytes(bytes.into()),
+        mime_type: CopyMimeType::Specific(blob.mime_type.clone()),
+    }];
+    for f in convert::image_companions(blob, true) {
+        sources.push(MimeSource {
+            source: Source::Bytes(f.raw_bytes()?.into()),
+            mime_type: CopyMimeType::Specific(f.mime_type),
+        });
+    }
     let opts = CopyOptions::new();
-    opts.copy(
-        Source::Bytes(bytes.into()),
-        CopyMimeType::Specific(blob.mime_type.clone()),
-    )
-    .map_err(|e| format!("wl-clipboard-rs copy image failed: {}", e))
+    opts.copy_multi(sources)
+        .map_err(|e| format!("wl-clipboard-rs copy image failed: {}", e))
 }
 
 /// Write plain text + alternate format representations (text/html, text/rtf, …)
@@ -470,6 +501,8 @@ fn clipboard_probe_mime(offered: &std::collections::HashSet<String>) -> Option<S
         "image/svg+xml",
         "image/gif",
         "image/webp",
+        "image/avif",
+        "image/heic",
         "image/bmp",
         "image/tiff",
         "text/html",
@@ -0,0 +1,528 @@
+//! Membership in more than one cluster at a time.
+//!
+//! The cluster a device founded (or first joined) stays its *primary*
+//! cluster: id, name and PIN live in `AppState::{cluster_id, network_name,
+//! network_pin}` as before, and the single-cluster paths are unchanged.
+//! Every further cluster joined is a [`ClusterContext`] in
+//! `AppState::cluster_contexts`, persisted to `clusters.json`, with its own
+//! PIN, member list and mDNS advertisement.
+//!
+//! `known_peers` remains the one trust store, since mTLS has to pin every
+//! member whichever cluster it is in. A device belongs to at most one of our
+//! clusters: a known peer listed in no context is a primary member. What a
+//! member sees of us follows from that. `ClusterInfo` replies list only its
+//! own cluster's members, and a pairing initiator names the cluster it saw
+//! advertised so the responder answers with that cluster's PIN.
+//!
+//! Routing rules (`AppSettings::cluster_routing`) decide whether copies go
+//! to, and are accepted from, each cluster. They narrow each member's own
+//! `PeerPolicy` in `AppState::policy_for`, so every send and receive path
+//! honours them without knowing about clusters.
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::peer::Peer;
+use crate::protocol::ClusterInfo;
+use crate::state::AppState;
+
+/// An additional cluster this device is a member of.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterContext {
+    pub cluster_id: String,
+    pub name: String,
+    /// The cluster's PIN, used to answer pairing requests for it. Empty when
+    /// we joined with an invite token, in which case only the other members
+    /// can bring new devices in.
+    #[serde(default)]
+    pub pin: String,
+    /// Device ids of the members we know.
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Whether copies go to and are accepted from one of our clusters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterRoute {
+    #[serde(default = "default_true")]
+    pub send: bool,
+    #[serde(default = "default_true")]
+    pub receive: bool,
+}
+
+impl Default for ClusterRoute {
+    fn default() -> Self {
+        Self { send: true, receive: true }
+    }
+}
+
+/// A cluster as listed in Settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterView {
+    pub cluster_id: String,
+    pub name: String,
+    pub is_primary: bool,
+    pub members: usize,
+    pub route: ClusterRoute,
+}
+
+fn context_index(contexts: &[ClusterContext], device_id: &str) -> Option<usize> {
+    contexts.iter().position(|c| c.members.iter().any(|m| m == device_id))
+}
+
+/// The additional cluster `device_id` is a member of. None for primary
+/// members and strangers.
+pub(crate) fn context_of(state: &AppState, device_id: &str) -> Option<ClusterContext> {
+    let contexts = state.cluster_contexts.lock().unwrap();
+    context_index(&contexts, device_id).map(|i| contexts[i].clone())
+}
+
+/// Id of the cluster `device_id` counts towards: its context's, or the
+/// primary cluster's.
+pub(crate) fn cluster_id_of(state: &AppState, device_id: &str) -> String {
+    match context_of(state, device_id) {
+        Some(ctx) => ctx.cluster_id,
+        None => state.cluster_id.lock().unwrap().clone(),
+    }
+}
+
+pub(crate) fn route_for(state: &AppState, device_id: &str) -> ClusterRoute {
+    let cluster_id = cluster_id_of(state, device_id);
+    state
+        .settings
+        .lock()
+        .unwrap()
+        .cluster_routing
+        .get(&cluster_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub(crate) fn is_local_cluster_id(state: &AppState, cluster_id: &str) -> bool {
+    if cluster_id.is_empty() {
+        return false;
+    }
+    *state.cluster_id.lock().unwrap() == cluster_id
+        || state.cluster_contexts.lock().unwrap().iter().any(|c| c.cluster_id == cluster_id)
+}
+
+/// Whether a `ClusterInfo` for `cluster_id` from `sender_id` (the device its
+/// connection authenticated as) may be merged: only the sender's own cluster
+/// counts. Any of our cluster ids would let a member of one of them bring
+/// devices into another, with the trust that cluster gets.
+pub(crate) fn accepts_cluster_info(state: &AppState, sender_id: Option<&str>, cluster_id: &str) -> bool {
+    sender_id.map_or(false, |id| is_local_cluster_id(state, cluster_id) && cluster_id_of(state, id) == cluster_id)
+}
+
+/// Whether `sender_id` may gossip about `subject_id`: itself, a device we
+/// don't know yet (which then joins the sender's cluster), or a member of the
+/// cluster it shares with us. Never a member of another of our clusters,
+/// whose record the gossip would overwrite, fingerprint included.
+pub(crate) fn may_gossip_about(state: &AppState, sender_id: &str, subject_id: &str) -> bool {
+    if sender_id == subject_id || !state.known_peers.lock().unwrap().contains_key(subject_id) {
+        return true;
+    }
+    cluster_id_of(state, sender_id) == cluster_id_of(state, subject_id)
+}
+
+pub(crate) fn is_local_cluster_name(state: &AppState, name: &str) -> bool {
+    *state.network_name.lock().unwrap() == name
+        || state.cluster_contexts.lock().unwrap().iter().any(|c| c.name == name)
+}
+
+/// Name of the cluster a known `device_id` shares with us. A device in
+/// several of our clusters' neighbourhoods advertises one instance per
+/// cluster, so for members this, not whichever TXT record resolved last, is
+/// the name to show. Locks `known_peers`; don't call while holding it.
+pub(crate) fn member_cluster_name(state: &AppState, device_id: &str) -> Option<String> {
+    if let Some(ctx) = context_of(state, device_id) {
+        return Some(ctx.name);
+    }
+    state
+        .known_peers
+        .lock()
+        .unwrap()
+        .contains_key(device_id)
+        .then(|| state.network_name.lock().unwrap().clone())
+}
+
+/// Responder side: the context an initiator asked to join (None for the
+/// primary cluster) and the PIN to run SPAKE2 with. An unknown name, or a
+/// context we hold no PIN for, falls back to the primary cluster.
+pub(crate) fn pairing_target(state: &AppState, requested: Option<&str>) -> (Option<String>, String) {
+    if let Some(name) = requested {
+        let contexts = state.cluster_contexts.lock().unwrap();
+        if let Some(ctx) = contexts.iter().find(|c| c.name == name && !c.pin.is_empty()) {
+            return (Some(ctx.cluster_id.clone()), ctx.pin.clone());
+        }
+    }
+    (None, state.network_pin.lock().unwrap().clone())
+}
+
+/// Take `device_id` out of every additional cluster, making it a primary
+/// member if it is still known. Returns whether anything changed.
+pub(crate) fn remove_member(state: &AppState, app_handle: &tauri::AppHandle, device_id: &str) -> bool {
+    let mut contexts = state.cluster_contexts.lock().unwrap();
+    let mut changed = false;
+    for ctx in contexts.iter_mut() {
+        let before = ctx.members.len();
+        ctx.members.retain(|m| m != device_id);
+        changed |= ctx.members.len() != before;
+    }
+    if changed {
+        crate::storage::save_cluster_contexts(app_handle, &contexts);
+    }
+    changed
+}
+
+/// Record `device_id` as a member of additional cluster `cluster_id`, or of
+/// the primary cluster when `cluster_id` is None.
+pub(crate) fn assign_member(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    cluster_id: Option<&str>,
+    device_id: &str,
+) {
+    let Some(cluster_id) = cluster_id else {
+        remove_member(state, app_handle, device_id);
+        return;
+    };
+    let mut contexts = state.cluster_contexts.lock().unwrap();
+    for ctx in contexts.iter_mut() {
+        if ctx.cluster_id == cluster_id {
+            if !ctx.members.iter().any(|m| m == device_id) {
+                ctx.members.push(device_id.to_string());
+            }
+        } else {
+            ctx.members.retain(|m| m != device_id);
+        }
+    }
+    crate::storage::save_cluster_contexts(app_handle, &contexts);
+}
+
+/// Initiator side: whether the cluster a responder just described should
+/// become an additional context rather than replace our primary cluster.
+/// A device with no primary members yet adopts it as primary, which is the
+/// single-cluster behaviour.
+pub(crate) fn should_join_as_context(state: &AppState, cluster_id: &str) -> bool {
+    if *state.cluster_id.lock().unwrap() == cluster_id {
+        return false;
+    }
+    let contexts = state.cluster_contexts.lock().unwrap().clone();
+    if contexts.iter().any(|c| c.cluster_id == cluster_id) {
+        return true;
+    }
+    state
+        .known_peers
+        .lock()
+        .unwrap()
+        .values()
+        .any(|p| p.fingerprint.is_some() && !p.id.starts_with("manual-") && context_index(&contexts, &p.id).is_none())
+}
+
+/// Create or refresh the context for `cluster_id`. `pin` is None when we
+/// joined with an invite token; an existing PIN is kept then.
+pub(crate) fn upsert_context(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    cluster_id: &str,
+    name: &str,
+    pin: Option<String>,
+) {
+    let mut contexts = state.cluster_contexts.lock().unwrap();
+    match contexts.iter_mut().find(|c| c.cluster_id == cluster_id) {
+        Some(ctx) => {
+            ctx.name = name.to_string();
+            if let Some(pin) = pin {
+                ctx.pin = pin;
+            }
+        }
+        None => contexts.push(ClusterContext {
+            cluster_id: cluster_id.to_string(),
+            name: name.to_string(),
+            pin: pin.unwrap_or_default(),
+            members: Vec::new(),
+        }),
+    }
+    crate::storage::save_cluster_contexts(app_handle, &contexts);
+}
+
+/// `ClusterInfo` describing an additional cluster, for one of its members.
+/// Contexts don't run the cluster-name register, so the version is 0. They
+/// are always provisioned: every member shares the founder's PIN.
+pub(crate) fn context_cluster_info(state: &AppState, ctx: &ClusterContext) -> ClusterInfo {
+    let known_peers = state
+        .known_peers
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|p| ctx.members.contains(&p.id))
+        .cloned()
+        .collect();
+    let revocations = state
+        .revocations
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| ctx.members.contains(&r.device_id))
+        .cloned()
+        .collect();
+    let join_restrictions = state
+        .join_restrictions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| ctx.members.contains(&r.device_id))
+        .cloned()
+        .collect();
+    ClusterInfo {
+        cluster_id: ctx.cluster_id.clone(),
+        known_peers,
+        network_name: ctx.name.clone(),
+        network_name_version: 0,
+        network_name_origin: String::new(),
+        cluster_mode: "provisioned".to_string(),
+        revocations,
+        join_restrictions,
+    }
+}
+
+/// Drop members of additional clusters from a primary-cluster peer list.
+pub(crate) fn retain_primary_members(state: &AppState, peers: &mut Vec<Peer>) {
+    let contexts = state.cluster_contexts.lock().unwrap();
+    peers.retain(|p| context_index(&contexts, &p.id).is_none());
+}
+
+/// Advertise every additional cluster. Called wherever the primary
+/// advertisement is (re-)registered.
+pub(crate) fn register_contexts(
+    discovery: &mut crate::discovery::Discovery,
+    state: &AppState,
+    device_id: &str,
+    port: u16,
+) {
+    let contexts = state.cluster_contexts.lock().unwrap().clone();
+    for ctx in contexts {
+        if let Err(e) = discovery.register_context(device_id, &ctx.cluster_id, &ctx.name, port) {
+            tracing::error!("Failed to advertise cluster '{}': {}", ctx.name, e);
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn list_clusters(state: tauri::State<'_, AppState>) -> Vec<ClusterView> {
+    let routing = state.settings.lock().unwrap().cluster_routing.clone();
+    let contexts = state.cluster_contexts.lock().unwrap().clone();
+    let primary_id = state.cluster_id.lock().unwrap().clone();
+    let primary_members = state
+        .known_peers
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|p| !p.id.starts_with("manual-") && context_index(&contexts, &p.id).is_none())
+        .count();
+    let mut views = vec![ClusterView {
+        route: routing.get(&primary_id).copied().unwrap_or_default(),
+        cluster_id: primary_id,
+        name: state.network_name.lock().unwrap().clone(),
+        is_primary: true,
+        members: primary_members,
+    }];
+    views.extend(contexts.into_iter().map(|ctx| ClusterView {
+        route: routing.get(&ctx.cluster_id).copied().unwrap_or_default(),
+        members: ctx.members.len(),
+        cluster_id: ctx.cluster_id,
+        name: ctx.name,
+        is_primary: false,
+    }));
+    views
+}
+
+#[tauri::command]
+pub(crate) fn set_cluster_route(
+    cluster_id: String,
+    route: ClusterRoute,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    if !is_local_cluster_id(&state, &cluster_id) {
+        return Err("Not a member of that cluster".to_string());
+    }
+    let snapshot = {
+        let mut settings = state.settings.lock().unwrap();
+        if route == ClusterRoute::default() {
+            settings.cluster_routing.remove(&cluster_id);
+        } else {
+            settings.cluster_routing.insert(cluster_id, route);
+        }
+        settings.clone()
+    };
+    crate::storage::save_settings(&app_handle, &snapshot);
+    let _ = app_handle.emit("settings-changed", snapshot);
+    Ok(())
+}
+
+/// Drop additional cluster `cluster_id`: forget its members, stop
+/// advertising it and clear its routing rule.
+pub(crate) fn forget_context(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    cluster_id: &str,
+) -> Result<ClusterContext, String> {
+    let ctx = {
+        let mut contexts = state.cluster_contexts.lock().unwrap();
+        let idx = contexts
+            .iter()
+            .position(|c| c.cluster_id == cluster_id)
+            .ok_or("Not a member of that cluster as an additional cluster")?;
+        let ctx = contexts.remove(idx);
+        crate::storage::save_cluster_contexts(app_handle, &contexts);
+        ctx
+    };
+    {
+        let mut kp = state.known_peers.lock().unwrap();
+        let mut peers = state.peers.lock().unwrap();
+        for id in &ctx.members {
+            kp.remove(id);
+            peers.remove(id);
+        }
+        crate::storage::save_known_peers(app_handle, &kp);
+    }
+    for id in &ctx.members {
+        let _ = app_handle.emit("peer-remove", id);
+    }
+    if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
+        discovery.unregister_context(&ctx.cluster_id);
+    }
+    let snapshot = {
+        let mut settings = state.settings.lock().unwrap();
+        settings.cluster_routing.remove(&ctx.cluster_id).map(|_| settings.clone())
+    };
+    if let Some(snapshot) = snapshot {
+        crate::storage::save_settings(app_handle, &snapshot);
+    }
+    tracing::info!("Left cluster '{}' ({}); forgot {} member(s).", ctx.name, ctx.cluster_id, ctx.members.len());
+    let _ = app_handle.emit("network-update", ());
+    Ok(ctx)
+}
+
+/// Leave an additional cluster. The primary cluster is left through
+/// `leave_network` as before.
+#[tauri::command]
+pub(crate) fn leave_cluster(
+    cluster_id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    forget_context(&state, &app_handle, &cluster_id).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(id: &str, name: &str, pin: &str, members: &[&str]) -> ClusterContext {
+        ClusterContext {
+            cluster_id: id.to_string(),
+            name: name.to_string(),
+            pin: pin.to_string(),
+            members: members.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn pairing_target_picks_the_named_context() {
+        let state = AppState::new();
+        *state.network_pin.lock().unwrap() = "home12".to_string();
+        state.cluster_contexts.lock().unwrap().extend([
+            context("work-id", "Work", "work34", &[]),
+            context("token-id", "Joined By Token", "", &[]),
+        ]);
+        assert_eq!(pairing_target(&state, Some("Work")), (Some("work-id".to_string()), "work34".to_string()));
+        assert_eq!(pairing_target(&state, None), (None, "home12".to_string()));
+        // Unknown name, or a context we can't answer for: the primary PIN.
+        assert_eq!(pairing_target(&state, Some("Elsewhere")).0, None);
+        assert_eq!(pairing_target(&state, Some("Joined By Token")).0, None);
+    }
+
+    #[test]
+    fn routing_defaults_open_and_follows_membership() {
+        let state = AppState::new();
+        *state.cluster_id.lock().unwrap() = "home-id".to_string();
+        state.cluster_contexts.lock().unwrap().push(context("work-id", "Work", "work34", &["laptop"]));
+        state
+            .settings
+            .lock()
+            .unwrap()
+            .cluster_routing
+            .insert("work-id".to_string(), ClusterRoute { send: false, receive: true });
+        assert_eq!(route_for(&state, "laptop"), ClusterRoute { send: false, receive: true });
+        assert_eq!(route_for(&state, "phone"), ClusterRoute::default());
+        assert_eq!(cluster_id_of(&state, "phone"), "home-id");
+    }
+
+    fn known(state: &AppState, id: &str) {
+        let peer = Peer {
+            id: id.to_string(),
+            ip: "192.168.96.7".parse().unwrap(),
+            port: 4654,
+            hostname: id.to_string(),
+            last_seen: 0,
+            is_trusted: true,
+            is_manual: false,
+            network_name: None,
+            signature: None,
+            fingerprint: Some(id.as_bytes().to_vec()),
+            protocol_version: None,
+            addresses: Vec::new(),
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
+        };
+        state.known_peers.lock().unwrap().insert(id.to_string(), peer);
+    }
+
+    #[test]
+    fn members_of_one_cluster_cant_speak_for_another() {
+        let state = AppState::new();
+        *state.cluster_id.lock().unwrap() = "home-id".to_string();
+        state.cluster_contexts.lock().unwrap().push(context("work-id", "Work", "", &["colleague", "printer"]));
+        for id in ["colleague", "printer", "phone"] {
+            known(&state, id);
+        }
+
+        // A Work member injecting into Home, by ClusterInfo or by gossip.
+        assert!(!accepts_cluster_info(&state, Some("colleague"), "home-id"));
+        assert!(!may_gossip_about(&state, "colleague", "phone"));
+        // And the other way round.
+        assert!(!accepts_cluster_info(&state, Some("phone"), "work-id"));
+        assert!(!may_gossip_about(&state, "phone", "printer"));
+
+        assert!(accepts_cluster_info(&state, Some("colleague"), "work-id"));
+        assert!(accepts_cluster_info(&state, Some("phone"), "home-id"));
+        assert!(!accepts_cluster_info(&state, None, "home-id"));
+        assert!(!accepts_cluster_info(&state, Some("phone"), "cafe-id"));
+        assert!(may_gossip_about(&state, "colleague", "printer"));
+        assert!(may_gossip_about(&state, "colleague", "colleague"));
+        assert!(may_gossip_about(&state, "colleague", "newcomer"));
+    }
+
+    #[test]
+    fn local_cluster_checks_cover_every_context() {
+        let state = AppState::new();
+        *state.cluster_id.lock().unwrap() = "home-id".to_string();
+        *state.network_name.lock().unwrap() = "Home".to_string();
+        state.cluster_contexts.lock().unwrap().push(context("work-id", "Work", "", &[]));
+        assert!(is_local_cluster_id(&state, "work-id"));
+        assert!(is_local_cluster_id(&state, "home-id"));
+        assert!(!is_local_cluster_id(&state, ""));
+        assert!(is_local_cluster_name(&state, "Work"));
+        assert!(!is_local_cluster_name(&state, "Cafe"));
+    }
+}
@@ -33,6 +33,9 @@ pub(crate) async fn send_clipboard(
         files: None,
         blob: None,
         formats: None,
+        ttl_secs: None,
+        seq: Some(state.next_clipboard_seq()),
+        cut: false,
     };
 
     // Emit local event so history updates
@@ -40,11 +43,14 @@ pub(crate) async fn send_clipboard(
 
     // Send (mTLS provides confidentiality + sender auth; no app-layer
     // encryption needed since v0.3 dropped cluster_key).
-    let msg = Message::Clipboard(payload_obj);
+    let msg = Message::Clipboard(payload_obj.clone());
     let data = serde_json::to_vec(&msg).map_err(|e| e.to_string())?;
 
     let peers = state.get_peers();
     for p in peers.values() {
+        if !state.policy_for(&p.id).allows_outgoing(&payload_obj) {
+            continue;
+        }
         let addr = std::net::SocketAddr::new(p.ip, p.port);
         let transport_clone = (*transport).clone();
         let data_vec = data.clone();
@@ -251,7 +257,9 @@ pub(crate) async fn confirm_pending_clipboard(
             }
         }
 
+        use crate::clipboard::common::{arm_ephemeral, ClipboardContent};
         if let Some(blob) = payload.blob.clone() {
+            arm_ephemeral(&app_handle, payload.ttl_secs, ClipboardContent::Image(blob.clone()));
             crate::clipboard::set_clipboard_image(&app_handle, blob);
         } else if let Some(formats) = payload
             .formats
@@ -259,8 +267,14 @@ pub(crate) async fn confirm_pending_clipboard(
             .filter(|fs| !fs.is_empty())
             .cloned()
         {
+            arm_ephemeral(
+                &app_handle,
+                payload.ttl_secs,
+                ClipboardContent::Rich { text: payload.text.clone(), formats: formats.clone() },
+            );
             crate::clipboard::set_clipboard_rich(&app_handle, payload.text.clone(), formats);
         } else {
+            arm_ephemeral(&app_handle, payload.ttl_secs, ClipboardContent::Text(payload.text.clone()));
             crate::clipboard::set_clipboard(&app_handle, payload.text.clone());
         }
 
@@ -317,6 +331,17 @@ pub(crate) async fn promote_pending_rich(
         formats.iter().map(|f| f.mime_type.as_str()).collect::<Vec<_>>().join(", ")
     );
 
+    // An ephemeral item stays ephemeral across the promotion. Re-arming starts
+    // a fresh TTL and inherits the original restore point from the plain-text
+    // hold, so expiry never "restores" the secret itself.
+    crate::clipboard::common::arm_ephemeral(
+        &app_handle,
+        payload.ttl_secs,
+        crate::clipboard::common::ClipboardContent::Rich {
+            text: payload.text.clone(),
+            formats: formats.clone(),
+        },
+    );
     crate::clipboard::set_clipboard_rich(&app_handle, payload.text.clone(), formats);
     crate::clipboard::common::record_and_emit(&app_handle, &state, "clipboard-change", &payload);
     Ok(())
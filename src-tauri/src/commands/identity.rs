@@ -64,7 +64,7 @@ fn apply_local_rename(
         .as_ref()
         .and_then(|t| t.local_addr().ok())
         .map(|a| a.port())
-        .unwrap_or(4654);
+        .unwrap_or(crate::net_util::DEFAULT_PORT);
     if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
         let _ = discovery.register(&device_id, name, port);
     }
@@ -108,3 +108,14 @@ pub(crate) fn regenerate_network_identity(
 
     apply_local_rename(&name, &state, &transport, &app_handle);
 }
+
+/// Replace this device's TLS cert and hand the new fingerprint to every
+/// paired peer (see key_rotation.rs). Returns which peers couldn't be told.
+#[tauri::command]
+pub(crate) async fn rotate_device_cert(
+    state: State<'_, AppState>,
+    transport: State<'_, crate::transport::Transport>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::key_rotation::RotationSummary, String> {
+    crate::key_rotation::rotate_device_cert(&app_handle, &state, &transport).await
+}
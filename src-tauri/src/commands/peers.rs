@@ -1,10 +1,10 @@
 //! Peer management commands.
 
-use crate::peer::{Peer, PeerView};
+use crate::peer::{Peer, PeerPolicy, PeerView};
 use crate::state::AppState;
 use crate::{net_util, perform_factory_reset};
 use crate::protocol::Message;
-use crate::storage::save_known_peers;
+use crate::storage::{save_known_peers, save_peer_policies, save_revocations};
 use crate::transport::Transport;
 use ipnetwork::IpNetwork;
 use tauri::{Emitter, State};
@@ -22,6 +22,30 @@ pub(crate) fn get_known_peers(state: State<AppState>) -> std::collections::HashM
     state.known_peers.lock().unwrap().clone()
 }
 
+/// Every stored per-peer policy, keyed by device id. Peers without an entry
+/// use `PeerPolicy::default()`.
+#[tauri::command]
+pub(crate) fn get_peer_policies(state: State<AppState>) -> std::collections::HashMap<String, PeerPolicy> {
+    state.peer_policies.lock().unwrap().clone()
+}
+
+/// Store (or, when it equals the default, drop) the policy for `peer_id`.
+#[tauri::command]
+pub(crate) fn set_peer_policy(
+    peer_id: String,
+    policy: PeerPolicy,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) {
+    let mut policies = state.peer_policies.lock().unwrap();
+    if policy == PeerPolicy::default() {
+        policies.remove(&peer_id);
+    } else {
+        policies.insert(peer_id, policy);
+    }
+    save_peer_policies(&app_handle, &policies);
+}
+
 /// List of peers loaded from `known_peers.json` without a stored cert
 /// fingerprint. Returns an empty Vec for clean v0.3 installs. The frontend
 /// reads this on mount to decide whether to show the "please re-pair"
@@ -59,9 +83,9 @@ pub(crate) fn expects_remote_manual_peers(state: State<AppState>) -> bool {
 #[tauri::command]
 pub(crate) fn get_listening_port(state: State<'_, AppState>) -> u16 {
     if let Some(transport) = state.transport.lock().unwrap().as_ref() {
-        transport.local_addr().map(|a| a.port()).unwrap_or(4654)
+        transport.local_addr().map(|a| a.port()).unwrap_or(net_util::DEFAULT_PORT)
     } else {
-        4654
+        net_util::DEFAULT_PORT
     }
 }
 
@@ -82,7 +106,7 @@ pub(crate) fn peer_already_paired(
 ) -> bool {
     peers
         .values()
-        .any(|p| p.is_trusted && p.fingerprint.is_some() && p.ip == ip)
+        .any(|p| p.is_trusted && p.fingerprint.is_some() && p.has_ip(ip))
 }
 
 /// Outcome of an "Add Remote" attempt for a single IP. `Connected` means we
@@ -105,22 +129,17 @@ pub(crate) async fn add_remote_peer(
     transport: State<'_, Transport>,
     app_handle: tauri::AppHandle,
 ) -> Result<AddRemoteOutcome, String> {
-    // Parse as IP or IP:PORT (default 4654), matching add_manual_peer's single-IP branch.
-    let (addr, port) = if let Ok(sock) = ip.parse::<std::net::SocketAddr>() {
-        (sock.ip(), sock.port())
-    } else if let Ok(ip_addr) = ip.parse::<std::net::IpAddr>() {
-        (ip_addr, 4654)
-    } else {
-        return Err("Invalid Format. Use IP or IP:PORT.".to_string());
-    };
+    // Parse as IP or IP:PORT (default port), matching add_manual_peer's single-IP branch.
+    let addr = net_util::parse_peer_addr(&ip, net_util::DEFAULT_PORT)
+        .map_err(|e| format!("Invalid Format ({}). Use IP, IP:PORT or [IPv6]:PORT.", e))?;
 
     let already_paired = {
         let peers = state.known_peers.lock().unwrap();
-        peer_already_paired(&peers, addr)
+        peer_already_paired(&peers, addr.ip())
     };
 
     if already_paired {
-        net_util::probe_ip(addr, port, (*state).clone(), (*transport).clone(), app_handle, true).await;
+        net_util::probe_ip(addr, (*state).clone(), (*transport).clone(), app_handle, true).await;
         Ok(AddRemoteOutcome::Connected)
     } else {
         Ok(AddRemoteOutcome::NeedsPairing)
@@ -137,44 +156,16 @@ pub(crate) async fn add_manual_peer(
 
     // 1. Try parsing as CIDR
     if let Ok(net) = ip.parse::<IpNetwork>() {
-        tracing::info!("Scanning range: {}", net);
-        let ips: Vec<std::net::IpAddr> = net.iter().collect();
-
-        // Scan in small batches with concurrency
-        let batch_size = 50;
-        for chunk in ips.chunks(batch_size) {
-            let mut tasks = Vec::new();
-            for ip_addr in chunk {
-                 let s = (*state).clone();
-                 let t = (*transport).clone();
-                 let a = app_handle.clone();
-                 let addr = *ip_addr;
-
-                 // Skip own IP
-                 if let Ok(local) = t.local_addr() {
-                     if local.ip() == addr { continue; }
-                 }
-
-                 tasks.push(tauri::async_runtime::spawn(async move {
-                     net_util::probe_ip(addr, 4654, s, t, a, true).await; // Fixed Port 4654
-                 }));
-            }
-            futures::future::join_all(tasks).await;
-        }
-        Ok(())
+        net_util::sweep_range(net, (*state).clone(), (*transport).clone(), app_handle, true).await
     } else {
          // 2. Try parsing as normal IP or SocketAddr
-        // If just IP, assume port 4654.
-        let (addr, port) = if let Ok(sock) = ip.parse::<std::net::SocketAddr>() {
-            (sock.ip(), sock.port())
-        } else if let Ok(ip_addr) = ip.parse::<std::net::IpAddr>() {
-            (ip_addr, 4654)
-        } else {
-             return Err("Invalid Format. Use IP, IP:PORT, or CIDR (e.g. 192.168.1.0/24)".to_string());
-        };
+        // If just IP, assume the default port.
+        let addr = net_util::parse_peer_addr(&ip, net_util::DEFAULT_PORT).map_err(|e| {
+            format!("Invalid Format ({}). Use IP, IP:PORT, [IPv6]:PORT, or CIDR (e.g. 192.168.1.0/24)", e)
+        })?;
 
         // For single IP, PROBE IT.
-        net_util::probe_ip(addr, port, (*state).clone(), (*transport).clone(), app_handle, true).await;
+        net_util::probe_ip(addr, (*state).clone(), (*transport).clone(), app_handle, true).await;
         Ok(())
     }
 }
@@ -239,8 +230,25 @@ pub(crate) async fn delete_peer(
     //    fingerprint, which step 1 removes. Spawning-and-forgetting raced that
     //    removal and could drop the notification to the very device being
     //    kicked. Awaiting keeps every fingerprint alive until the kicks are out.
-    let removal_msg = Message::PeerRemoval(peer_id.clone());
-    let data = serde_json::to_vec(&removal_msg).unwrap_or_default();
+    //    The kick is a signed revocation of the target's pinned fingerprint,
+    //    recorded locally only AFTER the sends: a recorded revocation makes
+    //    our own resolver refuse the target, and the kick must reach it.
+    //    A peer with no pinned fingerprint can't be reached under mTLS and
+    //    can't be named in a revocation, so it is only removed locally.
+    let fingerprint = state
+        .known_peers
+        .lock()
+        .unwrap()
+        .get(&peer_id)
+        .and_then(|p| p.fingerprint.clone());
+    let revocation = match fingerprint {
+        Some(fp) => Some(crate::revocation::issue_revocation(&state, &transport, &peer_id, fp)?),
+        None => None,
+    };
+    let data = revocation
+        .as_ref()
+        .map(|rev| serde_json::to_vec(&Message::Revocation(rev.clone())).unwrap_or_default())
+        .unwrap_or_default();
 
     // Manual iteration (rather than gossip) so it hits everyone incl. the target.
     let local_id = state.local_device_id.lock().unwrap().clone();
@@ -248,7 +256,7 @@ pub(crate) async fn delete_peer(
     let mut sends = Vec::new();
     for (id, p) in peers_snapshot.iter() {
          // Don't gossip to self (obv)
-         if *id == local_id {
+         if *id == local_id || data.is_empty() {
              continue;
          }
 
@@ -266,8 +274,16 @@ pub(crate) async fn delete_peer(
     )
     .await;
 
-    // 1. Remove from Known Peers. Tombstone first so a member that missed
-    //    the kick broadcast can't gossip the device back via membership sync.
+    // 1. Remove from Known Peers. Revoke and tombstone first so a member
+    //    that missed the kick can't gossip the device back via membership
+    //    sync; the persisted revocation also travels in our ClusterInfo.
+    if let Some(rev) = revocation {
+        let mut revocations = state.revocations.lock().unwrap();
+        if !revocations.iter().any(|r| r.fingerprint == rev.fingerprint) {
+            revocations.push(rev);
+            save_revocations(&app_handle, &revocations);
+        }
+    }
     state
         .removed_peer_tombstones
         .lock()
@@ -279,6 +295,13 @@ pub(crate) async fn delete_peer(
             save_known_peers(&app_handle, &kp);
         }
     }
+    {
+        let mut policies = state.peer_policies.lock().unwrap();
+        if policies.remove(&peer_id).is_some() {
+            save_peer_policies(&app_handle, &policies);
+        }
+    }
+    crate::clusters::remove_member(&state, &app_handle, &peer_id);
 
     // 2. Remove from Runtime Peers
     {
@@ -312,6 +335,12 @@ mod add_remote_tests {
             signature: None,
             fingerprint,
             protocol_version: None,
+            addresses: Vec::new(),
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
         }
     }
 
@@ -366,3 +395,15 @@ pub(crate) async fn retry_connection(
     }
     Ok(())
 }
+
+/// Measure the link to a connected peer: a few timed pings, then a bounded
+/// speed test (see link.rs).
+#[tauri::command]
+pub(crate) async fn test_connection(
+    peer_id: String,
+    state: State<'_, AppState>,
+    transport: State<'_, Transport>,
+    app_handle: tauri::AppHandle,
+) -> Result<crate::link::ConnectionTest, String> {
+    crate::link::test_connection(&state, &transport, &app_handle, &peer_id).await
+}
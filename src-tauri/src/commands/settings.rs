@@ -25,6 +25,7 @@ pub(crate) fn save_settings(
     // copy could clobber a value the header toggle just changed.
     settings.flatpak_autostart = prev.flatpak_autostart;
     settings.pairing_accept_enabled = prev.pairing_accept_enabled;
+    settings.network_policy_active = prev.network_policy_active.clone();
     *state.settings.lock().unwrap() = settings.clone();
     // Re-budget the History content store (may evict down on a lower cap),
     // then clean up evicted entries: delete their disk files, drop their
@@ -43,42 +44,52 @@ pub(crate) fn save_settings(
     let _ = app_handle.emit("settings-changed", settings.clone());
 
     // --- Issue #18: apply mDNS advertising toggle live ---
-    if settings.mdns_advertising != prev.mdns_advertising {
-        let mut disc_lock = state.discovery.lock().unwrap();
-        if let Some(disc) = disc_lock.as_mut() {
-            if settings.mdns_advertising {
-                let device_id = state.local_device_id.lock().unwrap().clone();
-                let network_name = state.network_name.lock().unwrap().clone();
-                let port = state
-                    .transport
-                    .lock()
-                    .unwrap()
-                    .as_ref()
-                    .and_then(|t| t.local_addr().ok())
-                    .map(|a| a.port())
-                    .unwrap_or(4654);
-                if let Err(e) = disc.register(&device_id, &network_name, port) {
-                    tracing::error!("Failed to re-register mDNS service: {}", e);
-                }
-            } else {
+    // Interface rule edits re-register too, so the record drops or picks up
+    // the affected addresses right away.
+    if settings.mdns_advertising != prev.mdns_advertising || settings.interface_exclude != prev.interface_exclude {
+        if let Some(disc) = state.discovery.lock().unwrap().as_mut() {
+            disc.set_interface_exclude(settings.interface_exclude.clone());
+            if !settings.mdns_advertising {
                 disc.unregister();
             }
         }
+        if settings.mdns_advertising {
+            crate::netmon::reregister_discovery(&state);
+        }
     }
 
-    // --- Issue #18: apply firewall toggle live (Windows OFF->ON only) ---
+    // --- Issue #18: apply firewall toggle live (Windows OFF->ON only, or
+    // a new port range; the port itself only changes on restart) ---
     #[cfg(target_os = "windows")]
     {
-        if settings.configure_firewall && !prev.configure_firewall {
-            std::thread::spawn(|| {
-                crate::net_util::configure_windows_firewall();
+        let ports = crate::net_util::firewall_port_spec(settings.listen_port, settings.listen_port_range);
+        let prev_ports = crate::net_util::firewall_port_spec(prev.listen_port, prev.listen_port_range);
+        if settings.configure_firewall && (!prev.configure_firewall || ports != prev_ports) {
+            std::thread::spawn(move || {
+                crate::net_util::configure_windows_firewall(ports);
             });
         }
     }
 
+    if settings.network_policies != prev.network_policies {
+        crate::netpolicy::wake(&state);
+    }
+
     #[cfg(desktop)]
     crate::tray::update_tray_menu(&app_handle);
 
     // Update Shortcuts
     crate::shortcuts::register_shortcuts(&app_handle);
 }
+
+/// The current network as the policy rules see it, for writing rules.
+#[tauri::command]
+pub(crate) async fn get_network_identity(state: State<'_, AppState>) -> Result<crate::netpolicy::NetworkIdentity, ()> {
+    Ok(crate::netpolicy::current_identity(&state, true, true).await)
+}
+
+/// Whether quiet hours or Do Not Disturb are in effect.
+#[tauri::command]
+pub(crate) fn get_quiet_status(state: State<'_, AppState>) -> crate::quiet::QuietStatus {
+    crate::quiet::status(&state)
+}
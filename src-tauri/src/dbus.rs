@@ -117,7 +117,7 @@ impl ClusterCutDBus {
 pub async fn start_dbus_server(app_handle: tauri::AppHandle) -> zbus::Result<()> {
     let service = ClusterCutDBus::new(app_handle.clone());
     let conn = zbus::connection::Builder::session()?
-        .name("app.clustercut.clustercut")?
+        .name(crate::profile::dbus_name())?
         .serve_at("/org/gnome/Shell/Extensions/ClusterCut", service)?
         .build()
         .await?;
@@ -146,7 +146,7 @@ pub async fn start_dbus_server(app_handle: tauri::AppHandle) -> zbus::Result<()>
                     .emit_signal(
                         Option::<&str>::None, // destination (broadcast)
                         "/org/gnome/Shell/Extensions/ClusterCut",
-                        "app.clustercut.clustercut",
+                        crate::profile::DBUS_NAME,
                         "StateChanged",
                         &(payload.auto_send, payload.auto_receive),
                     )
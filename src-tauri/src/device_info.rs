@@ -0,0 +1,325 @@
+//! What each device runs: OS, desktop, clipboard backend, app version, the
+//! clipboard formats it can take, and whether it is a Flatpak.
+//!
+//! Sent as `Message::DeviceInfo` (wire 0.3.8) the first time we hear from a
+//! peer that doesn't have ours yet, with `want_reply` set so the peer
+//! answers with its own, and again to everyone whenever ours changes (the
+//! GNOME extension coming or going flips the backend). It is kept on
+//! `Peer::device`, never serialised, and the device list uses it to explain
+//! capability gaps such as a GNOME peer whose extension is missing.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::peer::{Peer, PeerView};
+use crate::protocol::Message;
+use crate::state::AppState;
+use crate::transport::Transport;
+
+/// How often we check whether our own info changed.
+const TICK: Duration = Duration::from_secs(30);
+/// A peer that hasn't answered is asked again after this long.
+const ASK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Mirrors `clipboard::ClipboardBackend`, which only exists on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// tauri-plugin-clipboard: X11, Windows and macOS.
+    Plugin,
+    WlrDataControl,
+    GnomeExtension,
+    /// No clipboard access at all.
+    Degraded,
+    /// A backend from a newer build.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    /// `std::env::consts::OS`: "linux", "windows", "macos".
+    pub os: String,
+    /// Distribution or release name, where known ("Fedora Linux 41").
+    #[serde(default)]
+    pub os_name: Option<String>,
+    /// `XDG_CURRENT_DESKTOP` on Linux.
+    #[serde(default)]
+    pub desktop: Option<String>,
+    pub backend: Backend,
+    pub app_version: String,
+    /// MIME types the device puts on its clipboard as they came, plus
+    /// "files". Passthrough images missing here are converted to PNG.
+    #[serde(default)]
+    pub formats: Vec<String>,
+    #[serde(default)]
+    pub flatpak: bool,
+}
+
+const FILES: &str = "files";
+const RICH_FORMATS: &[&str] = &["text/html", "text/rtf"];
+
+/// What `backend` can write. X11 has no rich text (clipboard/rich.rs) and
+/// no passthrough images.
+fn formats_for(backend: Backend, os: &str) -> Vec<String> {
+    if matches!(backend, Backend::Degraded | Backend::Unknown) {
+        return Vec::new();
+    }
+    let x11 = backend == Backend::Plugin && os == "linux";
+    let mut formats = vec!["text/plain".to_string(), "image/png".to_string()];
+    formats.extend(crate::clipboard::common::verbatim_image_mimes(os, backend).iter().map(|m| m.to_string()));
+    if !x11 {
+        formats.extend(RICH_FORMATS.iter().map(|m| m.to_string()));
+    }
+    formats.push(FILES.to_string());
+    formats
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn local_backend() -> Backend {
+    use crate::clipboard::ClipboardBackend;
+    match crate::clipboard::get_backend() {
+        ClipboardBackend::Plugin => Backend::Plugin,
+        ClipboardBackend::WlrDataControl => Backend::WlrDataControl,
+        ClipboardBackend::GnomeExtension => Backend::GnomeExtension,
+        ClipboardBackend::Degraded => Backend::Degraded,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn local_backend() -> Backend {
+    Backend::Plugin
+}
+
+/// `PRETTY_NAME` from os-release; the host's, when we are a Flatpak.
+#[cfg(target_os = "linux")]
+fn os_name() -> Option<String> {
+    static NAME: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    NAME.get_or_init(|| {
+        let text = ["/run/host/os-release", "/etc/os-release"]
+            .iter()
+            .find_map(|p| std::fs::read_to_string(p).ok())?;
+        text.lines()
+            .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+            .map(|v| v.trim_matches('"').to_string())
+            .filter(|v| !v.is_empty())
+    })
+    .clone()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_name() -> Option<String> {
+    None
+}
+
+/// Our own info, as of now.
+pub(crate) fn local() -> DeviceInfo {
+    let os = std::env::consts::OS.to_string();
+    let backend = local_backend();
+    #[cfg(target_os = "linux")]
+    let (desktop, flatpak) = (std::env::var("XDG_CURRENT_DESKTOP").ok(), crate::clipboard::is_flatpak());
+    #[cfg(not(target_os = "linux"))]
+    let (desktop, flatpak) = (None, false);
+    DeviceInfo {
+        formats: formats_for(backend, &os),
+        os,
+        os_name: os_name(),
+        desktop,
+        backend,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        flatpak,
+    }
+}
+
+impl DeviceInfo {
+    fn is_gnome(&self) -> bool {
+        self.desktop.as_deref().map_or(false, |d| d.to_ascii_uppercase().contains("GNOME"))
+    }
+
+    /// Why this device can't do everything `ours` can, in words for the
+    /// device list.
+    pub fn gaps(&self, ours: &DeviceInfo) -> Vec<String> {
+        let mut gaps = Vec::new();
+        if self.backend == Backend::Degraded {
+            let desktop = self.desktop.as_deref().unwrap_or("this desktop");
+            gaps.push(if self.is_gnome() {
+                "Degraded: the ClusterCut GNOME extension is missing or disabled, so it can't use the clipboard.".to_string()
+            } else if self.flatpak {
+                format!("Degraded: the Flatpak can't reach the clipboard on {}; the native package can.", desktop)
+            } else {
+                format!("Degraded: {} offers no clipboard access ClusterCut can use.", desktop)
+            });
+            return gaps;
+        }
+        let (converted, missing): (Vec<&str>, Vec<&str>) = ours
+            .formats
+            .iter()
+            .filter(|f| !self.formats.contains(f))
+            .map(String::as_str)
+            .partition(|f| {
+                // Passthrough images we can decode are converted on arrival.
+                crate::clipboard::common::is_passthrough_image_mime(f)
+                    && crate::clipboard::common::image_format_for_mime(f).is_some()
+            });
+        if !missing.is_empty() {
+            gaps.push(format!("Can't take {}.", missing.join(", ")));
+        }
+        if !converted.is_empty() {
+            gaps.push(format!("Gets {} as PNG.", converted.join(", ")));
+        }
+        if self.app_version != ours.app_version {
+            gaps.push(format!("Runs ClusterCut {}; this device runs {}.", self.app_version, ours.app_version));
+        }
+        gaps
+    }
+}
+
+fn encode(want_reply: bool) -> Option<Vec<u8>> {
+    serde_json::to_vec(&Message::DeviceInfo { info: local(), want_reply }).ok()
+}
+
+/// Called for each `PeerDiscovery` we accept: if `peer` doesn't have our
+/// info yet, send it and ask for theirs.
+pub(crate) fn on_heard(state: &AppState, transport: &Transport, peer: &Peer) {
+    if peer.device.is_some() || !crate::net_util::supports_device_info(peer.protocol_version.as_deref()) {
+        return;
+    }
+    {
+        let mut asked = state.device_info_asked.lock().unwrap();
+        if asked.get(&peer.id).map_or(false, |at| at.elapsed() < ASK_INTERVAL) {
+            return;
+        }
+        asked.insert(peer.id.clone(), Instant::now());
+    }
+    let Some(data) = encode(true) else {
+        return;
+    };
+    let addr = peer.addr();
+    let transport = transport.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = transport.send_message(addr, &data).await {
+            tracing::debug!("[DeviceInfo] Send to {} failed: {}", addr, e);
+        }
+    });
+}
+
+/// Handle a peer's `DeviceInfo` from `addr`.
+pub(crate) async fn receive(
+    info: DeviceInfo,
+    want_reply: bool,
+    addr: std::net::SocketAddr,
+    state: &AppState,
+    app: &tauri::AppHandle,
+    transport: &Transport,
+) {
+    let view = {
+        let mut peers = state.peers.lock().unwrap();
+        let Some(peer) = peers.values_mut().find(|p| p.reachable_at(addr)) else {
+            return;
+        };
+        tracing::debug!("[DeviceInfo] {} runs {} {:?} ({:?})", peer.id, info.os, info.desktop, info.backend);
+        peer.device = Some(info);
+        PeerView::from_peer(peer)
+    };
+    state.device_info_asked.lock().unwrap().remove(&view.id);
+    let _ = app.emit("peer-update", view);
+    if want_reply {
+        if let Some(data) = encode(false) {
+            let _ = transport.send_message(addr, &data).await;
+        }
+    }
+}
+
+/// Re-send our info to every capable peer whenever it changes.
+pub(crate) async fn run(state: AppState, transport: Transport) {
+    let mut last = local();
+    loop {
+        tokio::time::sleep(TICK).await;
+        let current = local();
+        if current == last {
+            continue;
+        }
+        tracing::info!("[DeviceInfo] Changed ({:?} -> {:?}); telling peers", last.backend, current.backend);
+        last = current;
+        let Some(data) = encode(false) else {
+            continue;
+        };
+        let targets: Vec<(String, std::net::SocketAddr)> = state
+            .get_peers()
+            .into_values()
+            .filter(|p| crate::net_util::supports_device_info(p.protocol_version.as_deref()))
+            .map(|p| (p.id.clone(), p.addr()))
+            .collect();
+        for (id, addr) in targets {
+            if let Err(e) = transport.send_message(addr, &data).await {
+                tracing::debug!("[DeviceInfo] Update to {} failed: {}", id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(backend: Backend, desktop: &str, flatpak: bool) -> DeviceInfo {
+        DeviceInfo {
+            os: "linux".to_string(),
+            os_name: None,
+            desktop: Some(desktop.to_string()),
+            backend,
+            app_version: "1.0.0".to_string(),
+            formats: formats_for(backend, "linux"),
+            flatpak,
+        }
+    }
+
+    #[test]
+    fn degraded_peers_explain_why() {
+        let ours = info(Backend::WlrDataControl, "KDE", false);
+        let gnome = info(Backend::Degraded, "ubuntu:GNOME", false).gaps(&ours);
+        assert_eq!(gnome.len(), 1);
+        assert!(gnome[0].contains("GNOME extension"));
+        let sandboxed = info(Backend::Degraded, "Hyprland", true).gaps(&ours);
+        assert!(sandboxed[0].contains("Flatpak") && sandboxed[0].contains("Hyprland"));
+    }
+
+    #[test]
+    fn missing_formats_and_versions_are_gaps() {
+        let ours = info(Backend::WlrDataControl, "KDE", false);
+        assert!(info(Backend::WlrDataControl, "Hyprland", false).gaps(&ours).is_empty());
+        assert!(ours.gaps(&info(Backend::GnomeExtension, "GNOME", false)).is_empty());
+        assert_eq!(
+            info(Backend::GnomeExtension, "GNOME", false).gaps(&ours),
+            vec!["Gets image/jpeg, image/webp, image/tiff as PNG.".to_string()]
+        );
+
+        let mut x11 = info(Backend::Plugin, "XFCE", false);
+        x11.app_version = "0.9.0".to_string();
+        let gaps = x11.gaps(&ours);
+        assert_eq!(gaps, vec![
+            "Can't take image/svg+xml, text/html, text/rtf.".to_string(),
+            "Gets image/gif, image/jpeg, image/webp, image/tiff as PNG.".to_string(),
+            "Runs ClusterCut 0.9.0; this device runs 1.0.0.".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn undecodable_images_are_not_promised_as_png() {
+        let mut mac = info(Backend::Plugin, "", false);
+        mac.os = "macos".to_string();
+        mac.formats = formats_for(Backend::Plugin, "macos");
+        let gaps = info(Backend::WlrDataControl, "KDE", false).gaps(&mac);
+        assert_eq!(gaps, vec!["Can't take image/avif, image/heic.".to_string()]);
+    }
+
+    #[test]
+    fn unknown_backends_parse() {
+        let json = r#"{"os":"linux","backend":"portal","app_version":"2.0.0"}"#;
+        let parsed: DeviceInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.backend, Backend::Unknown);
+        assert!(parsed.formats.is_empty());
+    }
+}
@@ -1,6 +1,12 @@
-use local_ip_address::local_ip;
 use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
 use std::error::Error;
+use std::net::IpAddr;
+use tauri::Emitter;
+
+use crate::peer::{Peer, PeerAddress};
+use crate::protocol::Message;
+use crate::state::AppState;
 
 pub const SERVICE_TYPE: &str = "_clustercut._tcp.local.";
 
@@ -24,11 +30,186 @@ pub const SERVICE_TYPE: &str = "_clustercut._tcp.local.";
 ///   (shared cluster-name convergence). NOT a pairing break — the
 ///   compatibility floor in `is_protocol_compatible` stays at 0.3.3; this
 ///   version is only used to gate whether we send `ClusterName` to a peer.
-pub const CLUSTERCUT_PROTOCOL_VERSION: &str = "0.3.4";
+/// - 0.3.5: advertises `TimedPing`/`TimedPong` (sender clock-offset
+///   estimation) and `ClipboardPayload::seq`. Also not a pairing break; only
+///   gates whether we send `TimedPing`.
+/// - 0.3.6: understands `Message::Relay` envelopes and relayed file streams,
+///   and reports `Peer::reaches` in heartbeats. Not a pairing break; gates
+///   whether a peer is used as, or reached through, a relay.
+/// - 0.3.7: accepts `DeliveryTarget::Discard` file streams (connection
+///   tests, link.rs). Not a pairing break; gates `test_connection`.
+/// - 0.3.8: exchanges `Message::DeviceInfo` (device_info.rs). Not a pairing
+///   break; gates whether we send it.
+/// - 0.3.9: marks cut files (`ClipboardPayload::cut`) and acknowledges
+///   their downloads with `Message::FileReceived` (moves.rs). Not a pairing
+///   break; gates whether we send receipts.
+///
+/// Not versioned: `Message::PeerRemoval` is honoured only as a self-leave,
+/// sent over a connection authenticated with the leaving device's own pinned
+/// certificate. Removing someone else takes a signed `Message::Revocation`
+/// (revocation.rs). A kick from a build that predates revocations still
+/// arrives as a `PeerRemoval` naming another device, and is dropped: the
+/// kicked device stays a member here until a current build revokes it.
+///
+/// Also not versioned: `Message::KeyRotation` carries a nonce in place of
+/// the earlier timestamp and is confirmed with `Message::KeyRotationAck`.
+/// Builds on either side of that change can't parse each other's
+/// announcements, so a peer running the other one never acks and is reported
+/// as needing to re-pair (key_rotation.rs).
+///
+/// Nor is `FileStreamHeader::sha256`: a relayed file stream without it is
+/// dropped, so files relayed from a build that doesn't send it stop
+/// arriving. Direct streams and connection tests are unaffected (relay.rs).
+pub const CLUSTERCUT_PROTOCOL_VERSION: &str = "0.3.9";
+
+/// What another device advertises about itself, however we heard it: an
+/// mDNS record, or a beacon (beacon.rs) on networks that drop multicast.
+/// The fields mirror the TXT properties.
+pub(crate) struct Advertisement {
+    pub id: String,
+    /// "n"
+    pub network_name: Option<String>,
+    /// "h"
+    pub hostname: String,
+    /// "proto"
+    pub proto: Option<String>,
+    pub port: u16,
+    /// Candidate paths, best first. Never empty.
+    pub addresses: Vec<PeerAddress>,
+}
+
+/// Fold an advertisement into the runtime peer list and, for a newly seen
+/// member of our cluster, raise a ping-verified "Device Joined".
+pub(crate) fn resolve_advertisement(state: &AppState, app_handle: &tauri::AppHandle, ad: Advertisement) {
+    let Some(ip) = ad.addresses.first().map(|a| a.addr.ip()) else {
+        return;
+    };
+    let id = ad.id;
+
+    let local_id = { state.local_device_id.lock().unwrap().clone() };
+    if id == local_id {
+        return;
+    }
+
+    // DEBOUNCE: Cancel any pending removal for this peer
+    {
+        let mut pending = state.pending_removals.lock().unwrap();
+        if pending.remove(&id).is_some() {
+            tracing::debug!("[Discovery] Debounce: Cancelled pending removal for reappearing peer {}", id);
+        }
+    }
+
+    // A device in several clusters advertises once per cluster; for our
+    // members keep the name of the cluster we share rather than flip with
+    // each record.
+    let network_name = crate::clusters::member_cluster_name(state, &id).or(ad.network_name);
+
+    if let Some(n) = &network_name {
+        tracing::debug!("Discovered peer {} with network name: {}", id, n);
+    } else {
+        tracing::warn!("Discovered peer {} WITHOUT network name", id);
+    }
+
+    // Lock known_peers to prevent race with PairRequest. Trust requires a
+    // stored fingerprint under the v0.3 strict-mTLS model — a
+    // known-but-unfingerprinted entry is a legacy peer and must re-pair.
+    let kp = state.known_peers.lock().unwrap();
+    let known_entry = kp.get(&id).cloned();
+    let is_trusted = known_entry.as_ref().map(|p| p.fingerprint.is_some()).unwrap_or(false);
+    let stored_fingerprint = known_entry.and_then(|p| p.fingerprint);
+
+    tracing::info!("[Discovery] Peer {} resolved. Hostname: {}", id, ad.hostname);
+
+    let peer = Peer {
+        id: id.clone(),
+        ip,
+        port: ad.port,
+        hostname: ad.hostname,
+        last_seen: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        is_trusted,
+        is_manual: false,
+        network_name,
+        signature: None,
+        // Carry the stored fingerprint forward into the runtime peer record
+        // so it shows up in the UI.
+        fingerprint: stored_fingerprint,
+        protocol_version: ad.proto,
+        addresses: ad.addresses,
+        reaches: Vec::new(),
+        relay_via: None,
+        status: None,
+        device: None,
+        link: Default::default(),
+    };
+
+    // Check if peer is already active to prevent duplicate notifications
+    let is_new_peer = {
+        let peers = state.peers.lock().unwrap();
+        !peers.contains_key(&id)
+    };
+
+    let peer = state.add_peer(peer);
+    drop(kp);
+    let _ = app_handle.emit("peer-update", crate::peer::PeerView::from_peer(&peer));
+
+    // Trigger Notification (with Layer 2 ping verification)
+    let same_network = peer
+        .network_name
+        .as_ref()
+        .map_or(false, |rn| crate::clusters::is_local_cluster_name(state, rn));
+
+    if same_network
+        && is_new_peer
+        && state.settings.lock().unwrap().notifications.device_join
+        && state.should_notify()
+    {
+        // Ping-verify before notifying
+        let verify_state = state.clone();
+        let verify_handle = app_handle.clone();
+        let verify_peer = peer;
+        tauri::async_runtime::spawn(async move {
+            let addr = std::net::SocketAddr::new(verify_peer.ip, verify_peer.port);
+            let transport_opt = { verify_state.transport.lock().unwrap().clone() };
+            let mut verified = false;
+
+            if let Some(transport) = transport_opt {
+                if let Ok(ping_data) = serde_json::to_vec(&Message::Ping) {
+                    if let Ok(Ok(_)) = tokio::time::timeout(
+                        std::time::Duration::from_secs(3),
+                        transport.send_message(addr, &ping_data),
+                    )
+                    .await
+                    {
+                        verified = true;
+                    }
+                }
+            }
+
+            if verified {
+                tracing::info!("[Notification] Ping-verified 'Device Joined' for: {}", verify_peer.hostname);
+                crate::send_notification(&verify_handle, "Device Joined", &format!("{} has joined your cluster", verify_peer.hostname), false, Some(1), "devices", crate::NotificationPayload::None);
+            } else {
+                tracing::info!("[Notification] Deferring 'Device Joined' for {} (ping failed, will fire on heartbeat)", verify_peer.hostname);
+                verify_state.pending_join_notifications.lock().unwrap().insert(verify_peer.id.clone());
+            }
+        });
+    }
+}
 
 pub struct Discovery {
     daemon: ServiceDaemon,
     registered_service: Option<String>, // Stores fullname of registered service
+    /// Fullnames of the advertisements for additional cluster contexts
+    /// (clusters.rs), keyed by cluster id.
+    context_services: HashMap<String, String>,
+    /// `AppSettings::interface_exclude`, applied on every registration.
+    interface_exclude: Vec<String>,
+    /// Addresses the current registration published, so netmon can tell
+    /// when an interface came or went and re-register.
+    advertised: Vec<IpAddr>,
 }
 
 impl Discovery {
@@ -37,9 +218,40 @@ impl Discovery {
         Ok(Self {
             daemon,
             registered_service: None,
+            context_services: HashMap::new(),
+            interface_exclude: crate::storage::default_interface_exclude(),
+            advertised: Vec::new(),
         })
     }
 
+    /// Replace the interface exclusion rules. Takes effect on the next
+    /// registration.
+    pub fn set_interface_exclude(&mut self, rules: Vec<String>) {
+        self.interface_exclude = rules;
+    }
+
+    /// Whether we are advertising and the set of usable addresses no longer
+    /// matches what the registration published.
+    pub fn addresses_changed(&self) -> bool {
+        if self.registered_service.is_none() {
+            return false;
+        }
+        match crate::net_util::advertised_ips(&self.interface_exclude) {
+            Ok(ips) => ips != self.advertised,
+            Err(_) => false,
+        }
+    }
+
+    /// Usable addresses in the comma-separated form mdns-sd takes. mdns-sd
+    /// only answers on each interface with the addresses in its subnet, so
+    /// publishing all of them is safe.
+    fn host_ips(&mut self) -> Result<String, Box<dyn Error>> {
+        let ips = crate::net_util::advertised_ips(&self.interface_exclude)?;
+        let joined = ips.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(",");
+        self.advertised = ips;
+        Ok(joined)
+    }
+
     pub fn register(
         &mut self,
         device_id: &str,
@@ -73,8 +285,8 @@ impl Discovery {
             }
         }
 
-        // Get the local IP address
-        let ip = local_ip()?;
+        // Every usable interface, default route first.
+        let ip = self.host_ips()?;
 
         // Hostname usually needs to be unique on the network, but we'll base it on device ID for now.
         // Format: device_id.local.
@@ -101,7 +313,7 @@ impl Discovery {
             SERVICE_TYPE,
             device_id,
             &m_hostname,
-            &ip.to_string(),
+            &ip[..],
             port,
             &properties[..],
         )?;
@@ -123,6 +335,61 @@ impl Discovery {
         Ok(())
     }
 
+    /// Advertise this device in an additional cluster context. Each context
+    /// gets its own instance (`<device_id>-<cluster id prefix>`) carrying
+    /// that cluster's name in "n"; the "id" property stays the device id, so
+    /// browsers still resolve every instance to the same peer.
+    pub fn register_context(
+        &mut self,
+        device_id: &str,
+        cluster_id: &str,
+        network_name: &str,
+        port: u16,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(fullname) = self.context_services.remove(cluster_id) {
+            if let Ok(receiver) = self.daemon.unregister(&fullname) {
+                let _ = receiver.recv_timeout(std::time::Duration::from_secs(1));
+            }
+        }
+
+        let ip = self.host_ips()?;
+        let instance = format!("{}-{}", device_id, &cluster_id[..cluster_id.len().min(8)]);
+        let m_hostname = format!("{}.local.", device_id);
+        let system_hostname = hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "Unknown Device".to_string());
+        let properties = [
+            ("version", "0.1.0"),
+            ("proto", CLUSTERCUT_PROTOCOL_VERSION),
+            ("id", device_id),
+            ("n", network_name),
+            ("h", &system_hostname),
+        ];
+        let service_info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &m_hostname,
+            &ip[..],
+            port,
+            &properties[..],
+        )?;
+        let fullname = service_info.get_fullname().to_string();
+        self.daemon.register(service_info)?;
+        tracing::info!("Registered cluster context '{}' as {} on {}:{}", network_name, fullname, ip, port);
+        self.context_services.insert(cluster_id.to_string(), fullname);
+        Ok(())
+    }
+
+    /// Stop advertising one additional cluster context.
+    pub fn unregister_context(&mut self, cluster_id: &str) {
+        if let Some(fullname) = self.context_services.remove(cluster_id) {
+            tracing::info!("Unregistering cluster context service: {}", fullname);
+            if let Err(e) = self.daemon.unregister(&fullname) {
+                tracing::error!("Failed to unregister service: {}", e);
+            }
+        }
+    }
+
     /// Stop advertising this device without tearing down the daemon or any
     /// active browse. Used to apply the mDNS-advertising toggle live. Safe to
     /// call when nothing is registered.
@@ -133,6 +400,15 @@ impl Discovery {
                 tracing::error!("Failed to unregister service: {}", e);
             }
         }
+        self.unregister_contexts();
+    }
+
+    /// Stop advertising every additional cluster context.
+    pub fn unregister_contexts(&mut self) {
+        let ids: Vec<String> = self.context_services.keys().cloned().collect();
+        for id in ids {
+            self.unregister_context(&id);
+        }
     }
 
     pub fn browse(&self) -> Result<mdns_sd::Receiver<ServiceEvent>, Box<dyn Error>> {
@@ -143,6 +419,9 @@ impl Discovery {
 
 impl Drop for Discovery {
     fn drop(&mut self) {
+        for fullname in self.context_services.values() {
+            let _ = self.daemon.unregister(fullname);
+        }
         if let Some(fullname) = &self.registered_service {
             tracing::info!("Unregistering service: {}", fullname);
             if let Err(e) = self.daemon.unregister(fullname) {
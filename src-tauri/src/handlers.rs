@@ -4,9 +4,10 @@ use crate::protocol::Message;
 use crate::state::AppState;
 use crate::transport::Transport;
 use crate::{net_util, storage};
-use crate::{NotificationPayload, send_notification, get_hostname_internal, check_and_notify_leave, perform_factory_reset};
+use crate::{NotificationPayload, send_notification, get_hostname_internal, check_and_notify_leave};
 use tauri::{Emitter, Manager};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, AsyncBufReadExt, BufReader};
+use sha2::Digest;
 use std::path::PathBuf;
 use tokio::fs::File;
 
@@ -24,9 +25,7 @@ fn stage_received_clipboard_blob(
     height: Option<u32>,
     bytes: &[u8],
 ) -> Option<std::path::PathBuf> {
-    let cache_dir = app
-        .path()
-        .app_cache_dir()
+    let cache_dir = crate::profile::cache_dir(app)
         .ok()?
         .join("temp_downloads");
     std::fs::create_dir_all(&cache_dir).ok()?;
@@ -171,6 +170,7 @@ async fn handle_incoming_clipboard_blob_stream(
         total_time,
         mime_type
     );
+    crate::link::record_inbound(&state, &app, addr, accum.len() as u64, total_time);
 
     if accum.len() as u64 != header.file_size {
         tracing::warn!(
@@ -180,6 +180,11 @@ async fn handle_incoming_clipboard_blob_stream(
         );
         return;
     }
+    // Relayed: the signed header's hash is all that vouches for the bytes.
+    if header.sha256.as_ref().is_some_and(|h| *h != crate::moves::to_hex(&sha2::Sha256::digest(&accum))) {
+        tracing::warn!("Clipboard-blob {} doesn't match its header's hash — dropping.", header.id);
+        return;
+    }
 
     // Race protection: only land on clipboard if this id is still the in-flight one.
     let still_current = {
@@ -202,8 +207,11 @@ async fn handle_incoming_clipboard_blob_stream(
 
     let (auto_recv, notifications) = {
         let s = state.settings.lock().unwrap();
-        (s.auto_receive, s.notifications.clone())
+        (crate::quiet::effective_auto_receive(&state, &s), s.notifications.clone())
     };
+    // Ephemeral descriptor: never staged to disk (History must not keep it),
+    // so the inline fallback payloads below are used instead.
+    let ttl_secs = state.ephemeral_fetches.lock().unwrap().remove(&header.id);
     let byte_len = accum.len();
     let mb = byte_len as f64 / (1024.0 * 1024.0);
 
@@ -220,9 +228,13 @@ async fn handle_incoming_clipboard_blob_stream(
         };
         // Stage for re-call, then emit a light preview (record_and_emit reads
         // the staged file via local_clipboard_blobs to build the Disk entry).
-        let staged = stage_received_clipboard_blob(
-            &app, &state, &header.id, &mime_type, None, None, text.as_bytes(),
-        );
+        let staged = if ttl_secs.is_none() {
+            stage_received_clipboard_blob(
+                &app, &state, &header.id, &mime_type, None, None, text.as_bytes(),
+            )
+        } else {
+            None
+        };
         let payload_event = if staged.is_some() {
             // Descriptor → record_and_emit reads the staged file for the snippet.
             crate::protocol::ClipboardPayload {
@@ -243,6 +255,9 @@ async fn handle_incoming_clipboard_blob_stream(
                     .as_secs(),
                 sender: format!("{}", addr),
                 sender_id: String::new(),
+                ttl_secs,
+                seq: None,
+                cut: false,
             }
         } else {
             // Staging failed — inline the full text so the item stays
@@ -259,9 +274,17 @@ async fn handle_incoming_clipboard_blob_stream(
                     .as_secs(),
                 sender: format!("{}", addr),
                 sender_id: String::new(),
+                ttl_secs,
+                seq: None,
+                cut: false,
             }
         };
         if auto_recv {
+            crate::clipboard::common::arm_ephemeral(
+                &app,
+                ttl_secs,
+                crate::clipboard::common::ClipboardContent::Text(text.clone()),
+            );
             crate::clipboard::set_clipboard(&app, text);
         } else {
             let mut pending = state.pending_clipboard.lock().unwrap();
@@ -277,9 +300,13 @@ async fn handle_incoming_clipboard_blob_stream(
             );
         }
     } else {
-        let staged = stage_received_clipboard_blob(
-            &app, &state, &header.id, &mime_type, width, height, &accum,
-        );
+        let staged = if ttl_secs.is_none() {
+            stage_received_clipboard_blob(
+                &app, &state, &header.id, &mime_type, width, height, &accum,
+            )
+        } else {
+            None
+        };
         // Land the image on the OS clipboard (or stash as pending).
         let blob = crate::protocol::ClipboardBlob::from_bytes(mime_type.clone(), &accum, width, height);
         let payload_event = if staged.is_some() {
@@ -303,6 +330,9 @@ async fn handle_incoming_clipboard_blob_stream(
                     .as_secs(),
                 sender: format!("{}", addr),
                 sender_id: String::new(),
+                ttl_secs,
+                seq: None,
+                cut: false,
             }
         } else {
             // Staging failed — fall back to inline so History still shows it.
@@ -318,9 +348,17 @@ async fn handle_incoming_clipboard_blob_stream(
                     .as_secs(),
                 sender: format!("{}", addr),
                 sender_id: String::new(),
+                ttl_secs,
+                seq: None,
+                cut: false,
             }
         };
         if auto_recv {
+            crate::clipboard::common::arm_ephemeral(
+                &app,
+                ttl_secs,
+                crate::clipboard::common::ClipboardContent::Image(blob.clone()),
+            );
             crate::clipboard::set_clipboard_image(&app, blob);
         } else {
             let mut pending = state.pending_clipboard.lock().unwrap();
@@ -338,7 +376,7 @@ async fn handle_incoming_clipboard_blob_stream(
     }
 }
 
-pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: std::net::SocketAddr, state: AppState, app: tauri::AppHandle) {
+pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, mut addr: std::net::SocketAddr, state: AppState, app: tauri::AppHandle) {
     tracing::info!("Starting File Stream Handler for {}", addr);
 
     let mut reader = BufReader::new(recv);
@@ -350,8 +388,19 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
         return;
     }
 
-    let header: crate::protocol::FileStreamHeader = match serde_json::from_str(&header_line) {
-        Ok(h) => h,
+    let header = match serde_json::from_str(&header_line) {
+        Ok(crate::protocol::StreamPreamble::Direct(h)) => h,
+        // Through a relay: forwarded, or unwrapped to the originator's header.
+        Ok(crate::protocol::StreamPreamble::Relayed { relay }) => {
+            match crate::relay::receive_stream(relay, reader, addr, &state).await {
+                Some((h, origin, rest)) => {
+                    addr = origin;
+                    reader = rest;
+                    h
+                }
+                None => return,
+            }
+        }
         Err(e) => {
             tracing::error!("Failed to parse file stream header '{}': {}", header_line.trim(), e);
             return;
@@ -366,12 +415,16 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
         handle_incoming_clipboard_blob_stream(reader, header, mime_type, width, height, addr, state, app).await;
         return;
     }
+    if header.delivery_target == crate::protocol::DeliveryTarget::Discard {
+        crate::link::receive_discard(reader, addr, &state, &app).await;
+        return;
+    }
 
     tracing::info!("Receiving File: {} ({} bytes) [ID: {}]", header.file_name, header.file_size, header.id);
 
     // 2. Prepare Output File
     // Use Cache Directory -> temp_downloads
-    let root_cache_dir = match app.path().app_cache_dir() {
+    let root_cache_dir = match crate::profile::cache_dir(&app) {
         Ok(p) => p,
         Err(e) => {
              tracing::error!("Failed to get cache dir: {}", e);
@@ -379,7 +432,12 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
         }
     };
 
-    let cache_dir = root_cache_dir.join("temp_downloads");
+    // Files of a cut go where they outlive a restart instead: the receipt we
+    // send for them lets the sender delete its original (moves.rs).
+    let cut_dir = crate::moves::wants_receipt(&state, &header.id)
+        .then(|| crate::moves::lasting_dir(&app))
+        .flatten();
+    let cache_dir = cut_dir.clone().unwrap_or_else(|| root_cache_dir.join("temp_downloads"));
 
     if let Err(e) = std::fs::create_dir_all(&cache_dir) {
         tracing::error!("Failed to create cache dir: {}", e);
@@ -431,6 +489,9 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
     let mut total_written = 0u64;
     let mut last_emit = std::time::Instant::now();
     let mut chunk_count = 0;
+    // Hashed for a cut, whose sender checks the hash before moving the
+    // original, and for relayed streams, whose header carries the hash.
+    let mut hasher = (cut_dir.is_some() || header.sha256.is_some()).then(sha2::Sha256::new);
 
     if header.compressed {
         tracing::info!("[Receiver] Starting ZSTD Stream. Expecting {} bytes (decompressed).", header.file_size);
@@ -444,6 +505,9 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
                         break;
                     }
                     total_written += n as u64;
+                    if let Some(h) = hasher.as_mut() {
+                        h.update(&buf[0..n]);
+                    }
                     chunk_count += 1;
 
                     if last_emit.elapsed().as_millis() > 200 {
@@ -473,6 +537,9 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
                          break;
                     }
                     total_written += n as u64;
+                    if let Some(h) = hasher.as_mut() {
+                        h.update(&buf[0..n]);
+                    }
                     chunk_count += 1;
 
                     // Emit Progress (Throttled 200ms)
@@ -498,6 +565,15 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
     let mb = total_written as f64 / 1_000_000.0;
     let speed = mb / total_time.as_secs_f64();
     tracing::info!("File Stream Completed. Written {} chunks ({} bytes) in {:?}. Speed: {:.2} MB/s", chunk_count, total_written, total_time, speed);
+    crate::link::record_inbound(&state, &app, addr, total_written, total_time);
+
+    let sha256 = hasher.map(|h| crate::moves::to_hex(&h.finalize()));
+    if header.sha256.is_some() && sha256 != header.sha256 {
+        tracing::warn!("File {} doesn't match its header's hash; discarding it", header.file_name);
+        drop(file);
+        let _ = std::fs::remove_file(&file_path);
+        return;
+    }
 
     // Final Progress
     let _ = app.emit("file-progress", serde_json::json!({
@@ -517,8 +593,12 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
      }));
 
      // Notification
-     let settings = state.settings.lock().unwrap();
-     if settings.notify_large_files && header.file_size > settings.max_auto_download_size {
+     // Not held across send_notification, which reads settings itself.
+     let (notify_large, auto_limit) = {
+         let s = state.settings.lock().unwrap();
+         (s.notify_large_files, s.max_auto_download_size)
+     };
+     if notify_large && header.file_size > auto_limit {
          let body = format!("Download complete: {}", header.file_name);
          send_notification(&app, "Download Complete", &body, false, None, "history", NotificationPayload::None);
      }
@@ -526,6 +606,15 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
     // 5. Verify Size
     if total_written == header.file_size {
         tracing::info!("File Transfer Verified OK");
+        if let Some(sha256) = sha256.filter(|_| cut_dir.is_some()) {
+            let receipt = crate::protocol::FileReceipt {
+                id: header.id.clone(),
+                file_index: header.file_index,
+                size: total_written,
+                sha256,
+            };
+            crate::moves::downloaded(&state, &app, addr, receipt);
+        }
         if let Some(path_str) = file_path.to_str() {
              crate::clipboard::set_clipboard_paths(&app, vec![path_str.to_string()]);
         }
@@ -534,7 +623,17 @@ pub(crate) async fn handle_incoming_file_stream(recv: quinn::RecvStream, addr: s
     }
 }
 
-pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, listener_state: AppState, listener_handle: tauri::AppHandle, transport_inside: Transport) {
+/// Handle one message from `addr`. `fingerprint` is that of the certificate
+/// the sender authenticated with (`Transport::start_listening`), or of the
+/// originator of a relayed message.
+pub(crate) async fn handle_message(
+    msg: Message,
+    addr: std::net::SocketAddr,
+    fingerprint: Option<Vec<u8>>,
+    listener_state: AppState,
+    listener_handle: tauri::AppHandle,
+    transport_inside: Transport,
+) {
     match msg {
         Message::Clipboard(payload) => {
             tracing::debug!("Received Clipboard from {}", addr);
@@ -543,21 +642,15 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
             let ts = payload.timestamp;
             let sender = payload.sender.clone();
             {
-                            // Verify Timestamp Freshness (120s threshold)
-                            let now = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs();
-
-                            let diff = if now > ts {
-                                now - ts
-                            } else {
-                                ts - now // Future timestamp (clock skew)
-                            };
-
-                            if diff > 120 {
-                                tracing::warn!("Ignored stale clipboard message from {} (Timestamp: {}, Now: {}, Diff: {}s)", sender, ts, now, diff);
-                                return;
+                            // Replay / freshness check: drop repeats, out-of-order
+                            // sequence numbers and payloads stale by the
+                            // sender's own clock. See replay.rs.
+                            match crate::replay::check_incoming(&listener_state, addr, &payload) {
+                                crate::replay::Verdict::Accept => {}
+                                verdict => {
+                                    tracing::warn!("Ignored clipboard message {} from {}: {:?}", id, sender, verdict);
+                                    return;
+                                }
                             }
 
                             // Self-sender check
@@ -569,6 +662,21 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                 }
                             }
 
+                            // Paused by quiet hours: not even History gets it.
+                            if crate::quiet::mode(&listener_state) == Some(crate::quiet::QuietMode::Pause) {
+                                tracing::info!("[Quiet] Sync paused; ignoring clipboard from {}", sender);
+                                return;
+                            }
+
+                            // Per-peer policy: drop or trim what this peer
+                            // isn't allowed to push to us before anything
+                            // touches dedupe state, history or the clipboard.
+                            let policy = listener_state.policy_for_sender(addr.ip(), Some(&payload.sender_id));
+                            let Some(payload) = policy.filter_incoming(payload) else {
+                                tracing::info!("[Policy] Ignoring clipboard from {} ({}): blocked by peer policy", sender, addr);
+                                return;
+                            };
+
                             // Loop/Dedupe Check — must match the sender-side
                             // signature in clipboard::common::payload_signature
                             // so a blob received from a peer correctly suppresses
@@ -619,6 +727,9 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                 timestamp: ts,
                                 sender: sender.clone(),
                                 sender_id: payload.sender_id.clone(),
+                                ttl_secs: payload.ttl_secs,
+                                seq: payload.seq,
+                                cut: payload.cut,
                             };
 
                             // FILE HANDLING
@@ -626,11 +737,15 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                 if !files.is_empty() {
                                     tracing::info!("Received File Metadata from {}: {} files", sender, files.len());
                                     crate::clipboard::common::record_and_emit(&listener_handle, &listener_state, "clipboard-change", &payload_obj);
+                                    if payload.cut {
+                                        // The sender waits for our receipts before moving the originals.
+                                        crate::moves::expect_receipts(&listener_state, &id, files.len());
+                                    }
 
                                     // Auto-Download Logic
                                     let (auto_recv, enable_ft, size_limit, notify_large) = {
                                         let s = listener_state.settings.lock().unwrap();
-                                        (s.auto_receive, s.enable_file_transfer, s.max_auto_download_size, s.notify_large_files)
+                                        (crate::quiet::effective_auto_receive(&listener_state, &s), s.enable_file_transfer, policy.auto_download_limit(s.max_auto_download_size), s.notify_large_files)
                                     };
 
                                     if !enable_ft {
@@ -700,6 +815,9 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                     // ride the `clustercut-file` ALPN, not
                                     // inline. Decide auto-fetch vs. user-
                                     // confirm based on `max_auto_download_size`.
+                                    if let Some(ttl) = payload_obj.ttl_secs {
+                                        listener_state.ephemeral_fetches.lock().unwrap().insert(id.clone(), ttl);
+                                    }
                                     let total_size = blob.total_size.unwrap_or(0);
                                     let mb = total_size as f64 / (1024.0 * 1024.0);
                                     let is_text = blob.mime_type.starts_with("text/");
@@ -707,7 +825,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                     let kind_title = if is_text { "Text" } else { "Image" };
                                     let (auto_recv, enable_ft, size_limit) = {
                                         let s = listener_state.settings.lock().unwrap();
-                                        (s.auto_receive, s.enable_file_transfer, s.max_auto_download_size)
+                                        (crate::quiet::effective_auto_receive(&listener_state, &s), s.enable_file_transfer, policy.auto_download_limit(s.max_auto_download_size))
                                     };
                                     tracing::info!(
                                         "Received clipboard descriptor from {}: mime={}, total={} bytes{} fetch_id={}",
@@ -837,8 +955,13 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                             _ => String::new(),
                                         }
                                     );
-                                    let auto_receiver = { listener_state.settings.lock().unwrap().auto_receive };
+                                    let auto_receiver = crate::quiet::auto_receive(&listener_state);
                                     if auto_receiver {
+                                        crate::clipboard::common::arm_ephemeral(
+                                            &listener_handle,
+                                            payload_obj.ttl_secs,
+                                            crate::clipboard::common::ClipboardContent::Image(blob.clone()),
+                                        );
                                         crate::clipboard::set_clipboard_image(&listener_handle, blob);
                                         crate::clipboard::common::record_and_emit(&listener_handle, &listener_state, "clipboard-change", &payload_obj);
                                     } else {
@@ -919,16 +1042,29 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                     }
                                 };
 
-                                let auto_receiver = { listener_state.settings.lock().unwrap().auto_receive };
+                                let auto_receiver = crate::quiet::auto_receive(&listener_state);
                                 if auto_receiver {
                                     if needs_promotion_dance {
                                         {
                                             let mut stash = listener_state.pending_rich_promotion.lock().unwrap();
                                             *stash = Some(payload_obj.clone());
                                         }
+                                        crate::clipboard::common::arm_ephemeral(
+                                            &listener_handle,
+                                            payload_obj.ttl_secs,
+                                            crate::clipboard::common::ClipboardContent::Text(text.clone()),
+                                        );
                                         crate::clipboard::set_clipboard(&listener_handle, text.clone());
                                         crate::clipboard::common::record_and_emit(&listener_handle, &listener_state, "clipboard-change", &payload_obj);
                                     } else {
+                                        crate::clipboard::common::arm_ephemeral(
+                                            &listener_handle,
+                                            payload_obj.ttl_secs,
+                                            crate::clipboard::common::ClipboardContent::Rich {
+                                                text: text.clone(),
+                                                formats: formats.clone(),
+                                            },
+                                        );
                                         crate::clipboard::set_clipboard_rich(&listener_handle, text.clone(), formats);
                                         crate::clipboard::common::record_and_emit(&listener_handle, &listener_state, "clipboard-change", &payload_obj);
                                     }
@@ -988,8 +1124,13 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                     sender,
                                     text.len()
                                 );
-                                let auto_receiver = { listener_state.settings.lock().unwrap().auto_receive };
+                                let auto_receiver = crate::quiet::auto_receive(&listener_state);
                                 if auto_receiver {
+                                    crate::clipboard::common::arm_ephemeral(
+                                        &listener_handle,
+                                        payload_obj.ttl_secs,
+                                        crate::clipboard::common::ClipboardContent::Text(text.clone()),
+                                    );
                                     crate::clipboard::set_clipboard(&listener_handle, text.clone());
                                     crate::clipboard::common::record_and_emit(&listener_handle, &listener_state, "clipboard-change", &payload_obj);
                                 } else {
@@ -1011,7 +1152,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                             // Relay Logic — re-broadcast to other cluster
                             // members (mTLS authenticates each hop; no
                             // app-layer encryption needed).
-                            let auto_send = { listener_state.settings.lock().unwrap().auto_send };
+                            let auto_send = crate::quiet::auto_send(&listener_state);
                             if !auto_send {
                                 return;
                             }
@@ -1033,6 +1174,11 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
         Message::PeerDiscovery(mut peer) => {
             tracing::debug!("Received PeerDiscovery for {}", peer.hostname);
 
+            if listener_state.is_revoked(&peer.id, peer.fingerprint.as_deref()) {
+                tracing::warn!("Ignoring PeerDiscovery for revoked device {} from {}", peer.id, addr);
+                return;
+            }
+
             let local_id = listener_state.local_device_id.lock().unwrap().clone();
             if peer.id == local_id {
                 // Collision Detection:
@@ -1060,6 +1206,39 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 }
             }
 
+            // Gossip only about the sender's own cluster: a member of one of
+            // our clusters mustn't add to, or re-pin, another (clusters.rs).
+            let sender_id = fingerprint.as_deref().and_then(|fp| listener_state.device_for_fingerprint(fp));
+            match sender_id.as_deref() {
+                Some(id) if crate::clusters::may_gossip_about(&listener_state, id, &peer.id) => {}
+                _ => {
+                    tracing::warn!("Ignoring PeerDiscovery for {} from {}: not in the sender's cluster", peer.id, addr);
+                    return;
+                }
+            }
+
+            // A device we don't know yet, gossiped by a member of one of our
+            // additional clusters, joins that cluster rather than the primary.
+            let gossip_context = {
+                let unknown = !listener_state.known_peers.lock().unwrap().contains_key(&peer.id);
+                sender_id
+                    .filter(|id| unknown && *id != peer.id)
+                    .and_then(|id| crate::clusters::context_of(&listener_state, &id))
+            };
+            if let Some(ctx) = &gossip_context {
+                crate::clusters::assign_member(&listener_state, &listener_handle, Some(&ctx.cluster_id), &peer.id);
+            }
+            // Members carry the name of the cluster we share; a device in
+            // several clusters only ever gossips its primary one.
+            if let Some(name) = crate::clusters::member_cluster_name(&listener_state, &peer.id) {
+                peer.network_name = Some(name);
+            } else if let Some(ctx) = &gossip_context {
+                peer.network_name = Some(ctx.name.clone());
+            }
+
+            crate::relay::note_reach(&listener_state, &peer.id, addr, std::mem::take(&mut peer.reaches));
+            crate::status::accept(&listener_state, &mut peer, addr);
+
             peer.ip = addr.ip();
             peer.port = addr.port();
             peer.last_seen = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
@@ -1069,8 +1248,14 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 if let Some(existing) = kp.get(&peer.id) {
                      peer.is_manual = existing.is_manual;
                      // Don't let a gossip update without a fingerprint clobber an
-                     // already-pinned one. Sticky pinning until re-pair.
-                     if peer.fingerprint.is_none() {
+                     // already-pinned one. Sticky pinning until re-pair. Same
+                     // for a fingerprint the peer has since rotated away from:
+                     // a member that missed the KeyRotation mustn't revert us.
+                     let retired = peer
+                         .fingerprint
+                         .as_deref()
+                         .map_or(false, |fp| listener_state.is_retired_fingerprint(&peer.id, fp));
+                     if peer.fingerprint.is_none() || retired {
                          peer.fingerprint = existing.fingerprint.clone();
                      }
                 } else {
@@ -1081,7 +1266,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
             let mut should_reply = false;
             {
                  let mut kp_lock = listener_state.known_peers.lock().unwrap();
-                 let manual_id = format!("manual-{}", peer.ip);
+                 let manual_id = crate::net_util::manual_id(addr);
                  if kp_lock.contains_key(&manual_id) {
                      tracing::info!("Replacing manual placeholder {} with real peer {}", manual_id, peer.id);
                      kp_lock.remove(&manual_id);
@@ -1103,8 +1288,12 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                  // taken as cluster membership.
                  peer.is_trusted = true;
 
-                 listener_state.add_peer(peer.clone());
+                 peer = listener_state.add_peer(peer);
+                 if let Some(updated) = crate::paths::record_inbound(&listener_state, &peer.id, addr) {
+                     peer = updated;
+                 }
                  let _ = listener_handle.emit("peer-update", crate::peer::PeerView::from_peer(&peer));
+                 crate::device_info::on_heard(&listener_state, &transport_inside, &peer);
 
                  // Fire deferred join notification if this peer was pending verification
                  {
@@ -1120,7 +1309,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                  }
 
                  if peer.is_trusted || peer.is_manual {
-                     kp_lock.insert(peer.id.clone(), peer.clone());
+                     kp_lock.insert(peer.id.clone(), crate::peer::Peer { status: None, ..peer.clone() });
                      storage::save_known_peers(listener_handle.app_handle(), &kp_lock);
                  } else {
                      if kp_lock.contains_key(&peer.id) {
@@ -1137,6 +1326,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 let hostname = hostname::get().map(|h| h.to_string_lossy().to_string()).unwrap_or("Unknown".to_string());
                 let network_name = listener_state.network_name.lock().unwrap().clone();
 
+                let exclude = listener_state.settings.lock().unwrap().interface_exclude.clone();
                 let my_peer = crate::peer::Peer {
                     id: local_id,
                     ip: transport_inside.local_addr().unwrap().ip(),
@@ -1149,6 +1339,12 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                     signature: None,
                     fingerprint: Some(transport_inside.local_fingerprint()),
                     protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+                    addresses: crate::paths::local_candidates(&exclude, transport_inside.local_addr().unwrap().port()),
+                    reaches: Vec::new(),
+                    relay_via: None,
+                    status: Some(crate::status::local(&listener_state)),
+                    device: None,
+                    link: Default::default(),
                 };
 
                 let msg = Message::PeerDiscovery(my_peer);
@@ -1163,7 +1359,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
             // rediscovery, gossip), tell it our current cluster-name register so
             // peers that were offline during a rename converge. Gated on the
             // peer's protocol version inside send_cluster_name_to.
-            {
+            if crate::clusters::context_of(&listener_state, &peer.id).is_none() {
                 let name = listener_state.network_name.lock().unwrap().clone();
                 let version = *listener_state.network_name_version.lock().unwrap();
                 let origin = listener_state.network_name_origin.lock().unwrap().clone();
@@ -1178,14 +1374,22 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
         }
         Message::PeerRemoval(target_id) => {
             tracing::info!("Received PeerRemoval for {}", target_id);
-            let local_id = listener_state.local_device_id.lock().unwrap().clone();
-
-            if target_id == local_id {
-                tracing::warn!("I have been removed from the network! resetting state...");
-                perform_factory_reset(
-                    &listener_handle,
+            // Unsigned, so only honored as a self-leave: the certificate the
+            // sender connected with must be the one pinned for the device it
+            // names. Kicking someone else takes a signed `Message::Revocation`.
+            let from_target = fingerprint
+                .as_deref()
+                .and_then(|fp| listener_state.device_for_fingerprint(fp))
+                .map_or(false, |id| id == target_id);
+            if !from_target {
+                tracing::warn!("Ignoring PeerRemoval for {} from {}: not sent by that device", target_id, addr);
+                crate::diagnostics::push_diagnostic(
                     &listener_state,
-                    transport_inside.local_addr().map(|a| a.port()).unwrap_or(0)
+                    &listener_handle,
+                    crate::diagnostics::DiagLevel::Detailed,
+                    "mtls",
+                    Some(addr.to_string()),
+                    format!("Ignored unsigned removal of {}", target_id),
                 );
             } else {
                 // Tombstone the removed device so a member that missed this
@@ -1201,6 +1405,12 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                         storage::save_known_peers(listener_handle.app_handle(), &kp);
                     }
                 }
+                {
+                    let mut policies = listener_state.peer_policies.lock().unwrap();
+                    if policies.remove(&target_id).is_some() {
+                        storage::save_peer_policies(listener_handle.app_handle(), &policies);
+                    }
+                }
                 {
                     let mut peers = listener_state.peers.lock().unwrap();
                     if let Some(peer) = peers.remove(&target_id) {
@@ -1226,6 +1436,14 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
              // app-layer auth token (issue #9 follow-up).
              tracing::info!("Received File Request from {}: ID={}, Index={}", addr, req.id, req.file_index);
 
+             // Per-peer policy. The request only names an id, so resolve the
+             // requester from the (mTLS-pinned) source address.
+             let policy = listener_state.policy_for_sender(addr.ip(), None);
+             if !policy.send_to {
+                 tracing::warn!("[Policy] Refusing File Request from {}: sending to this peer is disabled", addr);
+                 return;
+             }
+
              // 2a. Clipboard-blob serve (§3.3): if `req.id` matches a
              // registered large clipboard blob, serve it with
              // `delivery_target = Clipboard{…}` so the receiver lands
@@ -1249,6 +1467,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                           listener_state.serving_clipboard_blobs.clone(),
                                           req_id.clone(),
                                       );
+                                      let stream_state = listener_state.clone();
                                       tauri::async_runtime::spawn(async move {
                                           let _serve_guard = serve_guard;
                                           let mut file = match File::open(&file_path).await {
@@ -1271,30 +1490,22 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                               "Opening QUIC Stream to {} for clipboard-blob '{}' ({} bytes, mime={})",
                                               addr, file_name, file_size, mime_type
                                           );
-                                          match transport_inside.send_file_stream(addr).await {
+                                          let header = crate::protocol::FileStreamHeader {
+                                              id: req_id,
+                                              file_index: req_file_index,
+                                              file_name,
+                                              file_size,
+                                              compressed: is_text,
+                                              delivery_target: crate::protocol::DeliveryTarget::Clipboard {
+                                                  mime_type,
+                                                  width,
+                                                  height,
+                                              },
+                                              sha256: None,
+                                          };
+                                          // Writes the header line, through a relay if need be.
+                                          match crate::relay::open_file_stream(&stream_state, &transport_inside, addr, &header, Some(&file_path)).await {
                                               Ok((_connection, mut stream)) => {
-                                                  let header = crate::protocol::FileStreamHeader {
-                                                      id: req_id,
-                                                      file_index: req_file_index,
-                                                      file_name,
-                                                      file_size,
-                                                      compressed: is_text,
-                                                      delivery_target: crate::protocol::DeliveryTarget::Clipboard {
-                                                          mime_type,
-                                                          width,
-                                                          height,
-                                                      },
-                                                  };
-                                                  if let Ok(h_json) = serde_json::to_string(&header) {
-                                                      if let Err(e) = stream.write_all(h_json.as_bytes()).await {
-                                                          tracing::error!("Header Write Error: {}", e);
-                                                          return;
-                                                      }
-                                                      if let Err(e) = stream.write_all(b"\n").await {
-                                                          tracing::error!("Header Newline Error: {}", e);
-                                                          return;
-                                                      }
-                                                  }
                                                   let mut buf = vec![0u8; 1024 * 1024];
                                                   let start_time = std::time::Instant::now();
                                                   let mut chunks_sent = 0;
@@ -1363,6 +1574,10 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                  }
 
                                  // 2b. Find File Path (existing files path)
+                                 if !policy.file_transfer {
+                                     tracing::warn!("[Policy] Refusing File Request from {}: file transfer with this peer is disabled", addr);
+                                     return;
+                                 }
                                  let path = {
                                      let map = listener_state.local_files.lock().unwrap();
                                      if let Some(paths) = map.get(&req.id) {
@@ -1376,6 +1591,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                       let file_path = PathBuf::from(p_str.clone());
                                       let compress_enabled = listener_state.settings.lock().unwrap().compress_file_transfers;
                                       // 3. Open Stream & Send
+                                      let stream_state = listener_state.clone();
                                       tauri::async_runtime::spawn(async move {
                                            // Open File
                                            let mut file = match File::open(&file_path).await {
@@ -1386,28 +1602,25 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                                            let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
 
                                            tracing::info!("Opening QUIC Stream to {} for file '{}' ({} bytes)", addr, file_name, file_size);
-                                           // Open QUIC Stream
-                                           match transport_inside.send_file_stream(addr).await {
-                                               Ok((_connection, mut stream)) => {
-                                                   // Decide whether to compress this file (deterministic rules).
-                                                   let compressed = compress_enabled
-                                                       && crate::compression::should_compress(&file_name, file_size);
-
-                                                   // Send Header (no auth_token; mTLS authenticates the sender).
-                                                   let header = crate::protocol::FileStreamHeader {
-                                                       id: req.id,
-                                                       file_index: req.file_index,
-                                                       file_name,
-                                                       file_size,
-                                                       compressed,
-                                                       delivery_target: crate::protocol::DeliveryTarget::Disk,
-                                                   };
-
-                                                   if let Ok(h_json) = serde_json::to_string(&header) {
-                                                       if let Err(e) = stream.write_all(h_json.as_bytes()).await { tracing::error!("Header Write Error: {}", e); return; }
-                                                       if let Err(e) = stream.write_all(b"\n").await { tracing::error!("Header Newline Error: {}", e); return; }
-                                                   }
+                                           // Decide whether to compress this file (deterministic rules).
+                                           let compressed = compress_enabled
+                                               && crate::compression::should_compress(&file_name, file_size);
+
+                                           // Header (no auth_token; mTLS, or the relay envelope,
+                                           // authenticates the sender).
+                                           let header = crate::protocol::FileStreamHeader {
+                                               id: req.id,
+                                               file_index: req.file_index,
+                                               file_name,
+                                               file_size,
+                                               compressed,
+                                               delivery_target: crate::protocol::DeliveryTarget::Disk,
+                                               sha256: None,
+                                           };
 
+                                           // Open QUIC Stream and send the header, through a relay if need be
+                                           match crate::relay::open_file_stream(&stream_state, &transport_inside, addr, &header, Some(&file_path)).await {
+                                               Ok((_connection, mut stream)) => {
                                                    // 5. Send File (raw or zstd-compressed depending on flag)
                                                    let mut buf = vec![0u8; 1024 * 1024]; // 1MB chunks
                                                    let mut chunks_sent = 0;
@@ -1491,23 +1704,53 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 let _ = transport_inside.send_message(addr, &pong_data).await;
             }
         }
+        Message::TimedPing { sent_ms } => {
+            tracing::debug!("Received TimedPing from {}. Sending TimedPong.", addr);
+            crate::presence::touch_peer_by_addr(&listener_state, addr);
+            let pong = Message::TimedPong { echo_ms: sent_ms, replied_ms: crate::replay::now_ms() };
+            if let Ok(pong_data) = serde_json::to_vec(&pong) {
+                let _ = transport_inside.send_message(addr, &pong_data).await;
+            }
+        }
         Message::ClusterInfoRequest => {
             // Post-pairing bootstrap reply (T6 → T7). The sender has already
             // passed our mTLS client-cert verifier (we just pinned its cert
             // in `handle_pairing_connection`), so the request is authenticated
             // and we can hand over our cluster state without further checks.
+            //
+            // A member of one of our additional clusters gets that cluster's
+            // info, and only its members (clusters.rs).
+            let requester_context = listener_state
+                .get_peers()
+                .values()
+                .find(|p| p.reachable_at(addr))
+                .and_then(|p| crate::clusters::context_of(&listener_state, &p.id));
+            if let Some(ctx) = requester_context {
+                let info = crate::clusters::context_cluster_info(&listener_state, &ctx);
+                tracing::debug!("Replying to ClusterInfoRequest from {} for cluster '{}'", addr, ctx.name);
+                match serde_json::to_vec(&Message::ClusterInfo(info)) {
+                    Ok(bytes) => {
+                        if let Err(e) = transport_inside.send_message(addr, &bytes).await {
+                            tracing::warn!("Failed to send ClusterInfo to {}: {}", addr, e);
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to serialise ClusterInfo: {}", e),
+                }
+                return;
+            }
             let cluster_id = listener_state.cluster_id.lock().unwrap().clone();
             if cluster_id.is_empty() {
                 tracing::warn!("ClusterInfoRequest from {} but we have no cluster_id", addr);
                 return;
             }
-            let known_peers_vec: Vec<_> = listener_state
+            let mut known_peers_vec: Vec<_> = listener_state
                 .known_peers
                 .lock()
                 .unwrap()
                 .values()
                 .cloned()
                 .collect();
+            crate::clusters::retain_primary_members(&listener_state, &mut known_peers_vec);
             let network_name = listener_state.network_name.lock().unwrap().clone();
             let network_name_version = *listener_state.network_name_version.lock().unwrap();
             let network_name_origin = listener_state.network_name_origin.lock().unwrap().clone();
@@ -1515,6 +1758,8 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
             // converge onto the shared PIN (and persist it) rather than keep
             // its own per-device one. See ClusterInfo::cluster_mode.
             let cluster_mode = listener_state.settings.lock().unwrap().cluster_mode.clone();
+            let revocations = listener_state.revocations.lock().unwrap().clone();
+            let join_restrictions = listener_state.join_restrictions.lock().unwrap().clone();
             let info = crate::protocol::ClusterInfo {
                 cluster_id,
                 known_peers: known_peers_vec,
@@ -1522,6 +1767,8 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 network_name_version,
                 network_name_origin,
                 cluster_mode,
+                revocations,
+                join_restrictions,
             };
             tracing::debug!("Replying to ClusterInfoRequest from {}", addr);
             match serde_json::to_vec(&Message::ClusterInfo(info)) {
@@ -1557,20 +1804,37 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 None => {
                     // Unsolicited ClusterInfo = reply to an anti-entropy
                     // membership-sync request (the sender passed mTLS, so it
-                    // is a paired member). Merge members we're missing.
-                    let local_cluster = listener_state.cluster_id.lock().unwrap().clone();
-                    if local_cluster.is_empty() || info.cluster_id != local_cluster {
+                    // is a paired member). Merge members we're missing, as
+                    // long as it speaks for the cluster it is in with us.
+                    let sender_id = fingerprint.as_deref().and_then(|fp| listener_state.device_for_fingerprint(fp));
+                    if !crate::clusters::accepts_cluster_info(&listener_state, sender_id.as_deref(), &info.cluster_id) {
                         tracing::warn!(
-                            "Ignoring ClusterInfo from {} for foreign/unset cluster ({})",
+                            "Ignoring ClusterInfo from {} for cluster {}: not the cluster it shares with us",
                             addr,
                             info.cluster_id
                         );
                         return;
                     }
-                    let imported = crate::presence::merge_cluster_membership(&listener_state, &info);
+                    // Revocations first, so a removal we missed also keeps
+                    // the removed device out of this merge.
+                    let revoked = crate::revocation::merge_revocations(&listener_state, &info.revocations);
+                    crate::revocation::apply_revocations(&listener_state, &listener_handle, &revoked);
+                    let mut imported = crate::presence::merge_cluster_membership(&listener_state, &info);
+                    if !imported.is_empty() {
+                        // Issuers we only just learned about can be verified now.
+                        let late = crate::revocation::merge_revocations(&listener_state, &info.revocations);
+                        crate::revocation::apply_revocations(&listener_state, &listener_handle, &late);
+                        imported.retain(|p| !listener_state.is_revoked(&p.id, p.fingerprint.as_deref()));
+                    }
+                    crate::pairing::tokens::merge_restrictions(&listener_state, &listener_handle, &info.join_restrictions);
                     if imported.is_empty() {
                         return;
                     }
+                    if *listener_state.cluster_id.lock().unwrap() != info.cluster_id {
+                        for peer in &imported {
+                            crate::clusters::assign_member(&listener_state, &listener_handle, Some(&info.cluster_id), &peer.id);
+                        }
+                    }
                     {
                         let kp = listener_state.known_peers.lock().unwrap();
                         storage::save_known_peers(listener_handle.app_handle(), &kp);
@@ -1584,7 +1848,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                         let t = transport_inside.clone();
                         let a = listener_handle.clone();
                         tauri::async_runtime::spawn(async move {
-                            let _ = crate::net_util::probe_ip(peer.ip, peer.port, s, t, a, false).await;
+                            let _ = crate::net_util::probe_ip(peer.addr(), s, t, a, false).await;
                         });
                     }
                 }
@@ -1596,6 +1860,18 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
             // mDNS, notify the UI, and re-gossip to other peers (excluding the
             // sender). If we are strictly newer, push our register back so the
             // sender converges up.
+            //
+            // The register belongs to the primary cluster; a member of one of
+            // our additional clusters is talking about its own.
+            let from_context = listener_state
+                .get_peers()
+                .values()
+                .find(|p| p.reachable_at(addr))
+                .map_or(false, |p| crate::clusters::context_of(&listener_state, &p.id).is_some());
+            if from_context {
+                tracing::debug!("Ignoring ClusterName from {}: member of an additional cluster", addr);
+                return;
+            }
             let (local_version, local_origin) = {
                 let v = *listener_state.network_name_version.lock().unwrap();
                 let o = listener_state.network_name_origin.lock().unwrap().clone();
@@ -1623,7 +1899,7 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                     .as_ref()
                     .and_then(|t| t.local_addr().ok())
                     .map(|a| a.port())
-                    .unwrap_or(4654);
+                    .unwrap_or(crate::net_util::DEFAULT_PORT);
                 if let Some(discovery) = listener_state.discovery.lock().unwrap().as_mut() {
                     let _ = discovery.register(&device_id, &name, port);
                 }
@@ -1658,25 +1934,57 @@ pub(crate) async fn handle_message(msg: Message, addr: std::net::SocketAddr, lis
                 );
             }
         }
-        Message::Pong => {
-             tracing::debug!("Received Pong from {}. Connection Verified.", addr);
-             crate::presence::touch_peer_by_addr(&listener_state, addr);
-             // Fire deferred join notification if the responding peer was pending
-             let peer_id_opt = {
-                 let peers = listener_state.peers.lock().unwrap();
-                 peers.values().find(|p| p.ip == addr.ip() && p.port == addr.port()).map(|p| (p.id.clone(), p.hostname.clone()))
-             };
-             if let Some((peer_id, hostname)) = peer_id_opt {
-                 let mut pending_joins = listener_state.pending_join_notifications.lock().unwrap();
-                 if pending_joins.remove(&peer_id) {
-                     if listener_state.should_notify()
-                         && listener_state.settings.lock().unwrap().notifications.device_join
-                     {
-                         tracing::info!("[Notification] Deferred 'Device Joined' fired for {} (confirmed by Pong)", hostname);
-                         send_notification(&listener_handle, "Device Joined", &format!("{} has joined your cluster", hostname), false, Some(1), "devices", NotificationPayload::None);
-                     }
-                 }
-             }
+        Message::KeyRotation(payload) => {
+            crate::key_rotation::handle_key_rotation(payload, addr, &listener_state, &listener_handle, &transport_inside).await;
+        }
+        Message::KeyRotationAck { nonce } => {
+            match fingerprint.as_deref().and_then(|fp| listener_state.device_for_fingerprint(fp)) {
+                Some(device_id) => crate::key_rotation::handle_ack(&listener_state, device_id, &nonce),
+                None => tracing::warn!("[KeyRotation] Acknowledgement from unknown sender {}", addr),
+            }
+        }
+        Message::Revocation(rev) => {
+            crate::revocation::handle_revocation(rev, addr, &listener_state, &listener_handle, &transport_inside);
+        }
+        Message::TimedPong { echo_ms, replied_ms } => {
+            crate::replay::record_pong(&listener_state, addr, echo_ms, replied_ms);
+            crate::link::record_pong(&listener_state, &listener_handle, addr, echo_ms);
+            handle_pong(addr, &listener_state, &listener_handle);
+        }
+        Message::Pong => handle_pong(addr, &listener_state, &listener_handle),
+        Message::DeviceInfo { info, want_reply } => {
+            crate::device_info::receive(info, want_reply, addr, &listener_state, &listener_handle, &transport_inside).await;
+        }
+        Message::FileReceived(receipt) => {
+            crate::moves::on_receipt(receipt, addr, &listener_state, &listener_handle).await;
+        }
+        Message::Relay(env) => {
+            // Handled as if it came straight from the originator; the
+            // envelope's signature stands in for its mTLS handshake.
+            if let Some((inner, origin, origin_fp)) = crate::relay::receive(env, addr, &listener_state, &transport_inside).await {
+                Box::pin(handle_message(inner, origin, Some(origin_fp), listener_state, listener_handle, transport_inside)).await;
+            }
+        }
+    }
+}
+
+fn handle_pong(addr: std::net::SocketAddr, listener_state: &AppState, listener_handle: &tauri::AppHandle) {
+    tracing::debug!("Received Pong from {}. Connection Verified.", addr);
+    crate::presence::touch_peer_by_addr(listener_state, addr);
+    // Fire deferred join notification if the responding peer was pending
+    let peer_id_opt = {
+        let peers = listener_state.peers.lock().unwrap();
+        peers.values().find(|p| p.reachable_at(addr)).map(|p| (p.id.clone(), p.hostname.clone()))
+    };
+    if let Some((peer_id, hostname)) = peer_id_opt {
+        let mut pending_joins = listener_state.pending_join_notifications.lock().unwrap();
+        if pending_joins.remove(&peer_id) {
+            if listener_state.should_notify()
+                && listener_state.settings.lock().unwrap().notifications.device_join
+            {
+                tracing::info!("[Notification] Deferred 'Device Joined' fired for {} (confirmed by Pong)", hostname);
+                send_notification(listener_handle, "Device Joined", &format!("{} has joined your cluster", hostname), false, Some(1), "devices", NotificationPayload::None);
+            }
         }
     }
 }
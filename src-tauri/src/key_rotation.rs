@@ -0,0 +1,334 @@
+//! Device certificate rotation with a signed handoff.
+//!
+//! The rotating device generates a fresh cert, announces it to every paired
+//! peer in a [`Message::KeyRotation`] signed by the *old* key, waits for the
+//! peers to acknowledge it, then switches its transport over. A receiver
+//! checks that the old cert is the one it has pinned for that device,
+//! verifies the signature with the old cert's public key, re-pins
+//! `Peer.fingerprint` to the new value, answers with a
+//! [`Message::KeyRotationAck`] carrying the announcement's nonce, and keeps
+//! accepting the old fingerprint for [`ROTATION_GRACE`], so connections
+//! opened just before the switch (in either direction) still complete.
+//!
+//! Nothing here depends on the two clocks agreeing. An announcement can only
+//! move a pin away from the cert it names, so once applied it can't be
+//! replayed; and it travels over mTLS, so only a member could hold one to
+//! replay. The nonce ties acknowledgements to this rotation.
+//!
+//! Only peers that acknowledge count as notified. The rest (offline, or
+//! rejecting the announcement) have to re-pair, exactly as after a factory
+//! reset — the rotating device reports them by hostname.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use rand::RngCore;
+use tauri::Emitter;
+
+use crate::protocol::{KeyRotationPayload, Message};
+use crate::state::{AppState, RetiredFingerprint};
+use crate::transport::{cert_fingerprint, verify_cert_signature, Transport};
+
+/// How long a receiver keeps accepting the retired fingerprint.
+pub const ROTATION_GRACE: Duration = Duration::from_secs(10 * 60);
+
+/// Domain separator for the signed handoff, so a signature over these bytes
+/// can never be replayed as any other kind of proof.
+const ROTATION_DOMAIN: &[u8] = b"clustercut-key-rotation-v2";
+
+/// How long the announcement has to be sent and acknowledged, per rotation.
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome reported to the UI after a rotation.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RotationSummary {
+    pub notified: usize,
+    /// Hostnames of paired peers that didn't acknowledge the announcement
+    /// and will need to re-pair.
+    pub unreachable: Vec<String>,
+}
+
+/// Bytes covered by the old key's signature.
+pub(crate) fn signed_bytes(device_id: &str, new_fingerprint: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ROTATION_DOMAIN.len() + device_id.len() + new_fingerprint.len() + nonce.len() + 2);
+    out.extend_from_slice(ROTATION_DOMAIN);
+    out.push(0);
+    out.extend_from_slice(device_id.as_bytes());
+    out.push(0);
+    out.extend_from_slice(new_fingerprint);
+    out.extend_from_slice(nonce);
+    out
+}
+
+/// Validate an announcement against the fingerprint we have pinned for its
+/// device. Pure so it can be unit-tested without an `AppHandle`.
+pub(crate) fn check_rotation(payload: &KeyRotationPayload, pinned: Option<&[u8]>) -> Result<(), String> {
+    let pinned = pinned.ok_or("no pinned fingerprint for this device")?;
+    if cert_fingerprint(&payload.old_cert) != pinned {
+        return Err("retired cert does not match the pinned fingerprint".to_string());
+    }
+    if payload.new_fingerprint.len() != 32 || payload.new_fingerprint == pinned {
+        return Err("invalid new fingerprint".to_string());
+    }
+    if payload.nonce.len() != 16 {
+        return Err("invalid nonce".to_string());
+    }
+    let message = signed_bytes(&payload.device_id, &payload.new_fingerprint, &payload.nonce);
+    if !verify_cert_signature(&payload.old_cert, payload.scheme, &message, &payload.signature) {
+        return Err("signature does not verify under the retired cert".to_string());
+    }
+    Ok(())
+}
+
+/// Rotate this device's cert: announce, collect acknowledgements, then
+/// switch. Aborts without touching the current cert if there are paired
+/// peers and none of them acknowledged.
+pub(crate) async fn rotate_device_cert(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    transport: &Transport,
+) -> Result<RotationSummary, String> {
+    let (new_cert, new_key) = crate::transport::generate_self_signed_cert().map_err(|e| e.to_string())?;
+    let new_fingerprint = cert_fingerprint(&new_cert);
+    let device_id = state.local_device_id.lock().unwrap().clone();
+    let mut nonce = vec![0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let (scheme, signature) = transport.sign_with_local_key(&signed_bytes(&device_id, &new_fingerprint, &nonce))?;
+    let payload = KeyRotationPayload {
+        device_id,
+        old_cert: transport.local_cert_der(),
+        new_fingerprint,
+        nonce: nonce.clone(),
+        scheme,
+        signature,
+    };
+    let data = serde_json::to_vec(&Message::KeyRotation(payload)).map_err(|e| e.to_string())?;
+
+    // Every paired peer, at its live address when it's online.
+    let targets: Vec<(String, String, std::net::SocketAddr)> = {
+        let known: Vec<crate::peer::Peer> = state
+            .known_peers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.fingerprint.is_some() && !p.id.starts_with("manual-"))
+            .cloned()
+            .collect();
+        let runtime = state.get_peers();
+        known
+            .into_iter()
+            .map(|p| {
+                let live = runtime.get(&p.id).unwrap_or(&p);
+                (p.id.clone(), p.hostname.clone(), std::net::SocketAddr::new(live.ip, live.port))
+            })
+            .collect()
+    };
+
+    let (ack_tx, mut ack_rx) = tokio::sync::mpsc::unbounded_channel();
+    *state.pending_rotation_acks.lock().unwrap() = Some((nonce, ack_tx));
+    for (_, _, addr) in &targets {
+        let transport = transport.clone();
+        let data = data.clone();
+        let addr = *addr;
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = transport.send_message(addr, &data).await {
+                tracing::debug!("[KeyRotation] Announcement to {} failed: {}", addr, e);
+            }
+        });
+    }
+    let wanted: HashSet<&str> = targets.iter().map(|(id, _, _)| id.as_str()).collect();
+    let mut acked: HashSet<String> = HashSet::new();
+    let _ = tokio::time::timeout(ACK_TIMEOUT, async {
+        while acked.len() < wanted.len() {
+            match ack_rx.recv().await {
+                Some(id) if wanted.contains(id.as_str()) => {
+                    acked.insert(id);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+    })
+    .await;
+    state.pending_rotation_acks.lock().unwrap().take();
+
+    let notified = acked.len();
+    let unreachable: Vec<String> = targets
+        .iter()
+        .filter(|(id, _, _)| !acked.contains(id))
+        .map(|(_, hostname, _)| hostname.clone())
+        .collect();
+
+    if !targets.is_empty() && notified == 0 {
+        return Err("No paired device acknowledged the new certificate; it was not rotated.".to_string());
+    }
+
+    // Persist only once the live endpoint serves the new cert: a cert on
+    // disk that never went live would lock us out of peers on restart.
+    transport
+        .rotate_identity(new_cert.clone(), new_key.clone())
+        .map_err(|e| e.to_string())?;
+    crate::storage::save_device_cert(app_handle, &new_cert, &new_key);
+    tracing::info!(
+        "[KeyRotation] Rotated device cert (acknowledged by {}, not by {:?})",
+        notified,
+        unreachable
+    );
+    Ok(RotationSummary { notified, unreachable })
+}
+
+/// Rotating side: `device_id` (authenticated by its connection) has
+/// re-pinned us after the announcement carrying `nonce`.
+pub(crate) fn handle_ack(state: &AppState, device_id: String, nonce: &[u8]) {
+    let pending = state.pending_rotation_acks.lock().unwrap();
+    match pending.as_ref() {
+        Some((expected, tx)) if expected.as_slice() == nonce => {
+            let _ = tx.send(device_id);
+        }
+        _ => tracing::debug!("[KeyRotation] Stray acknowledgement from {}", device_id),
+    }
+}
+
+/// Receiver side of `Message::KeyRotation`.
+pub(crate) async fn handle_key_rotation(
+    payload: KeyRotationPayload,
+    addr: std::net::SocketAddr,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+) {
+    if !repin(&payload, addr, state, app_handle) {
+        return;
+    }
+    let ack = Message::KeyRotationAck { nonce: payload.nonce };
+    if let Ok(data) = serde_json::to_vec(&ack) {
+        if let Err(e) = transport.send_message(addr, &data).await {
+            tracing::warn!("[KeyRotation] Acknowledgement to {} failed: {}", addr, e);
+        }
+    }
+}
+
+/// Check the announcement and move the pin. True once re-pinned.
+fn repin(payload: &KeyRotationPayload, addr: std::net::SocketAddr, state: &AppState, app_handle: &tauri::AppHandle) -> bool {
+    let mut kp = state.known_peers.lock().unwrap();
+    let pinned = kp.get(&payload.device_id).and_then(|p| p.fingerprint.clone());
+    if let Err(reason) = check_rotation(payload, pinned.as_deref()) {
+        drop(kp);
+        tracing::warn!("[KeyRotation] Rejected rotation for {} from {}: {}", payload.device_id, addr, reason);
+        crate::diagnostics::push_diagnostic(
+            state,
+            app_handle,
+            crate::diagnostics::DiagLevel::Detailed,
+            "mtls",
+            Some(addr.to_string()),
+            format!("Rejected certificate rotation for {}: {}", payload.device_id, reason),
+        );
+        return false;
+    }
+    let Some(old_fingerprint) = pinned else {
+        return false;
+    };
+
+    let Some(peer) = kp.get_mut(&payload.device_id) else {
+        return false;
+    };
+    peer.fingerprint = Some(payload.new_fingerprint.clone());
+    let updated = peer.clone();
+    crate::storage::save_known_peers(app_handle, &kp);
+    drop(kp);
+
+    if let Some(live) = state.peers.lock().unwrap().get_mut(&payload.device_id) {
+        live.fingerprint = Some(payload.new_fingerprint.clone());
+    }
+    state.retired_fingerprints.lock().unwrap().push(RetiredFingerprint {
+        device_id: payload.device_id.clone(),
+        ip: addr.ip(),
+        fingerprint: old_fingerprint,
+        retired_at: std::time::Instant::now(),
+    });
+
+    tracing::info!("[KeyRotation] {} rotated its certificate; re-pinned", updated.hostname);
+    let _ = app_handle.emit("peer-update", crate::peer::PeerView::from_peer(&updated));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    }
+
+    fn announcement(old_cert: &[u8], old_key: &[u8], device_id: &str) -> KeyRotationPayload {
+        let (new_cert, _) = crate::transport::generate_self_signed_cert().unwrap();
+        let new_fingerprint = cert_fingerprint(&new_cert);
+        let nonce = vec![9; 16];
+        let (scheme, signature) =
+            crate::transport::sign_with_key(old_key, &signed_bytes(device_id, &new_fingerprint, &nonce)).unwrap();
+        KeyRotationPayload {
+            device_id: device_id.to_string(),
+            old_cert: old_cert.to_vec(),
+            new_fingerprint,
+            nonce,
+            scheme,
+            signature,
+        }
+    }
+
+    #[test]
+    fn accepts_announcement_signed_by_pinned_cert() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let payload = announcement(&cert, &key, "clustercut-a");
+        let pinned = cert_fingerprint(&cert);
+        assert!(check_rotation(&payload, Some(&pinned)).is_ok());
+    }
+
+    #[test]
+    fn rejects_cert_that_is_not_pinned() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let (other, _) = crate::transport::generate_self_signed_cert().unwrap();
+        let payload = announcement(&cert, &key, "clustercut-a");
+        assert!(check_rotation(&payload, Some(&cert_fingerprint(&other))).is_err());
+        assert!(check_rotation(&payload, None).is_err());
+    }
+
+    #[test]
+    fn rejects_forged_or_tampered_announcements() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let (_, attacker_key) = crate::transport::generate_self_signed_cert().unwrap();
+        let pinned = cert_fingerprint(&cert);
+
+        // Claims the victim's cert but is signed by someone else's key.
+        let forged = announcement(&cert, &attacker_key, "clustercut-a");
+        assert!(check_rotation(&forged, Some(&pinned)).is_err());
+
+        // Genuine signature, but the new fingerprint was swapped in transit.
+        let mut swapped = announcement(&cert, &key, "clustercut-a");
+        swapped.new_fingerprint = vec![7; 32];
+        assert!(check_rotation(&swapped, Some(&pinned)).is_err());
+
+        // Signed for a different device id.
+        let mut renamed = announcement(&cert, &key, "clustercut-a");
+        renamed.device_id = "clustercut-b".to_string();
+        assert!(check_rotation(&renamed, Some(&pinned)).is_err());
+
+        // Nonce swapped, so acknowledgements would match another rotation.
+        let mut renonced = announcement(&cert, &key, "clustercut-a");
+        renonced.nonce = vec![1; 16];
+        assert!(check_rotation(&renonced, Some(&pinned)).is_err());
+    }
+
+    #[test]
+    fn an_applied_announcement_cant_be_replayed() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let payload = announcement(&cert, &key, "clustercut-a");
+        assert!(check_rotation(&payload, Some(&cert_fingerprint(&cert))).is_ok());
+        // Once re-pinned, the pin no longer names the cert the replay retires.
+        assert!(check_rotation(&payload, Some(&payload.new_fingerprint)).is_err());
+    }
+}
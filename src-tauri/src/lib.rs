@@ -1,21 +1,35 @@
 mod app;
+mod beacon;
 mod clipboard;
 mod cluster_name;
+mod clusters;
 mod commands;
 mod compression;
+mod device_info;
 mod diagnostics;
 #[cfg(target_os = "linux")]
 mod dbus;
 mod handlers;
+mod key_rotation;
+mod link;
+mod moves;
+mod replay;
+mod revocation;
 mod net_util;
 mod pairing;
 mod discovery;
 mod netmon;
+mod netpolicy;
+mod paths;
 mod peer;
 mod presence;
+mod profile;
 mod protocol;
+mod quiet;
+mod relay;
 mod shortcuts;
 mod state;
+mod status;
 mod storage;
 mod transport;
 mod tray;
@@ -48,6 +62,11 @@ pub(crate) fn get_hostname_internal() -> String {
 
 // Helper to broadcast a new peer to all known peers (Gossip)
 pub(crate) fn send_notification(app_handle: &tauri::AppHandle, title: &str, body: &str, increment_badge: bool, _id: Option<i32>, target_view: &str, _payload: NotificationPayload) {
+    // 0. Held while the desktop is in Do Not Disturb (quiet.rs)
+    if crate::quiet::hold(app_handle, title, body, target_view, &_payload) {
+        return;
+    }
+
     // 1. Windows (Native windows-rs with XML Actions)
     #[cfg(target_os = "windows")]
     {
@@ -724,9 +743,8 @@ pub(crate) fn check_and_notify_leave(app_handle: &tauri::AppHandle, state: &AppS
 
     let notifications = state.settings.lock().unwrap().notifications.clone();
     if notifications.device_leave {
-        let local_net = state.network_name.lock().unwrap().clone();
         if let Some(remote_net) = &peer.network_name {
-            if *remote_net == local_net {
+            if crate::clusters::is_local_cluster_name(state, remote_net) {
                 tracing::info!("[Notification] Device Left: {}", peer.hostname);
                 send_notification(app_handle, "Device Left", &format!("{} has left the cluster", peer.hostname), false, Some(1), "devices", NotificationPayload::None);
             }
@@ -764,9 +782,14 @@ pub(crate) fn perform_factory_reset(app_handle: &tauri::AppHandle, state: &AppSt
     // 1. Reset Config on Disk
     reset_network_state(app_handle);
 
-    // Fresh identity, fresh cluster: tombstones from the previous cluster
-    // life must not suppress membership sync in the next one.
+    // Fresh identity, fresh cluster: tombstones and revocations from the
+    // previous cluster life must not suppress membership sync in the next one.
     state.removed_peer_tombstones.lock().unwrap().clear();
+    state.revocations.lock().unwrap().clear();
+    state.invite_tokens.lock().unwrap().clear();
+    state.join_restrictions.lock().unwrap().clear();
+    state.cluster_contexts.lock().unwrap().clear();
+    state.peer_policies.lock().unwrap().clear();
 
     // 2. Update Runtime State
     {
@@ -843,6 +866,7 @@ pub(crate) fn perform_factory_reset(app_handle: &tauri::AppHandle, state: &AppSt
         let local_id = state.local_device_id.lock().unwrap().clone();
         let new_name = state.network_name.lock().unwrap().clone();
         if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
+             discovery.unregister_contexts();
              let _ = discovery.register(&local_id, &new_name, port);
         }
     }
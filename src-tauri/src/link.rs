@@ -0,0 +1,297 @@
+//! Per-peer link measurements, to tell which device or hop is the slow one.
+//!
+//! - Round-trip time and jitter come from `TimedPong`s: the pong echoes our
+//!   own send time, so the round trip needs no clock agreement with the peer.
+//!   The last `RTT_SAMPLES` are kept.
+//! - Inbound throughput comes from completed file streams the peer sent us.
+//! - `test_connection` adds a bounded speed test: a burst of pings, then a
+//!   discard stream (`DeliveryTarget::Discard`, wire 0.3.7) over
+//!   `clustercut-file`, through a relay when that is how the peer is reached.
+//!   It measures outbound throughput, and the peer records it as inbound.
+//!
+//! Like the path stats in paths.rs, all of this is ours: it lives on
+//! `Peer::link`, which is never serialised, and reaches the UI through
+//! `PeerView::link`.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::peer::PeerView;
+use crate::protocol::{DeliveryTarget, FileStreamHeader, Message};
+use crate::state::AppState;
+use crate::transport::Transport;
+
+/// Round trips kept per peer.
+const RTT_SAMPLES: usize = 16;
+/// Echoes further back than this are stale or forged, not a round trip.
+const MAX_RTT: Duration = Duration::from_secs(60);
+/// Smaller transfers say more about latency than about bandwidth.
+const MIN_THROUGHPUT_BYTES: u64 = 256 * 1024;
+/// Most a speed test sends, and most a receiver reads from one.
+pub(crate) const SPEED_TEST_BYTES: u64 = 16 * 1024 * 1024;
+/// A speed test stops sending after this long, however much it has sent.
+const SPEED_TEST_DURATION: Duration = Duration::from_secs(5);
+/// Wait for the peer to acknowledge the last byte of a speed test.
+const SPEED_TEST_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+const SPEED_TEST_CHUNK: usize = 256 * 1024;
+/// Pings sent by a test, and how long their pongs get to come back.
+const TEST_PINGS: usize = 5;
+const TEST_PING_INTERVAL: Duration = Duration::from_millis(100);
+const TEST_PONG_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Throughput {
+    pub bytes: u64,
+    pub bytes_per_sec: u64,
+    /// Unix seconds the transfer finished.
+    pub at: u64,
+}
+
+impl Throughput {
+    /// None for transfers too small or too quick to mean anything.
+    pub(crate) fn measure(bytes: u64, elapsed: Duration, at: u64) -> Option<Self> {
+        if bytes < MIN_THROUGHPUT_BYTES || elapsed.is_zero() {
+            return None;
+        }
+        let bytes_per_sec = (bytes as f64 / elapsed.as_secs_f64()) as u64;
+        Some(Self { bytes, bytes_per_sec, at })
+    }
+}
+
+/// What we have measured on the link to one peer.
+#[derive(Debug, Clone, Default)]
+pub struct LinkStats {
+    rtt_ms: VecDeque<u64>,
+    inbound: Option<Throughput>,
+    outbound: Option<Throughput>,
+}
+
+/// [`LinkStats`] as the UI sees it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LinkSummary {
+    /// Latest round trip.
+    pub rtt_ms: Option<u64>,
+    pub rtt_min_ms: Option<u64>,
+    pub rtt_avg_ms: Option<u64>,
+    /// Mean change between consecutive round trips.
+    pub jitter_ms: Option<u64>,
+    pub samples: usize,
+    /// Peer to us, from the last sizeable stream it sent.
+    pub inbound: Option<Throughput>,
+    /// Us to the peer, from the last speed test.
+    pub outbound: Option<Throughput>,
+}
+
+impl LinkStats {
+    pub fn record_rtt(&mut self, rtt_ms: u64) {
+        if self.rtt_ms.len() == RTT_SAMPLES {
+            self.rtt_ms.pop_front();
+        }
+        self.rtt_ms.push_back(rtt_ms);
+    }
+
+    pub fn summary(&self) -> LinkSummary {
+        let samples = self.rtt_ms.len();
+        let avg = (samples > 0).then(|| self.rtt_ms.iter().sum::<u64>() / samples as u64);
+        let jitter = (samples > 1).then(|| {
+            let deltas = self.rtt_ms.iter().zip(self.rtt_ms.iter().skip(1)).map(|(a, b)| a.abs_diff(*b));
+            deltas.sum::<u64>() / (samples as u64 - 1)
+        });
+        LinkSummary {
+            rtt_ms: self.rtt_ms.back().copied(),
+            rtt_min_ms: self.rtt_ms.iter().min().copied(),
+            rtt_avg_ms: avg,
+            jitter_ms: jitter,
+            samples,
+            inbound: self.inbound,
+            outbound: self.outbound,
+        }
+    }
+}
+
+/// Apply `f` to the stats of the runtime peer at `addr` and push the
+/// updated view to the UI. False if no peer is at `addr`.
+fn update(state: &AppState, app: &tauri::AppHandle, addr: SocketAddr, f: impl FnOnce(&mut LinkStats)) -> bool {
+    let view = {
+        let mut peers = state.peers.lock().unwrap();
+        let Some(peer) = peers.values_mut().find(|p| p.reachable_at(addr)) else {
+            return false;
+        };
+        f(&mut peer.link);
+        PeerView::from_peer(peer)
+    };
+    let _ = app.emit("peer-update", view);
+    true
+}
+
+/// Record the round trip a `TimedPong` from `addr` closes.
+pub(crate) fn record_pong(state: &AppState, app: &tauri::AppHandle, addr: SocketAddr, echo_ms: u64) {
+    let Some(rtt) = crate::replay::now_ms().checked_sub(echo_ms) else {
+        return;
+    };
+    if rtt > MAX_RTT.as_millis() as u64 {
+        return;
+    }
+    update(state, app, addr, |link| link.record_rtt(rtt));
+}
+
+/// Record a completed stream of `bytes` from the peer at `addr`.
+pub(crate) fn record_inbound(state: &AppState, app: &tauri::AppHandle, addr: SocketAddr, bytes: u64, elapsed: Duration) {
+    let Some(measured) = Throughput::measure(bytes, elapsed, crate::paths::now_secs()) else {
+        return;
+    };
+    tracing::debug!("[Link] {} -> us: {} bytes at {} B/s", addr, bytes, measured.bytes_per_sec);
+    update(state, app, addr, |link| link.inbound = Some(measured));
+}
+
+/// Receive a speed-test stream: read at most `SPEED_TEST_BYTES`, throw them
+/// away and record the rate.
+pub(crate) async fn receive_discard<R: AsyncRead + Unpin>(
+    reader: R,
+    addr: SocketAddr,
+    state: &AppState,
+    app: &tauri::AppHandle,
+) {
+    let start = Instant::now();
+    match tokio::io::copy(&mut reader.take(SPEED_TEST_BYTES), &mut tokio::io::sink()).await {
+        Ok(bytes) => {
+            tracing::info!("[Link] Speed test from {}: {} bytes in {:?}", addr, bytes, start.elapsed());
+            record_inbound(state, app, addr, bytes, start.elapsed());
+        }
+        Err(e) => tracing::warn!("[Link] Speed test from {} failed: {}", addr, e),
+    }
+}
+
+/// Result of `test_connection`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionTest {
+    pub peer_id: String,
+    /// Member the test stream went through, if the peer wasn't reached directly.
+    pub relay_via: Option<String>,
+    /// QUIC's own estimate on the test stream's connection (to the relay,
+    /// when there is one).
+    pub quic_rtt_ms: u64,
+    pub bytes_sent: u64,
+    pub elapsed_ms: u64,
+    /// The peer's stats after the test.
+    pub link: LinkSummary,
+}
+
+/// Ping the peer a few times, then push up to `SPEED_TEST_BYTES` at it for
+/// at most `SPEED_TEST_DURATION` and time how long it takes to be
+/// acknowledged.
+pub(crate) async fn test_connection(
+    state: &AppState,
+    transport: &Transport,
+    app: &tauri::AppHandle,
+    peer_id: &str,
+) -> Result<ConnectionTest, String> {
+    let (addr, version) = {
+        let peers = state.peers.lock().unwrap();
+        let peer = peers.get(peer_id).ok_or_else(|| format!("Device {} is not connected.", peer_id))?;
+        (peer.addr(), peer.protocol_version.clone())
+    };
+    if !crate::net_util::supports_link_test(version.as_deref()) {
+        return Err("This device runs an older ClusterCut that can't take a connection test.".to_string());
+    }
+
+    for _ in 0..TEST_PINGS {
+        let data = serde_json::to_vec(&Message::TimedPing { sent_ms: crate::replay::now_ms() }).map_err(|e| e.to_string())?;
+        if let Err(e) = transport.send_message(addr, &data).await {
+            return Err(format!("Device unreachable: {}", e));
+        }
+        tokio::time::sleep(TEST_PING_INTERVAL).await;
+    }
+    tokio::time::sleep(TEST_PONG_GRACE).await;
+
+    let header = FileStreamHeader {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_index: 0,
+        file_name: "speed-test".to_string(),
+        file_size: SPEED_TEST_BYTES,
+        compressed: false,
+        delivery_target: DeliveryTarget::Discard,
+        sha256: None,
+    };
+    let (connection, mut stream) = crate::relay::open_file_stream(state, transport, addr, &header, None)
+        .await
+        .map_err(|e| format!("Failed to open test stream: {}", e))?;
+    let relay_via = state.peers.lock().unwrap().get(peer_id).and_then(|p| p.relay_via.clone());
+
+    let chunk = vec![0u8; SPEED_TEST_CHUNK];
+    let start = Instant::now();
+    let mut sent = 0u64;
+    while sent < SPEED_TEST_BYTES && start.elapsed() < SPEED_TEST_DURATION {
+        let n = (SPEED_TEST_BYTES - sent).min(SPEED_TEST_CHUNK as u64) as usize;
+        stream.write_all(&chunk[..n]).await.map_err(|e| format!("Test stream failed: {}", e))?;
+        sent += n as u64;
+    }
+    stream.finish().map_err(|e| format!("Test stream failed: {}", e))?;
+    // Counted once the peer has acknowledged everything, not when our send
+    // buffer took it.
+    match tokio::time::timeout(SPEED_TEST_ACK_TIMEOUT, stream.stopped()).await {
+        Ok(Ok(None)) => {}
+        Ok(other) => return Err(format!("Test stream was not acknowledged: {:?}", other)),
+        Err(_) => return Err("Timed out waiting for the device to acknowledge the test.".to_string()),
+    }
+    let elapsed = start.elapsed();
+    let quic_rtt_ms = connection.rtt().as_millis() as u64;
+    connection.close(0u32.into(), b"speed test done");
+
+    let measured = Throughput::measure(sent, elapsed, crate::paths::now_secs());
+    let view = {
+        let mut peers = state.peers.lock().unwrap();
+        let peer = peers.get_mut(peer_id).ok_or_else(|| format!("Device {} went away during the test.", peer_id))?;
+        if measured.is_some() {
+            peer.link.outbound = measured;
+        }
+        PeerView::from_peer(peer)
+    };
+    let link = view.link.clone();
+    let _ = app.emit("peer-update", view);
+    tracing::info!("[Link] Speed test to {}: {} bytes in {:?}, QUIC RTT {} ms", peer_id, sent, elapsed, quic_rtt_ms);
+    Ok(ConnectionTest {
+        peer_id: peer_id.to_string(),
+        relay_via,
+        quic_rtt_ms,
+        bytes_sent: sent,
+        elapsed_ms: elapsed.as_millis() as u64,
+        link,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rtt_window_rolls_and_reports_jitter() {
+        let mut link = LinkStats::default();
+        assert_eq!(link.summary(), LinkSummary::default());
+        for rtt in [10, 14, 12] {
+            link.record_rtt(rtt);
+        }
+        let s = link.summary();
+        assert_eq!((s.rtt_ms, s.rtt_min_ms, s.rtt_avg_ms), (Some(12), Some(10), Some(12)));
+        // |14-10| and |12-14|.
+        assert_eq!(s.jitter_ms, Some(3));
+        for _ in 0..RTT_SAMPLES {
+            link.record_rtt(50);
+        }
+        let s = link.summary();
+        assert_eq!((s.samples, s.rtt_min_ms, s.jitter_ms), (RTT_SAMPLES, Some(50), Some(0)));
+    }
+
+    #[test]
+    fn small_or_instant_transfers_are_not_throughput() {
+        assert_eq!(Throughput::measure(1024, Duration::from_millis(10), 0), None);
+        assert_eq!(Throughput::measure(MIN_THROUGHPUT_BYTES, Duration::ZERO, 0), None);
+        let t = Throughput::measure(4 * 1024 * 1024, Duration::from_secs(2), 7).unwrap();
+        assert_eq!((t.bytes_per_sec, t.at), (2 * 1024 * 1024, 7));
+    }
+}
@@ -0,0 +1,420 @@
+//! Cut-and-paste of files across devices.
+//!
+//! A cut (`ClipboardContent::Files { cut: true }`) goes out like a copy, with
+//! `ClipboardPayload::cut` set. Receivers on 0.3.9+ answer each finished
+//! download of a cut with `Message::FileReceived`, carrying the size and
+//! SHA-256 of what they wrote. With `move_cut_files` on, the sender checks
+//! both against the original, and once a single device has confirmed every
+//! file of the cut, the originals go to the Trash (`trash_moved_files`) or
+//! are deleted, and the cut can no longer be fetched.
+//!
+//! The Trash is the freedesktop one in `$XDG_DATA_HOME/Trash` on Linux, with
+//! the `.trashinfo` file managers need to restore from it, and `~/.Trash` on
+//! macOS. Inside Flatpak `$XDG_DATA_HOME` is the sandbox's own, where no file
+//! manager looks, so the Trash portal does it instead. Files on another
+//! filesystem than the Trash, and every file on Windows, can't be trashed and
+//! are left where they are.
+//!
+//! Receivers write the files of a cut to `Downloads/ClusterCut`
+//! ([`lasting_dir`]), not the download cache: that cache is wiped on every
+//! start and exit, and a receipt for a copy that is about to be wiped would
+//! let the sender delete the only lasting one. A receiver without a
+//! Downloads folder keeps the files in the cache and sends no receipts.
+//!
+//! A cut nobody finishes within `MOVE_TTL` is forgotten; its files stay.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+
+use crate::protocol::{FileReceipt, Message};
+use crate::state::AppState;
+use crate::transport::Transport;
+use crate::NotificationPayload;
+
+/// How long a cut waits for a device to fetch all of it.
+const MOVE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+pub(crate) struct PendingMove {
+    paths: Vec<String>,
+    /// File indices each device has confirmed, by device id.
+    confirmed: HashMap<String, HashSet<usize>>,
+    at: Instant,
+}
+
+impl PendingMove {
+    /// Record that `device` has file `index`; true once it has them all.
+    fn confirm(&mut self, device: &str, index: usize) -> bool {
+        let got = self.confirmed.entry(device.to_string()).or_default();
+        got.insert(index);
+        got.len() == self.paths.len()
+    }
+}
+
+/// Remember a cut we are broadcasting as `id`, if moves are on.
+pub(crate) fn track(state: &AppState, id: &str, paths: &[String]) {
+    if !state.settings.lock().unwrap().move_cut_files {
+        return;
+    }
+    let mut pending = state.pending_moves.lock().unwrap();
+    pending.retain(|_, m| m.at.elapsed() < MOVE_TTL);
+    pending.insert(id.to_string(), PendingMove { paths: paths.to_vec(), confirmed: HashMap::new(), at: Instant::now() });
+}
+
+/// Receiver side: we are about to download the `count` files of cut `id`.
+pub(crate) fn expect_receipts(state: &AppState, id: &str, count: usize) {
+    state.cut_downloads.lock().unwrap().insert(id.to_string(), count);
+}
+
+/// Whether downloads for `id` need a receipt (and so a hash).
+pub(crate) fn wants_receipt(state: &AppState, id: &str) -> bool {
+    state.cut_downloads.lock().unwrap().contains_key(id)
+}
+
+/// Where the files of a cut are written on the receiver, or `None` when
+/// there is no Downloads folder to put them in.
+pub(crate) fn lasting_dir(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    match app.path().download_dir() {
+        Ok(dir) => Some(dir.join("ClusterCut")),
+        Err(e) => {
+            tracing::warn!("[Move] No Downloads folder, so cut files stay in the cache unconfirmed: {}", e);
+            None
+        }
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Receiver side: file `file_index` of cut `id` arrived intact from `addr`.
+/// Tell the sender, if it understands receipts.
+pub(crate) fn downloaded(state: &AppState, app: &tauri::AppHandle, addr: SocketAddr, receipt: FileReceipt) {
+    {
+        let mut downloads = state.cut_downloads.lock().unwrap();
+        let Some(left) = downloads.get_mut(&receipt.id) else {
+            return;
+        };
+        *left = left.saturating_sub(1);
+        if *left == 0 {
+            downloads.remove(&receipt.id);
+        }
+    }
+    let version = state
+        .peers
+        .lock()
+        .unwrap()
+        .values()
+        .find(|p| p.reachable_at(addr))
+        .and_then(|p| p.protocol_version.clone());
+    if !crate::net_util::supports_file_receipts(version.as_deref()) {
+        return;
+    }
+    let Ok(data) = serde_json::to_vec(&Message::FileReceived(receipt)) else {
+        return;
+    };
+    let transport = app.state::<Transport>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = transport.send_message(addr, &data).await {
+            tracing::warn!("[Move] Receipt to {} failed: {}", addr, e);
+        }
+    });
+}
+
+pub(crate) fn file_sha256(path: &Path) -> io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Sender side: `receipt` arrived from `addr`. Once one device holds every
+/// file of the cut, move the originals away.
+pub(crate) async fn on_receipt(receipt: FileReceipt, addr: SocketAddr, state: &AppState, app: &tauri::AppHandle) {
+    let device = {
+        let peers = state.peers.lock().unwrap();
+        peers.values().find(|p| p.reachable_at(addr)).map(|p| (p.id.clone(), p.hostname.clone()))
+    };
+    let Some((device_id, hostname)) = device else {
+        tracing::warn!("[Move] Receipt from unknown address {}", addr);
+        return;
+    };
+    let path = {
+        let pending = state.pending_moves.lock().unwrap();
+        match pending.get(&receipt.id).and_then(|m| m.paths.get(receipt.file_index)) {
+            Some(p) => p.clone(),
+            None => return,
+        }
+    };
+
+    // The receiver's copy must be the file as it is now, not as it was.
+    let (check_path, size, sha256) = (path.clone(), receipt.size, receipt.sha256.clone());
+    let intact = tokio::task::spawn_blocking(move || {
+        let p = Path::new(&check_path);
+        std::fs::metadata(p).is_ok_and(|m| m.len() == size) && file_sha256(p).is_ok_and(|h| h == sha256)
+    })
+    .await
+    .unwrap_or(false);
+    if !intact {
+        tracing::warn!("[Move] {}'s copy of {} doesn't match the original; keeping it", hostname, path);
+        return;
+    }
+
+    let done = {
+        let mut pending = state.pending_moves.lock().unwrap();
+        match pending.get_mut(&receipt.id) {
+            Some(m) if m.confirm(&device_id, receipt.file_index) => pending.remove(&receipt.id),
+            _ => None,
+        }
+    };
+    let Some(done) = done else {
+        return;
+    };
+    let (enabled, trash) = {
+        let s = state.settings.lock().unwrap();
+        (s.move_cut_files, s.trash_moved_files)
+    };
+    if !enabled {
+        return;
+    }
+    state.local_files.lock().unwrap().remove(&receipt.id);
+
+    let paths = done.paths;
+    let count = paths.len();
+    let failed: Vec<String> = tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter(|p| match dispose(Path::new(p), trash) {
+                Ok(()) => false,
+                Err(e) => {
+                    tracing::warn!("[Move] Couldn't remove {}: {}", p, e);
+                    true
+                }
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default();
+
+    tracing::info!("[Move] {} of {} file(s) moved to {}", count - failed.len(), count, hostname);
+    let (title, body) = if failed.is_empty() {
+        let verb = if trash { "The originals are in the Trash." } else { "The originals were deleted." };
+        ("Files Moved", format!("Moved {} file(s) to {}. {}", count, hostname, verb))
+    } else {
+        (
+            "Files Copied, Not Moved",
+            format!("{} has the files, but {} original(s) couldn't be removed and are still here.", hostname, failed.len()),
+        )
+    };
+    crate::send_notification(app, title, &body, false, None, "history", NotificationPayload::None);
+}
+
+fn dispose(path: &Path, trash: bool) -> io::Result<()> {
+    if trash {
+        to_trash(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// `name` with `.n` before its extension: "report.pdf" -> "report.2.pdf".
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+fn numbered(name: &str, n: u32) -> String {
+    let p = Path::new(name);
+    let stem = p.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    match p.extension() {
+        Some(ext) if !stem.is_empty() => format!("{}.{}.{}", stem, n, ext.to_string_lossy()),
+        _ => format!("{}.{}", name, n),
+    }
+}
+
+/// The `.trashinfo` body for `path` (absolute), deleted at `date`
+/// ("YYYY-MM-DDThh:mm:ss", local time).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn trash_info(path: &Path, date: &str) -> Option<String> {
+    let encoded = url::Url::from_file_path(path).ok()?.path().to_string();
+    Some(format!("[Trash Info]\nPath={}\nDeletionDate={}\n", encoded, date))
+}
+
+#[cfg(target_os = "linux")]
+fn deletion_date() -> String {
+    // SAFETY: `time` with a null pointer only returns the time, and
+    // `localtime_r` writes into the `tm` we own.
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+}
+
+/// Move `path` into the home Trash: through the Trash portal inside Flatpak,
+/// by hand everywhere else.
+#[cfg(target_os = "linux")]
+fn to_trash(path: &Path) -> io::Result<()> {
+    if crate::clipboard::is_flatpak() {
+        return portal_trash(path);
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| std::path::PathBuf::from(h).join(".local/share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    trash_into(path, &data_home.join("Trash"))
+}
+
+/// `org.freedesktop.portal.Trash.TrashFile`, which trashes the file behind
+/// an open descriptor in the host's Trash. Blocking.
+#[cfg(target_os = "linux")]
+fn portal_trash(path: &Path) -> io::Result<()> {
+    use std::os::fd::AsFd;
+
+    let file = std::fs::File::open(path)?;
+    let conn = zbus::blocking::Connection::session().map_err(io::Error::other)?;
+    let reply = conn
+        .call_method(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            Some("org.freedesktop.portal.Trash"),
+            "TrashFile",
+            &(zbus::zvariant::Fd::from(file.as_fd()),),
+        )
+        .map_err(io::Error::other)?;
+    match reply.body().deserialize::<u32>() {
+        Ok(1) => Ok(()),
+        Ok(_) => Err(io::Error::other("the Trash portal refused the file")),
+        Err(e) => Err(io::Error::other(e)),
+    }
+}
+
+/// Move `path` into the freedesktop Trash at `trash`: reserve a name by
+/// creating `info/<name>.trashinfo` exclusively, then rename the file into
+/// `files/<name>`.
+#[cfg(target_os = "linux")]
+fn trash_into(path: &Path, trash: &Path) -> io::Result<()> {
+    use std::io::Write;
+
+    let (files, info) = (trash.join("files"), trash.join("info"));
+    std::fs::create_dir_all(&files)?;
+    std::fs::create_dir_all(&info)?;
+
+    let abs = std::fs::canonicalize(path)?;
+    let name = abs
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?
+        .to_string_lossy()
+        .into_owned();
+    let body = trash_info(&abs, &deletion_date())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path can't be encoded"))?;
+
+    for n in 1..1000 {
+        let candidate = if n == 1 { name.clone() } else { numbered(&name, n) };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        let mut f = match std::fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let dest = files.join(&candidate);
+        let moved = if dest.exists() {
+            Err(io::Error::from(io::ErrorKind::AlreadyExists))
+        } else {
+            f.write_all(body.as_bytes()).and_then(|()| std::fs::rename(&abs, &dest))
+        };
+        match moved {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                let _ = std::fs::remove_file(&info_path);
+                if e.kind() != io::ErrorKind::AlreadyExists {
+                    return Err(e);
+                }
+            }
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "no free name in the Trash"))
+}
+
+/// Move `path` into `~/.Trash`, as the Finder does (without "Put Back").
+#[cfg(target_os = "macos")]
+fn to_trash(path: &Path) -> io::Result<()> {
+    let home = std::env::var_os("HOME").ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    let trash = std::path::PathBuf::from(home).join(".Trash");
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?
+        .to_string_lossy()
+        .into_owned();
+    let dest = (1..1000)
+        .map(|n| trash.join(if n == 1 { name.clone() } else { numbered(&name, n) }))
+        .find(|d| !d.exists())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "no free name in the Trash"))?;
+    std::fs::rename(path, dest)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn to_trash(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "no Trash support on this platform"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_move_completes_when_one_device_has_every_file() {
+        let mut m = PendingMove { paths: vec!["/a".into(), "/b".into()], confirmed: HashMap::new(), at: Instant::now() };
+        assert!(!m.confirm("laptop", 0));
+        assert!(!m.confirm("desktop", 1));
+        assert!(!m.confirm("laptop", 0));
+        assert!(m.confirm("laptop", 1));
+    }
+
+    #[test]
+    fn trash_names_and_info() {
+        assert_eq!(numbered("report.pdf", 2), "report.2.pdf");
+        assert_eq!(numbered("Makefile", 3), "Makefile.3");
+        assert_eq!(numbered(".bashrc", 2), ".bashrc.2");
+        #[cfg(unix)]
+        assert_eq!(
+            trash_info(Path::new("/home/me/My File.txt"), "2026-10-19T09:30:00").unwrap(),
+            "[Trash Info]\nPath=/home/me/My%20File.txt\nDeletionDate=2026-10-19T09:30:00\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn trashing_moves_the_file_and_records_where_it_was() {
+        let dir = std::env::temp_dir().join(format!("clustercut_trash_test_{}", std::process::id()));
+        let trash = dir.join("Trash");
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("notes.txt");
+        std::fs::write(&original, b"cut me").unwrap();
+        std::fs::create_dir_all(trash.join("files")).unwrap();
+        std::fs::write(trash.join("files/notes.txt"), b"older").unwrap();
+
+        let expected = url::Url::from_file_path(std::fs::canonicalize(&original).unwrap()).unwrap().path().to_string();
+        trash_into(&original, &trash).unwrap();
+
+        let moved = std::fs::read(trash.join("files/notes.2.txt"));
+        let info = std::fs::read_to_string(trash.join("info/notes.2.txt.trashinfo"));
+        let gone = !original.exists();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(gone);
+        assert_eq!(moved.unwrap(), b"cut me");
+        let info = info.unwrap();
+        assert!(info.starts_with(&format!("[Trash Info]\nPath={}\nDeletionDate=", expected)));
+    }
+}
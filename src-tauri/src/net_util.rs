@@ -7,6 +7,21 @@ use crate::state::AppState;
 use crate::storage::save_known_peers;
 use crate::transport::Transport;
 
+/// The port we listen on unless `AppSettings::listen_port` says otherwise,
+/// and the one assumed for a typed address without a port.
+pub(crate) const DEFAULT_PORT: u16 = 4654;
+
+/// Id of the placeholder `probe_ip` stores for an unidentified address. The
+/// port is only part of it when it isn't the default, so placeholders from
+/// older builds keep their ids.
+pub(crate) fn manual_id(addr: std::net::SocketAddr) -> String {
+    if addr.port() == DEFAULT_PORT {
+        format!("manual-{}", addr.ip())
+    } else {
+        format!("manual-{}", std::net::SocketAddr::new(addr.ip(), addr.port()))
+    }
+}
+
 /// Parse a "MAJOR.MINOR.PATCH" string. Accepts a trailing `-prerelease`
 /// segment (digits-only prefix is taken from the patch component, so e.g.
 /// `0.3.0-alpha.1` parses as (0, 3, 0)). Returns `None` for unparseable
@@ -45,6 +60,41 @@ pub(crate) fn supports_cluster_name(version: Option<&str>) -> bool {
     parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 4))
 }
 
+/// True if a peer advertising `version` answers `Message::TimedPing`
+/// (introduced in wire 0.3.5). Older peers only get plain `Ping`s.
+pub(crate) fn supports_clock_sync(version: Option<&str>) -> bool {
+    let Some(v) = version else { return false };
+    parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 5))
+}
+
+/// True if a peer advertising `version` handles `Message::Relay` (introduced
+/// in wire 0.3.6), both as a relay and as the final recipient.
+pub(crate) fn supports_relay(version: Option<&str>) -> bool {
+    let Some(v) = version else { return false };
+    parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 6))
+}
+
+/// True if a peer advertising `version` accepts `DeliveryTarget::Discard`
+/// streams (introduced in wire 0.3.7), i.e. can take a connection test.
+pub(crate) fn supports_link_test(version: Option<&str>) -> bool {
+    let Some(v) = version else { return false };
+    parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 7))
+}
+
+/// True if a peer advertising `version` understands `Message::DeviceInfo`
+/// (introduced in wire 0.3.8).
+pub(crate) fn supports_device_info(version: Option<&str>) -> bool {
+    let Some(v) = version else { return false };
+    parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 8))
+}
+
+/// True if a peer advertising `version` understands `Message::FileReceived`
+/// (introduced in wire 0.3.9).
+pub(crate) fn supports_file_receipts(version: Option<&str>) -> bool {
+    let Some(v) = version else { return false };
+    parse_protocol_version(v).map_or(false, |parsed| parsed >= (0, 3, 9))
+}
+
 pub(crate) fn gossip_peer(
     new_peer: &Peer,
     state: &AppState,
@@ -54,12 +104,17 @@ pub(crate) fn gossip_peer(
     let peers = state.get_peers();
     let msg = Message::PeerDiscovery(new_peer.clone());
     let data = serde_json::to_vec(&msg).unwrap_or_default();
+    // Only the new peer's own cluster hears about it (clusters.rs).
+    let cluster = crate::clusters::cluster_id_of(state, &new_peer.id);
 
     for p in peers.values() {
         // Don't gossip to the new peer itself
         if p.id == new_peer.id {
             continue;
         }
+        if crate::clusters::cluster_id_of(state, &p.id) != cluster {
+            continue;
+        }
         let addr = std::net::SocketAddr::new(p.ip, p.port);
         if Some(addr) == exclude_addr {
             continue;
@@ -121,6 +176,10 @@ pub(crate) fn broadcast_cluster_name(
         if Some(addr) == exclude_addr {
             continue;
         }
+        // The register is the primary cluster's name.
+        if crate::clusters::context_of(state, &p.id).is_some() {
+            continue;
+        }
         send_cluster_name_to(
             addr,
             p.protocol_version.as_deref(),
@@ -139,14 +198,14 @@ pub(crate) fn broadcast_cluster_name(
 /// kicks) would otherwise toast "Connection Failed" for every offline peer
 /// on every pass.
 pub(crate) async fn probe_ip(
-    ip: std::net::IpAddr,
-    port: u16,
+    addr: std::net::SocketAddr,
     state: AppState,
     transport: Transport,
     app_handle: tauri::AppHandle,
     notify: bool,
 ) -> bool {
-    let addr = std::net::SocketAddr::new(ip, port);
+    let ip = addr.ip();
+    let port = addr.port();
 
     // The probed peer records us from this PeerDiscovery and its next
     // heartbeat (<=5s) carries its own record back, which is what actually
@@ -156,6 +215,7 @@ pub(crate) async fn probe_ip(
     let network_name = state.network_name.lock().unwrap().clone();
 
     // Send OUR info so they can add us.
+    let exclude = state.settings.lock().unwrap().interface_exclude.clone();
     let my_peer = Peer {
         id: local_id.clone(),
         ip: transport.local_addr().unwrap().ip(),
@@ -168,6 +228,12 @@ pub(crate) async fn probe_ip(
         signature: None,
         fingerprint: Some(transport.local_fingerprint()),
         protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+        addresses: crate::paths::local_candidates(&exclude, transport.local_addr().unwrap().port()),
+        reaches: Vec::new(),
+        relay_via: None,
+        status: None,
+        device: None,
+        link: Default::default(),
     };
 
     let msg = Message::PeerDiscovery(my_peer);
@@ -204,25 +270,33 @@ pub(crate) async fn probe_ip(
                    // row until the peer's next heartbeat and churn
                    // known_peers.json twice per reconnection.
                      let mut peers = state.known_peers.lock().unwrap();
-                     let id = format!("manual-{}", ip);
+                     let id = manual_id(addr);
                      let ip_belongs_to_known_peer = peers
                          .values()
-                         .any(|p| p.ip == ip && !p.id.starts_with("manual-"));
+                         .any(|p| p.reachable_at(addr) && !p.id.starts_with("manual-"));
                      if ip_belongs_to_known_peer {
                          tracing::debug!("Probe to {} OK — IP belongs to a known peer; no placeholder.", addr);
                      } else if !peers.contains_key(&id) {
+                         let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
                          let peer = Peer {
                              id: id.clone(),
                              ip,
                              port,
-                             hostname: format!("Manual ({})", ip),
-                             last_seen: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+                             hostname: format!("Manual ({})", id.trim_start_matches("manual-")),
+                             last_seen: now,
                              is_trusted: false,
                              is_manual: true,
                              network_name: None,
                              signature: None,
                              fingerprint: None,
                              protocol_version: None,
+                             // Holds the IPv6 scope id `ip` can't.
+                             addresses: vec![crate::peer::PeerAddress::new(addr, now)],
+                             reaches: Vec::new(),
+                             relay_via: None,
+                             status: None,
+                             device: None,
+                             link: Default::default(),
                          };
                          peers.insert(id.clone(), peer.clone());
                          let _ = app_handle.emit("peer-update", crate::peer::PeerView::from_peer(&peer));
@@ -277,8 +351,21 @@ pub(crate) async fn probe_ip(
 #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 const FIREWALL_RULE_SENTINEL: &str = "ClusterCut sync v0.3.1 (UDP+TCP pair)";
 
+/// The `localport` value covering `listen_port` and its fallbacks (see
+/// `AppSettings::listen_port_range`), e.g. `4654-4664`.
 #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
-pub(crate) fn configure_windows_firewall() {
+pub(crate) fn firewall_port_spec(listen_port: u16, range: u16) -> String {
+    let first = if listen_port == 0 { DEFAULT_PORT } else { listen_port };
+    let last = first.saturating_add(range);
+    if last == first {
+        first.to_string()
+    } else {
+        format!("{}-{}", first, last)
+    }
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(crate) fn configure_windows_firewall(ports: String) {
     // Check if the firewall rule already exists (does not require elevation).
     // Existing rules from 0.2.x are inbound-only with no description, which
     // surfaces in the wild as "Windows accepts QUIC packets but its outbound
@@ -295,7 +382,7 @@ pub(crate) fn configure_windows_firewall() {
             if output.status.success()
                 && stdout.contains("UDP")
                 && stdout.contains("TCP")
-                && stdout.contains("4654")
+                && stdout.contains(&ports)
                 && stdout.contains(FIREWALL_RULE_SENTINEL)
             {
                 tracing::info!(
@@ -317,6 +404,8 @@ pub(crate) fn configure_windows_firewall() {
     // Five netsh calls in one elevated session:
     //   1. Delete any existing "ClusterCut" rules so we don't end up with
     //      duplicate / stale rules layered on top of each other.
+    // All four cover the listen port and its fallbacks (`ports`, e.g.
+    // 4654-4664); the numbers below are the defaults.
     //   2. Add inbound UDP/4654 (any source IP, edge traversal allowed) —
     //      QUIC steady-state traffic.
     //   3. Add outbound UDP/4654. Windows defaults to "allow outbound" but
@@ -334,10 +423,10 @@ pub(crate) fn configure_windows_firewall() {
     // pre-flight check above can detect it.
     let cmd = format!(
         "netsh advfirewall firewall delete rule name=\\\"ClusterCut\\\" 2>$null; \
-         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=in action=allow protocol=UDP localport=4654 remoteip=any profile=any edge=yes description=\\\"{sentinel}\\\"; \
-         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=out action=allow protocol=UDP localport=4654 remoteip=any profile=any description=\\\"{sentinel}\\\"; \
-         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=in action=allow protocol=TCP localport=4654 remoteip=any profile=any edge=yes description=\\\"{sentinel}\\\"; \
-         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=out action=allow protocol=TCP localport=4654 remoteip=any profile=any description=\\\"{sentinel}\\\"",
+         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=in action=allow protocol=UDP localport={ports} remoteip=any profile=any edge=yes description=\\\"{sentinel}\\\"; \
+         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=out action=allow protocol=UDP localport={ports} remoteip=any profile=any description=\\\"{sentinel}\\\"; \
+         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=in action=allow protocol=TCP localport={ports} remoteip=any profile=any edge=yes description=\\\"{sentinel}\\\"; \
+         netsh advfirewall firewall add rule name=\\\"ClusterCut\\\" dir=out action=allow protocol=TCP localport={ports} remoteip=any profile=any description=\\\"{sentinel}\\\"",
         sentinel = FIREWALL_RULE_SENTINEL
     );
 
@@ -370,23 +459,302 @@ pub(crate) fn is_local_ip(ip: std::net::IpAddr) -> bool {
 }
 
 // Approximate same-subnet check: same first three IPv4 octets as one of our
-// own NIC IPs. We don't have netmask info from `local-ip-address`, so this is
-// a /24 heuristic — fine for the home/SMB networks ClusterCut targets but
-// will miss /23 or wider segments.
+// own NIC IPs, or for IPv6 the same /64 (or any link-local address). We don't
+// have netmask info from `local-ip-address`, so the IPv4 side is a /24
+// heuristic — fine for the home/SMB networks ClusterCut targets but will miss
+// /23 or wider segments. IPv6 LANs are /64 by design.
 pub(crate) fn is_in_local_subnet(ip: std::net::IpAddr) -> bool {
-    let target = match ip {
-        std::net::IpAddr::V4(v4) => v4.octets(),
-        std::net::IpAddr::V6(_) => return false,
-    };
+    if let std::net::IpAddr::V6(v6) = ip {
+        if is_v6_link_local(v6) {
+            return true;
+        }
+    }
     if let Ok(ifaces) = list_afinet_netifas() {
         for (_name, local_ip) in ifaces {
-            if let std::net::IpAddr::V4(local_v4) = local_ip {
-                let local = local_v4.octets();
-                if local[0] == target[0] && local[1] == target[1] && local[2] == target[2] {
-                    return true;
+            let same = match (local_ip, ip) {
+                (std::net::IpAddr::V4(local), std::net::IpAddr::V4(target)) => {
+                    local.octets()[0..3] == target.octets()[0..3]
+                }
+                (std::net::IpAddr::V6(local), std::net::IpAddr::V6(target)) => {
+                    !local.is_loopback() && local.segments()[0..4] == target.segments()[0..4]
                 }
+                _ => false,
+            };
+            if same {
+                return true;
             }
         }
     }
     false
 }
+
+/// Probe every host in `net` on the default port, 50 at a time. Used for a
+/// range typed into "Add device" and for the configured sweep ranges
+/// (beacon.rs) on networks where mDNS is blocked. Found devices answer our
+/// `PeerDiscovery` and surface through the usual handler path.
+pub(crate) async fn sweep_range(
+    net: ipnetwork::IpNetwork,
+    state: AppState,
+    transport: Transport,
+    app_handle: tauri::AppHandle,
+    notify: bool,
+) -> Result<(), String> {
+    // An IPv6 LAN is a /64: far too many hosts to sweep one by one.
+    if let ipnetwork::IpNetwork::V6(v6) = net {
+        if v6.prefix() < 112 {
+            return Err("IPv6 ranges can only be scanned up to /112. Enter the device's address instead.".to_string());
+        }
+    }
+    tracing::info!("Scanning range: {}", net);
+    let ips: Vec<std::net::IpAddr> = net.iter().collect();
+    // Devices on the default port, plus any sharing our own non-default one.
+    let mut ports = vec![DEFAULT_PORT];
+    let listen_port = state.settings.lock().unwrap().listen_port;
+    if listen_port != 0 && listen_port != DEFAULT_PORT {
+        ports.push(listen_port);
+    }
+
+    // Scan in small batches with concurrency
+    let batch_size = 50;
+    for chunk in ips.chunks(batch_size) {
+        let mut tasks = Vec::new();
+        for ip_addr in chunk {
+            let s = state.clone();
+            let t = transport.clone();
+            let a = app_handle.clone();
+            let addr = *ip_addr;
+
+            // Skip own IP
+            if let Ok(local) = t.local_addr() {
+                if local.ip() == addr {
+                    continue;
+                }
+            }
+
+            let ports = ports.clone();
+            tasks.push(tauri::async_runtime::spawn(async move {
+                for port in ports {
+                    probe_ip(std::net::SocketAddr::new(addr, port), s.clone(), t.clone(), a.clone(), notify).await;
+                }
+            }));
+        }
+        futures::future::join_all(tasks).await;
+    }
+    Ok(())
+}
+
+/// Interface rules applied when `AppSettings::interface_exclude` is unset:
+/// container, VM and bridge interfaces whose addresses no peer can reach.
+pub(crate) const DEFAULT_INTERFACE_EXCLUDE: &[&str] = &[
+    "docker*",
+    "br-*",
+    "veth*",
+    "virbr*",
+    "vmnet*",
+    "vboxnet*",
+    "cni*",
+    "flannel*",
+    "podman*",
+    "lxcbr*",
+    "lxdbr*",
+    "vEthernet (WSL*",
+    "vEthernet (Default Switch)*",
+];
+
+/// Case-insensitive match of an interface name against a pattern in which
+/// `*` stands for any run of characters.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    // Classic greedy wildcard match, backtracking to the last `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((sp, sn)) = star {
+            p = sp + 1;
+            n = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Whether an interface address is excluded by `rules`. A rule that parses
+/// as a CIDR block (`172.17.0.0/16`) matches by address, anything else is an
+/// interface-name pattern.
+pub(crate) fn interface_excluded(name: &str, ip: std::net::IpAddr, rules: &[String]) -> bool {
+    rules.iter().map(|r| r.trim()).filter(|r| !r.is_empty()).any(|rule| {
+        match rule.parse::<ipnetwork::IpNetwork>() {
+            Ok(net) if rule.contains('/') => net.contains(ip),
+            _ => name_matches(rule, name),
+        }
+    })
+}
+
+/// Every address we can usefully be reached on: all interfaces not excluded
+/// by `rules`, minus loopback, unspecified and IPv4 link-local. The
+/// default-route address comes first, then IPv4 before IPv6. Falls back to
+/// the default-route address alone if enumeration fails or every interface
+/// is excluded, so we never advertise nothing.
+pub(crate) fn advertised_ips(rules: &[String]) -> Result<Vec<std::net::IpAddr>, local_ip_address::Error> {
+    let primary = local_ip_address::local_ip().or_else(|_| local_ip_address::local_ipv6())?;
+    let mut ips: Vec<std::net::IpAddr> = Vec::new();
+    if let Ok(ifaces) = list_afinet_netifas() {
+        for (name, ip) in ifaces {
+            let usable = match ip {
+                std::net::IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_unspecified() && !v4.is_link_local(),
+                std::net::IpAddr::V6(v6) => !v6.is_loopback() && !v6.is_unspecified(),
+            };
+            if usable && !interface_excluded(&name, ip, rules) && !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+    // Stable sort: primary, then v4, then v6, otherwise in interface order.
+    ips.sort_by_key(|ip| (*ip != primary, ip.is_ipv6()));
+    if ips.is_empty() {
+        ips.push(primary);
+    }
+    Ok(ips)
+}
+
+/// fe80::/10. Only reachable through the interface it was seen on, so it
+/// needs a scope id to connect to.
+pub(crate) fn is_v6_link_local(ip: std::net::Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// Resolve an IPv6 zone (`%3`, `%eth0`) to a scope id.
+fn parse_zone(zone: &str) -> Option<u32> {
+    if let Ok(index) = zone.parse::<u32>() {
+        return Some(index);
+    }
+    #[cfg(unix)]
+    {
+        let name = std::ffi::CString::new(zone).ok()?;
+        // SAFETY: `name` is a valid NUL-terminated string for the call.
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index != 0 {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Parse a peer address as typed into "Add device" or reported by mDNS:
+/// `ip`, `ip:port`, `[v6]:port`, each IPv6 form optionally with a zone
+/// (`fe80::1%eth0`, `[fe80::1%3]:4654`). `default_port` applies when none is
+/// given. IPv4-mapped IPv6 comes back as plain IPv4, and a link-local IPv6
+/// address without a zone is refused since it can't be connected to.
+pub(crate) fn parse_peer_addr(input: &str, default_port: u16) -> Result<std::net::SocketAddr, String> {
+    let input = input.trim();
+    let (host, port) = if let Some(rest) = input.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').ok_or("Missing ']' in IPv6 address")?;
+        let port = match after {
+            "" => default_port,
+            _ => after
+                .strip_prefix(':')
+                .and_then(|p| p.parse().ok())
+                .ok_or("Invalid port")?,
+        };
+        (host, port)
+    } else {
+        match input.rsplit_once(':') {
+            // Exactly one colon: IPv4 (or hostname-less) with a port. Bare
+            // IPv6 has several and no port.
+            Some((host, port)) if !host.contains(':') => (host, port.parse().map_err(|_| "Invalid port")?),
+            _ => (input, default_port),
+        }
+    };
+    let (ip, zone) = match host.split_once('%') {
+        Some((ip, zone)) => (ip, Some(zone)),
+        None => (host, None),
+    };
+    let ip: std::net::IpAddr = ip.parse().map_err(|_| format!("'{}' is not an IP address", ip))?;
+    match ip {
+        std::net::IpAddr::V4(v4) => Ok(std::net::SocketAddr::new(v4.into(), port)),
+        std::net::IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return Ok(std::net::SocketAddr::new(v4.into(), port));
+            }
+            let scope_id = match zone {
+                Some(zone) => parse_zone(zone).ok_or_else(|| format!("Unknown interface '{}'", zone))?,
+                None => 0,
+            };
+            if scope_id == 0 && is_v6_link_local(v6) {
+                return Err("Link-local IPv6 needs an interface, e.g. fe80::1%eth0".to_string());
+            }
+            Ok(std::net::SocketAddrV6::new(v6, port, 0, scope_id).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v4_and_v6_peer_addresses() {
+        let sa = |s: &str| s.parse::<std::net::SocketAddr>().unwrap();
+        assert_eq!(parse_peer_addr("192.168.1.5", 4654), Ok(sa("192.168.1.5:4654")));
+        assert_eq!(parse_peer_addr(" 192.168.1.5:5000 ", 4654), Ok(sa("192.168.1.5:5000")));
+        assert_eq!(parse_peer_addr("2001:db8::5", 4654), Ok(sa("[2001:db8::5]:4654")));
+        assert_eq!(parse_peer_addr("[2001:db8::5]:5000", 4654), Ok(sa("[2001:db8::5]:5000")));
+        assert_eq!(parse_peer_addr("[2001:db8::5]", 4654), Ok(sa("[2001:db8::5]:4654")));
+        assert_eq!(parse_peer_addr("[::ffff:10.0.0.7]:4654", 1), Ok(sa("10.0.0.7:4654")));
+        assert_eq!(parse_peer_addr("fe80::1%3", 4654), Ok(sa("[fe80::1%3]:4654")));
+        assert_eq!(parse_peer_addr("[fe80::1%3]:5000", 4654), Ok(sa("[fe80::1%3]:5000")));
+    }
+
+    #[test]
+    fn rejects_unusable_peer_addresses() {
+        assert!(parse_peer_addr("fe80::1", 4654).is_err(), "link-local needs a zone");
+        assert!(parse_peer_addr("[2001:db8::5]:x", 4654).is_err());
+        assert!(parse_peer_addr("[2001:db8::5", 4654).is_err());
+        assert!(parse_peer_addr("printer.local", 4654).is_err());
+        assert!(parse_peer_addr("fe80::1%no-such-interface0", 4654).is_err());
+    }
+
+    #[test]
+    fn interface_rules_match_names_and_cidrs() {
+        let mut rules: Vec<String> = DEFAULT_INTERFACE_EXCLUDE.iter().map(|r| r.to_string()).collect();
+        rules.push("10.99.0.0/16".to_string());
+        let ip = |s: &str| s.parse::<std::net::IpAddr>().unwrap();
+        assert!(interface_excluded("docker0", ip("172.17.0.1"), &rules));
+        assert!(interface_excluded("br-3f2a9c", ip("172.18.0.1"), &rules));
+        assert!(interface_excluded("vEthernet (WSL (Hyper-V firewall))", ip("172.20.0.1"), &rules));
+        assert!(interface_excluded("wg0", ip("10.99.4.2"), &rules), "CIDR rules match by address");
+        assert!(!interface_excluded("eth0", ip("192.168.1.5"), &rules));
+        assert!(!interface_excluded("Wi-Fi", ip("192.168.1.6"), &rules));
+        assert!(!interface_excluded("en0", ip("2001:db8::5"), &rules));
+    }
+
+    #[test]
+    fn name_patterns_are_case_insensitive_wildcards() {
+        assert!(name_matches("VETH*", "veth12ab"));
+        assert!(name_matches("*bridge*", "my-bridge0"));
+        assert!(name_matches("eth0", "ETH0"));
+        assert!(!name_matches("eth0", "eth01"));
+        assert!(!name_matches("br-*", "bridge0"));
+        assert!(name_matches("*", ""));
+    }
+
+    #[test]
+    fn manual_ids_include_only_non_default_ports() {
+        let sa = |s: &str| s.parse::<std::net::SocketAddr>().unwrap();
+        assert_eq!(manual_id(sa("192.168.1.5:4654")), "manual-192.168.1.5");
+        assert_eq!(manual_id(sa("192.168.1.5:4655")), "manual-192.168.1.5:4655");
+        assert_eq!(manual_id(sa("[2001:db8::5]:5000")), "manual-[2001:db8::5]:5000");
+        assert_eq!(firewall_port_spec(4654, 10), "4654-4664");
+        assert_eq!(firewall_port_spec(5000, 0), "5000");
+        assert_eq!(firewall_port_spec(0, 2), "4654-4656");
+    }
+}
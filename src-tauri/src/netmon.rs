@@ -141,6 +141,47 @@ pub fn check_and_handle_network_change(state: &AppState, app_handle: &tauri::App
     start_recovery_tasks(app_handle);
 }
 
+/// Re-publish our mDNS records (primary and every cluster context) with the
+/// current interface addresses. No-op while advertising is turned off.
+pub(crate) fn reregister_discovery(state: &AppState) {
+    if !state.settings.lock().unwrap().mdns_advertising {
+        return;
+    }
+    let device_id = state.local_device_id.lock().unwrap().clone();
+    let network_name = state.network_name.lock().unwrap().clone();
+
+    let transport_opt = state.transport.lock().unwrap().clone();
+    if let Some(transport) = transport_opt {
+        if let Ok(addr) = transport.local_addr() {
+            let mut discovery = state.discovery.lock().unwrap();
+            if let Some(disc) = discovery.as_mut() {
+                match disc.register(&device_id, &network_name, addr.port()) {
+                    Ok(()) => tracing::info!("[Netmon] Re-registered mDNS service"),
+                    Err(e) => tracing::error!("[Netmon] Failed to re-register mDNS: {}", e),
+                }
+                crate::clusters::register_contexts(disc, state, &device_id, addr.port());
+            }
+        }
+    }
+}
+
+/// Re-register mDNS when an interface appeared or vanished, or an address
+/// on one changed. A second NIC or a VPN coming up rarely moves the default
+/// route, so `check_and_handle_network_change` doesn't see it, but the
+/// record should still list the new path. Polled from the heartbeat.
+pub fn check_interface_change(state: &AppState) {
+    let changed = state
+        .discovery
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|d| d.addresses_changed());
+    if changed {
+        tracing::info!("[Netmon] Usable interface addresses changed — re-advertising");
+        reregister_discovery(state);
+    }
+}
+
 /// Spawns async tasks for mDNS re-registration and peer re-probing.
 /// Must be called from an async context with access to AppHandle.
 pub fn start_recovery_tasks(app_handle: &tauri::AppHandle) {
@@ -169,23 +210,10 @@ pub fn start_recovery_tasks(app_handle: &tauri::AppHandle) {
         }
 
         // Re-register mDNS
-        {
-            let device_id = state.local_device_id.lock().unwrap().clone();
-            let network_name = state.network_name.lock().unwrap().clone();
-
-            let transport_opt = state.transport.lock().unwrap().clone();
-            if let Some(transport) = transport_opt {
-                if let Ok(addr) = transport.local_addr() {
-                    let mut discovery = state.discovery.lock().unwrap();
-                    if let Some(disc) = discovery.as_mut() {
-                        match disc.register(&device_id, &network_name, addr.port()) {
-                            Ok(()) => tracing::info!("[Netmon] Re-registered mDNS service"),
-                            Err(e) => tracing::error!("[Netmon] Failed to re-register mDNS: {}", e),
-                        }
-                    }
-                }
-            }
-        }
+        reregister_discovery(&state);
+
+        // A new network may fall under a different policy rule.
+        crate::netpolicy::wake(&state);
 
         // Re-probe absent known peers with PeerDiscovery bursts. The old code
         // sent bare `Message::Ping`s here — but Ping/Pong were presence-inert
@@ -348,11 +376,47 @@ async fn linux_nm_network_monitor(state: AppState, app_handle: tauri::AppHandle)
             } else if nm_state <= 40 {
                 on_network_down(&state);
             }
+            // The primary connection may have changed without crossing
+            // the up/down threshold (CONNECTED_SITE ↔ CONNECTED_GLOBAL).
+            crate::netpolicy::wake(&state);
         }
     }
     Ok(())
 }
 
+/// Id of NetworkManager's primary connection ("Home Wi-Fi"), for
+/// `NetworkMatch::NmConnection` rules. None without NetworkManager or
+/// while disconnected.
+#[cfg(target_os = "linux")]
+pub(crate) async fn nm_primary_connection(state: &AppState) -> Option<String> {
+    let conn = state.system_bus().await?;
+    let nm: zbus::Proxy<'_> = zbus::proxy::Builder::new(&conn)
+        .destination("org.freedesktop.NetworkManager")
+        .ok()?
+        .path("/org/freedesktop/NetworkManager")
+        .ok()?
+        .interface("org.freedesktop.NetworkManager")
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+    let primary: zbus::zvariant::OwnedObjectPath = nm.get_property("PrimaryConnection").await.ok()?;
+    if primary.as_str() == "/" {
+        return None;
+    }
+    let active: zbus::Proxy<'_> = zbus::proxy::Builder::new(&conn)
+        .destination("org.freedesktop.NetworkManager")
+        .ok()?
+        .path(primary.into_inner())
+        .ok()?
+        .interface("org.freedesktop.NetworkManager.Connection.Active")
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+    active.get_property::<String>("Id").await.ok()
+}
+
 
 // ── Windows Implementation ─────────────────────────────────────────────────
 
@@ -0,0 +1,451 @@
+//! Network-aware sync policies.
+//!
+//! A rule names a network (a CIDR block holding one of our addresses, the
+//! default gateway's MAC, or on Linux a NetworkManager connection id) and
+//! what to switch while we're on it: auto-send, auto-receive, file transfer
+//! and advertising. The first matching rule wins.
+//!
+//! A rule flips the real settings, the same ones the tray and D-Bus toggle,
+//! so nothing downstream needs to know about policies. What it changed is
+//! kept in `AppSettings::network_policy_active` (persisted, so a restart on
+//! the same network doesn't lose it) and undone when we leave the network,
+//! except for a setting the user has changed by hand in the meantime.
+//!
+//! Rules are re-checked on every netmon recovery, when they are edited, and
+//! every `POLL_INTERVAL` for changes netmon doesn't report (a roam between
+//! two access points of different networks keeps connectivity up).
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::state::AppState;
+use crate::storage::AppSettings;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How a rule recognises its network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum NetworkMatch {
+    /// A CIDR block containing one of our interface addresses.
+    Subnet(String),
+    /// The default gateway's MAC address, in any common notation.
+    GatewayMac(String),
+    /// A NetworkManager connection id, e.g. "Home Wi-Fi". Linux only.
+    NmConnection(String),
+}
+
+/// A setting a rule can switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyToggle {
+    AutoSend,
+    AutoReceive,
+    FileTransfer,
+    Advertise,
+}
+
+impl PolicyToggle {
+    fn get(self, settings: &AppSettings) -> bool {
+        match self {
+            PolicyToggle::AutoSend => settings.auto_send,
+            PolicyToggle::AutoReceive => settings.auto_receive,
+            PolicyToggle::FileTransfer => settings.enable_file_transfer,
+            PolicyToggle::Advertise => settings.mdns_advertising,
+        }
+    }
+
+    fn set(self, settings: &mut AppSettings, on: bool) {
+        match self {
+            PolicyToggle::AutoSend => settings.auto_send = on,
+            PolicyToggle::AutoReceive => settings.auto_receive = on,
+            PolicyToggle::FileTransfer => settings.enable_file_transfer = on,
+            PolicyToggle::Advertise => settings.mdns_advertising = on,
+        }
+    }
+}
+
+/// One user rule. A `None` setting is left alone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkPolicy {
+    pub name: String,
+    #[serde(rename = "match")]
+    pub network: NetworkMatch,
+    #[serde(default)]
+    pub auto_send: Option<bool>,
+    #[serde(default)]
+    pub auto_receive: Option<bool>,
+    #[serde(default)]
+    pub file_transfer: Option<bool>,
+    #[serde(default)]
+    pub advertise: Option<bool>,
+}
+
+impl NetworkPolicy {
+    fn targets(&self) -> Vec<(PolicyToggle, bool)> {
+        [
+            (PolicyToggle::AutoSend, self.auto_send),
+            (PolicyToggle::AutoReceive, self.auto_receive),
+            (PolicyToggle::FileTransfer, self.file_transfer),
+            (PolicyToggle::Advertise, self.advertise),
+        ]
+        .into_iter()
+        .filter_map(|(toggle, value)| value.map(|v| (toggle, v)))
+        .collect()
+    }
+
+    fn matches(&self, net: &NetworkIdentity) -> bool {
+        match &self.network {
+            NetworkMatch::Subnet(cidr) => match cidr.trim().parse::<ipnetwork::IpNetwork>() {
+                Ok(block) => net.addresses.iter().any(|ip| block.contains(*ip)),
+                Err(_) => false,
+            },
+            NetworkMatch::GatewayMac(mac) => {
+                normalize_mac(mac).is_some_and(|mac| net.gateway_mac.as_deref() == Some(mac.as_str()))
+            }
+            NetworkMatch::NmConnection(id) => net.nm_connection.as_deref() == Some(id.trim()),
+        }
+    }
+}
+
+/// A setting a rule changed, and what it was before.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyChange {
+    pub setting: PolicyToggle,
+    pub before: bool,
+    pub applied: bool,
+}
+
+/// The rule in force and what it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivePolicy {
+    pub rule: String,
+    pub changes: Vec<PolicyChange>,
+}
+
+/// What we know about the network we're on. Also shown in settings so the
+/// user can see what a rule would match.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkIdentity {
+    pub addresses: Vec<IpAddr>,
+    pub gateway_mac: Option<String>,
+    pub nm_connection: Option<String>,
+}
+
+/// `aa:bb:cc:dd:ee:ff` from colon, dash or bare-hex notation (single-digit
+/// octets too, as macOS `arp` prints them). None for anything else and for
+/// the all-zero address of an incomplete ARP entry.
+fn normalize_mac(input: &str) -> Option<String> {
+    let input = input.trim();
+    let octets: Vec<&str> = if input.contains(':') || input.contains('-') {
+        input.split([':', '-']).collect()
+    } else if input.len() == 12 && input.is_ascii() {
+        (0..12).step_by(2).map(|i| &input[i..i + 2]).collect()
+    } else {
+        return None;
+    };
+    if octets.len() != 6 {
+        return None;
+    }
+    let mut bytes = [0u8; 6];
+    for (byte, octet) in bytes.iter_mut().zip(&octets) {
+        if octet.is_empty() || octet.len() > 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(octet, 16).ok()?;
+    }
+    if bytes == [0; 6] {
+        return None;
+    }
+    Some(bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"))
+}
+
+/// The MAC on the ARP-table line for `ip`. Handles Linux `/proc/net/arp`,
+/// macOS `arp -n` and Windows `arp -a` output alike.
+fn arp_lookup(table: &str, ip: IpAddr) -> Option<String> {
+    let ip = ip.to_string();
+    table.lines().find_map(|line| {
+        let tokens: Vec<&str> = line.split_whitespace().map(|t| t.trim_matches(|c| c == '(' || c == ')')).collect();
+        if !tokens.contains(&ip.as_str()) {
+            return None;
+        }
+        tokens.iter().find_map(|t| normalize_mac(t))
+    })
+}
+
+/// The default gateway from `/proc/net/route`, whose addresses are hex in
+/// host byte order.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_route(table: &str) -> Option<IpAddr> {
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(fields[2], 16).ok().filter(|g| *g != 0)?;
+        Some(IpAddr::from(gateway.to_ne_bytes()))
+    })
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args);
+    // Polled, so don't flash a console window each time.
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let out = cmd.output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn gateway_mac() -> Option<String> {
+    let gateway = parse_proc_route(&std::fs::read_to_string("/proc/net/route").ok()?)?;
+    arp_lookup(&std::fs::read_to_string("/proc/net/arp").ok()?, gateway)
+}
+
+#[cfg(target_os = "macos")]
+fn gateway_mac() -> Option<String> {
+    let route = command_output("route", &["-n", "get", "default"])?;
+    let gateway: IpAddr = route.lines().find_map(|l| l.trim().strip_prefix("gateway:"))?.trim().parse().ok()?;
+    arp_lookup(&command_output("arp", &["-n", &gateway.to_string()])?, gateway)
+}
+
+#[cfg(target_os = "windows")]
+fn gateway_mac() -> Option<String> {
+    let route = command_output("route", &["print", "-4", "0.0.0.0"])?;
+    let gateway: IpAddr = route.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 3 && fields[0] == "0.0.0.0" && fields[1] == "0.0.0.0" {
+            fields[2].parse().ok()
+        } else {
+            None
+        }
+    })?;
+    arp_lookup(&command_output("arp", &["-a", &gateway.to_string()])?, gateway)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn gateway_mac() -> Option<String> {
+    None
+}
+
+/// Look up the current network. The gateway and NetworkManager lookups
+/// are skipped unless asked for, since no rule may need them.
+pub(crate) async fn current_identity(state: &AppState, with_gateway: bool, with_nm: bool) -> NetworkIdentity {
+    let mut addresses: Vec<IpAddr> = Vec::new();
+    if let Ok(ifaces) = local_ip_address::list_afinet_netifas() {
+        for (_name, ip) in ifaces {
+            if !ip.is_loopback() && !ip.is_unspecified() && !addresses.contains(&ip) {
+                addresses.push(ip);
+            }
+        }
+    }
+    let gateway_mac = if with_gateway {
+        tokio::task::spawn_blocking(gateway_mac).await.ok().flatten()
+    } else {
+        None
+    };
+    #[cfg(target_os = "linux")]
+    let nm_connection = if with_nm { crate::netmon::nm_primary_connection(state).await } else { None };
+    #[cfg(not(target_os = "linux"))]
+    let nm_connection = {
+        let _ = (state, with_nm);
+        None
+    };
+    NetworkIdentity { addresses, gateway_mac, nm_connection }
+}
+
+/// Move `settings` from the active rule (if any) to `matched`: undo what
+/// the old rule changed, then apply the new one. Returns false when
+/// nothing needed doing.
+fn transition(settings: &mut AppSettings, matched: Option<&NetworkPolicy>) -> bool {
+    let current = settings.network_policy_active.take();
+    let unchanged = match (&current, matched) {
+        (Some(active), Some(rule)) => {
+            active.rule == rule.name
+                && active.changes.iter().map(|c| (c.setting, c.applied)).eq(rule.targets())
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        settings.network_policy_active = current;
+        return false;
+    }
+
+    if let Some(active) = current {
+        for change in &active.changes {
+            // A hand-made change since the rule applied wins.
+            if change.setting.get(settings) == change.applied {
+                change.setting.set(settings, change.before);
+            }
+        }
+    }
+    let applied = matched.map(|rule| ActivePolicy {
+        rule: rule.name.clone(),
+        changes: rule
+            .targets()
+            .into_iter()
+            .map(|(setting, applied)| {
+                let before = setting.get(settings);
+                setting.set(settings, applied);
+                PolicyChange { setting, before, applied }
+            })
+            .collect(),
+    });
+    settings.network_policy_active = applied;
+    true
+}
+
+/// Re-check the rules against the current network and apply the result.
+async fn evaluate(state: &AppState, app_handle: &tauri::AppHandle) {
+    let (rules, active) = {
+        let s = state.settings.lock().unwrap();
+        (s.network_policies.clone(), s.network_policy_active.is_some())
+    };
+    if rules.is_empty() && !active {
+        return;
+    }
+    let with_gateway = rules.iter().any(|r| matches!(r.network, NetworkMatch::GatewayMac(_)));
+    let with_nm = rules.iter().any(|r| matches!(r.network, NetworkMatch::NmConnection(_)));
+    let identity = current_identity(state, with_gateway, with_nm).await;
+    let matched = rules.iter().find(|r| r.matches(&identity));
+
+    let (settings, was_advertising) = {
+        let mut s = state.settings.lock().unwrap();
+        let was_advertising = s.mdns_advertising;
+        if !transition(&mut s, matched) {
+            return;
+        }
+        (s.clone(), was_advertising)
+    };
+    match &settings.network_policy_active {
+        Some(active) => tracing::info!("[Policy] Network matches '{}'; applied {} setting(s)", active.rule, active.changes.len()),
+        None => tracing::info!("[Policy] No rule matches this network; settings restored"),
+    }
+    crate::storage::save_settings(app_handle, &settings);
+    let _ = app_handle.emit("settings-changed", settings.clone());
+
+    if settings.mdns_advertising != was_advertising {
+        if settings.mdns_advertising {
+            crate::netmon::reregister_discovery(state);
+        } else if let Some(disc) = state.discovery.lock().unwrap().as_mut() {
+            disc.unregister();
+        }
+    }
+
+    #[cfg(desktop)]
+    crate::tray::update_tray_menu(app_handle);
+}
+
+/// Apply network rules for the life of the app.
+pub(crate) async fn run(state: AppState, app_handle: tauri::AppHandle) {
+    loop {
+        evaluate(&state, &app_handle).await;
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = state.network_policy_wake.notified() => {}
+        }
+    }
+}
+
+/// Re-check the rules now instead of at the next poll.
+pub(crate) fn wake(state: &AppState) {
+    state.network_policy_wake.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, network: NetworkMatch) -> NetworkPolicy {
+        NetworkPolicy { name: name.to_string(), network, auto_send: None, auto_receive: None, file_transfer: None, advertise: None }
+    }
+
+    #[test]
+    fn macs_normalize_across_notations() {
+        let mac = Some("0a:1b:2c:3d:4e:5f".to_string());
+        assert_eq!(normalize_mac("0A:1B:2C:3D:4E:5F"), mac);
+        assert_eq!(normalize_mac("0a-1b-2c-3d-4e-5f"), mac);
+        assert_eq!(normalize_mac("0a1b2c3d4e5f"), mac);
+        assert_eq!(normalize_mac("a:1b:2c:3d:4e:5f"), mac);
+        assert_eq!(normalize_mac("00:00:00:00:00:00"), None);
+        assert_eq!(normalize_mac("192.168.1.1"), None);
+        assert_eq!(normalize_mac("0a:1b:2c"), None);
+    }
+
+    #[test]
+    fn gateway_is_found_in_route_and_arp_tables() {
+        let gw: IpAddr = "192.168.1.1".parse().unwrap();
+        let hex = format!("{:08X}", u32::from_ne_bytes([192, 168, 1, 1]));
+        let route = format!(
+            "Iface\tDestination\tGateway\tFlags\n\
+             wlan0\t0000A8C0\t00000000\t0001\n\
+             wlan0\t00000000\t{}\t0003\n",
+            hex
+        );
+        assert_eq!(parse_proc_route(&route), Some(gw));
+
+        let mac = Some("0a:1b:2c:3d:4e:5f".to_string());
+        let linux = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                     192.168.1.10     0x1         0x0         00:00:00:00:00:00     *        wlan0\n\
+                     192.168.1.1      0x1         0x2         0a:1b:2c:3d:4e:5f     *        wlan0\n";
+        assert_eq!(arp_lookup(linux, gw), mac);
+        let macos = "? (192.168.1.1) at a:1b:2c:3d:4e:5f on en0 ifscope [ethernet]\n";
+        assert_eq!(arp_lookup(macos, gw), mac);
+        let windows = "\nInterface: 192.168.1.20 --- 0x7\n  Internet Address      Physical Address      Type\n  192.168.1.1           0a-1b-2c-3d-4e-5f     dynamic\n";
+        assert_eq!(arp_lookup(windows, gw), mac);
+        assert_eq!(arp_lookup(linux, "10.0.0.1".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn rules_match_on_subnet_gateway_or_connection() {
+        let net = NetworkIdentity {
+            addresses: vec!["192.168.1.20".parse().unwrap()],
+            gateway_mac: Some("0a:1b:2c:3d:4e:5f".to_string()),
+            nm_connection: Some("Home".to_string()),
+        };
+        assert!(rule("a", NetworkMatch::Subnet("192.168.1.0/24".to_string())).matches(&net));
+        assert!(!rule("b", NetworkMatch::Subnet("10.0.0.0/8".to_string())).matches(&net));
+        assert!(!rule("c", NetworkMatch::Subnet("bogus".to_string())).matches(&net));
+        assert!(rule("d", NetworkMatch::GatewayMac("0A-1B-2C-3D-4E-5F".to_string())).matches(&net));
+        assert!(rule("e", NetworkMatch::NmConnection("Home".to_string())).matches(&net));
+        assert!(!rule("f", NetworkMatch::NmConnection("Work".to_string())).matches(&net));
+    }
+
+    #[test]
+    fn leaving_a_network_restores_settings_but_keeps_manual_changes() {
+        let mut settings = AppSettings::default();
+        let mut public = rule("public", NetworkMatch::Subnet("10.0.0.0/8".to_string()));
+        public.advertise = Some(false);
+        public.auto_receive = Some(false);
+
+        assert!(transition(&mut settings, Some(&public)));
+        assert!(!settings.mdns_advertising && !settings.auto_receive);
+        assert!(!transition(&mut settings, Some(&public)));
+
+        // The user turns receiving back on while still there.
+        settings.auto_receive = true;
+        settings.auto_send = false;
+        assert!(transition(&mut settings, None));
+        assert!(settings.mdns_advertising && settings.auto_receive);
+        assert!(!settings.auto_send);
+        assert_eq!(settings.network_policy_active, None);
+
+        // Switching rules undoes the first before applying the second.
+        let mut home = rule("home", NetworkMatch::Subnet("192.168.0.0/16".to_string()));
+        home.auto_send = Some(true);
+        transition(&mut settings, Some(&public));
+        assert!(transition(&mut settings, Some(&home)));
+        assert!(settings.mdns_advertising && settings.auto_receive && settings.auto_send);
+        assert_eq!(settings.network_policy_active.as_ref().map(|a| a.rule.as_str()), Some("home"));
+    }
+}
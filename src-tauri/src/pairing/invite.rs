@@ -0,0 +1,229 @@
+//! `clustercut://pair` invite links and their QR rendering.
+//!
+//! An invite carries everything a joiner would otherwise have to find or
+//! type: the responder's address, the SHA-256 fingerprint of its cert and,
+//! optionally, a single-use invite token (see tokens.rs) to pair with. The
+//! fingerprint is checked against the SPAKE2-authenticated T3 identity, so
+//! a link (or QR code) that reaches the wrong device — or a device
+//! impersonating the intended one with a leaked PIN — fails before the
+//! joiner reveals its own identity.
+//!
+//! ```text
+//! clustercut://pair?addr=203.0.113.7:4654&fp=<64 hex chars>&token=<secret>
+//! ```
+
+use base64::Engine;
+use std::io::Cursor;
+
+/// Pixels per QR module in the rendered PNG.
+const QR_MODULE_PX: u32 = 8;
+/// Quiet zone around the code, in modules (the spec minimum).
+const QR_QUIET_MODULES: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PairingInvite {
+    /// `IP[:port]`, in the form `start_pairing` accepts as an address override.
+    pub addr: String,
+    /// Lowercase hex SHA-256 of the responder's cert.
+    pub fingerprint: String,
+    /// Invite token code to use instead of a typed PIN, if the inviter
+    /// chose to embed one.
+    pub token: Option<String>,
+}
+
+/// What the frontend gets back from `create_pairing_invite`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PairingInviteView {
+    pub uri: String,
+    /// Base64 PNG of the URI as a QR code.
+    pub qr_png: String,
+}
+
+pub(crate) fn encode_fingerprint(fp: &[u8]) -> String {
+    fp.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parse a 64-char hex fingerprint. Colons and case are tolerated so a value
+/// copied from a settings screen also works.
+pub(crate) fn decode_fingerprint(hex: &str) -> Option<Vec<u8>> {
+    let hex: String = hex.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+impl PairingInvite {
+    pub fn to_uri(&self) -> String {
+        let mut url = url::Url::parse("clustercut://pair").expect("static invite base");
+        {
+            let mut q = url.query_pairs_mut();
+            q.append_pair("addr", &self.addr);
+            q.append_pair("fp", &self.fingerprint);
+            if let Some(token) = &self.token {
+                q.append_pair("token", token);
+            }
+        }
+        url.to_string()
+    }
+
+    pub fn parse(uri: &str) -> Result<Self, String> {
+        let url = url::Url::parse(uri.trim()).map_err(|e| format!("Invalid invite link: {}", e))?;
+        if url.scheme() != "clustercut" || url.host_str() != Some("pair") {
+            return Err("Not a ClusterCut pairing invite".to_string());
+        }
+        let mut addr = None;
+        let mut fingerprint = None;
+        let mut token = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "addr" => addr = Some(value.into_owned()),
+                "fp" => fingerprint = Some(value.into_owned()),
+                "token" if !value.is_empty() => token = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        let addr = addr.ok_or("Invite is missing the device address")?;
+        if addr.parse::<std::net::SocketAddr>().is_err() && addr.parse::<std::net::IpAddr>().is_err() {
+            return Err(format!("Invalid address in invite: {}", addr));
+        }
+        let fingerprint = fingerprint
+            .as_deref()
+            .and_then(decode_fingerprint)
+            .map(|fp| encode_fingerprint(&fp))
+            .ok_or("Invite has a missing or malformed fingerprint")?;
+        Ok(Self { addr, fingerprint, token })
+    }
+}
+
+/// Render `data` as a black-on-white QR code PNG.
+pub(crate) fn render_qr_png(data: &str) -> Result<Vec<u8>, String> {
+    let code = qrcode::QrCode::new(data.as_bytes()).map_err(|e| e.to_string())?;
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let side = (width + 2 * QR_QUIET_MODULES) * QR_MODULE_PX;
+    let img = image::GrayImage::from_fn(side, side, |x, y| {
+        let mx = (x / QR_MODULE_PX) as i64 - QR_QUIET_MODULES as i64;
+        let my = (y / QR_MODULE_PX) as i64 - QR_QUIET_MODULES as i64;
+        let dark = mx >= 0
+            && my >= 0
+            && (mx as u32) < width
+            && (my as u32) < width
+            && colors[(my as u32 * width + mx as u32) as usize] == qrcode::Color::Dark;
+        image::Luma([if dark { 0 } else { 255 }])
+    });
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Build an invite for this device. `addr` overrides the advertised address
+/// (e.g. a VPN or port-forwarded one); otherwise the primary local IP is used.
+/// With `include_token`, a fresh single-use invite token rides along so the
+/// joiner doesn't have to type anything, and the link stops working once
+/// used or after an hour.
+#[tauri::command]
+pub(crate) fn create_pairing_invite(
+    addr: Option<String>,
+    include_token: bool,
+    state: tauri::State<'_, crate::state::AppState>,
+    transport: tauri::State<'_, crate::transport::Transport>,
+    app_handle: tauri::AppHandle,
+) -> Result<PairingInviteView, String> {
+    let port = transport.local_addr().map(|a| a.port()).map_err(|e| e.to_string())?;
+    let addr = match addr.filter(|a| !a.trim().is_empty()) {
+        Some(a) => a.trim().to_string(),
+        None => {
+            let ip = local_ip_address::local_ip().map_err(|e| format!("No local address to advertise: {}", e))?;
+            std::net::SocketAddr::new(ip, port).to_string()
+        }
+    };
+    let mut invite = PairingInvite {
+        addr,
+        fingerprint: encode_fingerprint(&transport.local_fingerprint()),
+        token: None,
+    };
+    // Validates a user-supplied override the same way a scanner would, before
+    // a token is spent on it.
+    PairingInvite::parse(&invite.to_uri())?;
+    if include_token {
+        let token = super::tokens::mint(
+            &state,
+            &app_handle,
+            Some("Invite link".to_string()),
+            super::tokens::INVITE_LINK_TOKEN_TTL_SECS,
+            1,
+            super::tokens::TokenRestrictions::default(),
+        )?;
+        invite.token = Some(token.code());
+    }
+    let uri = invite.to_uri();
+    let qr_png = base64::engine::general_purpose::STANDARD.encode(render_qr_png(&uri)?);
+    Ok(PairingInviteView { uri, qr_png })
+}
+
+/// Decode an invite link (from a deep link, a pasted string or a scanned QR
+/// code) for the join flow.
+#[tauri::command]
+pub(crate) fn parse_pairing_invite(uri: String) -> Result<PairingInvite, String> {
+    PairingInvite::parse(&uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invite(token: Option<&str>) -> PairingInvite {
+        PairingInvite {
+            addr: "192.168.1.20:4654".to_string(),
+            fingerprint: encode_fingerprint(&[0xab; 32]),
+            token: token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn uri_round_trips_with_and_without_token() {
+        for inv in [invite(None), invite(Some("a b&c=d"))] {
+            let uri = inv.to_uri();
+            assert!(uri.starts_with("clustercut://pair?"));
+            assert_eq!(PairingInvite::parse(&uri).unwrap(), inv);
+        }
+    }
+
+    #[test]
+    fn parse_accepts_bare_ip_and_colon_fingerprint() {
+        let colons = vec!["AB"; 32].join(":");
+        let uri = format!("clustercut://pair?addr=10.8.0.5&fp={}", colons);
+        let inv = PairingInvite::parse(&uri).unwrap();
+        assert_eq!(inv.addr, "10.8.0.5");
+        assert_eq!(decode_fingerprint(&inv.fingerprint), Some(vec![0xab; 32]));
+        assert_eq!(inv.token, None);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_invites() {
+        let fp = encode_fingerprint(&[1; 32]);
+        for uri in [
+            format!("https://pair?addr=10.0.0.1&fp={}", fp),
+            format!("clustercut://action/show?addr=10.0.0.1&fp={}", fp),
+            format!("clustercut://pair?fp={}", fp),
+            format!("clustercut://pair?addr=not-an-ip&fp={}", fp),
+            "clustercut://pair?addr=10.0.0.1&fp=abcd".to_string(),
+            "clustercut://pair?addr=10.0.0.1".to_string(),
+        ] {
+            assert!(PairingInvite::parse(&uri).is_err(), "{} should be rejected", uri);
+        }
+    }
+
+    #[test]
+    fn qr_png_decodes_to_a_square_image() {
+        let png = render_qr_png(&invite(Some("123456")).to_uri()).unwrap();
+        let img = image::load_from_memory(&png).unwrap();
+        assert_eq!(img.width(), img.height());
+        assert_eq!(img.width() % QR_MODULE_PX, 0);
+    }
+}
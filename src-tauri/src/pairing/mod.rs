@@ -1,4 +1,8 @@
 mod crypto;
+pub(crate) mod invite;
+pub(crate) mod ratelimit;
+pub(crate) mod sas;
+pub(crate) mod tokens;
 
 pub(crate) use crypto::{
     derive_pair_subkeys, finish_spake2, fresh_pair_nonce, pair_aead_decrypt,
@@ -9,16 +13,18 @@ use crate::state::AppState;
 use crate::transport::Transport;
 use tauri::Emitter;
 
-/// True if the pairing listener has tripped its global AEAD-failure
-/// lockout and is refusing inbound pairing connections until manually
-/// re-armed. See WIRE-PROTOCOL-0.3.1 §H1.
+/// True while the rate limiter's global failure ceiling has pairing paused
+/// for everyone. The pause lifts by itself, or on `rearm_pairing`; see
+/// ratelimit.rs. Per-source backoff doesn't count: it only affects the
+/// source that earned it.
 #[tauri::command]
 pub(crate) fn is_pairing_locked_out(state: tauri::State<'_, AppState>) -> bool {
     state.is_pairing_locked_out()
 }
 
-/// Clear the pairing lockout and reset the failure counter. Invoked by the
-/// frontend when the user explicitly re-arms via the lockout banner / modal.
+/// Manual override on top of the rate limiter: lift a global pause and
+/// forget every source's failures and backoff. Invoked by the frontend when
+/// the user re-arms via the lockout banner instead of waiting.
 #[tauri::command]
 pub(crate) fn rearm_pairing(state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
     state.rearm_pairing();
@@ -28,8 +34,8 @@ pub(crate) fn rearm_pairing(state: tauri::State<'_, AppState>, app_handle: tauri
 }
 
 /// Read the user's "accept inbound pairing" flag. The pairing listener is
-/// gated on this flag AND on `pairing_locked_out` — both must be clear for
-/// inbound SPAKE to proceed. See issue #16.
+/// gated on this flag AND on the rate limiter (ratelimit.rs) — both must
+/// admit a connection for inbound SPAKE to proceed. See issue #16.
 #[tauri::command]
 pub(crate) fn get_pairing_accept(state: tauri::State<'_, AppState>) -> bool {
     state.settings.lock().unwrap().pairing_accept_enabled
@@ -37,7 +43,7 @@ pub(crate) fn get_pairing_accept(state: tauri::State<'_, AppState>) -> bool {
 
 /// Write the user's "accept inbound pairing" flag, persist the change, and
 /// emit `pairing-accept-changed` so any subscribed UI surface stays in sync.
-/// Does NOT touch `pairing_locked_out` — abuse defence and user intent are
+/// Does NOT touch the rate limiter — abuse defence and user intent are
 /// orthogonal. See issue #16.
 #[tauri::command]
 pub(crate) fn set_pairing_accept(
@@ -60,6 +66,7 @@ pub(crate) async fn start_pairing(
     peer_id: String,
     pin: String,
     peer_addr: Option<String>,
+    expected_fingerprint: Option<String>,
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
     transport: tauri::State<'_, Transport>,
@@ -75,24 +82,26 @@ pub(crate) async fn start_pairing(
     // authenticated responder identity from T3 is the canonical id used for
     // storage below.
     let is_manual_pair = peer_addr.is_some();
-    let (peer_addr, discovered_proto_version, discovered_hostname) = if let Some(addr_str) = peer_addr {
-        let sock = if let Ok(sock) = addr_str.parse::<std::net::SocketAddr>() {
-            sock
-        } else if let Ok(ip) = addr_str.parse::<std::net::IpAddr>() {
-            std::net::SocketAddr::new(ip, 4654)
-        } else {
-            return Err(format!("Invalid peer address: {}", addr_str));
-        };
+    // Invite links pre-pin the responder's fingerprint; checked against the
+    // T3 identity below, before we send ours.
+    let expected_fingerprint = match expected_fingerprint.filter(|f| !f.is_empty()) {
+        Some(hex) => Some(invite::decode_fingerprint(&hex).ok_or("Invalid expected fingerprint")?),
+        None => None,
+    };
+    let (peer_addr, discovered_proto_version, discovered_hostname, discovered_cluster) = if let Some(addr_str) = peer_addr {
+        let sock = crate::net_util::parse_peer_addr(&addr_str, crate::net_util::DEFAULT_PORT)
+            .map_err(|e| format!("Invalid peer address {}: {}", addr_str, e))?;
         // Add-Remote path: no mDNS data, so we can't pre-check the proto.
         // Fall through to the wire-level failure if the remote is incompatible.
-        (sock, None, None)
+        (sock, None, None, None)
     } else {
         let peers = state.get_peers();
         if let Some(peer) = peers.get(&peer_id) {
             (
-                std::net::SocketAddr::new(peer.ip, peer.port),
+                peer.addr(),
                 peer.protocol_version.clone(),
                 Some(peer.hostname.clone()),
+                peer.network_name.clone(),
             )
         } else {
             return Err("Peer not found".to_string());
@@ -140,6 +149,12 @@ pub(crate) async fn start_pairing(
     let local_id_raw = { state.local_device_id.lock().unwrap().clone() };
     let local_id = crate::protocol::truncate_device_id(&local_id_raw);
 
+    // An invite token's code is used as the password verbatim; its id tells
+    // the responder which token to check it against.
+    let token_id = tokens::token_id_of(&pin);
+    let used_token = token_id.is_some();
+    let pin = if used_token { pin.trim().to_string() } else { pin };
+
     let (spake_state, spake_msg_i) =
         start_spake2(&pin, &local_id, &peer_id).map_err(|e| e.to_string())?;
 
@@ -152,7 +167,7 @@ pub(crate) async fn start_pairing(
         .map_err(|e| format!("Failed to connect to peer: {}", e))?;
 
     // T0 — opening SPAKE2 element. No identity bytes on the wire.
-    let req = PairingMessage::PairRequest { spake_msg: spake_msg_i.clone() };
+    let req = PairingMessage::PairRequest { spake_msg: spake_msg_i.clone(), token_id, cluster: discovered_cluster };
     crate::transport::write_pairing_frame(&mut stream, &req)
         .await
         .map_err(|e| format!("Failed to send PairRequest: {}", e))?;
@@ -344,7 +359,11 @@ pub(crate) async fn start_pairing(
     };
     let r_inner: crate::protocol::PairIdInner = serde_json::from_slice(&r_inner_bytes)
         .map_err(|e| format!("Malformed ResponderId inner payload: {}", e))?;
-    let crate::protocol::PairIdInner { device_id: responder_device_id, fingerprint: responder_fingerprint } = r_inner;
+    let crate::protocol::PairIdInner {
+        device_id: responder_device_id,
+        fingerprint: responder_fingerprint,
+        sas: responder_sas,
+    } = r_inner;
     // Apply the same canonicalisation the responder uses on its T4 receive
     // path, so both sides key on identical bytes.
     let responder_device_id = crate::protocol::truncate_device_id(&responder_device_id);
@@ -352,12 +371,59 @@ pub(crate) async fn start_pairing(
         "Authenticated responder identity (initiator). Pinning fingerprint for {}.",
         responder_device_id
     );
+    if expected_fingerprint.as_ref().map_or(false, |fp| *fp != responder_fingerprint) {
+        let _ = app_handle.emit(
+            "pairing-failed",
+            "The device that answered doesn't match the invite. Pairing was cancelled.",
+        );
+        crate::diagnostics::push_diagnostic(
+            &*state,
+            &app_handle,
+            crate::diagnostics::DiagLevel::Minimal,
+            "pairing",
+            Some(peer_addr.to_string()),
+            format!(
+                "Pairing refused: {} presented a certificate that doesn't match the invite",
+                responder_device_id
+            ),
+        );
+        return Err("Responder fingerprint does not match the invite".to_string());
+    }
+    if state.is_revoked_fingerprint(&responder_fingerprint) {
+        let _ = app_handle.emit("pairing-failed", "This device was removed from the cluster and can't be re-paired.");
+        crate::diagnostics::push_diagnostic(
+            &*state,
+            &app_handle,
+            crate::diagnostics::DiagLevel::Detailed,
+            "pairing",
+            Some(peer_addr.to_string()),
+            format!("Pairing refused: {} presented a revoked certificate", responder_device_id),
+        );
+        return Err("Responder certificate is revoked".to_string());
+    }
+    let require_sas = state.settings.lock().unwrap().require_sas_confirmation;
+    if require_sas && responder_sas.is_none() {
+        let _ = app_handle.emit(
+            "pairing-failed",
+            "The other device can't show a security code. Update it, or turn off \"Require Security Code\".",
+        );
+        crate::diagnostics::push_diagnostic(
+            &*state,
+            &app_handle,
+            crate::diagnostics::DiagLevel::Detailed,
+            "pairing",
+            Some(peer_addr.to_string()),
+            format!("Pairing refused: {} does not support SAS confirmation", responder_device_id),
+        );
+        return Err("Responder does not support SAS confirmation".to_string());
+    }
 
     // T4 (wire 0.3.3) — initiator's AEAD-wrapped identity. Build, encrypt, send.
     let local_fp = transport.local_fingerprint();
     let i_inner = crate::protocol::PairIdInner {
         device_id: local_id.clone(),
-        fingerprint: local_fp,
+        fingerprint: local_fp.clone(),
+        sas: Some(require_sas),
     };
     let i_inner_bytes = serde_json::to_vec(&i_inner)
         .map_err(|e| format!("Failed to serialise InitiatorId inner: {}", e))?;
@@ -389,6 +455,44 @@ pub(crate) async fn start_pairing(
         return Err(format!("Failed to send InitiatorId: {}", e));
     }
 
+    // T4a — SAS verdicts, when both builds support it. Nothing is pinned
+    // until both sides have accepted.
+    if let Some(responder_requires) = responder_sas {
+        let prompt = sas::SasPrompt {
+            session: uuid::Uuid::new_v4().to_string(),
+            peer: state
+                .get_peers()
+                .get(&responder_device_id)
+                .map(|p| p.hostname.clone())
+                .unwrap_or_else(|| peer_addr.ip().to_string()),
+            symbols: sas::derive_sas(&transcript, &local_fp, &responder_fingerprint),
+            requires_confirmation: require_sas || responder_requires,
+        };
+        let accepted = sas::local_verdict(&state, &app_handle, prompt).await;
+        let confirmed = match sas::exchange_verdicts(&mut stream, &k_i2r, &k_r2i, accepted).await {
+            Ok(confirmed) => confirmed,
+            Err(e) => {
+                tracing::warn!("SAS exchange with {} failed: {}", peer_addr, e);
+                false
+            }
+        };
+        if !confirmed {
+            let _ = app_handle.emit(
+                "pairing-failed",
+                "Pairing cancelled: the security code wasn't confirmed on both devices.",
+            );
+            crate::diagnostics::push_diagnostic(
+                &*state,
+                &app_handle,
+                crate::diagnostics::DiagLevel::Minimal,
+                "pairing",
+                Some(peer_addr.to_string()),
+                "Pairing cancelled: security code not confirmed".to_string(),
+            );
+            return Err("Security code was not confirmed".to_string());
+        }
+    }
+
     // Pin the responder's fingerprint locally NOW (after sending T4, before
     // the QUIC step that depends on it). Touching state in this order keeps
     // the pinning visible to a concurrent inbound mTLS verifier on this side.
@@ -426,6 +530,19 @@ pub(crate) async fn start_pairing(
             signature: None,
             fingerprint: Some(responder_fingerprint.clone()),
             protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+            // Carries the IPv6 scope id of a link-local address.
+            addresses: vec![crate::peer::PeerAddress::new(
+                peer_addr,
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            )],
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
         };
         runtime_peers.insert(responder_device_id.clone(), pinned.clone());
         kp_lock.insert(responder_device_id.clone(), pinned.clone());
@@ -545,9 +662,23 @@ pub(crate) async fn start_pairing(
         network_name_version,
         network_name_origin,
         cluster_mode: responder_cluster_mode,
+        revocations,
+        join_restrictions,
     } = cluster_info;
     tracing::info!("Joined Network: {} (cluster {})", network_name, cluster_id);
-    {
+    // Already in a cluster with members of its own: keep it, and join this
+    // one alongside as an additional context (clusters.rs).
+    let as_context = crate::clusters::should_join_as_context(&state, &cluster_id);
+    let local_quic_port = transport.local_addr().map(|a| a.port()).unwrap_or(0);
+    if as_context {
+        tracing::info!("Keeping the current cluster; '{}' joined as an additional cluster.", network_name);
+        crate::clusters::upsert_context(&state, &app_handle, &cluster_id, &network_name, (!used_token).then(|| pin.clone()));
+        if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
+            if let Err(e) = discovery.register_context(&local_id, &cluster_id, &network_name, local_quic_port) {
+                tracing::error!("Failed to advertise cluster '{}': {}", network_name, e);
+            }
+        }
+    } else {
         let mut cid = state.cluster_id.lock().unwrap();
         *cid = cluster_id.clone();
         crate::storage::save_cluster_id(&app_handle, &cluster_id);
@@ -570,17 +701,17 @@ pub(crate) async fn start_pairing(
         *state.network_name_origin.lock().unwrap() = adopted_origin.clone();
         crate::storage::save_network_name_version(&app_handle, network_name_version);
         crate::storage::save_network_name_origin(&app_handle, &adopted_origin);
-    }
+        drop(cid);
 
-    let local_quic_port = transport.local_addr().map(|a| a.port()).unwrap_or(0);
-    if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
-        let _ = discovery.register(&local_id, &network_name, local_quic_port);
+        if let Some(discovery) = state.discovery.lock().unwrap().as_mut() {
+            let _ = discovery.register(&local_id, &network_name, local_quic_port);
+        }
     }
 
     {
         let mut kp_lock = state.known_peers.lock().unwrap();
         let mut runtime_peers = state.peers.lock().unwrap();
-        for peer in known_peers {
+        for peer in known_peers.iter().cloned() {
             // The cluster's view of the responder shouldn't clobber the
             // local pinned record we just wrote (which carries our pinned
             // fingerprint and any is_manual flag).
@@ -609,6 +740,22 @@ pub(crate) async fn start_pairing(
         }
         crate::storage::save_known_peers(&app_handle, &kp_lock);
     }
+    let context_id = as_context.then_some(cluster_id.as_str());
+    crate::clusters::assign_member(&state, &app_handle, context_id, &responder_device_id);
+    if as_context {
+        for peer in &known_peers {
+            if peer.id != local_id {
+                crate::clusters::assign_member(&state, &app_handle, context_id, &peer.id);
+            }
+        }
+    }
+
+    // Adopt the cluster's revocation list. Issuers are verified against the
+    // members just imported, and anything the snapshot still carried for a
+    // revoked device is dropped again.
+    let revoked = crate::revocation::merge_revocations(&*state, &revocations);
+    crate::revocation::apply_revocations(&*state, &app_handle, &revoked);
+    tokens::merge_restrictions(&*state, &app_handle, &join_restrictions);
 
     // Provisioned-cluster PIN convergence. In a provisioned cluster every
     // device shares one PIN, but the join handshake never carried it — so a
@@ -623,7 +770,11 @@ pub(crate) async fn start_pairing(
     // into provisioned mode: otherwise the next launch's
     // `establish_network_pin("auto")` would delete the adopted PIN and generate
     // a fresh ephemeral one, breaking the cluster again on restart.
-    if crate::storage::should_adopt_cluster_pin(&responder_cluster_mode) {
+    //
+    // A joiner that paired with an invite token typed the token, not the
+    // cluster PIN, so there is nothing to adopt; it keeps its own PIN. An
+    // additional cluster's PIN was stored with its context above.
+    if !as_context && !used_token && crate::storage::should_adopt_cluster_pin(&responder_cluster_mode) {
         let pin_changed = {
             let mut np = state.network_pin.lock().unwrap();
             if *np != pin {
@@ -688,10 +839,11 @@ fn log_pairing_failure(state: &AppState, peer_addr: std::net::SocketAddr, detail
     }
 }
 
-/// Treat an AEAD-decrypt failure as a brute-force attempt: bump the global
-/// counter, and if it crosses `PAIRING_FAILURE_LOCKOUT_THRESHOLD`, lock the
-/// pairing listener and surface an urgent, user-actionable notification +
-/// frontend event. See WIRE-PROTOCOL-0.3.1 §H1.
+/// Treat an AEAD-decrypt failure as a brute-force attempt: record it
+/// against the source (which may put the source into backoff), and if it
+/// hits the global ceiling, pause pairing for everyone and surface an urgent
+/// notification + frontend event. The pause lifts by itself. See
+/// ratelimit.rs and WIRE-PROTOCOL-0.3.1 §H1.
 fn record_pairing_aead_failure(
     state: &AppState,
     app_handle: &tauri::AppHandle,
@@ -699,31 +851,63 @@ fn record_pairing_aead_failure(
     detail: &str,
 ) {
     log_pairing_failure(state, peer_addr, detail);
-    if state.record_pairing_failure() {
+    let outcome = state.record_pairing_failure(peer_addr.ip());
+    if let Some(backoff) = outcome.source_backoff {
+        crate::diagnostics::push_diagnostic(
+            state,
+            app_handle,
+            crate::diagnostics::DiagLevel::Minimal,
+            "pairing",
+            Some(peer_addr.ip().to_string()),
+            format!(
+                "Pairing from this address paused for {}s after {} failed attempts",
+                backoff.as_secs(),
+                outcome.failures
+            ),
+        );
+    }
+    if let Some(pause) = outcome.global_pause {
         tracing::error!(
-            "Pairing listener LOCKED OUT after {} AEAD failures — user must re-arm via the UI.",
-            crate::state::PAIRING_FAILURE_LOCKOUT_THRESHOLD,
+            "Pairing listener paused for {:?}: {} failures from all sources within {:?}.",
+            pause,
+            ratelimit::GLOBAL_CEILING,
+            ratelimit::GLOBAL_WINDOW,
         );
-        let _ = app_handle.emit("pairing-locked-out", ());
+        let _ = app_handle.emit("pairing-locked-out", pause.as_secs());
         crate::diagnostics::push_diagnostic(
             state,
             app_handle,
             crate::diagnostics::DiagLevel::Minimal,
             "pairing",
             None,
-            "Pairing listener locked out (too many failures)".to_string(),
+            format!("Pairing paused for everyone for {}s (too many failures)", pause.as_secs()),
         );
-        // Urgent OS-level notification so the user actually sees the lockout
+        // Urgent OS-level notification so the user actually sees the pause
         // rather than only spotting it the next time they open Settings.
         crate::send_notification(
             app_handle,
-            "ClusterCut pairing locked",
-            "Too many failed PIN attempts. Pairing is paused — open ClusterCut to re-enable it.",
+            "ClusterCut pairing paused",
+            &format!(
+                "Too many failed PIN attempts. Pairing resumes in {} minutes, or open ClusterCut to re-enable it now.",
+                pause.as_secs().div_ceil(60)
+            ),
             true, // urgent
             None,
             "pairing",
             crate::NotificationPayload::None,
         );
+        // Tell the UI when the pause has lifted on its own. A manual re-arm
+        // in the meantime has already sent `pairing-rearmed`; a repeat is
+        // harmless.
+        let state = state.clone();
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(pause).await;
+            while state.is_pairing_locked_out() {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            let _ = app_handle.emit("pairing-rearmed", ());
+        });
     }
 }
 
@@ -731,8 +915,9 @@ fn record_pairing_aead_failure(
 ///
 /// Drives T0 (PairRequest) → T1 (PairResponse) → T2 (InitiatorKC, AEAD) →
 /// T3 (ResponderId, AEAD) → T4 (InitiatorId, AEAD) to completion. After
-/// T4 decrypts cleanly, the responder pins the initiator's fingerprint
-/// and gossips the new peer to the rest of the cluster. The TCP socket
+/// T4 decrypts cleanly (and, when both builds support it, both SAS verdicts
+/// in T4a are "accept" — see sas.rs), the responder pins the initiator's
+/// fingerprint and gossips the new peer to the rest of the cluster. The TCP socket
 /// then closes at T5 — the initiator uses the close as its signal to
 /// open QUIC for the post-pairing `ClusterInfo` exchange.
 pub(crate) async fn handle_pairing_connection(
@@ -754,8 +939,8 @@ pub(crate) async fn handle_pairing_connection(
     );
 
     // T0 — opening SPAKE2 element from the initiator. No identity bytes.
-    let spake_msg_i = match crate::transport::read_pairing_frame(&mut stream).await {
-        Ok(PairingMessage::PairRequest { spake_msg }) => spake_msg,
+    let (spake_msg_i, token_id, requested_cluster) = match crate::transport::read_pairing_frame(&mut stream).await {
+        Ok(PairingMessage::PairRequest { spake_msg, token_id, cluster }) => (spake_msg, token_id, cluster),
         Ok(other) => {
             let d = format!("expected PairRequest, got {:?}", other);
             log_pairing_failure(&state, peer_addr, &d);
@@ -777,10 +962,31 @@ pub(crate) async fn handle_pairing_connection(
     };
     tracing::info!("Received PairRequest from {}; running SPAKE2.", peer_addr);
 
-    // T1 — responder's SPAKE2 element. The PIN comes from local state.
+    // T1 — responder's SPAKE2 element. The password is the PIN of the
+    // cluster the initiator asked for (clusters.rs), or the code of the
+    // invite token it named. An unknown, expired or used-up token isn't a
+    // guess at a secret, so it fails without touching the lockout counter.
     let local_id_raw = state.local_device_id.lock().unwrap().clone();
     let local_id = crate::protocol::truncate_device_id(&local_id_raw);
-    let pin = state.network_pin.lock().unwrap().clone();
+    let (target_context, cluster_pin) = match &token_id {
+        Some(_) => (None, String::new()),
+        None => crate::clusters::pairing_target(&state, requested_cluster.as_deref()),
+    };
+    let pin = match &token_id {
+        Some(id) => match tokens::password_for(&state, id) {
+            Some(code) => code,
+            None => {
+                let d = format!("invite token {} is unknown, expired or used up", id);
+                log_pairing_failure(&state, peer_addr, &d);
+                crate::diagnostics::push_diagnostic(
+                    &state, &app_handle, crate::diagnostics::DiagLevel::Detailed, "pairing",
+                    Some(peer_addr.to_string()), format!("Pairing refused (responder): {}", d),
+                );
+                return;
+            }
+        },
+        None => cluster_pin,
+    };
     let (spake_state, spake_msg_r) = match start_spake2(&pin, &local_id, "initiator") {
         Ok(v) => v,
         Err(e) => {
@@ -941,6 +1147,24 @@ pub(crate) async fn handle_pairing_connection(
         peer_addr
     );
 
+    // The initiator has proven the token code; spend a use now so two
+    // joiners can't race a single-use token through the later stages.
+    let restrictions = match &token_id {
+        Some(id) => match tokens::redeem(&state, &app_handle, id) {
+            Some(r) => Some(r),
+            None => {
+                let d = format!("invite token {} was used up or revoked mid-pairing", id);
+                log_pairing_failure(&state, peer_addr, &d);
+                crate::diagnostics::push_diagnostic(
+                    &state, &app_handle, crate::diagnostics::DiagLevel::Detailed, "pairing",
+                    Some(peer_addr.to_string()), format!("Pairing refused (responder): {}", d),
+                );
+                return;
+            }
+        },
+        None => None,
+    };
+
     // Refuse to advance to T3 if we have no cluster identity to bind to.
     // Responding here would leak a valid ResponderId for a half-built
     // cluster; better to abort early.
@@ -957,9 +1181,11 @@ pub(crate) async fn handle_pairing_connection(
     // T3 (wire 0.3.3) — responder's AEAD-wrapped identity, decryptable by
     // the initiator only if it derived the same SPAKE2 key (i.e. correct
     // PIN). Sent only after T2 InitiatorKC has been verified.
+    let require_sas = state.settings.lock().unwrap().require_sas_confirmation;
     let r_inner = crate::protocol::PairIdInner {
         device_id: local_id.clone(),
         fingerprint: transport.local_fingerprint(),
+        sas: Some(require_sas),
     };
     let r_inner_bytes = match serde_json::to_vec(&r_inner) {
         Ok(b) => b,
@@ -1056,18 +1282,103 @@ pub(crate) async fn handle_pairing_connection(
     let crate::protocol::PairIdInner {
         device_id: initiator_device_id,
         fingerprint: initiator_fingerprint,
+        sas: initiator_sas,
     } = i_inner;
 
     // Apply truncation defensively on the receive side too — the spec
     // requires both ends to apply the same canonicalisation so the pinned
     // identifier matches what the initiator believes its device_id to be.
     let initiator_device_id = crate::protocol::truncate_device_id(&initiator_device_id);
+    if state.is_revoked_fingerprint(&initiator_fingerprint) {
+        let d = format!("initiator {} presented a revoked certificate", initiator_device_id);
+        log_pairing_failure(&state, peer_addr, &d);
+        crate::diagnostics::push_diagnostic(
+            &state, &app_handle, crate::diagnostics::DiagLevel::Detailed, "pairing",
+            Some(peer_addr.to_string()), format!("Pairing refused (responder): {}", d),
+        );
+        return;
+    }
+    if require_sas && initiator_sas.is_none() {
+        let d = format!("initiator {} does not support SAS confirmation", initiator_device_id);
+        log_pairing_failure(&state, peer_addr, &d);
+        crate::diagnostics::push_diagnostic(
+            &state, &app_handle, crate::diagnostics::DiagLevel::Detailed, "pairing",
+            Some(peer_addr.to_string()), format!("Pairing refused (responder): {}", d),
+        );
+        return;
+    }
     tracing::info!(
         "Authenticated initiator identity ({}) from {}; pinning fingerprint.",
         initiator_device_id,
         peer_addr
     );
 
+    // T4a — SAS verdicts. This can wait on a human for up to
+    // `SAS_CONFIRM_TIMEOUT`, far beyond the §H6 protocol timeout, so it runs
+    // in its own task and frees the single-flight slot. The slot only has to
+    // guard the unauthenticated exchange; the PIN has been proven by now.
+    if let Some(initiator_requires) = initiator_sas {
+        let prompt = sas::SasPrompt {
+            session: uuid::Uuid::new_v4().to_string(),
+            peer: state
+                .get_peers()
+                .get(&initiator_device_id)
+                .map(|p| p.hostname.clone())
+                .unwrap_or_else(|| peer_addr.ip().to_string()),
+            symbols: sas::derive_sas(&transcript, &initiator_fingerprint, &transport.local_fingerprint()),
+            requires_confirmation: require_sas || initiator_requires,
+        };
+        tauri::async_runtime::spawn(async move {
+            let accepted = sas::local_verdict(&state, &app_handle, prompt).await;
+            let confirmed = match sas::exchange_verdicts(&mut stream, &k_r2i, &k_i2r, accepted).await {
+                Ok(confirmed) => confirmed,
+                Err(e) => {
+                    tracing::warn!("SAS exchange with {} failed: {}", peer_addr, e);
+                    false
+                }
+            };
+            if !confirmed {
+                let _ = app_handle.emit(
+                    "pairing-failed",
+                    "Pairing cancelled: the security code wasn't confirmed on both devices.",
+                );
+                crate::diagnostics::push_diagnostic(
+                    &state,
+                    &app_handle,
+                    crate::diagnostics::DiagLevel::Minimal,
+                    "pairing",
+                    Some(peer_addr.to_string()),
+                    "Pairing cancelled: security code not confirmed".to_string(),
+                );
+                return;
+            }
+            if let Some(restrictions) = &restrictions {
+                tokens::restrict_joiner(&state, &app_handle, &transport, &initiator_device_id, &initiator_fingerprint, restrictions);
+            }
+            crate::clusters::assign_member(&state, &app_handle, target_context.as_deref(), &initiator_device_id);
+            commit_initiator_pin(stream, peer_addr, &state, &app_handle, &transport, initiator_device_id, initiator_fingerprint);
+        });
+        return;
+    }
+
+    if let Some(restrictions) = &restrictions {
+        tokens::restrict_joiner(&state, &app_handle, &transport, &initiator_device_id, &initiator_fingerprint, restrictions);
+    }
+    crate::clusters::assign_member(&state, &app_handle, target_context.as_deref(), &initiator_device_id);
+    commit_initiator_pin(stream, peer_addr, &state, &app_handle, &transport, initiator_device_id, initiator_fingerprint);
+}
+
+/// Responder's final step: pin the authenticated initiator, gossip it and
+/// close the pairing socket (T5).
+fn commit_initiator_pin(
+    stream: tokio::net::TcpStream,
+    peer_addr: std::net::SocketAddr,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    initiator_device_id: String,
+    initiator_fingerprint: Vec<u8>,
+) {
     // Insert / refresh the peer record with the pinned fingerprint. Pull
     // the hostname from any prior mDNS observation; otherwise placeholder.
     let prior_hostname = {
@@ -1084,7 +1395,10 @@ pub(crate) async fn handle_pairing_connection(
                     .map(|p| p.hostname.clone())
             })
     };
-    let network_name = state.network_name.lock().unwrap().clone();
+    let network_name = match crate::clusters::context_of(state, &initiator_device_id) {
+        Some(ctx) => ctx.name,
+        None => state.network_name.lock().unwrap().clone(),
+    };
     let pinned = crate::peer::Peer {
         id: initiator_device_id.clone(),
         ip: peer_addr.ip(),
@@ -1100,23 +1414,30 @@ pub(crate) async fn handle_pairing_connection(
         signature: None,
         fingerprint: Some(initiator_fingerprint),
         protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+        addresses: Vec::new(),
+        reaches: Vec::new(),
+        relay_via: None,
+        status: None,
+        device: None,
+        link: Default::default(),
     };
     {
         let mut kp_lock = state.known_peers.lock().unwrap();
         kp_lock.insert(initiator_device_id.clone(), pinned.clone());
-        crate::storage::save_known_peers(&app_handle, &kp_lock);
+        crate::storage::save_known_peers(app_handle, &kp_lock);
     }
     state.add_peer(pinned.clone());
+    state.pairing_limiter.lock().unwrap().record_success(peer_addr.ip());
     let _ = app_handle.emit("peer-update", crate::peer::PeerView::from_peer(&pinned));
 
     // Gossip the new peer to the rest of the cluster ONLY after T4 succeeds —
     // existing mTLS peers need the new fingerprint to accept its inbound
     // connections.
-    crate::net_util::gossip_peer(&pinned, &state, &transport, Some(peer_addr));
+    crate::net_util::gossip_peer(&pinned, state, transport, Some(peer_addr));
 
     crate::diagnostics::push_diagnostic(
-        &state,
-        &app_handle,
+        state,
+        app_handle,
         crate::diagnostics::DiagLevel::Minimal,
         "pairing",
         Some(peer_addr.to_string()),
@@ -0,0 +1,239 @@
+//! Per-source rate limiting for the pairing listener (§H1).
+//!
+//! The responder used to keep one global AEAD-failure counter and stop
+//! pairing for everyone once it tripped, so a single noisy device on a shared
+//! LAN could block every legitimate join until someone re-armed by hand.
+//! Failures are now tracked per source. Each source gets a few free attempts,
+//! then an exponentially growing pause. A global ceiling over a sliding
+//! window still catches a guesser spread across many addresses, and it lifts
+//! by itself once the window drains.
+//!
+//! IPv6 sources are keyed by their /64, since one host can trivially rotate
+//! through addresses inside its own prefix.
+//!
+//! The user can still override it: the `rearm_pairing` command (the lockout
+//! banner's "Re-enable pairing") calls [`PairingRateLimiter::clear`], which
+//! lifts a global pause early and forgets every source's backoff, and
+//! `is_pairing_locked_out` reports whether the global pause is in force.
+//! Nothing else clears the limiter.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Failures a source may make before it is backed off.
+pub(crate) const FREE_FAILURES: u32 = 3;
+/// Pause after the first failure past the free allowance; doubles per failure.
+pub(crate) const BASE_BACKOFF: Duration = Duration::from_secs(5);
+pub(crate) const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// A source quiet for this long starts again from zero.
+const SOURCE_FORGET_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+/// Failures from all sources within `GLOBAL_WINDOW` that pause pairing for
+/// everyone until the oldest of them ages out.
+pub(crate) const GLOBAL_CEILING: usize = 30;
+pub(crate) const GLOBAL_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Whether a new pairing connection may proceed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Allowed,
+    /// This source is backing off for the given time.
+    SourceBackoff(Duration),
+    /// The global ceiling is hit; pairing resumes after the given time.
+    GlobalPause(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureOutcome {
+    /// Total failures recorded for the source.
+    pub failures: u32,
+    /// Set when this failure put the source into backoff.
+    pub source_backoff: Option<Duration>,
+    /// Set when this failure hit the global ceiling: how long pairing is
+    /// paused for everyone.
+    pub global_pause: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+struct SourceRecord {
+    failures: u32,
+    last_failure: Instant,
+    blocked_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct PairingRateLimiter {
+    sources: HashMap<IpAddr, SourceRecord>,
+    /// Failure instants within `GLOBAL_WINDOW`, oldest first.
+    recent: VecDeque<Instant>,
+}
+
+/// The key a source is tracked under: the address itself for IPv4 (and
+/// IPv4-mapped IPv6), its /64 for IPv6.
+fn source_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => {
+                let s = v6.segments();
+                IpAddr::V6(std::net::Ipv6Addr::new(s[0], s[1], s[2], s[3], 0, 0, 0, 0))
+            }
+        },
+    }
+}
+
+fn backoff_for(failures: u32) -> Option<Duration> {
+    let over = failures.checked_sub(FREE_FAILURES).filter(|n| *n > 0)?;
+    let factor = 2u32.checked_pow(over - 1).unwrap_or(u32::MAX);
+    Some(BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF))
+}
+
+impl PairingRateLimiter {
+    fn prune(&mut self, now: Instant) {
+        while let Some(&oldest) = self.recent.front() {
+            if now.duration_since(oldest) < GLOBAL_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        self.sources
+            .retain(|_, r| now.duration_since(r.last_failure) < SOURCE_FORGET_AFTER);
+    }
+
+    /// How much longer pairing is paused for everyone, if it is.
+    pub(crate) fn global_pause(&mut self, now: Instant) -> Option<Duration> {
+        self.prune(now);
+        if self.recent.len() < GLOBAL_CEILING {
+            return None;
+        }
+        // Paused until enough failures age out to drop below the ceiling.
+        let release = self.recent[self.recent.len() - GLOBAL_CEILING] + GLOBAL_WINDOW;
+        Some(release.saturating_duration_since(now))
+    }
+
+    pub(crate) fn admit(&mut self, ip: IpAddr, now: Instant) -> Admission {
+        if let Some(remaining) = self.global_pause(now) {
+            return Admission::GlobalPause(remaining);
+        }
+        match self.sources.get(&source_key(ip)).and_then(|r| r.blocked_until) {
+            Some(until) if until > now => Admission::SourceBackoff(until - now),
+            _ => Admission::Allowed,
+        }
+    }
+
+    pub(crate) fn record_failure(&mut self, ip: IpAddr, now: Instant) -> FailureOutcome {
+        let was_paused = self.global_pause(now).is_some();
+        self.recent.push_back(now);
+        let record = self.sources.entry(source_key(ip)).or_insert(SourceRecord {
+            failures: 0,
+            last_failure: now,
+            blocked_until: None,
+        });
+        record.failures = record.failures.saturating_add(1);
+        record.last_failure = now;
+        let source_backoff = backoff_for(record.failures);
+        if let Some(backoff) = source_backoff {
+            record.blocked_until = Some(now + backoff);
+        }
+        let failures = record.failures;
+        let global_pause = if was_paused { None } else { self.global_pause(now) };
+        FailureOutcome { failures, source_backoff, global_pause }
+    }
+
+    /// A source that completed a pairing starts again from zero.
+    pub(crate) fn record_success(&mut self, ip: IpAddr) {
+        self.sources.remove(&source_key(ip));
+    }
+
+    /// The manual override: forget every source's failures and the global
+    /// window, so pairing is open to everyone again.
+    pub(crate) fn clear(&mut self) {
+        self.sources.clear();
+        self.recent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn source_backs_off_exponentially_after_free_failures() {
+        let mut rl = PairingRateLimiter::default();
+        let now = Instant::now();
+        let src = ip("192.168.1.50");
+        for _ in 0..FREE_FAILURES {
+            assert_eq!(rl.record_failure(src, now).source_backoff, None);
+        }
+        assert_eq!(rl.admit(src, now), Admission::Allowed);
+        assert_eq!(rl.record_failure(src, now).source_backoff, Some(BASE_BACKOFF));
+        assert_eq!(rl.record_failure(src, now).source_backoff, Some(BASE_BACKOFF * 2));
+        assert_eq!(rl.admit(src, now), Admission::SourceBackoff(BASE_BACKOFF * 2));
+        // Other sources are unaffected, and the backoff expires on its own.
+        assert_eq!(rl.admit(ip("192.168.1.51"), now), Admission::Allowed);
+        assert_eq!(rl.admit(src, now + BASE_BACKOFF * 2), Admission::Allowed);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_for(FREE_FAILURES + 40), Some(MAX_BACKOFF));
+    }
+
+    #[test]
+    fn success_resets_the_source() {
+        let mut rl = PairingRateLimiter::default();
+        let now = Instant::now();
+        let src = ip("10.0.0.9");
+        for _ in 0..=FREE_FAILURES {
+            rl.record_failure(src, now);
+        }
+        rl.record_success(src);
+        assert_eq!(rl.admit(src, now), Admission::Allowed);
+        assert_eq!(rl.record_failure(src, now).failures, 1);
+    }
+
+    #[test]
+    fn ipv6_sources_share_their_slash_64() {
+        let mut rl = PairingRateLimiter::default();
+        let now = Instant::now();
+        for i in 0..=FREE_FAILURES {
+            rl.record_failure(ip(&format!("2001:db8:1:2::{:x}", i + 1)), now);
+        }
+        assert!(matches!(rl.admit(ip("2001:db8:1:2::ffff"), now), Admission::SourceBackoff(_)));
+        assert_eq!(rl.admit(ip("2001:db8:1:3::1"), now), Admission::Allowed);
+    }
+
+    #[test]
+    fn global_ceiling_pauses_everyone_until_the_window_drains() {
+        let mut rl = PairingRateLimiter::default();
+        let start = Instant::now();
+        let mut tripped = 0;
+        for i in 0..GLOBAL_CEILING {
+            let out = rl.record_failure(ip(&format!("10.1.{}.{}", i / 200, i % 200 + 1)), start);
+            tripped += out.global_pause.is_some() as usize;
+        }
+        assert_eq!(tripped, 1, "the trip is reported once");
+        assert!(matches!(rl.admit(ip("10.9.9.9"), start), Admission::GlobalPause(_)));
+        assert_eq!(rl.admit(ip("10.9.9.9"), start + GLOBAL_WINDOW), Admission::Allowed);
+    }
+
+    #[test]
+    fn clearing_lifts_the_pause_and_every_backoff() {
+        let mut rl = PairingRateLimiter::default();
+        let now = Instant::now();
+        for i in 0..GLOBAL_CEILING {
+            rl.record_failure(ip("10.2.0.1"), now);
+            rl.record_failure(ip(&format!("10.3.0.{}", i + 1)), now);
+        }
+        assert!(rl.global_pause(now).is_some());
+        rl.clear();
+        assert_eq!(rl.global_pause(now), None);
+        assert_eq!(rl.admit(ip("10.2.0.1"), now), Admission::Allowed);
+        assert_eq!(rl.record_failure(ip("10.2.0.1"), now).failures, 1);
+    }
+}
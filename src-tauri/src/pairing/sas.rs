@@ -0,0 +1,189 @@
+//! Short authentication string (SAS) shown on both devices after pairing.
+//!
+//! SPAKE2 proves both sides know the PIN, but with a weak or widely shared
+//! PIN an attacker who also knows it can sit in the middle and run one
+//! SPAKE2 session with each device. Each session then has its own
+//! transcript and the attacker's cert fingerprint on one side, so a string
+//! derived from `pairing_transcript` plus both fingerprints differs between
+//! the two screens. Comparing six symbols catches that.
+//!
+//! Builds that understand SAS say so in `PairIdInner::sas`. When both do,
+//! each side sends a `SasConfirm` verdict after T4 and commits the pair only
+//! if both verdicts are "accept". A side auto-accepts unless either device
+//! has `require_sas_confirmation` on, in which case its user decides.
+
+use sha2::{Digest, Sha256};
+use tauri::Emitter;
+use tokio::net::TcpStream;
+
+use super::{fresh_pair_nonce, pair_aead_decrypt, pair_aead_encrypt};
+use crate::protocol::PairingMessage;
+use crate::state::AppState;
+
+/// How long either side waits for its user, and then for the other side's
+/// verdict. The pair is abandoned when it runs out.
+pub(crate) const SAS_CONFIRM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+const SAS_DOMAIN: &[u8] = b"clustercut-pair-v1 sas";
+const SAS_LEN: usize = 6;
+const SAS_ACCEPT: &[u8] = b"clustercut-sas-accept";
+const SAS_REJECT: &[u8] = b"clustercut-sas-reject";
+
+/// 64 symbols, 6 bits each. Picked to be distinct both as pictures and as
+/// spoken words.
+const SAS_SYMBOLS: [(&str, &str); 64] = [
+    ("🐶", "Dog"), ("🐱", "Cat"), ("🦁", "Lion"), ("🐎", "Horse"),
+    ("🦄", "Unicorn"), ("🐷", "Pig"), ("🐘", "Elephant"), ("🐰", "Rabbit"),
+    ("🐼", "Panda"), ("🐓", "Rooster"), ("🐧", "Penguin"), ("🐢", "Turtle"),
+    ("🐟", "Fish"), ("🐙", "Octopus"), ("🦋", "Butterfly"), ("🌷", "Flower"),
+    ("🌳", "Tree"), ("🌵", "Cactus"), ("🍄", "Mushroom"), ("🌏", "Globe"),
+    ("🌙", "Moon"), ("☁️", "Cloud"), ("🔥", "Fire"), ("🍌", "Banana"),
+    ("🍎", "Apple"), ("🍓", "Strawberry"), ("🌽", "Corn"), ("🍕", "Pizza"),
+    ("🎂", "Cake"), ("❤️", "Heart"), ("😀", "Smiley"), ("🤖", "Robot"),
+    ("🎩", "Hat"), ("👓", "Glasses"), ("🔧", "Spanner"), ("🎅", "Santa"),
+    ("👍", "Thumbs Up"), ("☂️", "Umbrella"), ("⌛", "Hourglass"), ("⏰", "Clock"),
+    ("🎁", "Gift"), ("💡", "Light Bulb"), ("📕", "Book"), ("✏️", "Pencil"),
+    ("📎", "Paperclip"), ("✂️", "Scissors"), ("🔒", "Lock"), ("🔑", "Key"),
+    ("🔨", "Hammer"), ("☎️", "Telephone"), ("🏁", "Flag"), ("🚂", "Train"),
+    ("🚲", "Bicycle"), ("✈️", "Aeroplane"), ("🚀", "Rocket"), ("🏆", "Trophy"),
+    ("⚽", "Ball"), ("🎸", "Guitar"), ("🎺", "Trumpet"), ("🔔", "Bell"),
+    ("⚓", "Anchor"), ("🎧", "Headphones"), ("📁", "Folder"), ("📌", "Pin"),
+];
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SasSymbol {
+    pub emoji: &'static str,
+    pub word: &'static str,
+}
+
+/// Payload of the `pairing-sas` event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SasPrompt {
+    /// Random per handshake, so two pairings from one address (or a
+    /// reused one) never answer each other's prompt. Pass back to
+    /// `confirm_pairing_sas`.
+    pub session: String,
+    pub peer: String,
+    pub symbols: Vec<SasSymbol>,
+    pub requires_confirmation: bool,
+}
+
+/// Derive the SAS. Both roles pass the fingerprints in (initiator,
+/// responder) order so the two screens agree.
+pub(crate) fn derive_sas(transcript: &[u8; 32], fp_initiator: &[u8], fp_responder: &[u8]) -> Vec<SasSymbol> {
+    let mut h = Sha256::new();
+    h.update(SAS_DOMAIN);
+    h.update(transcript);
+    h.update(fp_initiator);
+    h.update(fp_responder);
+    let digest = h.finalize();
+    let bits = u64::from_be_bytes(digest[..8].try_into().expect("8-byte prefix"));
+    (0..SAS_LEN)
+        .map(|i| {
+            let (emoji, word) = SAS_SYMBOLS[((bits >> (58 - 6 * i)) & 0x3f) as usize];
+            SasSymbol { emoji, word }
+        })
+        .collect()
+}
+
+/// Show the SAS and, when confirmation is required, wait for the user's
+/// verdict. Returns `true` straight away otherwise.
+pub(crate) async fn local_verdict(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    prompt: SasPrompt,
+) -> bool {
+    let session = prompt.session.clone();
+    let requires_confirmation = prompt.requires_confirmation;
+    let rx = requires_confirmation.then(|| {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        state.pending_sas.lock().unwrap().insert(session.clone(), tx);
+        rx
+    });
+    let _ = app_handle.emit("pairing-sas", prompt);
+    let Some(rx) = rx else {
+        return true;
+    };
+    let verdict = matches!(tokio::time::timeout(SAS_CONFIRM_TIMEOUT, rx).await, Ok(Ok(true)));
+    state.pending_sas.lock().unwrap().remove(&session);
+    verdict
+}
+
+/// Send our verdict under `send_key` and read the peer's under `recv_key`.
+/// `Ok(true)` only if both accepted.
+pub(crate) async fn exchange_verdicts(
+    stream: &mut TcpStream,
+    send_key: &[u8; 32],
+    recv_key: &[u8; 32],
+    accepted: bool,
+) -> Result<bool, String> {
+    let nonce = fresh_pair_nonce();
+    let plaintext = if accepted { SAS_ACCEPT } else { SAS_REJECT };
+    let ciphertext = pair_aead_encrypt(send_key, &nonce, plaintext).map_err(|e| e.to_string())?;
+    let frame = PairingMessage::SasConfirm { nonce: nonce.to_vec(), ciphertext };
+    crate::transport::write_pairing_frame(stream, &frame)
+        .await
+        .map_err(|e| format!("Failed to send SasConfirm: {}", e))?;
+
+    let (nonce, ciphertext) =
+        match tokio::time::timeout(SAS_CONFIRM_TIMEOUT, crate::transport::read_pairing_frame(stream)).await {
+            Ok(Ok(PairingMessage::SasConfirm { nonce, ciphertext })) => (nonce, ciphertext),
+            Ok(Ok(other)) => return Err(format!("expected SasConfirm, got {:?}", other)),
+            Ok(Err(e)) => return Err(format!("Failed to read SasConfirm: {}", e)),
+            Err(_) => return Err("Timed out waiting for the other device's confirmation".to_string()),
+        };
+    let nonce: [u8; 12] = nonce.as_slice().try_into().map_err(|_| "SasConfirm nonce must be 12 bytes".to_string())?;
+    let remote = pair_aead_decrypt(recv_key, &nonce, &ciphertext).map_err(|e| e.to_string())?;
+    Ok(accepted && remote == SAS_ACCEPT)
+}
+
+/// The user's verdict for a `pairing-sas` prompt.
+#[tauri::command]
+pub(crate) fn confirm_pairing_sas(
+    session: String,
+    accepted: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let tx = state
+        .pending_sas
+        .lock()
+        .unwrap()
+        .remove(&session)
+        .ok_or("This pairing is no longer waiting for confirmation")?;
+    let _ = tx.send(accepted);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sas_is_stable_and_six_symbols_long() {
+        let t = [7u8; 32];
+        let a = derive_sas(&t, &[1; 32], &[2; 32]);
+        assert_eq!(a.len(), SAS_LEN);
+        assert_eq!(a, derive_sas(&t, &[1; 32], &[2; 32]));
+    }
+
+    #[test]
+    fn sas_changes_with_transcript_or_either_fingerprint() {
+        let t = [7u8; 32];
+        let base = derive_sas(&t, &[1; 32], &[2; 32]);
+        assert_ne!(base, derive_sas(&[8; 32], &[1; 32], &[2; 32]));
+        assert_ne!(base, derive_sas(&t, &[3; 32], &[2; 32]));
+        assert_ne!(base, derive_sas(&t, &[1; 32], &[3; 32]));
+        // Role order matters too.
+        assert_ne!(base, derive_sas(&t, &[2; 32], &[1; 32]));
+    }
+
+    #[test]
+    fn symbol_table_has_no_duplicates() {
+        for (i, a) in SAS_SYMBOLS.iter().enumerate() {
+            for b in &SAS_SYMBOLS[i + 1..] {
+                assert_ne!(a.0, b.0);
+                assert_ne!(a.1, b.1);
+            }
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Invite tokens: short-lived, limited-use pairing secrets.
+//!
+//! A provisioned cluster shares one long-lived PIN, and handing it out means
+//! it works for anyone, forever. Any member can instead mint a token here and
+//! give that to a joiner. The token's code (`<id>.<secret>`) is typed or
+//! pasted wherever a PIN goes and becomes the SPAKE2 password. The id rides
+//! along in T0 so the responder knows which password to use. The secret
+//! never leaves the two devices.
+//!
+//! Tokens live only on the member that minted them, so the joiner must pair
+//! with that member. A use is consumed once the initiator proves knowledge of
+//! the code at T2. A pairing that later fails still spends the use.
+//!
+//! Restrictions are applied to the joiner's policy on the minting member once
+//! the pair is committed. That member also signs a [`JoinRestriction`] naming
+//! the joiner's fingerprint, which travels in every `ClusterInfo` like a
+//! revocation does (see revocation.rs). Other members accept it from a
+//! pinned member of the joiner's own cluster whose signature verifies, and
+//! narrow their policy for the joiner the same way.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::peer::PeerPolicy;
+use crate::protocol::JoinRestriction;
+use crate::state::AppState;
+use crate::transport::{cert_fingerprint, verify_cert_signature, Transport};
+
+const TOKEN_ID_LEN: usize = 8;
+const TOKEN_SECRET_LEN: usize = 16;
+const TOKEN_SECRET_CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+const TOKEN_MIN_TTL_SECS: u64 = 60;
+const TOKEN_MAX_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+const TOKEN_MAX_USES: u32 = 100;
+
+/// Domain separator for the signed [`JoinRestriction`] statement.
+const RESTRICTION_DOMAIN: &[u8] = b"clustercut-join-restriction-v1";
+
+/// Lifetime of the single-use token embedded in an invite link.
+pub(crate) const INVITE_LINK_TOKEN_TTL_SECS: u64 = 60 * 60;
+
+/// Limits placed on a device that joins with a token. Enforced by every
+/// member, through its policy for the joiner.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenRestrictions {
+    /// The joiner may receive our clipboard but we ignore its pushes.
+    #[serde(default)]
+    pub receive_only: bool,
+    /// No file transfer with the joiner in either direction.
+    #[serde(default)]
+    pub no_file_transfer: bool,
+}
+
+impl TokenRestrictions {
+    /// The policy to give the joiner, or None when unrestricted.
+    pub(crate) fn policy(&self) -> Option<PeerPolicy> {
+        if *self == Self::default() {
+            return None;
+        }
+        Some(PeerPolicy {
+            receive_from: !self.receive_only,
+            file_transfer: !self.no_file_transfer,
+            ..PeerPolicy::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InviteToken {
+    pub id: String,
+    pub secret: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub max_uses: u32,
+    #[serde(default)]
+    pub uses: u32,
+    #[serde(default)]
+    pub restrictions: TokenRestrictions,
+}
+
+impl InviteToken {
+    /// What the joiner types or pastes in place of the PIN.
+    pub fn code(&self) -> String {
+        format!("{}.{}", self.id, self.secret)
+    }
+
+    pub fn is_usable(&self, now: u64) -> bool {
+        self.uses < self.max_uses && now < self.expires_at
+    }
+}
+
+/// A token as listed in Settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct InviteTokenView {
+    pub id: String,
+    pub code: String,
+    pub label: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub restrictions: TokenRestrictions,
+    pub active: bool,
+}
+
+impl InviteTokenView {
+    fn from_token(token: &InviteToken, now: u64) -> Self {
+        Self {
+            id: token.id.clone(),
+            code: token.code(),
+            label: token.label.clone(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            max_uses: token.max_uses,
+            uses: token.uses,
+            restrictions: token.restrictions.clone(),
+            active: token.is_usable(now),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// The token id if `code` has the shape of a token code, None for anything
+/// else (i.e. a plain PIN). Codes are strict enough that a user-chosen PIN is
+/// very unlikely to be mistaken for one.
+pub(crate) fn token_id_of(code: &str) -> Option<String> {
+    let (id, secret) = code.trim().split_once('.')?;
+    let id_ok = id.len() == TOKEN_ID_LEN && id.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase());
+    let secret_ok = secret.len() == TOKEN_SECRET_LEN && secret.bytes().all(|b| TOKEN_SECRET_CHARSET.contains(&b));
+    (id_ok && secret_ok).then(|| id.to_string())
+}
+
+fn generate_token(label: Option<String>, ttl_secs: u64, max_uses: u32, restrictions: TokenRestrictions, now: u64) -> InviteToken {
+    let mut rng = rand::thread_rng();
+    let id = format!("{:08x}", rng.gen::<u32>());
+    let secret = (0..TOKEN_SECRET_LEN)
+        .map(|_| TOKEN_SECRET_CHARSET[rng.gen_range(0..TOKEN_SECRET_CHARSET.len())] as char)
+        .collect();
+    InviteToken {
+        id,
+        secret,
+        label,
+        created_at: now,
+        expires_at: now + ttl_secs,
+        max_uses,
+        uses: 0,
+        restrictions,
+    }
+}
+
+/// Mint and persist a token.
+pub(crate) fn mint(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    label: Option<String>,
+    ttl_secs: u64,
+    max_uses: u32,
+    restrictions: TokenRestrictions,
+) -> Result<InviteToken, String> {
+    if !(TOKEN_MIN_TTL_SECS..=TOKEN_MAX_TTL_SECS).contains(&ttl_secs) {
+        return Err("Token lifetime must be between one minute and seven days".to_string());
+    }
+    if !(1..=TOKEN_MAX_USES).contains(&max_uses) {
+        return Err(format!("Token uses must be between 1 and {}", TOKEN_MAX_USES));
+    }
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    let mut tokens = state.invite_tokens.lock().unwrap();
+    let token = loop {
+        let t = generate_token(label.clone(), ttl_secs, max_uses, restrictions.clone(), now_secs());
+        if !tokens.iter().any(|existing| existing.id == t.id) {
+            break t;
+        }
+    };
+    tokens.push(token.clone());
+    crate::storage::save_invite_tokens(app_handle, &tokens);
+    Ok(token)
+}
+
+/// The SPAKE2 password for token `id`, if it can still be used.
+pub(crate) fn password_for(state: &AppState, id: &str) -> Option<String> {
+    let now = now_secs();
+    state
+        .invite_tokens
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == id && t.is_usable(now))
+        .map(InviteToken::code)
+}
+
+/// Spend one use of token `id`. Called once the initiator has proven the
+/// code. Returns the token's restrictions, or None if it ran out or was
+/// revoked since `password_for`.
+pub(crate) fn redeem(state: &AppState, app_handle: &tauri::AppHandle, id: &str) -> Option<TokenRestrictions> {
+    let now = now_secs();
+    let mut tokens = state.invite_tokens.lock().unwrap();
+    let token = tokens.iter_mut().find(|t| t.id == id && t.is_usable(now))?;
+    token.uses += 1;
+    let restrictions = token.restrictions.clone();
+    crate::storage::save_invite_tokens(app_handle, &tokens);
+    Some(restrictions)
+}
+
+/// Narrow our policy for `device_id` to `restrictions`.
+fn apply_restrictions(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    device_id: &str,
+    restrictions: &TokenRestrictions,
+) {
+    let Some(policy) = restrictions.policy() else {
+        return;
+    };
+    let mut policies = state.peer_policies.lock().unwrap();
+    policies.entry(device_id.to_string()).or_default().narrow(&policy);
+    crate::storage::save_peer_policies(app_handle, &policies);
+}
+
+/// Bytes covered by the issuer's signature.
+pub(crate) fn restriction_signed_bytes(
+    device_id: &str,
+    fingerprint: &[u8],
+    restrictions: &TokenRestrictions,
+    timestamp: u64,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(RESTRICTION_DOMAIN.len() + device_id.len() + fingerprint.len() + 11);
+    out.extend_from_slice(RESTRICTION_DOMAIN);
+    out.push(0);
+    out.extend_from_slice(device_id.as_bytes());
+    out.push(0);
+    out.extend_from_slice(fingerprint);
+    out.push(u8::from(restrictions.receive_only) | (u8::from(restrictions.no_file_transfer) << 1));
+    out.extend_from_slice(&timestamp.to_be_bytes());
+    out
+}
+
+/// Validate a restriction against the fingerprint we have pinned for its
+/// issuer. Pure so it can be unit-tested without an `AppHandle`.
+pub(crate) fn check_restriction(r: &JoinRestriction, issuer_pinned: Option<&[u8]>) -> Result<(), String> {
+    let pinned = issuer_pinned.ok_or("issuer is not a pinned member")?;
+    if cert_fingerprint(&r.issuer_cert) != pinned {
+        return Err("issuer cert does not match its pinned fingerprint".to_string());
+    }
+    if r.fingerprint.len() != 32 {
+        return Err("invalid restricted fingerprint".to_string());
+    }
+    let message = restriction_signed_bytes(&r.device_id, &r.fingerprint, &r.restrictions, r.timestamp);
+    if !verify_cert_signature(&r.issuer_cert, r.scheme, &message, &r.signature) {
+        return Err("signature does not verify under the issuer cert".to_string());
+    }
+    Ok(())
+}
+
+/// Give a device that joined with a restricted token its policy here, and
+/// sign the restriction so the rest of the cluster applies it too.
+pub(crate) fn restrict_joiner(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    device_id: &str,
+    fingerprint: &[u8],
+    restrictions: &TokenRestrictions,
+) {
+    if restrictions.policy().is_none() {
+        return;
+    }
+    apply_restrictions(state, app_handle, device_id, restrictions);
+    let timestamp = now_secs();
+    let message = restriction_signed_bytes(device_id, fingerprint, restrictions, timestamp);
+    let (scheme, signature) = match transport.sign_with_local_key(&message) {
+        Ok(signed) => signed,
+        Err(e) => {
+            tracing::error!("[Tokens] Failed to sign the restriction for {}: {}", device_id, e);
+            return;
+        }
+    };
+    let statement = JoinRestriction {
+        device_id: device_id.to_string(),
+        fingerprint: fingerprint.to_vec(),
+        restrictions: restrictions.clone(),
+        issuer_id: state.local_device_id.lock().unwrap().clone(),
+        issuer_cert: transport.local_cert_der(),
+        timestamp,
+        scheme,
+        signature,
+    };
+    let mut held = state.join_restrictions.lock().unwrap();
+    held.push(statement);
+    crate::storage::save_join_restrictions(app_handle, &held);
+}
+
+/// Verify, record and apply restrictions we haven't seen yet. Ones naming
+/// this device are kept (and passed on) but change nothing here.
+pub(crate) fn merge_restrictions(state: &AppState, app_handle: &tauri::AppHandle, incoming: &[JoinRestriction]) {
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    let mut accepted = Vec::new();
+    for r in incoming {
+        if state.join_restrictions.lock().unwrap().contains(r) {
+            continue;
+        }
+        let pinned = crate::revocation::pinned_for(state, &r.issuer_id);
+        let verdict = check_restriction(r, pinned.as_deref()).and_then(|()| {
+            if crate::revocation::shares_cluster(state, &r.issuer_id, &r.device_id, &r.fingerprint) {
+                Ok(())
+            } else {
+                Err("not in the issuer's cluster".to_string())
+            }
+        });
+        if let Err(reason) = verdict {
+            tracing::warn!(
+                "[Tokens] Ignoring restriction on {} issued by {}: {}",
+                r.device_id,
+                r.issuer_id,
+                reason
+            );
+            continue;
+        }
+        state.join_restrictions.lock().unwrap().push(r.clone());
+        accepted.push(r);
+    }
+    if accepted.is_empty() {
+        return;
+    }
+    crate::storage::save_join_restrictions(app_handle, &state.join_restrictions.lock().unwrap());
+    for r in accepted {
+        if r.device_id != local_id {
+            tracing::info!("[Tokens] {} restricted {} when it joined", r.issuer_id, r.device_id);
+            apply_restrictions(state, app_handle, &r.device_id, &r.restrictions);
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn mint_invite_token(
+    label: Option<String>,
+    ttl_secs: u64,
+    max_uses: u32,
+    restrictions: TokenRestrictions,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<InviteTokenView, String> {
+    let token = mint(&state, &app_handle, label, ttl_secs, max_uses, restrictions)?;
+    Ok(InviteTokenView::from_token(&token, now_secs()))
+}
+
+#[tauri::command]
+pub(crate) fn list_invite_tokens(state: tauri::State<'_, AppState>) -> Vec<InviteTokenView> {
+    let now = now_secs();
+    let mut views: Vec<_> = state
+        .invite_tokens
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|t| InviteTokenView::from_token(t, now))
+        .collect();
+    views.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    views
+}
+
+/// Delete a token. A pairing already past T2 with it still completes.
+#[tauri::command]
+pub(crate) fn revoke_invite_token(
+    id: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut tokens = state.invite_tokens.lock().unwrap();
+    let before = tokens.len();
+    tokens.retain(|t| t.id != id);
+    if tokens.len() == before {
+        return Err("No such invite token".to_string());
+    }
+    crate::storage::save_invite_tokens(&app_handle, &tokens);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_are_recognised_and_pins_are_not() {
+        let token = generate_token(None, 3600, 1, TokenRestrictions::default(), 1_000);
+        assert_eq!(token_id_of(&token.code()), Some(token.id.clone()));
+        assert_eq!(token_id_of(&format!("  {}\n", token.code())), Some(token.id));
+        for not_a_token in ["a1b2c3", "123456", "deadbeef.short", "DEADBEEF.abcdefghijkmnpqr", "deadbeef.abcdefghijkmnpq0"] {
+            assert_eq!(token_id_of(not_a_token), None, "{} is not a token", not_a_token);
+        }
+    }
+
+    #[test]
+    fn token_stops_being_usable_when_expired_or_used_up() {
+        let mut token = generate_token(None, 60, 2, TokenRestrictions::default(), 1_000);
+        assert!(token.is_usable(1_000));
+        assert!(!token.is_usable(1_060));
+        token.uses = 2;
+        assert!(!token.is_usable(1_000));
+    }
+
+    #[test]
+    fn restriction_verifies_only_under_the_pinned_issuer() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let (_, other_key) = crate::transport::generate_self_signed_cert().unwrap();
+        let restrictions = TokenRestrictions { receive_only: true, no_file_transfer: false };
+        let sign = |key: &[u8], restrictions: &TokenRestrictions| {
+            let (scheme, signature) =
+                crate::transport::sign_with_key(key, &restriction_signed_bytes("clustercut-guest", &[7; 32], restrictions, 1_000))
+                    .unwrap();
+            JoinRestriction {
+                device_id: "clustercut-guest".to_string(),
+                fingerprint: vec![7; 32],
+                restrictions: restrictions.clone(),
+                issuer_id: "clustercut-admin".to_string(),
+                issuer_cert: cert.clone(),
+                timestamp: 1_000,
+                scheme,
+                signature,
+            }
+        };
+        let pinned = cert_fingerprint(&cert);
+
+        let genuine = sign(&key, &restrictions);
+        assert!(check_restriction(&genuine, Some(&pinned)).is_ok());
+        assert!(check_restriction(&genuine, None).is_err());
+        assert!(check_restriction(&sign(&other_key, &restrictions), Some(&pinned)).is_err());
+
+        // Loosened in transit: the signature no longer covers it.
+        let mut loosened = genuine;
+        loosened.restrictions = TokenRestrictions::default();
+        assert!(check_restriction(&loosened, Some(&pinned)).is_err());
+    }
+
+    #[test]
+    fn restrictions_map_onto_peer_policy() {
+        assert_eq!(TokenRestrictions::default().policy(), None);
+        let policy = TokenRestrictions { receive_only: true, no_file_transfer: false }.policy().unwrap();
+        assert!(policy.send_to);
+        assert!(!policy.receive_from);
+        assert!(policy.file_transfer);
+        let policy = TokenRestrictions { receive_only: false, no_file_transfer: true }.policy().unwrap();
+        assert!(policy.receive_from);
+        assert!(!policy.file_transfer);
+    }
+}
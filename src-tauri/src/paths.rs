@@ -0,0 +1,405 @@
+//! Candidate addresses per peer and choosing between them.
+//!
+//! A peer used to have exactly one address, taken from whichever record mDNS
+//! happened to resolve first. A desktop on both the LAN and a WireGuard link
+//! flipped between the two on every rediscovery, and a dead path stayed dead
+//! until the next one. Now every address a peer is seen at (mDNS, the source
+//! of its heartbeats, the interfaces it advertises about itself) is kept as a
+//! candidate with our own success/RTT history for it:
+//!
+//! - `Peer::ip`/`port` hold the preferred candidate and only move when that
+//!   one starts failing or another path wins a connection race;
+//! - `Transport` asks `candidates_for` before each connection, starts on the
+//!   preferred address and, if it hasn't connected within
+//!   `PATH_RACE_STAGGER`, races the next one, so a dead path fails over
+//!   without waiting for a timeout;
+//! - every attempt is reported back through `record_outcome`.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::peer::{same_endpoint, Peer, PeerAddress};
+use crate::state::AppState;
+
+/// Most candidates tried for a single connection.
+pub(crate) const MAX_RACE_CANDIDATES: usize = 4;
+/// Most candidates remembered per peer.
+const MAX_ADDRESSES: usize = 8;
+/// Failures after which a candidate is tried only once the others have been.
+const DEMOTE_AFTER_FAILURES: u32 = 2;
+/// A candidate neither advertised nor used for this long is forgotten.
+const FORGET_AFTER_SECS: u64 = 7 * 24 * 60 * 60;
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn is_demoted(a: &PeerAddress) -> bool {
+    a.failures >= DEMOTE_AFTER_FAILURES
+}
+
+/// Candidates best first: healthy before demoted, then `preferred`, then by
+/// measured RTT (unmeasured last), then most recent success. IPv4 before IPv6
+/// and address order break the remaining ties, so the order never depends on
+/// the order mDNS happened to report addresses in.
+pub(crate) fn ranked(addresses: &[PeerAddress], preferred: Option<SocketAddr>) -> Vec<SocketAddr> {
+    let mut sorted: Vec<&PeerAddress> = addresses.iter().collect();
+    sorted.sort_by_key(|a| {
+        (
+            is_demoted(a),
+            Some(a.addr) != preferred,
+            a.rtt_ms.is_none(),
+            a.rtt_ms,
+            std::cmp::Reverse(a.last_success),
+            a.addr.is_ipv6(),
+            a.addr,
+        )
+    });
+    sorted.into_iter().map(|a| a.addr).collect()
+}
+
+fn prune(addresses: &mut Vec<PeerAddress>, keep: SocketAddr, now: u64) {
+    addresses.retain(|a| {
+        let last_used = a.last_success.unwrap_or(0).max(a.last_seen);
+        same_endpoint(a.addr, keep) || now.saturating_sub(last_used) < FORGET_AFTER_SECS
+    });
+    if addresses.len() > MAX_ADDRESSES {
+        // Drop the least recently seen, never `keep`.
+        addresses.sort_by_key(|a| (a.addr != keep, std::cmp::Reverse(a.last_seen)));
+        addresses.truncate(MAX_ADDRESSES);
+    }
+}
+
+/// Fold what `incoming` says about a peer into the candidates we already hold
+/// for it in `existing`, and pick its preferred address. The addresses
+/// `incoming` carries are taken, its stats are not: they describe the
+/// sender's view of the network, not ours.
+pub(crate) fn merge_into(existing: Option<&Peer>, incoming: &mut Peer, now: u64) {
+    let mut addresses = existing.map(|p| p.addresses.clone()).unwrap_or_default();
+    // Records from older builds have no candidate list, only ip/port.
+    if let Some(prev) = existing {
+        if !addresses.iter().any(|a| same_endpoint(a.addr, prev.addr())) {
+            addresses.push(PeerAddress::new(prev.addr(), prev.last_seen));
+        }
+    }
+    let advertised = std::iter::once(incoming.addr()).chain(incoming.addresses.iter().map(|a| a.addr));
+    for addr in advertised {
+        if addr.ip().is_unspecified() {
+            continue;
+        }
+        match addresses.iter_mut().find(|a| same_endpoint(a.addr, addr)) {
+            Some(a) => {
+                a.last_seen = now;
+                // Keep whichever form carries an IPv6 scope id.
+                if let (SocketAddr::V6(known), SocketAddr::V6(seen)) = (a.addr, addr) {
+                    if known.scope_id() == 0 && seen.scope_id() != 0 {
+                        a.addr = addr;
+                    }
+                }
+            }
+            None => addresses.push(PeerAddress::new(addr, now)),
+        }
+    }
+
+    // Stay on the current path while it works; a rediscovery listing the
+    // addresses in another order must not move us.
+    let current = existing
+        .map(Peer::addr)
+        .filter(|cur| addresses.iter().any(|a| same_endpoint(a.addr, *cur) && !is_demoted(a)));
+    let preferred = current
+        .or_else(|| ranked(&addresses, None).first().copied())
+        .unwrap_or_else(|| incoming.addr());
+    prune(&mut addresses, preferred, now);
+    incoming.ip = preferred.ip();
+    incoming.port = preferred.port();
+    incoming.addresses = addresses;
+    // Our relay choice and link stats aren't part of anyone's record.
+    incoming.relay_via = existing.and_then(|p| p.relay_via.clone());
+    incoming.link = existing.map(|p| p.link.clone()).unwrap_or_default();
+    incoming.device = existing.and_then(|p| p.device.clone());
+    // Only heartbeats carry a status; other sightings keep the last one.
+    if incoming.status.is_none() {
+        incoming.status = existing.and_then(|p| p.status.clone());
+    }
+}
+
+/// Record the outcome of one connection attempt to `addr` on `peer`: the
+/// connection's RTT on success, None on failure. A success makes `addr` the
+/// preferred path; enough failures on the preferred path move it to the next
+/// best candidate.
+pub(crate) fn apply_outcome(peer: &mut Peer, addr: SocketAddr, rtt: Option<Duration>, now: u64) {
+    let Some(entry) = peer.addresses.iter_mut().find(|a| same_endpoint(a.addr, addr)) else {
+        return;
+    };
+    match rtt {
+        Some(rtt) => {
+            let sample = rtt.as_millis() as u64;
+            entry.rtt_ms = Some(entry.rtt_ms.map_or(sample, |old| (old * 3 + sample) / 4));
+            entry.last_success = Some(now);
+            entry.failures = 0;
+            peer.ip = addr.ip();
+            peer.port = addr.port();
+            peer.relay_via = None;
+        }
+        None => {
+            entry.failures = entry.failures.saturating_add(1);
+            let demoted = is_demoted(entry);
+            if demoted && same_endpoint(peer.addr(), addr) {
+                if let Some(next) = ranked(&peer.addresses, None).first().copied() {
+                    peer.ip = next.ip();
+                    peer.port = next.port();
+                }
+            }
+        }
+    }
+}
+
+/// A message from the peer arrived on `addr`, which proves the path works
+/// at least inbound. It becomes preferred if the current path never has.
+pub(crate) fn apply_inbound(peer: &mut Peer, addr: SocketAddr, now: u64) {
+    let current_proven = peer
+        .addresses
+        .iter()
+        .any(|a| same_endpoint(a.addr, peer.addr()) && a.last_success.is_some());
+    let Some(entry) = peer.addresses.iter_mut().find(|a| same_endpoint(a.addr, addr)) else {
+        return;
+    };
+    entry.last_success = Some(now);
+    entry.failures = 0;
+    if !current_proven {
+        peer.ip = addr.ip();
+        peer.port = addr.port();
+    }
+}
+
+/// Record an authenticated message from runtime peer `id` arriving on
+/// `addr`. Returns the updated record.
+pub(crate) fn record_inbound(state: &AppState, id: &str, addr: SocketAddr) -> Option<Peer> {
+    let mut peers = state.peers.lock().unwrap();
+    let peer = peers.get_mut(id)?;
+    apply_inbound(peer, addr, now_secs());
+    Some(peer.clone())
+}
+
+/// Addresses to try, best first, when connecting to `addr`. `addr` itself
+/// leads unless it has been failing; an address that belongs to no peer we
+/// know is tried on its own.
+pub(crate) fn candidates_for(state: &AppState, addr: SocketAddr) -> Vec<SocketAddr> {
+    let peers = state.peers.lock().unwrap();
+    let peer = peers
+        .values()
+        .find(|p| same_endpoint(p.addr(), addr))
+        .or_else(|| peers.values().find(|p| p.reachable_at(addr)));
+    let Some(peer) = peer else {
+        return vec![addr];
+    };
+    let mut out = ranked(&peer.addresses, Some(addr));
+    if !out.iter().any(|a| same_endpoint(*a, addr)) {
+        out.insert(0, addr);
+    }
+    out.truncate(MAX_RACE_CANDIDATES);
+    out
+}
+
+/// Record a connection attempt made by `Transport`.
+pub(crate) fn record_outcome(state: &AppState, addr: SocketAddr, rtt: Option<Duration>) {
+    let now = now_secs();
+    let reached = {
+        let mut peers = state.peers.lock().unwrap();
+        let Some(peer) = peers.values_mut().find(|p| p.addresses.iter().any(|a| same_endpoint(a.addr, addr))) else {
+            return;
+        };
+        let before = peer.addr();
+        apply_outcome(peer, addr, rtt, now);
+        if peer.addr() != before {
+            tracing::info!("[Paths] {} now preferred via {} (was {})", peer.id, peer.addr(), before);
+        }
+        rtt.map(|_| peer.id.clone())
+    };
+    // Outbound successes are what we offer to relay on (relay.rs).
+    if let Some(id) = reached {
+        state.relay_table.lock().unwrap().note_direct(&id, now);
+    }
+}
+
+/// Candidates from the addresses of one resolved mDNS record, best first.
+/// Link-local IPv6 is kept only when the record says which interface it was
+/// seen on.
+pub(crate) fn mdns_candidates<I, T>(ips: I, port: u16) -> Vec<PeerAddress>
+where
+    I: IntoIterator<Item = T>,
+    T: ToString,
+{
+    let now = now_secs();
+    let mut out: Vec<PeerAddress> = Vec::new();
+    for ip in ips {
+        let Ok(addr) = crate::net_util::parse_peer_addr(&ip.to_string(), port) else {
+            continue;
+        };
+        if !out.iter().any(|a| a.addr == addr) {
+            out.push(PeerAddress::new(addr, now));
+        }
+    }
+    let order = ranked(&out, None);
+    out.sort_by_key(|a| order.iter().position(|o| *o == a.addr));
+    out
+}
+
+/// Our own addresses on `port`, for the self record we gossip, so members
+/// learn every path to us and not just the one a heartbeat arrived on.
+/// Interfaces matched by `exclude` (`AppSettings::interface_exclude`) are
+/// left out, as they are from the mDNS record.
+pub(crate) fn local_candidates(exclude: &[String], port: u16) -> Vec<PeerAddress> {
+    let now = now_secs();
+    let Ok(ifaces) = local_ip_address::list_afinet_netifas() else {
+        return Vec::new();
+    };
+    let mut out: Vec<PeerAddress> = Vec::new();
+    for (name, ip) in ifaces {
+        if crate::net_util::interface_excluded(&name, ip, exclude) {
+            continue;
+        }
+        // Link-local scope ids are our interface indices, meaningless to the
+        // receiver; it learns those paths from where our packets arrive.
+        let usable = match ip {
+            std::net::IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local() && !v4.is_unspecified(),
+            std::net::IpAddr::V6(v6) => {
+                !v6.is_loopback() && !v6.is_unspecified() && !crate::net_util::is_v6_link_local(v6)
+            }
+        };
+        let addr = SocketAddr::new(ip, port);
+        if usable && !out.iter().any(|a| a.addr == addr) {
+            out.push(PeerAddress::new(addr, now));
+        }
+    }
+    out.truncate(MAX_ADDRESSES);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn sa(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    fn peer(addr: &str, addresses: Vec<PeerAddress>) -> Peer {
+        let addr = sa(addr);
+        Peer {
+            id: "clustercut-1".to_string(),
+            ip: addr.ip(),
+            port: addr.port(),
+            hostname: "desk".to_string(),
+            last_seen: NOW,
+            is_trusted: true,
+            is_manual: false,
+            network_name: None,
+            signature: None,
+            fingerprint: Some(vec![1]),
+            protocol_version: None,
+            addresses,
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
+        }
+    }
+
+    #[test]
+    fn rediscovery_in_another_order_keeps_the_current_path() {
+        let lan = sa("192.168.1.10:4654");
+        let wg = sa("10.8.0.2:4654");
+        let mut first = peer("192.168.1.10:4654", vec![PeerAddress::new(wg, NOW)]);
+        merge_into(None, &mut first, NOW);
+        // Nothing measured yet: the lower address wins, whatever order it came in.
+        assert_eq!(first.addr(), wg);
+
+        // mDNS reports only the LAN address this time.
+        let mut again = peer("192.168.1.10:4654", Vec::new());
+        merge_into(Some(&first), &mut again, NOW + 5);
+        assert_eq!(again.addr(), wg);
+        assert!(again.reachable_at(lan));
+        assert_eq!(again.addresses.len(), 2);
+    }
+
+    #[test]
+    fn failing_preferred_path_fails_over() {
+        let mut p = peer("192.168.1.10:4654", vec![PeerAddress::new(sa("10.8.0.2:4654"), NOW)]);
+        merge_into(None, &mut p, NOW);
+        let lan = sa("192.168.1.10:4654");
+        let wg = sa("10.8.0.2:4654");
+        apply_outcome(&mut p, lan, Some(Duration::from_millis(2)), NOW);
+        assert_eq!(p.addr(), lan);
+
+        apply_outcome(&mut p, lan, None, NOW);
+        assert_eq!(p.addr(), lan, "one failure is not enough to move");
+        apply_outcome(&mut p, lan, None, NOW);
+        assert_eq!(p.addr(), wg);
+
+        // The LAN coming back wins a race and takes over again.
+        apply_outcome(&mut p, lan, Some(Duration::from_millis(3)), NOW);
+        assert_eq!(p.addr(), lan);
+        assert_eq!(p.addresses.iter().find(|a| a.addr == lan).unwrap().failures, 0);
+    }
+
+    #[test]
+    fn inbound_traffic_replaces_an_unproven_path() {
+        // Pairing records the initiator at its TCP source port.
+        let mut p = peer("192.168.1.10:51234", Vec::new());
+        merge_into(None, &mut p, NOW);
+        let quic = sa("192.168.1.10:4654");
+        let mut heartbeat = peer("192.168.1.10:4654", Vec::new());
+        merge_into(Some(&p), &mut heartbeat, NOW);
+        assert_eq!(heartbeat.addr(), sa("192.168.1.10:51234"));
+        apply_inbound(&mut heartbeat, quic, NOW);
+        assert_eq!(heartbeat.addr(), quic);
+
+        // A proven path is not displaced by traffic arriving elsewhere.
+        let mut wg = peer("10.8.0.2:4654", Vec::new());
+        merge_into(Some(&heartbeat), &mut wg, NOW);
+        apply_inbound(&mut wg, sa("10.8.0.2:4654"), NOW);
+        assert_eq!(wg.addr(), quic);
+    }
+
+    #[test]
+    fn ranking_prefers_healthy_then_fast() {
+        let mut slow = PeerAddress::new(sa("10.8.0.2:4654"), NOW);
+        slow.rtt_ms = Some(40);
+        let mut fast = PeerAddress::new(sa("192.168.1.10:4654"), NOW);
+        fast.rtt_ms = Some(2);
+        let unmeasured = PeerAddress::new(sa("172.16.0.5:4654"), NOW);
+        let mut broken = PeerAddress::new(sa("10.0.0.1:4654"), NOW);
+        broken.rtt_ms = Some(1);
+        broken.failures = DEMOTE_AFTER_FAILURES;
+        let all = vec![broken.clone(), unmeasured.clone(), slow.clone(), fast.clone()];
+        assert_eq!(ranked(&all, None), vec![fast.addr, slow.addr, unmeasured.addr, broken.addr]);
+        // The requested address leads while it is healthy.
+        assert_eq!(ranked(&all, Some(slow.addr))[0], slow.addr);
+        assert_eq!(ranked(&all, Some(broken.addr))[0], fast.addr);
+    }
+
+    #[test]
+    fn gossiped_stats_are_not_imported_and_old_addresses_expire() {
+        let mut p = peer("192.168.1.10:4654", Vec::new());
+        merge_into(None, &mut p, NOW);
+
+        let mut claimed = PeerAddress::new(sa("10.8.0.2:4654"), NOW);
+        claimed.rtt_ms = Some(1);
+        claimed.last_success = Some(NOW);
+        let mut gossip = peer("192.168.1.10:4654", vec![claimed]);
+        merge_into(Some(&p), &mut gossip, NOW);
+        let imported = gossip.addresses.iter().find(|a| a.addr == sa("10.8.0.2:4654")).unwrap();
+        assert_eq!((imported.rtt_ms, imported.last_success), (None, None));
+
+        let mut later = peer("192.168.1.10:4654", Vec::new());
+        merge_into(Some(&gossip), &mut later, NOW + FORGET_AFTER_SECS);
+        assert_eq!(later.addresses.len(), 1);
+    }
+}
@@ -31,6 +31,67 @@ pub struct Peer {
     /// resolution.
     #[serde(default)]
     pub protocol_version: Option<String>,
+    /// Every address the peer has been seen at, with how each has worked for
+    /// us. `ip`/`port` always hold the preferred one out of these (see
+    /// `paths.rs`), so code and older builds that only know one address keep
+    /// working. Empty on records from older builds.
+    #[serde(default)]
+    pub addresses: Vec<PeerAddress>,
+    /// In a device's own heartbeat record: the members it has recently
+    /// reached directly, i.e. those it can relay to (relay.rs). Taken out on
+    /// receipt, so stored records always have it empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reaches: Vec<String>,
+    /// Ours only: the member currently relaying our traffic to this peer,
+    /// set while direct connections to it fail.
+    #[serde(skip)]
+    pub relay_via: Option<String>,
+    /// In a device's own heartbeat record: what it is doing (status.rs).
+    /// Records gossiped about third parties and stored records carry none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<crate::status::PeerStatus>,
+    /// Ours only: what the peer told us it runs (device_info.rs).
+    #[serde(skip)]
+    pub device: Option<crate::device_info::DeviceInfo>,
+    /// Ours only: round trips and throughput measured to this peer (link.rs).
+    #[serde(skip)]
+    pub link: crate::link::LinkStats,
+}
+
+/// Same IP and port, whatever the IPv6 scope id or flow info.
+pub fn same_endpoint(a: std::net::SocketAddr, b: std::net::SocketAddr) -> bool {
+    a.ip() == b.ip() && a.port() == b.port()
+}
+
+/// One candidate address of a [`Peer`]. The stats are ours: when a gossiped
+/// record is merged, only the addresses are taken from it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerAddress {
+    pub addr: std::net::SocketAddr,
+    /// Unix seconds the address was last advertised for the peer.
+    #[serde(default)]
+    pub last_seen: u64,
+    /// Unix seconds of the last connection that succeeded on this address.
+    #[serde(default)]
+    pub last_success: Option<u64>,
+    /// Smoothed QUIC round-trip time on this address.
+    #[serde(default)]
+    pub rtt_ms: Option<u64>,
+    /// Failed connection attempts since the last success.
+    #[serde(default)]
+    pub failures: u32,
+}
+
+impl PeerAddress {
+    pub fn new(addr: std::net::SocketAddr, now: u64) -> Self {
+        Self {
+            addr,
+            last_seen: now,
+            last_success: None,
+            rtt_ms: None,
+            failures: 0,
+        }
+    }
 }
 
 /// Frontend-only view of a peer. Carries all `Peer` fields plus a
@@ -56,6 +117,154 @@ impl Peer {
     pub fn needs_repair(&self) -> bool {
         self.fingerprint.is_none() && !self.id.starts_with("manual-")
     }
+
+    /// The preferred address. `ip` can't hold an IPv6 scope id, so for a
+    /// link-local address it is taken from the matching candidate.
+    pub fn addr(&self) -> std::net::SocketAddr {
+        let plain = std::net::SocketAddr::new(self.ip, self.port);
+        self.addresses
+            .iter()
+            .map(|a| a.addr)
+            .find(|a| same_endpoint(*a, plain))
+            .unwrap_or(plain)
+    }
+
+    /// True if `addr` is the preferred address or one of the candidates.
+    /// Scope ids are ignored: they are local interface indices.
+    pub fn reachable_at(&self, addr: std::net::SocketAddr) -> bool {
+        same_endpoint(self.addr(), addr) || self.addresses.iter().any(|a| same_endpoint(a.addr, addr))
+    }
+
+    /// True if any of the peer's addresses is on `ip`.
+    pub fn has_ip(&self, ip: std::net::IpAddr) -> bool {
+        self.ip == ip || self.addresses.iter().any(|a| a.addr.ip() == ip)
+    }
+}
+
+/// Broad content category a [`PeerPolicy`] can allow or block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentClass {
+    /// Plain text, inline or as a large-text descriptor.
+    Text,
+    /// Rich representations (HTML, RTF, …) riding alongside plain text.
+    Rich,
+    Image,
+    Files,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// What we let a single trusted peer do. Stored locally per device id in
+/// `peer_policies.json` (see `storage::load_peer_policies`) rather than on
+/// [`Peer`] itself: `Peer` records travel to other members in `ClusterInfo`
+/// and `PeerDiscovery`, and our restrictions on a device are nobody else's
+/// business. A peer with no entry gets `PeerPolicy::default()`, which is the
+/// historical allow-everything behaviour.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeerPolicy {
+    /// Broadcast our clipboard to this peer and serve its fetches.
+    #[serde(default = "default_true")]
+    pub send_to: bool,
+    /// Accept clipboard pushes from this peer.
+    #[serde(default = "default_true")]
+    pub receive_from: bool,
+    /// File transfer in either direction. Clipboard images and large text
+    /// are governed by `allowed_classes`, not this flag.
+    #[serde(default = "default_true")]
+    pub file_transfer: bool,
+    /// Overrides `AppSettings::max_auto_download_size` for content from this
+    /// peer. None ⇒ use the global setting.
+    #[serde(default)]
+    pub max_auto_download_size: Option<u64>,
+    /// Content classes we accept *from* this peer. None ⇒ all of them.
+    #[serde(default)]
+    pub allowed_classes: Option<Vec<ContentClass>>,
+}
+
+impl Default for PeerPolicy {
+    fn default() -> Self {
+        Self {
+            send_to: true,
+            receive_from: true,
+            file_transfer: true,
+            max_auto_download_size: None,
+            allowed_classes: None,
+        }
+    }
+}
+
+impl PeerPolicy {
+    pub fn allows_class(&self, class: ContentClass) -> bool {
+        self.allowed_classes
+            .as_ref()
+            .map_or(true, |classes| classes.contains(&class))
+    }
+
+    pub fn auto_download_limit(&self, global: u64) -> u64 {
+        self.max_auto_download_size.unwrap_or(global)
+    }
+
+    /// Narrow this policy to what `other` allows as well: each switch must
+    /// be on in both, the smaller download limit wins and only classes both
+    /// allow remain.
+    pub fn narrow(&mut self, other: &PeerPolicy) {
+        self.send_to &= other.send_to;
+        self.receive_from &= other.receive_from;
+        self.file_transfer &= other.file_transfer;
+        self.max_auto_download_size = match (self.max_auto_download_size, other.max_auto_download_size) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(theirs) = &other.allowed_classes {
+            let mine = self.allowed_classes.get_or_insert_with(|| theirs.clone());
+            mine.retain(|c| theirs.contains(c));
+        }
+    }
+
+    /// Whether `payload` may be broadcast to this peer.
+    pub fn allows_outgoing(&self, payload: &crate::protocol::ClipboardPayload) -> bool {
+        if !self.send_to {
+            return false;
+        }
+        let has_files = payload.files.as_ref().map_or(false, |f| !f.is_empty());
+        !has_files || self.file_transfer
+    }
+
+    /// Filter a payload pushed by this peer down to what we accept. Rich
+    /// formats are stripped (leaving plain text) when only `Rich` is blocked;
+    /// everything else is all-or-nothing. Returns None when nothing is left.
+    pub fn filter_incoming(
+        &self,
+        mut payload: crate::protocol::ClipboardPayload,
+    ) -> Option<crate::protocol::ClipboardPayload> {
+        if !self.receive_from {
+            return None;
+        }
+        if payload.files.as_ref().map_or(false, |f| !f.is_empty()) {
+            if self.file_transfer && self.allows_class(ContentClass::Files) {
+                return Some(payload);
+            }
+            return None;
+        }
+        if let Some(blob) = &payload.blob {
+            let class = if blob.mime_type.starts_with("text/") {
+                ContentClass::Text
+            } else {
+                ContentClass::Image
+            };
+            return self.allows_class(class).then_some(payload);
+        }
+        if !self.allows_class(ContentClass::Text) {
+            return None;
+        }
+        if payload.formats.is_some() && !self.allows_class(ContentClass::Rich) {
+            payload.formats = None;
+        }
+        Some(payload)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -70,6 +279,19 @@ pub struct PeerView {
     pub network_name: Option<String>,
     pub fingerprint: Option<Vec<u8>>,
     pub protocol_version: Option<String>,
+    /// Candidate addresses with our path stats; `ip`/`port` is the preferred one.
+    pub addresses: Vec<PeerAddress>,
+    /// Id of the member relaying our traffic to this peer, if any.
+    pub relay_via: Option<String>,
+    /// What the peer last said it is doing; None until it heartbeats.
+    pub status: Option<crate::status::PeerStatus>,
+    /// OS, desktop, clipboard backend, version and formats, once the peer
+    /// has sent them.
+    pub device: Option<crate::device_info::DeviceInfo>,
+    /// What the peer can't do that we can, in words (`DeviceInfo::gaps`).
+    pub capability_gaps: Vec<String>,
+    /// RTT, jitter and throughput we have measured to this peer.
+    pub link: crate::link::LinkSummary,
     /// True when the peer's advertised `protocol_version` is >= the minimum
     /// this build requires. Computed by `net_util::is_protocol_compatible`;
     /// never travels over the peer-to-peer wire.
@@ -89,6 +311,16 @@ impl PeerView {
             network_name: peer.network_name.clone(),
             fingerprint: peer.fingerprint.clone(),
             protocol_version: peer.protocol_version.clone(),
+            addresses: peer.addresses.clone(),
+            relay_via: peer.relay_via.clone(),
+            status: peer.status.clone(),
+            device: peer.device.clone(),
+            capability_gaps: peer
+                .device
+                .as_ref()
+                .map(|d| d.gaps(&crate::device_info::local()))
+                .unwrap_or_default(),
+            link: peer.link.summary(),
             compatible: crate::net_util::is_protocol_compatible(peer.protocol_version.as_deref()),
         }
     }
@@ -111,6 +343,12 @@ mod tests {
             signature: None,
             fingerprint,
             protocol_version: None,
+            addresses: Vec::new(),
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
         }
     }
 
@@ -134,6 +372,120 @@ mod tests {
         assert!(!peer("manual-192.168.96.17", None).needs_repair());
     }
 
+    fn payload(
+        text: &str,
+        files: bool,
+        blob_mime: Option<&str>,
+        rich: bool,
+    ) -> crate::protocol::ClipboardPayload {
+        crate::protocol::ClipboardPayload {
+            id: "id".to_string(),
+            text: text.to_string(),
+            files: files.then(|| {
+                vec![crate::protocol::FileMetadata {
+                    name: "a.pdf".to_string(),
+                    size: 10,
+                }]
+            }),
+            blob: blob_mime.map(|m| crate::protocol::ClipboardBlob::descriptor(m, "f", 10, None, None)),
+            formats: rich.then(|| vec![crate::protocol::ClipboardFormat::from_text("text/html", "<b>x</b>")]),
+            timestamp: 0,
+            sender: "h".to_string(),
+            sender_id: "d".to_string(),
+            ttl_secs: None,
+            seq: None,
+            cut: false,
+        }
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let policy = PeerPolicy::default();
+        assert!(policy.allows_outgoing(&payload("", true, None, false)));
+        assert!(policy.filter_incoming(payload("", true, None, false)).is_some());
+        assert!(policy.filter_incoming(payload("", false, Some("image/png"), false)).is_some());
+        let rich = policy.filter_incoming(payload("x", false, None, true)).unwrap();
+        assert!(rich.formats.is_some());
+        assert_eq!(policy.auto_download_limit(42), 42);
+    }
+
+    #[test]
+    fn policy_deserializes_missing_fields_as_permissive() {
+        let policy: PeerPolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, PeerPolicy::default());
+    }
+
+    #[test]
+    fn receive_only_peer_never_pushes_files() {
+        // The shared meeting-room PC: it gets our clipboard, but we refuse
+        // anything it pushes as files.
+        let policy = PeerPolicy {
+            allowed_classes: Some(vec![ContentClass::Text, ContentClass::Image]),
+            ..PeerPolicy::default()
+        };
+        assert!(policy.filter_incoming(payload("", true, None, false)).is_none());
+        assert!(policy.filter_incoming(payload("hi", false, None, false)).is_some());
+        assert!(policy.allows_outgoing(&payload("", true, None, false)));
+    }
+
+    #[test]
+    fn blocked_rich_falls_back_to_plain_text() {
+        let policy = PeerPolicy {
+            allowed_classes: Some(vec![ContentClass::Text]),
+            ..PeerPolicy::default()
+        };
+        let filtered = policy.filter_incoming(payload("x", false, None, true)).unwrap();
+        assert_eq!(filtered.text, "x");
+        assert!(filtered.formats.is_none());
+        assert!(policy.filter_incoming(payload("", false, Some("image/png"), false)).is_none());
+        // Large text arrives as a text/* descriptor and counts as Text.
+        assert!(policy.filter_incoming(payload("", false, Some("text/plain"), false)).is_some());
+    }
+
+    #[test]
+    fn file_transfer_flag_gates_both_directions() {
+        let policy = PeerPolicy {
+            file_transfer: false,
+            ..PeerPolicy::default()
+        };
+        assert!(!policy.allows_outgoing(&payload("", true, None, false)));
+        assert!(policy.allows_outgoing(&payload("hi", false, None, false)));
+        assert!(policy.filter_incoming(payload("", true, None, false)).is_none());
+    }
+
+    #[test]
+    fn narrowing_keeps_only_what_both_allow() {
+        let mut policy = PeerPolicy {
+            max_auto_download_size: Some(10),
+            allowed_classes: Some(vec![ContentClass::Text, ContentClass::Image]),
+            ..PeerPolicy::default()
+        };
+        policy.narrow(&PeerPolicy {
+            file_transfer: false,
+            max_auto_download_size: Some(50),
+            allowed_classes: Some(vec![ContentClass::Image, ContentClass::Files]),
+            ..PeerPolicy::default()
+        });
+        assert!(policy.send_to && policy.receive_from && !policy.file_transfer);
+        assert_eq!(policy.max_auto_download_size, Some(10));
+        assert_eq!(policy.allowed_classes, Some(vec![ContentClass::Image]));
+
+        let mut open = PeerPolicy::default();
+        open.narrow(&policy);
+        assert_eq!(open, policy);
+    }
+
+    #[test]
+    fn send_and_receive_switches() {
+        let policy = PeerPolicy {
+            send_to: false,
+            receive_from: false,
+            ..PeerPolicy::default()
+        };
+        assert!(!policy.allows_outgoing(&payload("hi", false, None, false)));
+        assert!(policy.filter_incoming(payload("hi", false, None, false)).is_none());
+    }
+
     #[test]
     fn manual_placeholder_with_fingerprint_does_not_need_repair() {
         // Defensive: a manual id should never be flagged regardless of fingerprint.
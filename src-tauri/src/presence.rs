@@ -122,7 +122,7 @@ pub(crate) fn touch_peer_by_addr(state: &AppState, addr: std::net::SocketAddr) -
         let mut peers = state.peers.lock().unwrap();
         match peers
             .values_mut()
-            .find(|p| p.ip == addr.ip() && p.port == addr.port())
+            .find(|p| p.reachable_at(addr))
         {
             Some(p) => {
                 p.last_seen = now;
@@ -176,9 +176,10 @@ pub(crate) fn peers_needing_probe(
 ///
 /// Conservative by design — inserts only entries that are new to us,
 /// fingerprinted, not ourselves, not `manual-<ip>` placeholders (those
-/// are the sender's local reachability hints, not members), and not
-/// tombstoned (deleted this session — a member that missed the removal
-/// broadcast must not gossip the deleted device straight back). Existing
+/// are the sender's local reachability hints, not members), and neither
+/// tombstoned (deleted this session) nor revoked (see revocation.rs) — a
+/// member that missed the removal must not gossip the deleted device
+/// straight back. Callers merge `info.revocations` first. Existing
 /// entries are never overwritten: direct contact refreshes those. Runtime
 /// presence is untouched; the caller probes imports to surface them.
 /// Cluster name/id/PIN adoption is pairing-only and does NOT happen here.
@@ -190,6 +191,7 @@ pub(crate) fn merge_cluster_membership(
 ) -> Vec<Peer> {
     let local_id = state.local_device_id.lock().unwrap().clone();
     let tombstones = state.removed_peer_tombstones.lock().unwrap().clone();
+    let revocations = state.revocations.lock().unwrap().clone();
     let mut kp = state.known_peers.lock().unwrap();
     let mut imported = Vec::new();
     for peer in &info.known_peers {
@@ -202,6 +204,12 @@ pub(crate) fn merge_cluster_membership(
         if tombstones.contains(&peer.id) {
             continue;
         }
+        if revocations
+            .iter()
+            .any(|r| r.device_id == peer.id || peer.fingerprint.as_deref() == Some(r.fingerprint.as_slice()))
+        {
+            continue;
+        }
         if peer.fingerprint.is_none() {
             // Unusable under strict mTLS, and importing it would trip the
             // "needs re-pair" banner for a device we never actually paired.
@@ -276,15 +284,14 @@ pub(crate) fn reprobe_known_peers(
                         .lock()
                         .unwrap()
                         .values()
-                        .any(|p| p.ip == peer.ip && p.port == peer.port);
+                        .any(|p| p.reachable_at(peer.addr()));
                     if surfaced {
                         return;
                     }
                 }
                 let last = attempt + 1 == attempts;
                 if crate::net_util::probe_ip(
-                    peer.ip,
-                    peer.port,
+                    peer.addr(),
                     s.clone(),
                     t.clone(),
                     a.clone(),
@@ -379,6 +386,12 @@ mod tests {
             signature: None,
             fingerprint: Some(vec![1, 2, 3]),
             protocol_version: Some("0.3.4".to_string()),
+            addresses: Vec::new(),
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
         }
     }
 
@@ -637,6 +650,8 @@ mod tests {
             network_name_version: 0,
             network_name_origin: String::new(),
             cluster_mode: "auto".to_string(),
+            revocations: vec![],
+            join_restrictions: vec![],
         }
     }
 
@@ -730,4 +745,24 @@ mod tests {
             .unwrap()
             .contains_key("clustercut-kicked"));
     }
+
+    #[test]
+    fn merge_skips_revoked_fingerprint_under_any_id() {
+        let state = AppState::new();
+        *state.local_device_id.lock().unwrap() = "clustercut-me".to_string();
+        state.revocations.lock().unwrap().push(crate::protocol::Revocation {
+            device_id: "clustercut-stolen".to_string(),
+            fingerprint: vec![1, 2, 3],
+            revoker_id: "clustercut-admin".to_string(),
+            revoker_cert: vec![],
+            timestamp: 0,
+            scheme: 0,
+            signature: vec![],
+        });
+        let reintroduced = peer("clustercut-renamed", "192.168.1.62", 4654);
+        let info = cluster_info(vec![reintroduced]);
+
+        assert!(merge_cluster_membership(&state, &info).is_empty());
+        assert!(state.known_peers.lock().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,153 @@
+//! Isolated profiles: several ClusterCut identities on one machine.
+//!
+//! `--profile <name>` (or `CLUSTERCUT_PROFILE`) moves everything we persist
+//! into `<app config dir>/profiles/<name>`, and our cache into the same
+//! place under the cache dir. A profile therefore has its own device id,
+//! cert, known peers, settings and PIN, and is a separate device to the rest
+//! of the cluster. Its logs and D-Bus name carry the profile name too.
+//!
+//! The single-instance plugin is keyed on the app identifier, so it can't
+//! tell profiles apart; a named profile holds a lock file in its directory
+//! instead. Without a profile, every path is exactly what it always was.
+//!
+//! The GNOME Shell extension only talks to the default profile's D-Bus name.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use tauri::{AppHandle, Manager};
+
+/// Environment variable consulted when `--profile` isn't given.
+pub(crate) const PROFILE_ENV: &str = "CLUSTERCUT_PROFILE";
+
+/// Default-profile D-Bus name (also the interface name).
+pub(crate) const DBUS_NAME: &str = "app.clustercut.clustercut";
+
+const MAX_NAME_LEN: usize = 32;
+const LOCK_FILE: &str = "profile.lock";
+
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+/// Held open (and locked) for the life of the process.
+static LOCK: OnceLock<std::fs::File> = OnceLock::new();
+
+/// Check a profile name. It becomes a directory name and part of a D-Bus
+/// name, so only ASCII letters, digits, `-` and `_` are allowed. Empty and
+/// `default` select the default profile.
+pub(crate) fn validate(name: &str) -> Result<Option<String>, String> {
+    let name = name.trim();
+    if name.is_empty() || name.eq_ignore_ascii_case("default") {
+        return Ok(None);
+    }
+    let valid = name.len() <= MAX_NAME_LEN && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid profile name '{}': use up to {} letters, digits, '-' or '_'.",
+            name, MAX_NAME_LEN
+        ));
+    }
+    Ok(Some(name.to_string()))
+}
+
+/// Pick the profile for this process from `--profile`, falling back to
+/// `CLUSTERCUT_PROFILE`. Must run before anything resolves a path.
+pub(crate) fn init(arg: Option<&str>) -> Result<(), String> {
+    let env = std::env::var(PROFILE_ENV).ok();
+    let profile = match arg.or(env.as_deref()) {
+        Some(name) => validate(name)?,
+        None => None,
+    };
+    let _ = PROFILE.set(profile);
+    Ok(())
+}
+
+/// The active profile, or None for the default one.
+pub(crate) fn name() -> Option<&'static str> {
+    PROFILE.get().and_then(|p| p.as_deref())
+}
+
+fn scoped(root: PathBuf, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => root.join("profiles").join(name),
+        None => root,
+    }
+}
+
+pub(crate) fn config_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    Ok(scoped(app.path().app_config_dir()?, name()))
+}
+
+pub(crate) fn config_file(app: &AppHandle, file: &str) -> tauri::Result<PathBuf> {
+    Ok(config_dir(app)?.join(file))
+}
+
+pub(crate) fn cache_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    Ok(scoped(app.path().app_cache_dir()?, name()))
+}
+
+/// Where the rolling log files go. Chosen before there is an `AppHandle`.
+pub(crate) fn log_dir() -> PathBuf {
+    scoped(std::env::temp_dir().join("ClusterCutLogs"), name())
+}
+
+fn dbus_name_for(profile: Option<&str>) -> String {
+    match profile {
+        // Bus name elements can't contain '-' portably.
+        Some(name) => format!("{}.profile_{}", DBUS_NAME, name.replace('-', "_")),
+        None => DBUS_NAME.to_string(),
+    }
+}
+
+/// The well-known D-Bus name we own.
+pub(crate) fn dbus_name() -> String {
+    dbus_name_for(name())
+}
+
+/// Take the named profile's lock, failing if another process has it. A
+/// no-op for the default profile, which the single-instance plugin covers.
+pub(crate) fn acquire_lock(app: &AppHandle) -> Result<(), String> {
+    let Some(profile) = name() else {
+        return Ok(());
+    };
+    let dir = config_dir(app).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(LOCK_FILE);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    match file.try_lock() {
+        Ok(()) => {
+            let _ = LOCK.set(file);
+            Ok(())
+        }
+        Err(std::fs::TryLockError::WouldBlock) => Err(format!("Profile '{}' is already running.", profile)),
+        Err(std::fs::TryLockError::Error(e)) => Err(format!("Failed to lock {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_names_are_validated() {
+        assert_eq!(validate("work"), Ok(Some("work".to_string())));
+        assert_eq!(validate(" node-2_b "), Ok(Some("node-2_b".to_string())));
+        assert_eq!(validate(""), Ok(None));
+        assert_eq!(validate("Default"), Ok(None));
+        assert!(validate("../etc").is_err());
+        assert!(validate("a b").is_err());
+        assert!(validate(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn profiles_get_their_own_paths_and_bus_names() {
+        let root = PathBuf::from("/cfg");
+        assert_eq!(scoped(root.clone(), None), root);
+        assert_eq!(scoped(root, Some("work")), PathBuf::from("/cfg/profiles/work"));
+        assert_eq!(dbus_name_for(None), "app.clustercut.clustercut");
+        assert_eq!(dbus_name_for(Some("node-2")), "app.clustercut.clustercut.profile_node_2");
+    }
+}
@@ -200,6 +200,42 @@ pub struct ClipboardPayload {
     pub timestamp: u64,
     pub sender: String,
     pub sender_id: String,
+    /// Self-destruct delay for ephemeral items. A receiver that applies the
+    /// payload clears (or restores) its OS clipboard this many seconds later
+    /// if the item is still there, and never retains it in History. `None`
+    /// for regular items; older peers omit the field and ignore it.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    /// Per-sender sequence number, strictly increasing across restarts
+    /// (it never drops below the sender's clock in ms). Receivers drop a
+    /// payload that isn't newer than the last one from the same sender; see
+    /// replay.rs. `None` from older peers.
+    #[serde(default)]
+    pub seq: Option<u64>,
+    /// The `files` were cut, not copied, on the sender. A 0.3.9+ receiver
+    /// answers each verified download with `Message::FileReceived`, and the
+    /// sender may then move the originals away (moves.rs). Older peers
+    /// omit it and ignore it.
+    #[serde(default)]
+    pub cut: bool,
+}
+
+impl ClipboardPayload {
+    /// True if the sender asked for this item to self-destruct.
+    pub fn is_ephemeral(&self) -> bool {
+        self.ttl_secs.is_some()
+    }
+}
+
+/// 0.3.9: receipt for one file of a cut, sent by the receiver once the
+/// download is complete. `sha256` is over the bytes written to disk; the
+/// sender checks it against the original before moving it away.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileReceipt {
+    pub id: String,
+    pub file_index: usize,
+    pub size: u64,
+    pub sha256: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -226,6 +262,10 @@ pub enum DeliveryTarget {
         #[serde(default)]
         height: Option<u32>,
     },
+    /// 0.3.7: a connection test (link.rs). The receiver reads at most
+    /// `link::SPEED_TEST_BYTES`, throws them away and records the rate.
+    /// Sent only to peers that advertise 0.3.7+.
+    Discard,
 }
 
 impl Default for DeliveryTarget {
@@ -253,6 +293,11 @@ pub struct FileStreamHeader {
     /// Disk and behave exactly as today.
     #[serde(default = "default_delivery_target")]
     pub delivery_target: DeliveryTarget,
+    /// SHA-256 (hex) of the uncompressed content. Set on relayed streams,
+    /// where this header is the only part signed end to end; the receiver
+    /// drops content that doesn't match it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 /// Wire-protocol 0.3.1: the inner struct of an AEAD-wrapped pairing frame
@@ -272,6 +317,12 @@ pub struct PairIdInner {
     /// expected fingerprint for the peer's `device_id` for all future QUIC
     /// mTLS handshakes.
     pub fingerprint: Vec<u8>,
+    /// SAS support (see pairing/sas.rs). `None` from builds that predate it;
+    /// otherwise whether this side requires the user to confirm the SAS
+    /// before the pair is committed. The confirmation exchange runs only
+    /// when both sides send `Some`.
+    #[serde(default)]
+    pub sas: Option<bool>,
 }
 
 /// Hard cap on `device_id` length, enforced via deterministic UTF-8-safe
@@ -295,6 +346,60 @@ pub fn truncate_device_id(s: &str) -> String {
     s[..end].to_string()
 }
 
+/// Signed certificate-rotation announcement (see key_rotation.rs). Proves
+/// possession of the cert being retired so a receiver can move its pin to
+/// `new_fingerprint` without a re-pair.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeyRotationPayload {
+    pub device_id: String,
+    /// DER of the cert being retired. Must hash to the fingerprint the
+    /// receiver has pinned for `device_id`; its public key verifies
+    /// `signature`.
+    pub old_cert: Vec<u8>,
+    pub new_fingerprint: Vec<u8>,
+    /// Random, per rotation. Receivers echo it in `Message::KeyRotationAck`.
+    pub nonce: Vec<u8>,
+    /// TLS `SignatureScheme` code the signature was made with.
+    pub scheme: u16,
+    pub signature: Vec<u8>,
+}
+
+/// Signed statement that a member was removed from the cluster (see
+/// revocation.rs). Names the removed device's pinned fingerprint so the
+/// revocation outlives the device id and can be enforced at the mTLS layer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Revocation {
+    pub device_id: String,
+    pub fingerprint: Vec<u8>,
+    /// Member that issued the removal. Its pinned fingerprint must match
+    /// `revoker_cert`, whose public key verifies `signature`.
+    pub revoker_id: String,
+    pub revoker_cert: Vec<u8>,
+    pub timestamp: u64,
+    /// TLS `SignatureScheme` code the signature was made with.
+    pub scheme: u16,
+    pub signature: Vec<u8>,
+}
+
+/// Signed statement that a device joined with a restricted invite token (see
+/// pairing/tokens.rs). Issued by the member that minted the token, so every
+/// member holds the joiner to the token's limits, not just the one it paired
+/// with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JoinRestriction {
+    pub device_id: String,
+    pub fingerprint: Vec<u8>,
+    pub restrictions: crate::pairing::tokens::TokenRestrictions,
+    /// Member that minted the token. Its pinned fingerprint must match
+    /// `issuer_cert`, whose public key verifies `signature`.
+    pub issuer_id: String,
+    pub issuer_cert: Vec<u8>,
+    pub timestamp: u64,
+    /// TLS `SignatureScheme` code the signature was made with.
+    pub scheme: u16,
+    pub signature: Vec<u8>,
+}
+
 /// Cluster bootstrap payload delivered post-pairing over QUIC/mTLS (T7).
 /// In 0.3.0 this lived inside the pairing-channel `Welcome` frame; 0.3.1
 /// moves it onto the already-authenticated QUIC channel so the pairing
@@ -321,6 +426,14 @@ pub struct ClusterInfo {
     /// never carried here — the joiner already typed it to complete SPAKE2.
     #[serde(default)]
     pub cluster_mode: String,
+    /// The responder's revocation list, so a joiner (or a member catching up
+    /// via anti-entropy) learns about removals it wasn't online for.
+    #[serde(default)]
+    pub revocations: Vec<Revocation>,
+    /// Restrictions members placed on devices that joined with a limited
+    /// invite token, so every member enforces them.
+    #[serde(default)]
+    pub join_restrictions: Vec<JoinRestriction>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -333,7 +446,8 @@ pub enum Message {
     Clipboard(ClipboardPayload),
     // Gossip: Broadcast new peer to known peers
     PeerDiscovery(crate::peer::Peer),
-    // Broadcast removal of a peer (kick/leave)
+    // Broadcast self-removal (leave). Only honored from the named device
+    // itself; removing someone else takes a signed `Revocation`.
     PeerRemoval(String), // Payload is device_id
     // Broadcast deletion of history item
     HistoryDelete(String), // Payload is item ID
@@ -342,6 +456,13 @@ pub enum Message {
     // Liveness Check
     Ping,
     Pong,
+    /// 0.3.5: `Ping` carrying our clock (unix ms) at send time. Answered
+    /// with `TimedPong`; sent only to peers advertising 0.3.5+.
+    TimedPing { sent_ms: u64 },
+    /// 0.3.5: reply to `TimedPing`, echoing `sent_ms` alongside the
+    /// replier's clock. The round trip gives the sender-clock offset used to
+    /// judge clipboard freshness (replay.rs).
+    TimedPong { echo_ms: u64, replied_ms: u64 },
     /// 0.3.1: initiator → responder over QUIC/mTLS immediately after pairing
     /// completes, asking for the cluster bootstrap state that 0.3.0 used to
     /// ship inside the pairing-channel `Welcome`.
@@ -358,6 +479,58 @@ pub enum Message {
         version: u64,
         origin: String,
     },
+    /// The sender is replacing its TLS cert. Signed by the outgoing key;
+    /// receivers re-pin and accept both fingerprints for a grace window.
+    KeyRotation(KeyRotationPayload),
+    /// The sender has re-pinned us after our `KeyRotation` with this nonce.
+    KeyRotationAck { nonce: Vec<u8> },
+    /// A member removed (kicked) another device. Signed by the remover and
+    /// re-gossiped via `ClusterInfo`.
+    Revocation(Revocation),
+    /// 0.3.6: a message for another member, handed to us (or by us) because
+    /// the two ends can't reach each other directly. See relay.rs.
+    Relay(RelayEnvelope),
+    /// 0.3.8: what the sender runs (device_info.rs). Sent on first contact
+    /// with `want_reply` set, answered in kind, and re-sent on change.
+    DeviceInfo {
+        info: crate::device_info::DeviceInfo,
+        want_reply: bool,
+    },
+    /// 0.3.9: a file of a cut (`ClipboardPayload::cut`) arrived intact.
+    /// Sent only to peers advertising 0.3.9+.
+    FileReceived(FileReceipt),
+}
+
+/// 0.3.6: a `Message`, or a file stream's header line, from `from` to `to`
+/// carried by a member that can reach both (relay.rs). Signed with the
+/// originator's device key, so the relay can drop it but not forge or alter
+/// it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RelayEnvelope {
+    pub from: String,
+    pub to: String,
+    /// Unique per envelope; the receiver's replay cache key.
+    pub id: String,
+    /// Originator's clock (unix ms) when sealed.
+    pub sent_ms: u64,
+    /// The carried bytes, base64-encoded for the same reason as
+    /// `ClipboardBlob::data`.
+    pub payload: String,
+    /// Originator's cert DER. Must hash to the fingerprint the receiver has
+    /// pinned for `from`; its public key verifies `signature`.
+    pub cert: Vec<u8>,
+    /// TLS `SignatureScheme` code the signature was made with.
+    pub scheme: u16,
+    pub signature: Vec<u8>,
+}
+
+/// The first line of a file stream: its header, or for a stream arriving
+/// through a relay, an envelope whose payload is the header.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum StreamPreamble {
+    Relayed { relay: RelayEnvelope },
+    Direct(FileStreamHeader),
 }
 
 /// Messages exchanged on the dedicated plaintext-TCP pairing channel.
@@ -369,7 +542,7 @@ pub enum Message {
 /// Wire-protocol 0.3.3 (this file is the sole spec):
 ///
 /// ```text
-/// T0  Initiator → Responder   PairRequest  { spake_msg }
+/// T0  Initiator → Responder   PairRequest  { spake_msg, token_id?, cluster? }
 /// T1  Responder → Initiator   PairResponse { spake_msg }
 /// T2  Initiator → Responder   InitiatorKC  { nonce, ciphertext = AEAD(k_i2r, nonce, INITIATOR_KC_PLAINTEXT) }
 /// T3  Responder → Initiator   ResponderId  { nonce, ciphertext = AEAD(k_r2i, nonce, inner) }
@@ -393,7 +566,18 @@ pub enum Message {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PairingMessage {
     /// T0 — opening SPAKE2 element from the initiator. No identity bytes.
-    PairRequest { spake_msg: Vec<u8> },
+    /// `token_id` names the invite token whose code is the SPAKE2 password
+    /// (see pairing/tokens.rs); absent ⇒ the responder's PIN. It is only a
+    /// selector: the secret half of the code never travels. `cluster` is
+    /// the cluster name the initiator saw advertised, so a responder in
+    /// several clusters answers with that cluster's PIN (clusters.rs).
+    PairRequest {
+        spake_msg: Vec<u8>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cluster: Option<String>,
+    },
     /// T1 — answering SPAKE2 element from the responder. No identity bytes.
     PairResponse { spake_msg: Vec<u8> },
     /// T2 (wire 0.3.3) — initiator's AEAD-wrapped key-confirmation frame.
@@ -405,6 +589,9 @@ pub enum PairingMessage {
     ResponderId { nonce: Vec<u8>, ciphertext: Vec<u8> },
     /// T4 — initiator's AEAD-wrapped identity, decryptable under `k_i2r`.
     InitiatorId { nonce: Vec<u8>, ciphertext: Vec<u8> },
+    /// T4a — each side's SAS verdict, AEAD-wrapped under its own send
+    /// sub-key. Only exchanged when both identities advertised `sas`.
+    SasConfirm { nonce: Vec<u8>, ciphertext: Vec<u8> },
 }
 
 #[cfg(test)]
@@ -432,6 +619,9 @@ mod tests {
             timestamp: 1_700_000_000,
             sender: "test-host".to_string(),
             sender_id: "test-id-123".to_string(),
+            ttl_secs: None,
+            seq: None,
+            cut: false,
         }
     }
 
@@ -499,6 +689,9 @@ mod tests {
             timestamp: 1_700_000_000,
             sender: "test-host".to_string(),
             sender_id: "test-id-123".to_string(),
+            ttl_secs: None,
+            seq: None,
+            cut: false,
         }
     }
 
@@ -641,7 +834,11 @@ mod tests {
         // `Message::ClusterInfo`. Asserting via serialised representations
         // here so an accidental future re-introduction of a Welcome-style
         // variant is caught by this test.
-        let t0 = PairingMessage::PairRequest { spake_msg: vec![1, 2, 3] };
+        let t0 = PairingMessage::PairRequest {
+            spake_msg: vec![1, 2, 3],
+            token_id: Some("0a1b2c3d".to_string()),
+            cluster: Some("Work".to_string()),
+        };
         let t1 = PairingMessage::PairResponse { spake_msg: vec![4, 5, 6] };
         let t2 = PairingMessage::ResponderId { nonce: vec![0; 12], ciphertext: vec![7, 8, 9] };
         let t3 = PairingMessage::InitiatorId { nonce: vec![0; 12], ciphertext: vec![10, 11, 12] };
@@ -657,6 +854,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pair_request_token_id_is_optional_on_the_wire() {
+        // A PIN pairing serialises exactly as before, so older responders
+        // and initiators interoperate.
+        let pin = PairingMessage::PairRequest { spake_msg: vec![1], token_id: None, cluster: None };
+        assert_eq!(serde_json::to_string(&pin).unwrap(), r#"{"PairRequest":{"spake_msg":[1]}}"#);
+        let parsed: PairingMessage = serde_json::from_str(r#"{"PairRequest":{"spake_msg":[1]}}"#).unwrap();
+        assert!(matches!(parsed, PairingMessage::PairRequest { token_id: None, cluster: None, .. }));
+    }
+
     #[test]
     fn cluster_info_lives_on_the_message_enum_only() {
         // Companion check to the above: ClusterInfo MUST be a `Message`
@@ -670,6 +877,8 @@ mod tests {
             network_name_version: 0,
             network_name_origin: String::new(),
             cluster_mode: String::new(),
+            revocations: vec![],
+            join_restrictions: vec![],
         };
         let wrapped = Message::ClusterInfo(info);
         let s = serde_json::to_string(&wrapped).unwrap();
@@ -692,6 +901,8 @@ mod tests {
             network_name_version: 0,
             network_name_origin: String::new(),
             cluster_mode: "provisioned".to_string(),
+            revocations: vec![],
+            join_restrictions: vec![],
         };
         let s = serde_json::to_string(&Message::ClusterInfo(info)).unwrap();
         match serde_json::from_str::<Message>(&s).unwrap() {
@@ -716,10 +927,16 @@ mod tests {
         let inner = PairIdInner {
             device_id: "device-a".to_string(),
             fingerprint: vec![0xAA, 0xBB, 0xCC, 0xDD],
+            sas: Some(true),
         };
         let bytes = serde_json::to_vec(&inner).unwrap();
         let parsed: PairIdInner = serde_json::from_slice(&bytes).unwrap();
         assert_eq!(parsed, inner);
+
+        // Pre-SAS builds omit the field.
+        let legacy = r#"{"device_id":"device-a","fingerprint":[1,2]}"#;
+        let parsed: PairIdInner = serde_json::from_str(legacy).unwrap();
+        assert_eq!(parsed.sas, None);
     }
 
     #[test]
@@ -735,6 +952,7 @@ mod tests {
                 width: Some(1920),
                 height: Some(1080),
             },
+            sha256: None,
         };
         let json = serde_json::to_string(&header).unwrap();
         let parsed: FileStreamHeader = serde_json::from_str(&json).unwrap();
@@ -754,6 +972,34 @@ mod tests {
         assert_eq!(parsed.height, None);
     }
 
+    #[test]
+    fn stream_preamble_tells_relayed_streams_from_plain_headers() {
+        let header = FileStreamHeader {
+            id: "f-1".to_string(),
+            file_index: 0,
+            file_name: "a.txt".to_string(),
+            file_size: 3,
+            compressed: false,
+            delivery_target: DeliveryTarget::Disk,
+            sha256: None,
+        };
+        let line = serde_json::to_string(&header).unwrap();
+        assert!(matches!(serde_json::from_str::<StreamPreamble>(&line), Ok(StreamPreamble::Direct(h)) if h.id == "f-1"));
+
+        let envelope = RelayEnvelope {
+            from: "a".to_string(),
+            to: "b".to_string(),
+            id: "r-1".to_string(),
+            sent_ms: 1,
+            payload: String::new(),
+            cert: vec![1],
+            scheme: 0x0403,
+            signature: vec![2],
+        };
+        let line = serde_json::to_string(&StreamPreamble::Relayed { relay: envelope.clone() }).unwrap();
+        assert!(matches!(serde_json::from_str::<StreamPreamble>(&line), Ok(StreamPreamble::Relayed { relay }) if relay == envelope));
+    }
+
     #[test]
     fn file_stream_header_text_clipboard_target_round_trips() {
         let header = FileStreamHeader {
@@ -0,0 +1,369 @@
+//! Quiet hours and Do Not Disturb.
+//!
+//! Schedules (weekday + time range, local time) put sync into one of two
+//! quiet modes without touching the user's toggles:
+//!
+//! - `Manual`: auto-send and auto-receive act as off. Incoming items still
+//!   land in History and the send/receive shortcuts work.
+//! - `Pause`: as `Manual`, and incoming clipboard items are dropped.
+//!
+//! Call sites read the effective toggles through `auto_send` /
+//! `auto_receive` (`effective_*` with a settings snapshot in hand) instead
+//! of `AppSettings` directly.
+//!
+//! Separately, while the desktop's Do Not Disturb is on, `send_notification`
+//! hands its notifications to `hold` and they are shown as one batch when
+//! it ends. DND is read from the settings portal (GNOME `show-banners`),
+//! falling back to `gsettings`; other platforms already keep their own
+//! focus modes quiet.
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+
+use crate::state::AppState;
+use crate::storage::AppSettings;
+use crate::NotificationPayload;
+
+const TICK: std::time::Duration = std::time::Duration::from_secs(10);
+/// Held notifications past this are dropped, oldest first.
+const MAX_HELD: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuietMode {
+    Manual,
+    Pause,
+}
+
+/// One quiet-hours range. `start`/`end` are local `HH:MM`; an `end` at or
+/// before `start` runs past midnight into the next day. `days` are
+/// 0 = Monday … 6 = Sunday, naming the day the range starts on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietSchedule {
+    pub days: Vec<u8>,
+    pub start: String,
+    pub end: String,
+    pub mode: QuietMode,
+}
+
+fn parse_hhmm(s: &str) -> Option<u16> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u16, u16) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+impl QuietSchedule {
+    /// Whether the range covers `minute` (since midnight) on `weekday`.
+    fn covers(&self, weekday: u8, minute: u16) -> bool {
+        let (Some(start), Some(end)) = (parse_hhmm(&self.start), parse_hhmm(&self.end)) else {
+            return false;
+        };
+        let on = |day: u8| self.days.contains(&day);
+        if start < end {
+            on(weekday) && minute >= start && minute < end
+        } else {
+            // Overnight (or all day when start == end): today's part, plus
+            // the tail of a range that started yesterday.
+            (on(weekday) && minute >= start) || (on((weekday + 6) % 7) && minute < end)
+        }
+    }
+}
+
+/// The strictest mode any schedule puts us in right now.
+fn scheduled_mode(schedules: &[QuietSchedule], weekday: u8, minute: u16) -> Option<QuietMode> {
+    schedules.iter().filter(|s| s.covers(weekday, minute)).map(|s| s.mode).max()
+}
+
+/// Local weekday (0 = Monday) and minute since midnight.
+#[cfg(unix)]
+fn local_now() -> Option<(u8, u16)> {
+    // SAFETY: `time` with a null pointer only returns the time, and
+    // `localtime_r` writes into the `tm` we own.
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&now, &mut tm).is_null() {
+            return None;
+        }
+        Some((((tm.tm_wday + 6) % 7) as u8, (tm.tm_hour * 60 + tm.tm_min) as u16))
+    }
+}
+
+#[cfg(windows)]
+fn local_now() -> Option<(u8, u16)> {
+    // SAFETY: plain FFI call with no arguments.
+    let st = unsafe { windows::Win32::System::SystemInformation::GetLocalTime() };
+    Some((((st.wDayOfWeek + 6) % 7) as u8, st.wHour * 60 + st.wMinute))
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HeldNotification {
+    title: String,
+    body: String,
+    target_view: String,
+    payload: NotificationPayload,
+}
+
+/// Runtime quiet state, in `AppState::quiet`.
+#[derive(Debug, Default)]
+pub(crate) struct QuietState {
+    mode: Option<QuietMode>,
+    dnd: bool,
+    held: Vec<HeldNotification>,
+}
+
+/// What the UI shows; also the `quiet-state-changed` payload.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct QuietStatus {
+    pub mode: Option<QuietMode>,
+    pub dnd: bool,
+    pub held: usize,
+}
+
+pub(crate) fn status(state: &AppState) -> QuietStatus {
+    let q = state.quiet.lock().unwrap();
+    QuietStatus { mode: q.mode, dnd: q.dnd, held: q.held.len() }
+}
+
+/// The quiet mode in force, if any.
+pub(crate) fn mode(state: &AppState) -> Option<QuietMode> {
+    state.quiet.lock().unwrap().mode
+}
+
+/// Whether quiet hours are holding automatic sync back.
+pub(crate) fn sync_held(state: &AppState) -> bool {
+    mode(state).is_some()
+}
+
+/// `settings.auto_send`, unless quiet hours hold it.
+pub(crate) fn auto_send(state: &AppState) -> bool {
+    let held = sync_held(state);
+    state.settings.lock().unwrap().auto_send && !held
+}
+
+/// `settings.auto_receive`, unless quiet hours hold it.
+pub(crate) fn auto_receive(state: &AppState) -> bool {
+    let held = sync_held(state);
+    state.settings.lock().unwrap().auto_receive && !held
+}
+
+/// Whether `settings` plus the current quiet mode leave auto-send on; for
+/// callers that already hold a settings snapshot.
+pub(crate) fn effective_auto_send(state: &AppState, settings: &AppSettings) -> bool {
+    settings.auto_send && !sync_held(state)
+}
+
+/// As `effective_auto_send`, for auto-receive.
+pub(crate) fn effective_auto_receive(state: &AppState, settings: &AppSettings) -> bool {
+    settings.auto_receive && !sync_held(state)
+}
+
+/// Keep a notification for later if Do Not Disturb is on and we follow it.
+/// Returns true when the caller should not show it now.
+pub(crate) fn hold(app_handle: &tauri::AppHandle, title: &str, body: &str, target_view: &str, payload: &NotificationPayload) -> bool {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return false;
+    };
+    if !state.settings.lock().unwrap().follow_dnd {
+        return false;
+    }
+    let mut q = state.quiet.lock().unwrap();
+    if !q.dnd {
+        return false;
+    }
+    if q.held.len() >= MAX_HELD {
+        q.held.remove(0);
+    }
+    q.held.push(HeldNotification {
+        title: title.to_string(),
+        body: body.to_string(),
+        target_view: target_view.to_string(),
+        payload: payload.clone(),
+    });
+    tracing::debug!("[Quiet] Holding notification '{}' during Do Not Disturb", title);
+    true
+}
+
+/// One notification for everything held: the original when there is just
+/// one, otherwise a count and the distinct titles.
+fn summarize(held: &[HeldNotification]) -> Option<(String, String)> {
+    match held {
+        [] => None,
+        [one] => Some((one.title.clone(), one.body.clone())),
+        many => {
+            let mut titles: Vec<(&str, usize)> = Vec::new();
+            for n in many {
+                match titles.iter_mut().find(|(t, _)| *t == n.title) {
+                    Some((_, count)) => *count += 1,
+                    None => titles.push((&n.title, 1)),
+                }
+            }
+            let body = titles
+                .iter()
+                .map(|(t, c)| if *c > 1 { format!("{} ×{}", t, c) } else { t.to_string() })
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some((format!("{} notifications while Do Not Disturb was on", many.len()), body))
+        }
+    }
+}
+
+fn flush(app_handle: &tauri::AppHandle, held: Vec<HeldNotification>) {
+    let Some((title, body)) = summarize(&held) else {
+        return;
+    };
+    // A lone notification keeps its view and actions; a batch opens History.
+    let (view, payload) = match held.as_slice() {
+        [one] => (one.target_view.clone(), one.payload.clone()),
+        _ => ("history".to_string(), NotificationPayload::None),
+    };
+    crate::send_notification(app_handle, &title, &body, false, None, &view, payload);
+}
+
+#[cfg(target_os = "linux")]
+async fn desktop_dnd(state: &AppState) -> bool {
+    use zbus::zvariant::{OwnedValue, Value};
+
+    fn extract_bool(v: &Value<'_>) -> Option<bool> {
+        match v {
+            Value::Bool(b) => Some(*b),
+            Value::Value(inner) => extract_bool(inner),
+            _ => None,
+        }
+    }
+
+    let portal = async {
+        let conn = state.session_bus().await?;
+        let proxy: zbus::Proxy<'_> = zbus::proxy::Builder::new(&conn)
+            .interface("org.freedesktop.portal.Settings")
+            .ok()?
+            .path("/org/freedesktop/portal/desktop")
+            .ok()?
+            .destination("org.freedesktop.portal.Desktop")
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        let reply = proxy.call_method("Read", &("org.gnome.desktop.notifications", "show-banners")).await.ok()?;
+        let (value,): (OwnedValue,) = reply.body().deserialize().ok()?;
+        extract_bool(&Value::from(value))
+    };
+    if let Some(show_banners) = portal.await {
+        return !show_banners;
+    }
+
+    // Portals that don't expose the key; only sees the host outside Flatpak.
+    let out = tokio::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .await;
+    matches!(out, Ok(o) if o.status.success() && String::from_utf8_lossy(&o.stdout).trim() == "false")
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn desktop_dnd(_state: &AppState) -> bool {
+    false
+}
+
+/// Track quiet hours and Do Not Disturb for the life of the app.
+pub(crate) async fn run(state: AppState, app_handle: tauri::AppHandle) {
+    loop {
+        let (schedules, follow_dnd) = {
+            let s = state.settings.lock().unwrap();
+            (s.quiet_hours.clone(), s.follow_dnd)
+        };
+        let mode = local_now().and_then(|(day, minute)| scheduled_mode(&schedules, day, minute));
+        let dnd = follow_dnd && desktop_dnd(&state).await;
+
+        let (mode_changed, dnd_changed, released) = {
+            let mut q = state.quiet.lock().unwrap();
+            let mode_changed = q.mode != mode;
+            let dnd_changed = q.dnd != dnd;
+            q.mode = mode;
+            q.dnd = dnd;
+            let released = if dnd { Vec::new() } else { std::mem::take(&mut q.held) };
+            (mode_changed, dnd_changed, released)
+        };
+
+        if mode_changed {
+            match mode {
+                Some(m) => tracing::info!("[Quiet] Quiet hours started ({:?})", m),
+                None => tracing::info!("[Quiet] Quiet hours ended"),
+            }
+            // The send/receive shortcuts are only registered in manual mode.
+            crate::shortcuts::register_shortcuts(&app_handle);
+        }
+        if dnd_changed {
+            tracing::info!("[Quiet] Do Not Disturb {}", if dnd { "on" } else { "off" });
+        }
+        if !released.is_empty() {
+            tracing::info!("[Quiet] Showing {} notification(s) held during Do Not Disturb", released.len());
+            flush(&app_handle, released);
+        }
+        if mode_changed || dnd_changed {
+            let _ = app_handle.emit("quiet-state-changed", status(&state));
+        }
+
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(days: &[u8], start: &str, end: &str, mode: QuietMode) -> QuietSchedule {
+        QuietSchedule { days: days.to_vec(), start: start.to_string(), end: end.to_string(), mode }
+    }
+
+    #[test]
+    fn schedules_cover_daytime_and_overnight_ranges() {
+        let weekdays = schedule(&[0, 1, 2, 3, 4], "09:00", "17:30", QuietMode::Manual);
+        assert!(weekdays.covers(0, 9 * 60));
+        assert!(!weekdays.covers(0, 17 * 60 + 30));
+        assert!(!weekdays.covers(5, 10 * 60));
+
+        // Friday night into Saturday morning.
+        let night = schedule(&[4], "22:00", "07:00", QuietMode::Pause);
+        assert!(night.covers(4, 23 * 60));
+        assert!(night.covers(5, 6 * 60));
+        assert!(!night.covers(5, 7 * 60));
+        assert!(!night.covers(4, 6 * 60));
+        // Sunday night wraps into Monday.
+        assert!(schedule(&[6], "23:00", "01:00", QuietMode::Pause).covers(0, 30));
+
+        assert!(schedule(&[2], "00:00", "00:00", QuietMode::Manual).covers(2, 12 * 60));
+        assert!(!schedule(&[2], "25:00", "08:00", QuietMode::Manual).covers(2, 60));
+    }
+
+    #[test]
+    fn pause_wins_over_manual() {
+        let schedules = vec![
+            schedule(&[0], "08:00", "18:00", QuietMode::Manual),
+            schedule(&[0], "12:00", "13:00", QuietMode::Pause),
+        ];
+        assert_eq!(scheduled_mode(&schedules, 0, 9 * 60), Some(QuietMode::Manual));
+        assert_eq!(scheduled_mode(&schedules, 0, 12 * 60 + 30), Some(QuietMode::Pause));
+        assert_eq!(scheduled_mode(&schedules, 1, 12 * 60), None);
+    }
+
+    #[test]
+    fn held_notifications_are_batched() {
+        let held = |title: &str| HeldNotification {
+            title: title.to_string(),
+            body: "body".to_string(),
+            target_view: "devices".to_string(),
+            payload: NotificationPayload::None,
+        };
+        assert_eq!(summarize(&[]), None);
+        assert_eq!(summarize(&[held("Device Joined")]), Some(("Device Joined".to_string(), "body".to_string())));
+        let batch = [held("Clipboard Received"), held("Device Joined"), held("Clipboard Received")];
+        assert_eq!(
+            summarize(&batch),
+            Some((
+                "3 notifications while Do Not Disturb was on".to_string(),
+                "Clipboard Received ×2, Device Joined".to_string()
+            ))
+        );
+    }
+}
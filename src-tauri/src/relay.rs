@@ -0,0 +1,518 @@
+//! Relaying through a member that can reach both ends.
+//!
+//! Two members on networks that can't reach each other (a VPN client and a
+//! LAN-only desktop, say) can often both reach a third. Each heartbeat
+//! carries the members its sender has recently connected to directly
+//! (`Peer::reaches`); when a direct send fails, `route` picks a member that
+//! reports reaching the destination and that we can reach ourselves, and
+//! the message travels to it wrapped in a [`RelayEnvelope`].
+//!
+//! The envelope is signed with the originator's device key and names both
+//! ends, so the relay can drop a message but not forge, alter or redirect
+//! it, and the receiver judges it against the originator's pinned
+//! fingerprint, not the relay's. Relays forward with
+//! `Transport::send_message_direct` only: one hop, never a chain.
+//!
+//! File streams go the same way, with the envelope in place of the header
+//! line. Only the header is signed end to end, so a relayed header carries
+//! the SHA-256 of the content (`FileStreamHeader::sha256`) and the receiver
+//! keeps nothing that doesn't match it: a relay can cut a stream short or
+//! garble it, but not swap in other bytes.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use base64::Engine as _;
+use tauri::Emitter;
+use tokio::io::{AsyncWriteExt, BufReader};
+
+use crate::peer::{same_endpoint, Peer};
+use crate::protocol::{DeliveryTarget, FileStreamHeader, Message, RelayEnvelope, StreamPreamble};
+use crate::state::AppState;
+use crate::transport::{cert_fingerprint, verify_cert_signature, Transport};
+
+/// Domain separator for envelope signatures.
+const RELAY_DOMAIN: &[u8] = b"clustercut-relay-v1";
+
+/// How long a direct connection, or a member's report of its own, counts as
+/// "can reach". Several heartbeat rounds.
+pub(crate) const REACH_WINDOW_SECS: u64 = 30;
+
+/// How long a relay waits for the destination to take a forwarded stream.
+const STREAM_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Who can reach whom, as far as we know.
+#[derive(Debug, Default)]
+pub struct RelayTable {
+    /// Member id -> the ids it last reported reaching, and when.
+    reach: HashMap<String, (Vec<String>, u64)>,
+    /// Member id -> when we last connected to it directly.
+    direct: HashMap<String, u64>,
+}
+
+impl RelayTable {
+    pub(crate) fn note_reach(&mut self, relay: &str, reaches: Vec<String>, now: u64) {
+        if reaches.is_empty() {
+            self.reach.remove(relay);
+        } else {
+            self.reach.insert(relay.to_string(), (reaches, now));
+        }
+    }
+
+    pub(crate) fn note_direct(&mut self, id: &str, now: u64) {
+        self.direct.insert(id.to_string(), now);
+    }
+
+    fn reached_directly(&self, id: &str, now: u64) -> bool {
+        self.direct
+            .get(id)
+            .map_or(false, |at| now.saturating_sub(*at) <= REACH_WINDOW_SECS)
+    }
+
+    fn reports_reaching(&self, relay: &str, dest: &str, now: u64) -> bool {
+        self.reach.get(relay).map_or(false, |(ids, at)| {
+            now.saturating_sub(*at) <= REACH_WINDOW_SECS && ids.iter().any(|id| id == dest)
+        })
+    }
+
+    /// Members we have connected to directly within the window, sorted.
+    fn direct_ids(&self, now: u64) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .direct
+            .keys()
+            .filter(|id| self.reached_directly(id, now))
+            .cloned()
+            .collect();
+        ids.sort();
+        ids
+    }
+}
+
+/// Bytes covered by the originator's signature.
+pub(crate) fn signed_bytes(from: &str, to: &str, id: &str, sent_ms: u64, payload: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(RELAY_DOMAIN.len() + from.len() + to.len() + id.len() + payload.len() + 12);
+    out.extend_from_slice(RELAY_DOMAIN);
+    for field in [from, to, id] {
+        out.push(0);
+        out.extend_from_slice(field.as_bytes());
+    }
+    out.push(0);
+    out.extend_from_slice(&sent_ms.to_be_bytes());
+    out.extend_from_slice(payload.as_bytes());
+    out
+}
+
+/// Wrap `data` for `to`, signing with `sign`. Split from [`seal`] so tests
+/// can sign with a bare key.
+fn seal_with(
+    sign: impl Fn(&[u8]) -> Result<(u16, Vec<u8>), String>,
+    cert: Vec<u8>,
+    from: &str,
+    to: &str,
+    data: &[u8],
+    sent_ms: u64,
+) -> Result<RelayEnvelope, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let payload = base64::engine::general_purpose::STANDARD.encode(data);
+    let (scheme, signature) = sign(&signed_bytes(from, to, &id, sent_ms, &payload))?;
+    Ok(RelayEnvelope {
+        from: from.to_string(),
+        to: to.to_string(),
+        id,
+        sent_ms,
+        payload,
+        cert,
+        scheme,
+        signature,
+    })
+}
+
+fn seal(transport: &Transport, from: &str, to: &str, data: &[u8]) -> Result<RelayEnvelope, String> {
+    seal_with(
+        |msg| transport.sign_with_local_key(msg),
+        transport.local_cert_der(),
+        from,
+        to,
+        data,
+        crate::replay::now_ms(),
+    )
+}
+
+/// Check an envelope against the fingerprint pinned for its originator.
+/// Pure so it can be unit-tested without an `AppHandle`.
+pub(crate) fn verify(env: &RelayEnvelope, pinned: Option<&[u8]>) -> Result<(), String> {
+    let pinned = pinned.ok_or("originator is not a paired device")?;
+    if cert_fingerprint(&env.cert) != pinned {
+        return Err("cert does not match the originator's pinned fingerprint".to_string());
+    }
+    let message = signed_bytes(&env.from, &env.to, &env.id, env.sent_ms, &env.payload);
+    if !verify_cert_signature(&env.cert, env.scheme, &message, &env.signature) {
+        return Err("signature does not verify".to_string());
+    }
+    Ok(())
+}
+
+/// Verify an envelope addressed to `local_id` and return the carried bytes.
+pub(crate) fn open(env: &RelayEnvelope, pinned: Option<&[u8]>, local_id: &str) -> Result<Vec<u8>, String> {
+    if env.to != local_id {
+        return Err(format!("addressed to {}", env.to));
+    }
+    verify(env, pinned)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(&env.payload)
+        .map_err(|e| format!("bad payload: {}", e))
+}
+
+/// The best member to relay to `dest`: one that recently reported reaching
+/// it and that we recently reached ourselves, lowest RTT first.
+pub(crate) fn pick_relay<'a>(
+    peers: impl IntoIterator<Item = &'a Peer>,
+    table: &RelayTable,
+    dest: &str,
+    now: u64,
+) -> Option<&'a Peer> {
+    peers
+        .into_iter()
+        .filter(|p| p.id != dest && p.fingerprint.is_some())
+        .filter(|p| crate::net_util::supports_relay(p.protocol_version.as_deref()))
+        .filter(|p| table.reached_directly(&p.id, now) && table.reports_reaching(&p.id, dest, now))
+        .min_by_key(|p| {
+            p.addresses
+                .iter()
+                .find(|a| same_endpoint(a.addr, p.addr()))
+                .and_then(|a| a.rtt_ms)
+                .unwrap_or(u64::MAX)
+        })
+}
+
+/// What our heartbeat advertises in `Peer::reaches`: nothing when relaying
+/// is turned off.
+pub(crate) fn direct_reach(state: &AppState) -> Vec<String> {
+    if !state.settings.lock().unwrap().allow_relay {
+        return Vec::new();
+    }
+    state.relay_table.lock().unwrap().direct_ids(crate::paths::now_secs())
+}
+
+/// Record the `reaches` list from `id`'s own heartbeat, arriving on `addr`.
+/// Gossiped records about third parties never carry one.
+pub(crate) fn note_reach(state: &AppState, id: &str, addr: SocketAddr, reaches: Vec<String>) {
+    let own = state
+        .peers
+        .lock()
+        .unwrap()
+        .get(id)
+        .map_or(false, |p| p.reachable_at(addr));
+    if own {
+        state
+            .relay_table
+            .lock()
+            .unwrap()
+            .note_reach(id, reaches, crate::paths::now_secs());
+    }
+}
+
+/// The relay router installed on the transport: find a member to carry
+/// `data` to the peer at `addr`, and the bytes to hand it.
+pub(crate) fn route(
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+    addr: SocketAddr,
+    data: &[u8],
+) -> Option<(SocketAddr, Vec<u8>)> {
+    let (dest, relay_id, relay_addr) = relay_for(state, addr)?;
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    let envelope = match seal(transport, &local_id, &dest, data) {
+        Ok(env) => env,
+        Err(e) => {
+            tracing::warn!("[Relay] Failed to seal message for {}: {}", dest, e);
+            return None;
+        }
+    };
+    let wrapped = serde_json::to_vec(&Message::Relay(envelope)).ok()?;
+    set_relay_via(state, app_handle, &dest, &relay_id);
+    Some((relay_addr, wrapped))
+}
+
+/// Destination id, relay id and relay address for the peer at `addr`.
+fn relay_for(state: &AppState, addr: SocketAddr) -> Option<(String, String, SocketAddr)> {
+    let peers = state.peers.lock().unwrap();
+    let dest = peers
+        .values()
+        .find(|p| same_endpoint(p.addr(), addr))
+        .or_else(|| peers.values().find(|p| p.reachable_at(addr)))?;
+    if dest.fingerprint.is_none() || !crate::net_util::supports_relay(dest.protocol_version.as_deref()) {
+        return None;
+    }
+    let table = state.relay_table.lock().unwrap();
+    let relay = pick_relay(peers.values(), &table, &dest.id, crate::paths::now_secs())?;
+    Some((dest.id.clone(), relay.id.clone(), relay.addr()))
+}
+
+fn set_relay_via(state: &AppState, app_handle: &tauri::AppHandle, dest: &str, relay_id: &str) {
+    let updated = {
+        let mut peers = state.peers.lock().unwrap();
+        let Some(peer) = peers.get_mut(dest) else {
+            return;
+        };
+        if peer.relay_via.as_deref() == Some(relay_id) {
+            return;
+        }
+        peer.relay_via = Some(relay_id.to_string());
+        peer.clone()
+    };
+    tracing::info!("[Relay] {} now reachable via {}", dest, relay_id);
+    let _ = app_handle.emit("peer-update", crate::peer::PeerView::from_peer(&updated));
+}
+
+/// The pinned fingerprint for a member, and its best-known address.
+fn member(state: &AppState, id: &str) -> Option<(Vec<u8>, SocketAddr)> {
+    let fingerprint = state.known_peers.lock().unwrap().get(id)?.fingerprint.clone()?;
+    let addr = {
+        let peers = state.peers.lock().unwrap();
+        peers.get(id).map(|p| p.addr())
+    };
+    let addr = addr.or_else(|| state.known_peers.lock().unwrap().get(id).map(|p| p.addr()))?;
+    Some((fingerprint, addr))
+}
+
+fn allow_relay(state: &AppState) -> bool {
+    state.settings.lock().unwrap().allow_relay
+}
+
+/// Handle `Message::Relay` from `addr`: forward it if it's for someone
+/// else, or unwrap it and return the inner message with the originator's
+/// address and pinned fingerprint to handle as if it had arrived directly.
+pub(crate) async fn receive(
+    env: RelayEnvelope,
+    addr: SocketAddr,
+    state: &AppState,
+    transport: &Transport,
+) -> Option<(Message, SocketAddr, Vec<u8>)> {
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    let origin = member(state, &env.from);
+    let pinned = origin.as_ref().map(|(fp, _)| fp.as_slice());
+
+    if env.to != local_id {
+        if !allow_relay(state) {
+            tracing::debug!("[Relay] Relaying disabled; dropping envelope from {} for {}", env.from, env.to);
+            return None;
+        }
+        if let Err(e) = verify(&env, pinned) {
+            tracing::warn!("[Relay] Not forwarding envelope from {} via {}: {}", env.from, addr, e);
+            return None;
+        }
+        let (_, dest_addr) = member(state, &env.to)?;
+        let to = env.to.clone();
+        let data = serde_json::to_vec(&Message::Relay(env)).ok()?;
+        match transport.send_message_direct(dest_addr, &data).await {
+            Ok(()) => tracing::debug!("[Relay] Forwarded message to {}", to),
+            Err(e) => tracing::warn!("[Relay] Failed to forward message to {}: {}", to, e),
+        }
+        return None;
+    }
+
+    let data = match open(&env, pinned, &local_id) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("[Relay] Rejected envelope from {} via {}: {}", env.from, addr, e);
+            return None;
+        }
+    };
+    let (fingerprint, origin_addr) = origin?;
+    let verdict = crate::replay::check_relayed(state, &fingerprint, &env.id, env.sent_ms);
+    if verdict != crate::replay::Verdict::Accept {
+        tracing::warn!("[Relay] Dropping envelope {} from {}: {:?}", env.id, env.from, verdict);
+        return None;
+    }
+    match serde_json::from_slice::<Message>(&data) {
+        Ok(Message::Relay(_)) => {
+            tracing::warn!("[Relay] Dropping nested envelope from {}", env.from);
+            None
+        }
+        Ok(msg) => Some((msg, origin_addr, fingerprint)),
+        Err(e) => {
+            tracing::warn!("[Relay] Undecodable message from {}: {}", env.from, e);
+            None
+        }
+    }
+}
+
+/// Open a file stream to the peer at `addr` and write its header line,
+/// through a relay if a direct connection fails. `source` is the file the
+/// body is read from; a relayed header carries its hash.
+pub(crate) async fn open_file_stream(
+    state: &AppState,
+    transport: &Transport,
+    addr: SocketAddr,
+    header: &FileStreamHeader,
+    source: Option<&Path>,
+) -> Result<(quinn::Connection, quinn::SendStream), Box<dyn std::error::Error + Send + Sync>> {
+    let (connection, mut stream, line) = match transport.send_file_stream(addr).await {
+        Ok((connection, stream)) => (connection, stream, serde_json::to_string(header)?),
+        Err(direct_err) => {
+            let Some((dest, relay_id, relay_addr)) = relay_for(state, addr) else {
+                return Err(direct_err);
+            };
+            let mut header = header.clone();
+            if let Some(path) = source {
+                let path = path.to_path_buf();
+                header.sha256 = Some(tokio::task::spawn_blocking(move || crate::moves::file_sha256(&path)).await??);
+            }
+            let local_id = state.local_device_id.lock().unwrap().clone();
+            let relay = seal(transport, &local_id, &dest, serde_json::to_string(&header)?.as_bytes())?;
+            tracing::info!("[Relay] File stream to {} unreachable ({}), relaying via {}", addr, direct_err, relay_id);
+            let (connection, stream) = transport.send_file_stream(relay_addr).await?;
+            (connection, stream, serde_json::to_string(&StreamPreamble::Relayed { relay })?)
+        }
+    };
+    stream.write_all(line.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    Ok((connection, stream))
+}
+
+/// Handle a file stream whose first line was an envelope: forward it if
+/// it's for someone else, or return the verified header, the originator's
+/// address and the rest of the stream.
+pub(crate) async fn receive_stream(
+    env: RelayEnvelope,
+    mut reader: BufReader<quinn::RecvStream>,
+    addr: SocketAddr,
+    state: &AppState,
+) -> Option<(FileStreamHeader, SocketAddr, BufReader<quinn::RecvStream>)> {
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    let origin = member(state, &env.from);
+    let pinned = origin.as_ref().map(|(fp, _)| fp.as_slice());
+
+    if env.to != local_id {
+        if !allow_relay(state) {
+            return None;
+        }
+        if let Err(e) = verify(&env, pinned) {
+            tracing::warn!("[Relay] Not forwarding stream from {} via {}: {}", env.from, addr, e);
+            return None;
+        }
+        let (_, dest_addr) = member(state, &env.to)?;
+        let transport = state.transport.lock().unwrap().clone()?;
+        let to = env.to.clone();
+        let line = serde_json::to_string(&StreamPreamble::Relayed { relay: env }).ok()?;
+        let result = async {
+            let (connection, mut send) = transport.send_file_stream(dest_addr).await?;
+            send.write_all(line.as_bytes()).await?;
+            send.write_all(b"\n").await?;
+            let copied = tokio::io::copy(&mut reader, &mut send).await?;
+            send.finish()?;
+            let _ = tokio::time::timeout(STREAM_DRAIN_TIMEOUT, connection.closed()).await;
+            Ok::<u64, Box<dyn std::error::Error + Send + Sync>>(copied)
+        }
+        .await;
+        match result {
+            Ok(copied) => tracing::info!("[Relay] Forwarded file stream to {} ({} bytes)", to, copied),
+            Err(e) => tracing::warn!("[Relay] Failed to forward file stream to {}: {}", to, e),
+        }
+        return None;
+    }
+
+    let data = match open(&env, pinned, &local_id) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("[Relay] Rejected stream from {} via {}: {}", env.from, addr, e);
+            return None;
+        }
+    };
+    let (fingerprint, origin_addr) = origin?;
+    let verdict = crate::replay::check_relayed(state, &fingerprint, &env.id, env.sent_ms);
+    if verdict != crate::replay::Verdict::Accept {
+        tracing::warn!("[Relay] Dropping stream {} from {}: {:?}", env.id, env.from, verdict);
+        return None;
+    }
+    let header: FileStreamHeader = serde_json::from_slice(&data).ok()?;
+    if header.sha256.is_none() && header.delivery_target != DeliveryTarget::Discard {
+        tracing::warn!("[Relay] Dropping stream {} from {}: no content hash", env.id, env.from);
+        return None;
+    }
+    Some((header, origin_addr, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::PeerAddress;
+    use crate::transport::{generate_self_signed_cert, sign_with_key};
+
+    fn sealed(to: &str) -> (RelayEnvelope, Vec<u8>) {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let (cert, key) = generate_self_signed_cert().unwrap();
+        let env = seal_with(|m| sign_with_key(&key, m), cert.clone(), "a", to, b"{\"Ping\":null}", 1_000).unwrap();
+        (env, cert_fingerprint(&cert))
+    }
+
+    fn peer(id: &str, rtt_ms: u64) -> Peer {
+        let addr: SocketAddr = format!("10.0.0.{}:4654", rtt_ms).parse().unwrap();
+        Peer {
+            id: id.to_string(),
+            ip: addr.ip(),
+            port: addr.port(),
+            hostname: id.to_string(),
+            last_seen: 0,
+            is_trusted: true,
+            is_manual: false,
+            network_name: None,
+            signature: None,
+            fingerprint: Some(vec![7; 32]),
+            protocol_version: Some(crate::discovery::CLUSTERCUT_PROTOCOL_VERSION.to_string()),
+            addresses: vec![PeerAddress { rtt_ms: Some(rtt_ms), ..PeerAddress::new(addr, 0) }],
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
+        }
+    }
+
+    #[test]
+    fn sealed_envelopes_open_for_their_recipient() {
+        let (env, fp) = sealed("b");
+        assert_eq!(open(&env, Some(&fp), "b").unwrap(), b"{\"Ping\":null}");
+    }
+
+    #[test]
+    fn envelopes_reject_wrong_pins_recipients_and_edits() {
+        let (env, fp) = sealed("b");
+        assert!(open(&env, None, "b").is_err());
+        assert!(open(&env, Some(&[0u8; 32]), "b").is_err());
+        assert!(open(&env, Some(&fp), "c").is_err());
+
+        // A relay can't redirect or rewrite what it carries.
+        let mut redirected = env.clone();
+        redirected.to = "c".to_string();
+        assert!(open(&redirected, Some(&fp), "c").is_err());
+        let mut edited = env;
+        edited.payload = base64::engine::general_purpose::STANDARD.encode(b"{\"Ping\":1}");
+        assert!(open(&edited, Some(&fp), "b").is_err());
+    }
+
+    #[test]
+    fn relay_must_reach_both_ends_and_lowest_rtt_wins() {
+        let now = 1_000;
+        let peers = vec![peer("slow", 80), peer("fast", 5), peer("stale", 1), peer("dest", 2)];
+        let mut table = RelayTable::default();
+        assert!(pick_relay(&peers, &table, "dest", now).is_none());
+
+        for id in ["slow", "fast", "stale"] {
+            table.note_direct(id, now);
+            table.note_reach(id, vec!["dest".to_string()], now);
+        }
+        table.note_reach("stale", vec!["dest".to_string()], now - REACH_WINDOW_SECS - 1);
+        assert_eq!(pick_relay(&peers, &table, "dest", now).map(|p| p.id.as_str()), Some("fast"));
+
+        // We can no longer reach "fast" ourselves.
+        table.note_direct("fast", now - REACH_WINDOW_SECS - 1);
+        assert_eq!(pick_relay(&peers, &table, "dest", now).map(|p| p.id.as_str()), Some("slow"));
+
+        table.note_reach("slow", Vec::new(), now);
+        assert!(pick_relay(&peers, &table, "dest", now).is_none());
+    }
+}
@@ -0,0 +1,312 @@
+//! Replay protection and sender clock-skew estimation for clipboard messages.
+//!
+//! Clipboard payloads used to be accepted if their timestamp was within 120 s
+//! of our wall clock. That let a captured message replay inside the window,
+//! and it dropped everything from a device whose clock had drifted (a paused
+//! VM, say). Now:
+//!
+//! - every accepted payload is remembered by (sender fingerprint, payload id)
+//!   in a bounded cache, so a repeat is dropped however fresh it looks;
+//! - senders that stamp `ClipboardPayload::seq` must strictly increase it.
+//!   The number is a boot epoch, persisted and bumped at every start, over a
+//!   per-run counter, so it keeps increasing across restarts whatever the
+//!   clock does. Sequence state is kept per authenticated sender (the pinned
+//!   fingerprint of the peer at the connection's address), never per
+//!   self-reported id, so one member can't advance another's counter;
+//! - freshness is judged against the sender's own clock, using an offset
+//!   estimated from `TimedPing`/`TimedPong` round trips.
+//!
+//! Until an offset is known, a sequenced payload is accepted on its sequence
+//! number alone. Payloads from older builds carry neither, so they keep the
+//! old wall-clock window.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::protocol::{ClipboardPayload, Message};
+use crate::state::AppState;
+use crate::transport::Transport;
+
+/// Payload ids remembered for duplicate detection.
+pub(crate) const SEEN_CAPACITY: usize = 4096;
+/// Largest accepted difference between a payload's timestamp and the
+/// sender's clock (or ours, for legacy payloads), in either direction.
+pub(crate) const FRESHNESS_WINDOW_SECS: i64 = 120;
+/// Round-trip samples kept per sender; the offset comes from the one with the
+/// shortest round trip, which has the least room for asymmetric delay.
+const SKEW_SAMPLES: usize = 8;
+/// How often each capable peer's clock is sampled.
+pub(crate) const CLOCK_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    /// Same sender and payload id as one already accepted.
+    Duplicate,
+    /// `seq` not above the last accepted one from this sender.
+    OutOfOrder { last: u64, got: u64 },
+    /// Timestamp too far from the sender's (estimated) clock.
+    Stale { age_secs: i64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClockSample {
+    rtt_ms: u64,
+    offset_ms: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayGuard {
+    seen: HashSet<(String, String)>,
+    order: VecDeque<(String, String)>,
+    last_seq: HashMap<String, u64>,
+    clocks: HashMap<String, VecDeque<ClockSample>>,
+}
+
+impl ReplayGuard {
+    /// Judge a payload from `sender` and, if accepted, remember it.
+    pub(crate) fn check(&mut self, sender: &str, id: &str, seq: Option<u64>, timestamp_secs: u64, now_ms: u64) -> Verdict {
+        let ts_ms = timestamp_secs.saturating_mul(1000) as i64;
+        let age_ms = match (self.offset_ms(sender), seq) {
+            // Sender's "now" is our now plus its offset.
+            (Some(offset), _) => Some(now_ms as i64 + offset - ts_ms),
+            (None, Some(_)) => None,
+            (None, None) => Some(now_ms as i64 - ts_ms),
+        };
+        if let Some(age_ms) = age_ms {
+            if age_ms.abs() > FRESHNESS_WINDOW_SECS * 1000 {
+                return Verdict::Stale { age_secs: age_ms / 1000 };
+            }
+        }
+
+        let key = (sender.to_string(), id.to_string());
+        if self.seen.contains(&key) {
+            return Verdict::Duplicate;
+        }
+        if let (Some(got), Some(&last)) = (seq, self.last_seq.get(sender)) {
+            if got <= last {
+                return Verdict::OutOfOrder { last, got };
+            }
+        }
+
+        if let Some(got) = seq {
+            self.last_seq.insert(sender.to_string(), got);
+        }
+        self.seen.insert(key.clone());
+        self.order.push_back(key);
+        while self.order.len() > SEEN_CAPACITY {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        Verdict::Accept
+    }
+
+    /// Record a round trip: we sent at `sent_ms`, the sender's clock read
+    /// `peer_ms` when it replied, and the reply landed at `now_ms`.
+    pub(crate) fn record_clock_sample(&mut self, sender: &str, sent_ms: u64, peer_ms: u64, now_ms: u64) {
+        let Some(rtt_ms) = now_ms.checked_sub(sent_ms) else {
+            return;
+        };
+        let midpoint = sent_ms + rtt_ms / 2;
+        let samples = self.clocks.entry(sender.to_string()).or_default();
+        samples.push_back(ClockSample { rtt_ms, offset_ms: peer_ms as i64 - midpoint as i64 });
+        while samples.len() > SKEW_SAMPLES {
+            samples.pop_front();
+        }
+    }
+
+    /// Estimated `sender clock - our clock`, if we have a sample.
+    pub(crate) fn offset_ms(&self, sender: &str) -> Option<i64> {
+        self.clocks
+            .get(sender)?
+            .iter()
+            .min_by_key(|s| s.rtt_ms)
+            .map(|s| s.offset_ms)
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Low bits of a sequence number that count payloads within one run; the
+/// boot epoch sits above them.
+const SEQ_EPOCH_SHIFT: u32 = 32;
+
+/// Counter value a run with boot epoch `epoch` starts from.
+pub(crate) fn first_seq(epoch: u64) -> u64 {
+    epoch.saturating_mul(1 << SEQ_EPOCH_SHIFT)
+}
+
+/// Next outgoing sequence number: one above the last.
+pub(crate) fn next_seq(counter: &AtomicU64) -> u64 {
+    let prev = counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |cur| Some(cur.saturating_add(1)))
+        .unwrap_or_default();
+    prev.saturating_add(1)
+}
+
+/// The key a sender is tracked under: its pinned cert fingerprint, or its
+/// address when no known peer is there.
+fn sender_key(fingerprint: Option<&[u8]>, addr: Option<std::net::SocketAddr>) -> String {
+    match (fingerprint, addr) {
+        (Some(fp), _) => fp.iter().map(|b| format!("{:02x}", b)).collect(),
+        (None, Some(addr)) => format!("addr:{}", addr),
+        (None, None) => String::new(),
+    }
+}
+
+/// Judge an incoming clipboard payload that arrived from `addr`. Relayed
+/// payloads are handed over with their originator's address and keep its
+/// id, sequence and timestamp, so they are judged against the originator,
+/// not the relay. `payload.sender_id` plays no part: it is self-reported.
+pub(crate) fn check_incoming(state: &AppState, addr: std::net::SocketAddr, payload: &ClipboardPayload) -> Verdict {
+    let fingerprint = {
+        let peers = state.peers.lock().unwrap();
+        peers
+            .values()
+            .find(|p| p.reachable_at(addr))
+            .and_then(|p| p.fingerprint.clone())
+    };
+    let sender = sender_key(fingerprint.as_deref(), Some(addr));
+    state
+        .replay_guard
+        .lock()
+        .unwrap()
+        .check(&sender, &payload.id, payload.seq, payload.timestamp, now_ms())
+}
+
+/// Judge a relayed envelope (relay.rs) from the device pinned to
+/// `fingerprint`. Envelope ids get their own namespace so they can't collide
+/// with clipboard payload ids.
+pub(crate) fn check_relayed(state: &AppState, fingerprint: &[u8], id: &str, sent_ms: u64) -> Verdict {
+    let sender = sender_key(Some(fingerprint), None);
+    state
+        .replay_guard
+        .lock()
+        .unwrap()
+        .check(&sender, &format!("relay:{}", id), None, sent_ms / 1000, now_ms())
+}
+
+/// Record a `TimedPong` from `addr`.
+pub(crate) fn record_pong(state: &AppState, addr: std::net::SocketAddr, echo_ms: u64, replied_ms: u64) {
+    let fingerprint = {
+        let peers = state.peers.lock().unwrap();
+        peers
+            .values()
+            .find(|p| p.reachable_at(addr))
+            .and_then(|p| p.fingerprint.clone())
+    };
+    let Some(fingerprint) = fingerprint else {
+        return;
+    };
+    let sender = sender_key(Some(&fingerprint), None);
+    state
+        .replay_guard
+        .lock()
+        .unwrap()
+        .record_clock_sample(&sender, echo_ms, replied_ms, now_ms());
+}
+
+/// Sample the clock of every runtime peer that understands `TimedPing`.
+pub(crate) async fn probe_clocks(state: &AppState, transport: &Transport) {
+    let targets: Vec<_> = state
+        .get_peers()
+        .values()
+        .filter(|p| crate::net_util::supports_clock_sync(p.protocol_version.as_deref()))
+        .map(|p| std::net::SocketAddr::new(p.ip, p.port))
+        .collect();
+    for addr in targets {
+        let Ok(data) = serde_json::to_vec(&Message::TimedPing { sent_ms: now_ms() }) else {
+            return;
+        };
+        if let Err(e) = transport.send_message(addr, &data).await {
+            tracing::debug!("Clock probe to {} failed: {}", addr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn duplicate_ids_are_rejected_per_sender() {
+        let mut g = ReplayGuard::default();
+        assert_eq!(g.check("a", "m1", Some(1), NOW / 1000, NOW), Verdict::Accept);
+        assert_eq!(g.check("a", "m1", Some(2), NOW / 1000, NOW), Verdict::Duplicate);
+        // Same id from a different sender is a different message.
+        assert_eq!(g.check("b", "m1", Some(1), NOW / 1000, NOW), Verdict::Accept);
+    }
+
+    #[test]
+    fn sequence_must_increase() {
+        let mut g = ReplayGuard::default();
+        assert_eq!(g.check("a", "m1", Some(10), NOW / 1000, NOW), Verdict::Accept);
+        assert_eq!(g.check("a", "m2", Some(10), NOW / 1000, NOW), Verdict::OutOfOrder { last: 10, got: 10 });
+        assert_eq!(g.check("a", "m3", Some(11), NOW / 1000, NOW), Verdict::Accept);
+    }
+
+    #[test]
+    fn seen_cache_is_bounded() {
+        let mut g = ReplayGuard::default();
+        for i in 0..SEEN_CAPACITY + 1 {
+            g.check("a", &i.to_string(), None, NOW / 1000, NOW);
+        }
+        assert_eq!(g.seen.len(), SEEN_CAPACITY);
+        assert_eq!(g.check("a", "0", None, NOW / 1000, NOW), Verdict::Accept, "oldest id was evicted");
+    }
+
+    #[test]
+    fn freshness_uses_the_senders_clock() {
+        let mut g = ReplayGuard::default();
+        // Sender's clock is 10 minutes behind ours.
+        let behind = 600_000;
+        g.record_clock_sample("vm", NOW, NOW + 20 - behind, NOW + 40);
+        assert_eq!(g.offset_ms("vm"), Some(-(behind as i64)));
+        let their_now = (NOW - behind) / 1000;
+        assert_eq!(g.check("vm", "fresh", Some(1), their_now, NOW), Verdict::Accept);
+        // A payload that is current by our clock is 10 minutes in its future.
+        assert!(matches!(g.check("vm", "odd", Some(2), NOW / 1000, NOW), Verdict::Stale { .. }));
+    }
+
+    #[test]
+    fn legacy_payloads_keep_the_wall_clock_window() {
+        let mut g = ReplayGuard::default();
+        let old = NOW / 1000 - FRESHNESS_WINDOW_SECS as u64 - 1;
+        assert!(matches!(g.check("old", "m", None, old, NOW), Verdict::Stale { .. }));
+        // A sequenced payload without a clock estimate is judged on seq alone.
+        assert_eq!(g.check("new", "m", Some(1), old, NOW), Verdict::Accept);
+    }
+
+    #[test]
+    fn lowest_rtt_sample_wins() {
+        let mut g = ReplayGuard::default();
+        g.record_clock_sample("a", NOW, NOW + 5_000, NOW + 4_000); // slow, skewed estimate
+        g.record_clock_sample("a", NOW, NOW + 105, NOW + 10);
+        assert_eq!(g.offset_ms("a"), Some(100));
+    }
+
+    #[test]
+    fn next_seq_increases_across_runs_without_the_clock() {
+        let run1 = AtomicU64::new(first_seq(1));
+        assert_eq!(next_seq(&run1), (1 << 32) + 1);
+        assert_eq!(next_seq(&run1), (1 << 32) + 2);
+        // Next start bumps the epoch: above anything the last run sent.
+        let run2 = AtomicU64::new(first_seq(2));
+        assert!(next_seq(&run2) > next_seq(&run1));
+    }
+
+    #[test]
+    fn senders_are_keyed_by_fingerprint_then_address() {
+        let addr: std::net::SocketAddr = "192.168.1.9:4654".parse().unwrap();
+        assert_eq!(sender_key(Some(&[0xab, 0x01]), Some(addr)), "ab01");
+        assert_eq!(sender_key(None, Some(addr)), "addr:192.168.1.9:4654");
+    }
+}
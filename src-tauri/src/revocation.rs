@@ -0,0 +1,398 @@
+//! Signed, persisted revocations for removed cluster members.
+//!
+//! Kicking a device produces a [`Revocation`] naming its pinned fingerprint,
+//! signed by the remover's own key. Receivers accept it only if the remover
+//! is a member they have pinned and the signature verifies under that
+//! member's cert, so a single compromised or confused peer can't forge
+//! removals in someone else's name. Accepted revocations are persisted to
+//! `revocations.json`, carried in every `ClusterInfo` (pairing bootstrap and
+//! anti-entropy) and checked by the mTLS resolvers: a removed laptop that a
+//! member still had pinned because it was offline during the removal is
+//! dropped as soon as that member syncs, and can't be gossiped back in.
+//!
+//! The removed device itself, on receiving its own revocation, regenerates
+//! its cert before factory-resetting, so a legitimate re-pair afterwards
+//! isn't blocked by the old fingerprint.
+//!
+//! Revocations are scoped to a cluster: one is accepted only when its issuer
+//! and the device it names are in the same cluster of ours (the primary one
+//! or the same additional context, see clusters.rs), so a member of one
+//! cluster can't evict devices from another.
+//!
+//! A revocation signed under a cert its author has since rotated away from
+//! can't be verified by members that learn of it afterwards; they keep it
+//! out only if another member's copy of the list reaches them first.
+
+use tauri::Emitter;
+
+use crate::protocol::Revocation;
+use crate::state::AppState;
+use crate::transport::{cert_fingerprint, verify_cert_signature, Transport};
+
+/// Domain separator for the signed statement.
+const REVOCATION_DOMAIN: &[u8] = b"clustercut-revocation-v1";
+
+/// Bytes covered by the revoker's signature.
+pub(crate) fn signed_bytes(device_id: &str, fingerprint: &[u8], timestamp: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(REVOCATION_DOMAIN.len() + device_id.len() + fingerprint.len() + 10);
+    out.extend_from_slice(REVOCATION_DOMAIN);
+    out.push(0);
+    out.extend_from_slice(device_id.as_bytes());
+    out.push(0);
+    out.extend_from_slice(fingerprint);
+    out.extend_from_slice(&timestamp.to_be_bytes());
+    out
+}
+
+/// Validate a revocation against the fingerprint we have pinned for its
+/// issuer. Pure so it can be unit-tested without an `AppHandle`.
+pub(crate) fn check_revocation(rev: &Revocation, revoker_pinned: Option<&[u8]>) -> Result<(), String> {
+    let pinned = revoker_pinned.ok_or("issuer is not a pinned member")?;
+    if cert_fingerprint(&rev.revoker_cert) != pinned {
+        return Err("issuer cert does not match its pinned fingerprint".to_string());
+    }
+    if rev.fingerprint.len() != 32 {
+        return Err("invalid revoked fingerprint".to_string());
+    }
+    let message = signed_bytes(&rev.device_id, &rev.fingerprint, rev.timestamp);
+    if !verify_cert_signature(&rev.revoker_cert, rev.scheme, &message, &rev.signature) {
+        return Err("signature does not verify under the issuer cert".to_string());
+    }
+    Ok(())
+}
+
+/// Fingerprint we trust for `revoker_id`: our own for revocations we issued,
+/// otherwise whatever `known_peers` has pinned.
+pub(crate) fn pinned_for(state: &AppState, revoker_id: &str) -> Option<Vec<u8>> {
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    if revoker_id == local_id {
+        return state.transport.lock().unwrap().as_ref().map(|t| t.local_fingerprint());
+    }
+    state
+        .known_peers
+        .lock()
+        .unwrap()
+        .get(revoker_id)
+        .and_then(|p| p.fingerprint.clone())
+}
+
+/// Whether `issuer_id` may make statements about the device named by
+/// `device_id`/`fingerprint`: both must count towards the same cluster of
+/// ours. The device is resolved by id or by its pinned fingerprint; one we
+/// don't know counts towards the primary cluster. Our own statements are
+/// always in scope.
+pub(crate) fn shares_cluster(state: &AppState, issuer_id: &str, device_id: &str, fingerprint: &[u8]) -> bool {
+    if issuer_id == *state.local_device_id.lock().unwrap() {
+        return true;
+    }
+    let subject_id = {
+        let kp = state.known_peers.lock().unwrap();
+        kp.values()
+            .find(|p| p.id == device_id || p.fingerprint.as_deref() == Some(fingerprint))
+            .map_or_else(|| device_id.to_string(), |p| p.id.clone())
+    };
+    crate::clusters::cluster_id_of(state, issuer_id) == crate::clusters::cluster_id_of(state, &subject_id)
+}
+
+/// Verify and record revocations we haven't seen yet. Returns the newly
+/// accepted ones; the caller persists and applies them.
+pub(crate) fn merge_revocations(state: &AppState, incoming: &[Revocation]) -> Vec<Revocation> {
+    let mut accepted = Vec::new();
+    for rev in incoming {
+        if state.is_revoked_fingerprint(&rev.fingerprint) {
+            continue;
+        }
+        let pinned = pinned_for(state, &rev.revoker_id);
+        if let Err(reason) = check_revocation(rev, pinned.as_deref()) {
+            tracing::warn!(
+                "[Revocation] Ignoring revocation of {} issued by {}: {}",
+                rev.device_id,
+                rev.revoker_id,
+                reason
+            );
+            continue;
+        }
+        if !shares_cluster(state, &rev.revoker_id, &rev.device_id, &rev.fingerprint) {
+            tracing::warn!(
+                "[Revocation] Ignoring revocation of {} issued by {}: not in the issuer's cluster",
+                rev.device_id,
+                rev.revoker_id
+            );
+            continue;
+        }
+        state.revocations.lock().unwrap().push(rev.clone());
+        accepted.push(rev.clone());
+    }
+    accepted
+}
+
+/// Persist newly accepted revocations and drop the devices they name.
+pub(crate) fn apply_revocations(state: &AppState, app_handle: &tauri::AppHandle, accepted: &[Revocation]) {
+    if accepted.is_empty() {
+        return;
+    }
+    crate::storage::save_revocations(app_handle, &state.revocations.lock().unwrap());
+
+    // A device may have re-appeared under a different id with the same
+    // cert, so match on the fingerprint as well as the named id.
+    let doomed: Vec<String> = {
+        let kp = state.known_peers.lock().unwrap();
+        kp.values()
+            .filter(|p| {
+                accepted.iter().any(|r| {
+                    r.device_id == p.id || p.fingerprint.as_deref() == Some(r.fingerprint.as_slice())
+                })
+            })
+            .map(|p| p.id.clone())
+            .chain(accepted.iter().map(|r| r.device_id.clone()))
+            .collect()
+    };
+    {
+        let mut tombstones = state.removed_peer_tombstones.lock().unwrap();
+        tombstones.extend(doomed.iter().cloned());
+    }
+    {
+        let mut kp = state.known_peers.lock().unwrap();
+        let before = kp.len();
+        kp.retain(|id, _| !doomed.contains(id));
+        if kp.len() != before {
+            crate::storage::save_known_peers(app_handle, &kp);
+        }
+    }
+    {
+        let mut policies = state.peer_policies.lock().unwrap();
+        let before = policies.len();
+        policies.retain(|id, _| !doomed.contains(id));
+        if policies.len() != before {
+            crate::storage::save_peer_policies(app_handle, &policies);
+        }
+    }
+    for id in &doomed {
+        crate::clusters::remove_member(state, app_handle, id);
+    }
+    for id in &doomed {
+        let removed = state.peers.lock().unwrap().remove(id);
+        if let Some(peer) = removed {
+            tracing::info!("[Revocation] {} ({}) was removed from the cluster", peer.hostname, peer.id);
+            crate::check_and_notify_leave(app_handle, state, &peer);
+            let _ = app_handle.emit("peer-remove", id);
+        }
+    }
+}
+
+/// Sign a revocation for `device_id`/`fingerprint` with this device's key.
+pub(crate) fn issue_revocation(
+    state: &AppState,
+    transport: &Transport,
+    device_id: &str,
+    fingerprint: Vec<u8>,
+) -> Result<Revocation, String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (scheme, signature) = transport.sign_with_local_key(&signed_bytes(device_id, &fingerprint, timestamp))?;
+    Ok(Revocation {
+        device_id: device_id.to_string(),
+        fingerprint,
+        revoker_id: state.local_device_id.lock().unwrap().clone(),
+        revoker_cert: transport.local_cert_der(),
+        timestamp,
+        scheme,
+        signature,
+    })
+}
+
+/// Receiver side of `Message::Revocation`.
+pub(crate) fn handle_revocation(
+    rev: Revocation,
+    addr: std::net::SocketAddr,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    transport: &Transport,
+) {
+    if rev.fingerprint == transport.local_fingerprint() {
+        let pinned = pinned_for(state, &rev.revoker_id);
+        if let Err(reason) = check_revocation(&rev, pinned.as_deref()) {
+            reject(state, app_handle, addr, &rev, &reason);
+            return;
+        }
+        // Removed from one of our additional clusters: leave just that one.
+        // Its members refuse our cert from now on; the others still pin it.
+        if let Some(ctx) = crate::clusters::context_of(state, &rev.revoker_id) {
+            tracing::warn!("Removed from cluster '{}' by {}; leaving it.", ctx.name, rev.revoker_id);
+            let _ = crate::clusters::forget_context(state, app_handle, &ctx.cluster_id);
+            return;
+        }
+        tracing::warn!("I have been removed from the network by {}! resetting state...", rev.revoker_id);
+        // The old cert is now refused cluster-wide; re-pairing needs a new one.
+        match crate::transport::generate_self_signed_cert() {
+            Ok((cert, key)) => {
+                crate::storage::save_device_cert(app_handle, &cert, &key);
+                if let Err(e) = transport.rotate_identity(cert, key) {
+                    tracing::error!("[Revocation] Failed to switch to a fresh cert: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("[Revocation] Failed to generate a fresh cert: {}", e),
+        }
+        crate::perform_factory_reset(
+            app_handle,
+            state,
+            transport.local_addr().map(|a| a.port()).unwrap_or(0),
+        );
+        return;
+    }
+
+    let accepted = merge_revocations(state, std::slice::from_ref(&rev));
+    if accepted.is_empty() {
+        if !state.is_revoked_fingerprint(&rev.fingerprint) {
+            reject(state, app_handle, addr, &rev, "issuer or signature not accepted");
+        }
+        return;
+    }
+    apply_revocations(state, app_handle, &accepted);
+}
+
+fn reject(state: &AppState, app_handle: &tauri::AppHandle, addr: std::net::SocketAddr, rev: &Revocation, reason: &str) {
+    tracing::warn!("[Revocation] Rejected removal of {} from {}: {}", rev.device_id, addr, reason);
+    crate::diagnostics::push_diagnostic(
+        state,
+        app_handle,
+        crate::diagnostics::DiagLevel::Detailed,
+        "mtls",
+        Some(addr.to_string()),
+        format!("Rejected removal of {}: {}", rev.device_id, reason),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    }
+
+    fn revocation(revoker_cert: &[u8], revoker_key: &[u8], device_id: &str) -> Revocation {
+        let (victim, _) = crate::transport::generate_self_signed_cert().unwrap();
+        let fingerprint = cert_fingerprint(&victim);
+        let (scheme, signature) =
+            crate::transport::sign_with_key(revoker_key, &signed_bytes(device_id, &fingerprint, 1_000)).unwrap();
+        Revocation {
+            device_id: device_id.to_string(),
+            fingerprint,
+            revoker_id: "clustercut-admin".to_string(),
+            revoker_cert: revoker_cert.to_vec(),
+            timestamp: 1_000,
+            scheme,
+            signature,
+        }
+    }
+
+    fn pin(state: &AppState, id: &str, fingerprint: Vec<u8>) {
+        let p = crate::peer::Peer {
+            id: id.to_string(),
+            ip: "192.168.1.2".parse().unwrap(),
+            port: 4654,
+            hostname: format!("host-{id}"),
+            last_seen: 0,
+            is_trusted: true,
+            is_manual: false,
+            network_name: None,
+            signature: None,
+            fingerprint: Some(fingerprint),
+            protocol_version: None,
+            addresses: Vec::new(),
+            reaches: Vec::new(),
+            relay_via: None,
+            status: None,
+            device: None,
+            link: Default::default(),
+        };
+        state.known_peers.lock().unwrap().insert(p.id.clone(), p);
+    }
+
+    #[test]
+    fn accepts_revocation_signed_by_pinned_member() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let rev = revocation(&cert, &key, "clustercut-laptop");
+        assert!(check_revocation(&rev, Some(&cert_fingerprint(&cert))).is_ok());
+    }
+
+    #[test]
+    fn rejects_unpinned_issuer_and_forged_signature() {
+        provider();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let (_, other_key) = crate::transport::generate_self_signed_cert().unwrap();
+        let pinned = cert_fingerprint(&cert);
+
+        assert!(check_revocation(&revocation(&cert, &key, "clustercut-laptop"), None).is_err());
+
+        // Claims a pinned member's cert but signed with another key.
+        let forged = revocation(&cert, &other_key, "clustercut-laptop");
+        assert!(check_revocation(&forged, Some(&pinned)).is_err());
+
+        // Genuine signature, but retargeted at a different device.
+        let mut retargeted = revocation(&cert, &key, "clustercut-laptop");
+        retargeted.fingerprint = vec![9; 32];
+        assert!(check_revocation(&retargeted, Some(&pinned)).is_err());
+    }
+
+    #[test]
+    fn merge_records_verified_revocations_once_and_blocks_fingerprint() {
+        provider();
+        let state = AppState::new();
+        *state.local_device_id.lock().unwrap() = "clustercut-me".to_string();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        pin(&state, "clustercut-admin", cert_fingerprint(&cert));
+        let rev = revocation(&cert, &key, "clustercut-laptop");
+        let fp = rev.fingerprint.clone();
+        // Still pinned by a member that was offline during the removal.
+        pin(&state, "clustercut-laptop", fp.clone());
+        assert!(state.knows_fingerprint(&fp));
+
+        assert_eq!(merge_revocations(&state, &[rev.clone()]).len(), 1);
+        assert!(merge_revocations(&state, &[rev]).is_empty());
+        assert_eq!(state.revocations.lock().unwrap().len(), 1);
+        assert!(state.is_revoked_fingerprint(&fp));
+        assert!(!state.knows_fingerprint(&fp));
+    }
+
+    #[test]
+    fn merge_keeps_revocations_inside_the_issuers_cluster() {
+        provider();
+        let state = AppState::new();
+        *state.local_device_id.lock().unwrap() = "clustercut-me".to_string();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        pin(&state, "clustercut-admin", cert_fingerprint(&cert));
+        state.cluster_contexts.lock().unwrap().push(crate::clusters::ClusterContext {
+            cluster_id: "work".to_string(),
+            name: "Work".to_string(),
+            pin: String::new(),
+            members: vec!["clustercut-admin".to_string(), "clustercut-printer".to_string()],
+        });
+
+        // A member of the additional cluster can't evict a primary member.
+        let cross = revocation(&cert, &key, "clustercut-laptop");
+        pin(&state, "clustercut-laptop", cross.fingerprint.clone());
+        assert!(merge_revocations(&state, &[cross.clone()]).is_empty());
+        assert!(!state.is_revoked_fingerprint(&cross.fingerprint));
+
+        // ...but can remove a fellow member of its own cluster.
+        let own = revocation(&cert, &key, "clustercut-printer");
+        assert_eq!(merge_revocations(&state, &[own]).len(), 1);
+    }
+
+    #[test]
+    fn merge_drops_revocations_from_unknown_issuers() {
+        provider();
+        let state = AppState::new();
+        *state.local_device_id.lock().unwrap() = "clustercut-me".to_string();
+        let (cert, key) = crate::transport::generate_self_signed_cert().unwrap();
+        let rev = revocation(&cert, &key, "clustercut-laptop");
+
+        assert!(merge_revocations(&state, &[rev]).is_empty());
+        assert!(state.revocations.lock().unwrap().is_empty());
+    }
+}
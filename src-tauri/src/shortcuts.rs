@@ -20,7 +20,7 @@ pub(crate) fn register_shortcuts(app_handle: &tauri::AppHandle) {
     }
 
     // Register Send Shortcut
-    if !settings.auto_send {
+    if !crate::quiet::effective_auto_send(&state, &settings) {
         if let Some(s) = &settings.shortcut_send {
             match Shortcut::from_str(s) {
                 Ok(shortcut) => {
@@ -35,8 +35,23 @@ pub(crate) fn register_shortcuts(app_handle: &tauri::AppHandle) {
         }
     }
 
+    // Register Send-Ephemeral Shortcut (active in both send modes: with
+    // auto-send on it arms the next copy instead of sending immediately).
+    if let Some(s) = &settings.shortcut_send_ephemeral {
+        match Shortcut::from_str(s) {
+            Ok(shortcut) => {
+                if let Err(e) = app_handle.global_shortcut().register(shortcut) {
+                    tracing::error!("Failed to register Send Ephemeral shortcut '{}': {}", s, e);
+                } else {
+                    tracing::debug!("Registered Send Ephemeral shortcut: {}", s);
+                }
+            }
+            Err(e) => tracing::error!("Invalid Send Ephemeral shortcut '{}': {}", s, e),
+        }
+    }
+
     // Register Receive Shortcut
-    if !settings.auto_receive {
+    if !crate::quiet::effective_auto_receive(&state, &settings) {
         if let Some(s) = &settings.shortcut_receive {
             match Shortcut::from_str(s) {
                 Ok(shortcut) => {
@@ -67,59 +82,7 @@ pub(crate) fn handle_shortcut(app_handle: &tauri::AppHandle, shortcut: &Shortcut
                // Trigger Send Logic
                // Get local content
                match clipboard::read_text(app_handle) {
-                   Ok(text) => {
-                        let hostname = hostname::get().map(|h| h.to_string_lossy().to_string()).unwrap_or("Unknown".to_string());
-                        let msg_id = uuid::Uuid::new_v4().to_string();
-                        let ts = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
-
-                            let local_id = state.local_device_id.lock().unwrap().clone();
-                            let payload_obj = crate::protocol::ClipboardPayload {
-                                id: msg_id.clone(),
-                                text: text.clone(),
-                                timestamp: ts,
-                                sender: hostname,
-                                sender_id: local_id,
-                                files: None,
-                                blob: None,
-                                formats: None,
-                            };
-
-                        // Emit local event
-                        let _ = app_handle.emit("clipboard-change", &payload_obj);
-
-                        // Send (mTLS handles confidentiality + sender auth).
-                        let msg = Message::Clipboard(payload_obj);
-                        if let Ok(data) = serde_json::to_vec(&msg) {
-                            let transport = app_handle.state::<Transport>();
-                            let peers = state.get_peers();
-                            for p in peers.values() {
-                                let addr = std::net::SocketAddr::new(p.ip, p.port);
-                                let transport_clone = (*transport).clone();
-                                let data_vec = data.clone();
-                                let app_clone = app_handle.clone();
-                                let peer_id = p.id.clone();
-                                let peer_hostname = p.hostname.clone();
-                                let peer_version = p.protocol_version.clone();
-                                tauri::async_runtime::spawn(async move {
-                                    if let Err(e) = transport_clone.send_message(addr, &data_vec).await {
-                                        report_send_failure(
-                                            &app_clone,
-                                            &peer_id,
-                                            &peer_hostname,
-                                            peer_version.as_deref(),
-                                            addr,
-                                            &e.to_string(),
-                                        );
-                                    }
-                                });
-                            }
-
-                            let notif_settings = settings.notifications.clone();
-                            if notif_settings.data_sent {
-                                send_notification(app_handle, "Clipboard Sent", "Manual broadcast successful.", false, Some(2), "history", NotificationPayload::None);
-                            }
-                        }
-                   },
+                   Ok(text) => send_text(app_handle, &state, &settings, text, None),
                    Err(e) => tracing::error!("Failed to read clipboard for global send: {}", e),
                }
                return;
@@ -127,6 +90,36 @@ pub(crate) fn handle_shortcut(app_handle: &tauri::AppHandle, shortcut: &Shortcut
         }
     }
 
+    // Check Send Ephemeral
+    if let Some(s) = &settings.shortcut_send_ephemeral {
+        if let Ok(parsed) = Shortcut::from_str(s) {
+            if parsed == *shortcut {
+                tracing::info!("Global Send Ephemeral Shortcut Triggered!");
+                let ttl = settings.ephemeral_ttl_secs.max(1);
+                if crate::quiet::effective_auto_send(&state, &settings) {
+                    // Whatever is on the clipboard now has already been
+                    // broadcast as a regular item; tag the next copy instead.
+                    *state.ephemeral_next_copy.lock().unwrap() = Some(std::time::Instant::now());
+                    send_notification(
+                        app_handle,
+                        "Ephemeral Send Armed",
+                        &format!("Your next copy will self-destruct on other devices after {}s.", ttl),
+                        false,
+                        Some(3),
+                        "history",
+                        NotificationPayload::None,
+                    );
+                } else {
+                    match clipboard::read_text(app_handle) {
+                        Ok(text) => send_text(app_handle, &state, &settings, text, Some(ttl)),
+                        Err(e) => tracing::error!("Failed to read clipboard for ephemeral send: {}", e),
+                    }
+                }
+                return;
+            }
+        }
+    }
+
     // Check Receive
     if let Some(s) = &settings.shortcut_receive {
         if let Ok(parsed) = Shortcut::from_str(s) {
@@ -161,14 +154,26 @@ pub(crate) fn handle_shortcut(app_handle: &tauri::AppHandle, shortcut: &Shortcut
                         });
                         send_notification(&app, "Receiving Clipboard Image", "Fetching pending image…", false, Some(2), "history", NotificationPayload::None);
                     } else if let Some(blob) = payload.blob.clone() {
+                        clipboard::common::arm_ephemeral(
+                            app_handle,
+                            payload.ttl_secs,
+                            clipboard::common::ClipboardContent::Image(blob.clone()),
+                        );
                         clipboard::set_clipboard_image(app_handle, blob);
                         tracing::info!("Confirmed pending clipboard image via shortcut.");
                         send_notification(app_handle, "Image Received", "Pending image applied.", false, Some(2), "history", NotificationPayload::None);
-                    } else if let Err(e) = clipboard::write_text_direct(app_handle, payload.text) {
-                        tracing::error!("Failed to write pending clipboard to system: {}", e);
                     } else {
-                        tracing::info!("Confirmed pending clipboard content via shortcut.");
-                        send_notification(app_handle, "Clipboard Received", "Pending content applied.", false, Some(2), "history", NotificationPayload::None);
+                        clipboard::common::arm_ephemeral(
+                            app_handle,
+                            payload.ttl_secs,
+                            clipboard::common::ClipboardContent::Text(payload.text.clone()),
+                        );
+                        if let Err(e) = clipboard::write_text_direct(app_handle, payload.text) {
+                            tracing::error!("Failed to write pending clipboard to system: {}", e);
+                        } else {
+                            tracing::info!("Confirmed pending clipboard content via shortcut.");
+                            send_notification(app_handle, "Clipboard Received", "Pending content applied.", false, Some(2), "history", NotificationPayload::None);
+                        }
                     }
                 } else {
                     tracing::info!("No pending clipboard content to receive.");
@@ -178,3 +183,79 @@ pub(crate) fn handle_shortcut(app_handle: &tauri::AppHandle, shortcut: &Shortcut
         }
     }
 }
+
+/// Broadcast `text` to every connected peer as a manual send, optionally as
+/// an ephemeral item with `ttl_secs`. Shared by the Send and Send Ephemeral
+/// shortcuts.
+fn send_text(
+    app_handle: &tauri::AppHandle,
+    state: &AppState,
+    settings: &crate::storage::AppSettings,
+    text: String,
+    ttl_secs: Option<u64>,
+) {
+    let hostname = hostname::get().map(|h| h.to_string_lossy().to_string()).unwrap_or("Unknown".to_string());
+    let msg_id = uuid::Uuid::new_v4().to_string();
+    let ts = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+
+    let local_id = state.local_device_id.lock().unwrap().clone();
+    let payload_obj = crate::protocol::ClipboardPayload {
+        id: msg_id.clone(),
+        text: text.clone(),
+        timestamp: ts,
+        sender: hostname,
+        sender_id: local_id,
+        files: None,
+        blob: None,
+        formats: None,
+        ttl_secs,
+        seq: Some(state.next_clipboard_seq()),
+        cut: false,
+    };
+
+    // Emit local event so history updates. Ephemeral items stay out of it.
+    if ttl_secs.is_none() {
+        let _ = app_handle.emit("clipboard-change", &payload_obj);
+    }
+
+    // Send (mTLS handles confidentiality + sender auth).
+    let msg = Message::Clipboard(payload_obj.clone());
+    if let Ok(data) = serde_json::to_vec(&msg) {
+        let transport = app_handle.state::<Transport>();
+        let peers = state.get_peers();
+        for p in peers.values() {
+            if !state.policy_for(&p.id).allows_outgoing(&payload_obj) {
+                continue;
+            }
+            let addr = std::net::SocketAddr::new(p.ip, p.port);
+            let transport_clone = (*transport).clone();
+            let data_vec = data.clone();
+            let app_clone = app_handle.clone();
+            let peer_id = p.id.clone();
+            let peer_hostname = p.hostname.clone();
+            let peer_version = p.protocol_version.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = transport_clone.send_message(addr, &data_vec).await {
+                    report_send_failure(
+                        &app_clone,
+                        &peer_id,
+                        &peer_hostname,
+                        peer_version.as_deref(),
+                        addr,
+                        &e.to_string(),
+                    );
+                }
+            });
+        }
+
+        let notif_settings = settings.notifications.clone();
+        if notif_settings.data_sent {
+            let body = if ttl_secs.is_some() {
+                "Ephemeral broadcast successful."
+            } else {
+                "Manual broadcast successful."
+            };
+            send_notification(app_handle, "Clipboard Sent", body, false, Some(2), "history", NotificationPayload::None);
+        }
+    }
+}
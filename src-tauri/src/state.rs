@@ -1,8 +1,8 @@
-use crate::peer::Peer;
+use crate::peer::{Peer, PeerPolicy};
 use crate::storage::AppSettings;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 /// One entry surfaced to the UI for the "needs re-pair" banner. A peer
@@ -29,6 +29,23 @@ pub struct ClipboardBlobMetadata {
     pub height: Option<u32>,
     pub total_size: u64,
 }
+/// A fingerprint a peer rotated away from (see key_rotation.rs). Still
+/// accepted for `key_rotation::ROTATION_GRACE` after `retired_at`, and never
+/// re-pinned from gossip afterwards.
+#[derive(Debug, Clone)]
+pub struct RetiredFingerprint {
+    pub device_id: String,
+    pub ip: std::net::IpAddr,
+    pub fingerprint: Vec<u8>,
+    pub retired_at: std::time::Instant,
+}
+
+impl RetiredFingerprint {
+    pub fn in_grace(&self) -> bool {
+        self.retired_at.elapsed() < crate::key_rotation::ROTATION_GRACE
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub peers: Arc<Mutex<HashMap<String, Peer>>>,
@@ -43,6 +60,27 @@ pub struct AppState {
     pub legacy_peers: Arc<Mutex<Vec<LegacyPeerInfo>>>,
     // Known Peers (Persisted list of devices we know about)
     pub known_peers: Arc<Mutex<HashMap<String, Peer>>>,
+    /// Per-peer permission policies keyed by device id, persisted to
+    /// `peer_policies.json`. Missing entry ⇒ `PeerPolicy::default()`.
+    pub peer_policies: Arc<Mutex<HashMap<String, PeerPolicy>>>,
+    /// Fingerprints peers have rotated away from this session. In-memory
+    /// only: the grace window is far shorter than a typical uptime.
+    pub retired_fingerprints: Arc<Mutex<Vec<RetiredFingerprint>>>,
+    /// Verified, signed removals of cluster members, persisted to
+    /// `revocations.json` and gossiped in `ClusterInfo`. A revoked
+    /// fingerprint is refused by the mTLS resolvers and never re-imported
+    /// from membership gossip. See revocation.rs.
+    pub revocations: Arc<Mutex<Vec<crate::protocol::Revocation>>>,
+    /// Invite tokens minted on this device, persisted to
+    /// `invite_tokens.json`. See pairing/tokens.rs.
+    pub invite_tokens: Arc<Mutex<Vec<crate::pairing::tokens::InviteToken>>>,
+    /// Verified restrictions on devices that joined with a limited invite
+    /// token, persisted to `join_restrictions.json` and gossiped in
+    /// `ClusterInfo`. See pairing/tokens.rs.
+    pub join_restrictions: Arc<Mutex<Vec<crate::protocol::JoinRestriction>>>,
+    /// Clusters joined in addition to the primary one, persisted to
+    /// `clusters.json`. See clusters.rs.
+    pub cluster_contexts: Arc<Mutex<Vec<crate::clusters::ClusterContext>>>,
     pub local_device_id: Arc<Mutex<String>>,
     // Discovery Service
     pub discovery: Arc<Mutex<Option<crate::discovery::Discovery>>>,
@@ -134,17 +172,9 @@ pub struct AppState {
     pub consecutive_heartbeat_failures: Arc<AtomicU32>,
 
     // ─── Wire-protocol 0.3.1 pairing hardening (H1, H6) ─────────────────────
-    /// Count of AEAD-decrypt failures on the pairing channel since the last
-    /// `pairing_locked_out` re-arm. Per WIRE-PROTOCOL-0.3.1 §H1, after
-    /// `PAIRING_FAILURE_LOCKOUT_THRESHOLD` failures (aggregated across all
-    /// source IPs — there is no per-IP state) the responder flips
-    /// `pairing_locked_out = true` and refuses further pairing connections
-    /// until the user manually re-arms via the UI.
-    pub pairing_failure_count: Arc<AtomicU32>,
-    /// Sticky lockout flag. When true, the pairing TCP listener accepts and
-    /// immediately closes any inbound connection. Cleared via the
-    /// `rearm_pairing` Tauri command.
-    pub pairing_locked_out: Arc<AtomicBool>,
+    /// Pairing-channel AEAD failures per source, with backoff and a global
+    /// ceiling (§H1). See pairing/ratelimit.rs.
+    pub pairing_limiter: Arc<Mutex<crate::pairing::ratelimit::PairingRateLimiter>>,
     /// Single-flight pairing capacity (cap = 1). Per WIRE-PROTOCOL-0.3.1 §H6,
     /// the responder accepts exactly one in-flight pairing exchange at a time;
     /// a second concurrent connection is refused at the handler edge. A
@@ -165,22 +195,60 @@ pub struct AppState {
     /// anti-entropy reply landing mid-pairing would otherwise be mistaken
     /// for the responder's bootstrap (wrong cluster_id/name adoption).
     pub pending_cluster_info: Arc<Mutex<Option<(std::net::SocketAddr, tokio::sync::oneshot::Sender<crate::protocol::ClusterInfo>)>>>,
+    /// The nonce of the certificate rotation we are announcing, and where to
+    /// report the device id of each peer that acknowledges it
+    /// (key_rotation.rs). One rotation runs at a time.
+    pub pending_rotation_acks: Arc<Mutex<Option<(Vec<u8>, tokio::sync::mpsc::UnboundedSender<String>)>>>,
     /// Device ids removed this session via `delete_peer` (kick) or an
-    /// inbound `PeerRemoval`. The membership-sync merge consults this so a
-    /// member that missed the removal broadcast can't gossip the deleted
-    /// device straight back into `known_peers`. In-memory only: after a
-    /// restart a device that every OTHER member still trusts is treated as
-    /// a member again, which is the best guess available without a
-    /// replicated tombstone log.
+    /// inbound `PeerRemoval`/`Revocation`. The membership-sync merge consults
+    /// this so a member that missed the removal broadcast can't gossip the
+    /// deleted device straight back into `known_peers`. In-memory only; it
+    /// covers devices without a pinned fingerprint (and self-leaves), which
+    /// can't be named in a signed `revocations` entry.
     pub removed_peer_tombstones: Arc<Mutex<HashSet<String>>>,
+    /// Set by the "send ephemeral" shortcut in auto-send mode: the next local
+    /// clipboard change within `EPHEMERAL_ARM_WINDOW` of this instant goes
+    /// out with a TTL. Consumed by `clipboard::common::ephemeral_ttl_for`.
+    pub ephemeral_next_copy: Arc<Mutex<Option<std::time::Instant>>>,
+    /// TTLs of ephemeral descriptor payloads whose bytes are still to be
+    /// fetched, keyed by payload id. The clipboard-blob stream handler takes
+    /// the entry when the bytes land so it can skip staging and arm expiry.
+    pub ephemeral_fetches: Arc<Mutex<HashMap<String, u64>>>,
+    /// Pairings waiting on the user's SAS verdict, keyed by the session id
+    /// sent in the `pairing-sas` event. See pairing/sas.rs.
+    pub pending_sas: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<bool>>>>,
+    /// Seen clipboard ids, per-sender sequence numbers and clock offsets.
+    /// See replay.rs.
+    pub replay_guard: Arc<Mutex<crate::replay::ReplayGuard>>,
+    /// Last `ClipboardPayload::seq` we stamped, starting from this run's
+    /// boot epoch (`storage::bump_seq_epoch`). See `next_clipboard_seq`.
+    pub clipboard_seq: Arc<AtomicU64>,
+    /// Which members can reach which, for picking a relay. See relay.rs.
+    pub relay_table: Arc<Mutex<crate::relay::RelayTable>>,
+    /// Wakes the network-policy task for an immediate re-check. See
+    /// netpolicy.rs.
+    pub network_policy_wake: Arc<tokio::sync::Notify>,
+    /// Quiet-hours mode, Do Not Disturb and notifications held during it.
+    /// See quiet.rs.
+    pub quiet: Arc<Mutex<crate::quiet::QuietState>>,
+    /// What we put in our heartbeats' `Peer::status`. See status.rs.
+    pub local_status: Arc<Mutex<crate::status::PeerStatus>>,
+    /// D-Bus connections for the periodic desktop probes (status.rs,
+    /// quiet.rs, netmon.rs), opened on first use and shared from then on.
+    /// Use `system_bus()` / `session_bus()`.
+    pub system_bus: Arc<tokio::sync::OnceCell<zbus::Connection>>,
+    pub session_bus: Arc<tokio::sync::OnceCell<zbus::Connection>>,
+    /// When we last sent each peer our `DeviceInfo` asking for theirs. See
+    /// device_info.rs.
+    pub device_info_asked: Arc<Mutex<HashMap<String, std::time::Instant>>>,
+    /// Our cuts waiting for a receiver to confirm every file, by payload id.
+    /// See moves.rs.
+    pub pending_moves: Arc<Mutex<HashMap<String, crate::moves::PendingMove>>>,
+    /// Cuts we are downloading, by payload id, with how many files are still
+    /// to be acknowledged to the sender.
+    pub cut_downloads: Arc<Mutex<HashMap<String, usize>>>,
 }
 
-/// Lockout threshold per WIRE-PROTOCOL-0.3.1 §H1 (picked from Michael's 10–20
-/// range). Once `pairing_failure_count` reaches this value, the responder
-/// flips `pairing_locked_out` and refuses further connections until manually
-/// re-armed via the UI.
-pub const PAIRING_FAILURE_LOCKOUT_THRESHOLD: u32 = 10;
-
 impl AppState {
     pub fn new() -> Self {
         Self {
@@ -188,6 +256,12 @@ impl AppState {
             cluster_id: Arc::new(Mutex::new(String::new())),
             legacy_peers: Arc::new(Mutex::new(Vec::new())),
             known_peers: Arc::new(Mutex::new(HashMap::new())),
+            peer_policies: Arc::new(Mutex::new(HashMap::new())),
+            retired_fingerprints: Arc::new(Mutex::new(Vec::new())),
+            revocations: Arc::new(Mutex::new(Vec::new())),
+            invite_tokens: Arc::new(Mutex::new(Vec::new())),
+            join_restrictions: Arc::new(Mutex::new(Vec::new())),
+            cluster_contexts: Arc::new(Mutex::new(Vec::new())),
             local_device_id: Arc::new(Mutex::new(String::new())),
             discovery: Arc::new(Mutex::new(None)),
             last_clipboard_content: Arc::new(Mutex::new(String::new())),
@@ -222,44 +296,76 @@ impl AppState {
             pending_network_wipe: Arc::new(AtomicBool::new(false)),
             pending_join_notifications: Arc::new(Mutex::new(HashSet::new())),
             consecutive_heartbeat_failures: Arc::new(AtomicU32::new(0)),
-            pairing_failure_count: Arc::new(AtomicU32::new(0)),
-            pairing_locked_out: Arc::new(AtomicBool::new(false)),
+            pairing_limiter: Arc::new(Mutex::new(Default::default())),
             pairing_slot: Arc::new(tokio::sync::Semaphore::new(1)),
             pending_cluster_info: Arc::new(Mutex::new(None)),
+            pending_rotation_acks: Arc::new(Mutex::new(None)),
             removed_peer_tombstones: Arc::new(Mutex::new(HashSet::new())),
+            ephemeral_next_copy: Arc::new(Mutex::new(None)),
+            ephemeral_fetches: Arc::new(Mutex::new(HashMap::new())),
+            pending_sas: Arc::new(Mutex::new(HashMap::new())),
+            replay_guard: Arc::new(Mutex::new(Default::default())),
+            clipboard_seq: Arc::new(AtomicU64::new(0)),
+            relay_table: Arc::new(Mutex::new(Default::default())),
+            network_policy_wake: Arc::new(tokio::sync::Notify::new()),
+            quiet: Arc::new(Mutex::new(Default::default())),
+            local_status: Arc::new(Mutex::new(Default::default())),
+            system_bus: Arc::new(tokio::sync::OnceCell::new()),
+            session_bus: Arc::new(tokio::sync::OnceCell::new()),
+            device_info_asked: Arc::new(Mutex::new(HashMap::new())),
+            pending_moves: Arc::new(Mutex::new(HashMap::new())),
+            cut_downloads: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    /// Bump the pairing-channel AEAD-failure counter. Returns true iff this
-    /// failure tripped the lockout threshold (i.e. the caller should fire
-    /// the lockout UI surface). Once tripped, further AEAD failures keep
-    /// counting but don't re-fire the trip signal.
-    pub fn record_pairing_failure(&self) -> bool {
-        let prior = self
-            .pairing_failure_count
-            .fetch_add(1, Ordering::SeqCst);
-        let new = prior.saturating_add(1);
-        if new >= PAIRING_FAILURE_LOCKOUT_THRESHOLD {
-            // Use compare_exchange so only the first failure to cross the
-            // threshold returns `true` — subsequent failures don't double-fire.
-            self
-                .pairing_locked_out
-                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-                .is_ok()
-        } else {
-            false
-        }
+    /// Record a pairing-channel AEAD failure from `ip`.
+    pub fn record_pairing_failure(&self, ip: std::net::IpAddr) -> crate::pairing::ratelimit::FailureOutcome {
+        self.pairing_limiter
+            .lock()
+            .unwrap()
+            .record_failure(ip, std::time::Instant::now())
+    }
+
+    /// Whether a pairing connection from `ip` may proceed right now.
+    pub fn admit_pairing(&self, ip: std::net::IpAddr) -> crate::pairing::ratelimit::Admission {
+        self.pairing_limiter
+            .lock()
+            .unwrap()
+            .admit(ip, std::time::Instant::now())
     }
 
-    /// Clear the lockout and reset the failure counter — called by the
-    /// `rearm_pairing` Tauri command after the user explicitly re-arms.
+    /// Forget every source's failures and lift any global pause — the manual
+    /// override behind the `rearm_pairing` Tauri command, for when the user
+    /// doesn't want to wait.
     pub fn rearm_pairing(&self) {
-        self.pairing_failure_count.store(0, Ordering::SeqCst);
-        self.pairing_locked_out.store(false, Ordering::SeqCst);
+        self.pairing_limiter.lock().unwrap().clear();
     }
 
+    /// True while the limiter's global failure ceiling has pairing paused for
+    /// everyone.
     pub fn is_pairing_locked_out(&self) -> bool {
-        self.pairing_locked_out.load(Ordering::SeqCst)
+        self.pairing_limiter
+            .lock()
+            .unwrap()
+            .global_pause(std::time::Instant::now())
+            .is_some()
+    }
+
+    /// The shared system-bus connection. None while the bus is unreachable;
+    /// a later call tries again.
+    pub async fn system_bus(&self) -> Option<zbus::Connection> {
+        self.system_bus.get_or_try_init(zbus::Connection::system).await.ok().cloned()
+    }
+
+    /// The shared session-bus connection. None while the bus is unreachable;
+    /// a later call tries again.
+    pub async fn session_bus(&self) -> Option<zbus::Connection> {
+        self.session_bus.get_or_try_init(zbus::Connection::session).await.ok().cloned()
+    }
+
+    /// Sequence number for the next clipboard payload we send.
+    pub fn next_clipboard_seq(&self) -> u64 {
+        crate::replay::next_seq(&self.clipboard_seq)
     }
 
     pub fn request_shutdown(&self) {
@@ -282,34 +388,183 @@ impl AppState {
     /// same "known fingerprint" trust model the server side already uses.
     /// Empty vec ⇒ no pin for this IP (peer must re-pair under strict mTLS).
     pub fn fingerprints_for(&self, addr: std::net::SocketAddr) -> Vec<Vec<u8>> {
+        let addr = crate::transport::canonical_addr(addr);
+        let revoked = self.revoked_fingerprints();
+        // A runtime peer may have been seen at addresses its stored record
+        // doesn't list yet; its pin still comes from known_peers.
+        let runtime_ids: Vec<String> = self
+            .peers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.has_ip(addr.ip()))
+            .map(|p| p.id.clone())
+            .collect();
         let peers = self.known_peers.lock().unwrap();
         let mut out: Vec<Vec<u8>> = Vec::new();
         for p in peers.values() {
-            if p.ip == addr.ip() {
+            if p.has_ip(addr.ip()) || runtime_ids.contains(&p.id) {
                 if let Some(fp) = &p.fingerprint {
-                    if !out.contains(fp) {
+                    if !out.contains(fp) && !revoked.contains(fp) {
                         out.push(fp.clone());
                     }
                 }
             }
         }
+        drop(peers);
+        // A peer mid-rotation may still present its previous cert.
+        for r in self.retired_fingerprints.lock().unwrap().iter() {
+            if r.ip == addr.ip() && r.in_grace() && !out.contains(&r.fingerprint) {
+                out.push(r.fingerprint.clone());
+            }
+        }
         out
     }
 
     /// True if `fp` matches the pinned fingerprint of any peer in
     /// known_peers. Used by the *server* side of QUIC handshakes (mTLS
     /// client-cert validation) where we only know the presented cert,
-    /// not which peer is connecting.
+    /// not which peer is connecting. Revoked fingerprints never pass, even
+    /// if a stale `known_peers` entry still carries one.
     pub fn knows_fingerprint(&self, fp: &[u8]) -> bool {
-        let peers = self.known_peers.lock().unwrap();
-        peers
+        if self.is_revoked_fingerprint(fp) {
+            return false;
+        }
+        let known = {
+            let peers = self.known_peers.lock().unwrap();
+            peers
+                .values()
+                .any(|p| p.fingerprint.as_deref() == Some(fp))
+        };
+        known
+            || self
+                .retired_fingerprints
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|r| r.fingerprint == fp && r.in_grace())
+    }
+
+    /// The device whose pinned certificate, or one it is rotating away from,
+    /// has fingerprint `fp`. None for strangers and revoked certificates.
+    pub fn device_for_fingerprint(&self, fp: &[u8]) -> Option<String> {
+        if self.is_revoked_fingerprint(fp) {
+            return None;
+        }
+        let pinned = self
+            .known_peers
+            .lock()
+            .unwrap()
             .values()
-            .any(|p| p.fingerprint.as_deref() == Some(fp))
+            .find(|p| p.fingerprint.as_deref() == Some(fp))
+            .map(|p| p.id.clone());
+        pinned.or_else(|| {
+            self.retired_fingerprints
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|r| r.fingerprint == fp && r.in_grace())
+                .map(|r| r.device_id.clone())
+        })
+    }
+
+    /// True if `fp` is a fingerprint `device_id` has rotated away from.
+    pub fn is_retired_fingerprint(&self, device_id: &str, fp: &[u8]) -> bool {
+        self.retired_fingerprints
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|r| r.device_id == device_id && r.fingerprint == fp)
+    }
+
+    fn revoked_fingerprints(&self) -> Vec<Vec<u8>> {
+        self.revocations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.fingerprint.clone())
+            .collect()
+    }
+
+    pub fn is_revoked_fingerprint(&self, fp: &[u8]) -> bool {
+        self.revocations.lock().unwrap().iter().any(|r| r.fingerprint == fp)
+    }
+
+    /// True if a verified revocation names this device id or fingerprint.
+    pub fn is_revoked(&self, device_id: &str, fingerprint: Option<&[u8]>) -> bool {
+        self.revocations
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|r| r.device_id == device_id || Some(r.fingerprint.as_slice()) == fingerprint)
+    }
+
+    /// The peer's own policy, narrowed by the routing rule of the cluster it
+    /// is in (clusters.rs).
+    pub fn policy_for(&self, peer_id: &str) -> PeerPolicy {
+        let mut policy = self
+            .peer_policies
+            .lock()
+            .unwrap()
+            .get(peer_id)
+            .cloned()
+            .unwrap_or_default();
+        let route = crate::clusters::route_for(self, peer_id);
+        policy.send_to &= route.send;
+        policy.receive_from &= route.receive;
+        policy
+    }
+
+    /// Policy for whoever sent us something from `ip`. The connection is
+    /// mTLS-pinned to *some* cluster member, but `claimed_id` is
+    /// self-reported, so only trust it when that peer actually lives at
+    /// `ip`; otherwise fall back to the known peer at that address. Several
+    /// records can share an IP (stale `manual-` placeholders, re-provisioned
+    /// devices) — in that case the most restrictive policy among them wins
+    /// rather than whichever HashMap entry comes first. A sender at an
+    /// address no known peer has gets the most restrictive policy we hold
+    /// for anyone: the claimed id is never enough to choose one.
+    pub fn policy_for_sender(&self, ip: std::net::IpAddr, claimed_id: Option<&str>) -> PeerPolicy {
+        let (at_ip, all): (Vec<String>, Vec<String>) = {
+            let peers = self.known_peers.lock().unwrap();
+            if let Some(id) = claimed_id {
+                if peers.get(id).map_or(false, |p| p.has_ip(ip)) {
+                    return self.policy_for(id);
+                }
+            }
+            let at_ip = peers
+                .values()
+                .filter(|p| p.has_ip(ip) && !p.id.starts_with("manual-"))
+                .map(|p| p.id.clone())
+                .collect();
+            (at_ip, peers.keys().cloned().collect())
+        };
+        let ids = if at_ip.is_empty() {
+            let mut all = all;
+            all.extend(self.peer_policies.lock().unwrap().keys().cloned());
+            all
+        } else {
+            at_ip
+        };
+        let mut policy = PeerPolicy::default();
+        for id in ids {
+            policy.narrow(&self.policy_for(&id));
+        }
+        policy
     }
 
-    pub fn add_peer(&self, peer: Peer) {
+    /// Insert or refresh a runtime peer, merging its addresses into the
+    /// candidates we already hold (see `paths::merge_into`). Returns the
+    /// record as stored, whose `ip`/`port` may differ from `peer`'s.
+    pub fn add_peer(&self, mut peer: Peer) -> Peer {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
         let mut peers = self.peers.lock().unwrap();
-        peers.insert(peer.id.clone(), peer);
+        crate::paths::merge_into(peers.get(&peer.id), &mut peer, now);
+        peers.insert(peer.id.clone(), peer.clone());
+        peer
     }
 
     pub fn get_peers(&self) -> HashMap<String, Peer> {
@@ -0,0 +1,243 @@
+//! What a device is doing, beyond being online.
+//!
+//! Each device puts a [`PeerStatus`] in its own heartbeat record: whether
+//! someone is at it (active, idle, or locked, from the desktop session), and
+//! whether incoming clipboards would be dropped (quiet hours paused sync) or
+//! kept in History only (auto-receive off). Senders use it to skip locked
+//! machines when `skip_locked_peers` is on, and to say why a clipboard
+//! wasn't applied somewhere; the device list shows it.
+//!
+//! Only a device's own record is believed. A status in a record gossiped by
+//! someone else is replaced with the one we already hold, and stored records
+//! never carry one.
+//!
+//! Session state comes from logind, Mutter's idle monitor or the
+//! freedesktop screensaver on Linux, from the last input time on Windows and
+//! from IOKit's HID idle time on macOS. Lock state is Linux only.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::peer::Peer;
+use crate::state::AppState;
+
+/// How often the local status is re-read.
+const TICK: Duration = Duration::from_secs(15);
+/// No input for this long counts as idle.
+const IDLE_AFTER_SECS: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activity {
+    #[default]
+    Active,
+    Idle,
+    Locked,
+    /// A state from a newer build.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerStatus {
+    pub activity: Activity,
+    /// Seconds since the last input, where the desktop reports it.
+    #[serde(default)]
+    pub idle_secs: Option<u64>,
+    /// Quiet hours paused sync: incoming clipboards are dropped.
+    #[serde(default)]
+    pub sync_paused: bool,
+    /// Auto-receive is off (or quiet hours made it manual): incoming
+    /// clipboards land in History only.
+    #[serde(default)]
+    pub manual: bool,
+}
+
+impl PeerStatus {
+    /// Why a clipboard sent to this device won't reach its clipboard, if
+    /// it won't.
+    pub fn not_applied_reason(&self) -> Option<&'static str> {
+        if self.sync_paused {
+            Some("sync paused")
+        } else if self.manual {
+            Some("History only")
+        } else {
+            None
+        }
+    }
+}
+
+fn classify(locked: bool, idle_secs: Option<u64>, idle_hint: bool) -> Activity {
+    if locked {
+        Activity::Locked
+    } else if idle_hint || idle_secs.map_or(false, |s| s >= IDLE_AFTER_SECS) {
+        Activity::Idle
+    } else {
+        Activity::Active
+    }
+}
+
+/// What the desktop session tells us: locked, seconds idle, and whether it
+/// flags itself idle.
+#[derive(Debug, Default)]
+struct Session {
+    locked: bool,
+    idle_secs: Option<u64>,
+    idle_hint: bool,
+}
+
+#[cfg(target_os = "linux")]
+async fn proxy<'a>(conn: &zbus::Connection, destination: &'a str, path: &'a str, interface: &'a str) -> Option<zbus::Proxy<'a>> {
+    zbus::proxy::Builder::new(conn)
+        .destination(destination)
+        .ok()?
+        .path(path)
+        .ok()?
+        .interface(interface)
+        .ok()?
+        .build()
+        .await
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+async fn session(state: &AppState) -> Session {
+    let mut out = Session::default();
+
+    if let Some(system) = state.system_bus().await {
+        let logind = proxy(&system, "org.freedesktop.login1", "/org/freedesktop/login1/session/auto", "org.freedesktop.login1.Session").await;
+        if let Some(logind) = logind {
+            out.locked = logind.get_property::<bool>("LockedHint").await.unwrap_or(false);
+            out.idle_hint = logind.get_property::<bool>("IdleHint").await.unwrap_or(false);
+        }
+    }
+
+    let Some(conn) = state.session_bus().await else {
+        return out;
+    };
+    // GNOME: milliseconds since the last input.
+    if let Some(mutter) = proxy(&conn, "org.gnome.Mutter.IdleMonitor", "/org/gnome/Mutter/IdleMonitor/Core", "org.gnome.Mutter.IdleMonitor").await {
+        if let Ok(reply) = mutter.call_method("GetIdletime", &()).await {
+            out.idle_secs = reply.body().deserialize::<u64>().ok().map(|ms| ms / 1000);
+        }
+    }
+    // KDE and others: seconds idle, and whether the screen is locked.
+    if let Some(saver) = proxy(&conn, "org.freedesktop.ScreenSaver", "/org/freedesktop/ScreenSaver", "org.freedesktop.ScreenSaver").await {
+        if out.idle_secs.is_none() {
+            if let Ok(reply) = saver.call_method("GetSessionIdleTime", &()).await {
+                out.idle_secs = reply.body().deserialize::<u32>().ok().map(u64::from);
+            }
+        }
+        if !out.locked {
+            if let Ok(reply) = saver.call_method("GetActive", &()).await {
+                out.locked = reply.body().deserialize::<bool>().unwrap_or(false);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(target_os = "windows")]
+async fn session(_state: &AppState) -> Session {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+    // SAFETY: `info` is a properly sized LASTINPUTINFO.
+    let ok = unsafe { GetLastInputInfo(&mut info) }.as_bool();
+    let idle_secs = ok.then(|| u64::from(unsafe { GetTickCount() }.wrapping_sub(info.dwTime)) / 1000);
+    Session { idle_secs, ..Default::default() }
+}
+
+#[cfg(target_os = "macos")]
+async fn session(_state: &AppState) -> Session {
+    // `"HIDIdleTime" = <nanoseconds>` in the IOHIDSystem entry.
+    let out = tokio::process::Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]).output().await;
+    let idle_secs = out.ok().and_then(|o| {
+        let text = String::from_utf8_lossy(&o.stdout).into_owned();
+        let line = text.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+        let ns: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+        Some(ns / 1_000_000_000)
+    });
+    Session { idle_secs, ..Default::default() }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+async fn session(_state: &AppState) -> Session {
+    Session::default()
+}
+
+/// Our status as of the last tick.
+pub(crate) fn local(state: &AppState) -> PeerStatus {
+    state.local_status.lock().unwrap().clone()
+}
+
+/// Keep the local status current for the life of the app. Heartbeats pick
+/// it up from there.
+pub(crate) async fn run(state: AppState) {
+    loop {
+        let s = session(&state).await;
+        let status = PeerStatus {
+            activity: classify(s.locked, s.idle_secs, s.idle_hint),
+            idle_secs: s.idle_secs,
+            sync_paused: crate::quiet::mode(&state) == Some(crate::quiet::QuietMode::Pause),
+            manual: !crate::quiet::auto_receive(&state),
+        };
+        {
+            let mut current = state.local_status.lock().unwrap();
+            if current.activity != status.activity {
+                tracing::info!("[Status] {:?} -> {:?}", current.activity, status.activity);
+            }
+            *current = status;
+        }
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+/// Keep the status in `peer` (a `PeerDiscovery` record that arrived on
+/// `addr`) only if the record is the device's own; otherwise carry over the
+/// one we already hold.
+pub(crate) fn accept(state: &AppState, peer: &mut Peer, addr: SocketAddr) {
+    let peers = state.peers.lock().unwrap();
+    let existing = peers.get(&peer.id);
+    if !existing.map_or(false, |p| p.reachable_at(addr)) {
+        peer.status = existing.and_then(|p| p.status.clone());
+    }
+}
+
+/// Whether to leave `peer` out of a clipboard broadcast.
+pub(crate) fn skip_for_clipboard(peer: &Peer, skip_locked: bool) -> bool {
+    skip_locked && peer.status.as_ref().map_or(false, |s| s.activity == Activity::Locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_follows_lock_and_idle_time() {
+        assert_eq!(classify(false, Some(10), false), Activity::Active);
+        assert_eq!(classify(false, None, false), Activity::Active);
+        assert_eq!(classify(false, Some(IDLE_AFTER_SECS), false), Activity::Idle);
+        assert_eq!(classify(false, None, true), Activity::Idle);
+        assert_eq!(classify(true, Some(0), false), Activity::Locked);
+    }
+
+    #[test]
+    fn unknown_states_parse_and_reasons_rank() {
+        let s: PeerStatus = serde_json::from_str(r#"{"activity":"on_a_call"}"#).unwrap();
+        assert_eq!(s.activity, Activity::Unknown);
+        assert_eq!(s.not_applied_reason(), None);
+
+        // Locked machines still take the clipboard; only skipping them
+        // (skip_locked_peers) keeps it off.
+        let mut s = PeerStatus { activity: Activity::Locked, ..Default::default() };
+        assert_eq!(s.not_applied_reason(), None);
+        s.manual = true;
+        assert_eq!(s.not_applied_reason(), Some("History only"));
+        s.sync_paused = true;
+        assert_eq!(s.not_applied_reason(), Some("sync paused"));
+    }
+}
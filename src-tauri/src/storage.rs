@@ -1,10 +1,15 @@
-use crate::peer::Peer;
+use crate::clusters::{ClusterContext, ClusterRoute};
+use crate::netpolicy::{ActivePolicy, NetworkPolicy};
+use crate::pairing::tokens::InviteToken;
+use crate::peer::{Peer, PeerPolicy};
+use crate::protocol::{JoinRestriction, Revocation};
+use crate::quiet::QuietSchedule;
 use names::Generator;
 use rand::Rng;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use tauri::{path::BaseDirectory, AppHandle, Manager};
+use tauri::AppHandle;
 
 /// Restrict a file to owner-only access. On Unix sets mode 0600. On Windows
 /// this is intentionally a no-op: `%APPDATA%\<app>` is already ACL-restricted to
@@ -24,9 +29,14 @@ fn set_owner_only(path: &Path) {
     }
 }
 
+/// Where a config file lives: the app config dir, or the active profile's
+/// directory inside it (see profile.rs).
+fn config_path(app: &AppHandle, file: &str) -> tauri::Result<std::path::PathBuf> {
+    crate::profile::config_file(app, file)
+}
+
 pub fn load_network_name(app: &AppHandle) -> String {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name") {
         Ok(p) => p,
         Err(_) => return String::from("unknown-network"),
     };
@@ -53,8 +63,7 @@ pub fn load_network_name(app: &AppHandle) -> String {
 }
 
 pub fn save_network_name(app: &AppHandle, name: &str) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name") {
         Ok(p) => p,
         Err(_) => return,
     };
@@ -68,8 +77,7 @@ pub fn save_network_name(app: &AppHandle, name: &str) {
 /// Load the cluster-name version counter. Missing/invalid file → 0 (pre-issue
 /// default; an upgraded install starts unversioned and converges by origin).
 pub fn load_network_name_version(app: &AppHandle) -> u64 {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name_version", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name_version") {
         Ok(p) => p,
         Err(_) => return 0,
     };
@@ -82,8 +90,7 @@ pub fn load_network_name_version(app: &AppHandle) -> u64 {
 }
 
 pub fn save_network_name_version(app: &AppHandle, version: u64) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name_version", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name_version") {
         Ok(p) => p,
         Err(_) => return,
     };
@@ -97,8 +104,7 @@ pub fn save_network_name_version(app: &AppHandle, version: u64) {
 /// file → empty string; callers seed it with the local device_id at startup so
 /// an unversioned install has a well-formed origin.
 pub fn load_network_name_origin(app: &AppHandle) -> String {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name_origin", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name_origin") {
         Ok(p) => p,
         Err(_) => return String::new(),
     };
@@ -112,8 +118,7 @@ pub fn load_network_name_origin(app: &AppHandle) -> String {
 }
 
 pub fn save_network_name_origin(app: &AppHandle, origin: &str) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_name_origin", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_name_origin") {
         Ok(p) => p,
         Err(_) => return,
     };
@@ -124,8 +129,7 @@ pub fn save_network_name_origin(app: &AppHandle, origin: &str) {
 }
 
 pub fn load_cluster_id(app: &AppHandle) -> Option<String> {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("cluster_id", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "cluster_id") {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Failed to resolve cluster id path: {}", e);
@@ -155,8 +159,7 @@ pub fn load_cluster_id(app: &AppHandle) -> Option<String> {
 }
 
 pub fn save_cluster_id(app: &AppHandle, id: &str) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("cluster_id", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "cluster_id") {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Failed to resolve cluster_id path for saving: {}", e);
@@ -175,13 +178,38 @@ pub fn save_cluster_id(app: &AppHandle, id: &str) {
     }
 }
 
+/// Bump and return the boot epoch that outgoing clipboard sequence numbers
+/// start from (see replay.rs), so they keep increasing across restarts
+/// without trusting the clock. Not part of the network state a factory
+/// reset wipes: peers may still hold our last sequence number.
+pub fn bump_seq_epoch(app: &AppHandle) -> u64 {
+    let path = match config_path(app, "seq_epoch") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve seq epoch path: {}", e);
+            return 0;
+        }
+    };
+    let previous = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    let epoch = previous.saturating_add(1);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(&path, epoch.to_string()) {
+        tracing::error!("Failed to write seq epoch file: {}", e);
+    }
+    epoch
+}
+
 /// Delete the legacy `cluster_key.bin` file from earlier versions. v0.3+
 /// no longer treats the cluster key as a secret (mTLS replaces its role);
 /// the file is wiped on first boot of the new build to avoid leaving a
 /// stale 32-byte secret on disk.
 pub fn wipe_legacy_cluster_key(app: &AppHandle) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("cluster_key.bin", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "cluster_key.bin") {
         Ok(p) => p,
         Err(_) => return,
     };
@@ -194,8 +222,7 @@ pub fn wipe_legacy_cluster_key(app: &AppHandle) {
 }
 
 pub fn load_known_peers(app: &AppHandle) -> HashMap<String, Peer> {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("known_peers.json", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "known_peers.json") {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Failed to resolve config path: {}", e);
@@ -226,8 +253,7 @@ pub fn load_known_peers(app: &AppHandle) -> HashMap<String, Peer> {
 }
 
 pub fn save_known_peers(app: &AppHandle, peers: &HashMap<String, Peer>) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("known_peers.json", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "known_peers.json") {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Failed to resolve config path for saving: {}", e);
@@ -253,14 +279,243 @@ pub fn save_known_peers(app: &AppHandle, peers: &HashMap<String, Peer>) {
     }
 }
 
+/// Per-peer policies, keyed by device id. Kept out of `known_peers.json`
+/// because `Peer` records are shared with other cluster members.
+pub fn load_peer_policies(app: &AppHandle) -> HashMap<String, PeerPolicy> {
+    let path = match config_path(app, "peer_policies.json") {
+        Ok(p) => p,
+        Err(_) => return HashMap::new(),
+    };
+    if !path.exists() {
+        return HashMap::new();
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<HashMap<String, PeerPolicy>>(&content) {
+            Ok(policies) => policies,
+            Err(e) => {
+                tracing::error!("Failed to parse peer policies: {}", e);
+                HashMap::new()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read peer policies file: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+pub fn save_peer_policies(app: &AppHandle, policies: &HashMap<String, PeerPolicy>) {
+    let path = match config_path(app, "peer_policies.json") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve peer policies path: {}", e);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(policies) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                tracing::error!("Failed to write peer policies file: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize peer policies: {}", e),
+    }
+}
+
+/// Signed revocations for removed members (see revocation.rs). Persisted so
+/// a removal survives restarts and keeps being gossiped.
+pub fn load_revocations(app: &AppHandle) -> Vec<Revocation> {
+    let path = match config_path(app, "revocations.json") {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    if !path.exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<Vec<Revocation>>(&content) {
+            Ok(revocations) => revocations,
+            Err(e) => {
+                tracing::error!("Failed to parse revocations: {}", e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read revocations file: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_revocations(app: &AppHandle, revocations: &[Revocation]) {
+    let path = match config_path(app, "revocations.json") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve revocations path: {}", e);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(revocations) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                tracing::error!("Failed to write revocations file: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize revocations: {}", e),
+    }
+}
+
+/// Signed restrictions on devices that joined with a limited invite token
+/// (see pairing/tokens.rs). Persisted so they keep being gossiped.
+pub fn load_join_restrictions(app: &AppHandle) -> Vec<JoinRestriction> {
+    let path = match config_path(app, "join_restrictions.json") {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    if !path.exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<Vec<JoinRestriction>>(&content) {
+            Ok(restrictions) => restrictions,
+            Err(e) => {
+                tracing::error!("Failed to parse join restrictions: {}", e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read join restrictions file: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_join_restrictions(app: &AppHandle, restrictions: &[JoinRestriction]) {
+    let path = match config_path(app, "join_restrictions.json") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve join restrictions path: {}", e);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(restrictions) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                tracing::error!("Failed to write join restrictions file: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize join restrictions: {}", e),
+    }
+}
+
+/// Invite tokens this device has minted (see pairing/tokens.rs). Holds
+/// pairing secrets, so the file is owner-only like `network_pin`.
+pub fn load_invite_tokens(app: &AppHandle) -> Vec<InviteToken> {
+    let path = match config_path(app, "invite_tokens.json") {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    if !path.exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<Vec<InviteToken>>(&content) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                tracing::error!("Failed to parse invite tokens: {}", e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read invite tokens file: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_invite_tokens(app: &AppHandle, tokens: &[InviteToken]) {
+    let path = match config_path(app, "invite_tokens.json") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve invite tokens path: {}", e);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(tokens) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                tracing::error!("Failed to write invite tokens file: {}", e);
+            } else {
+                set_owner_only(&path);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize invite tokens: {}", e),
+    }
+}
+
+/// Additional cluster memberships (see clusters.rs). Each carries that
+/// cluster's PIN, so the file is owner-only like `network_pin`.
+pub fn load_cluster_contexts(app: &AppHandle) -> Vec<ClusterContext> {
+    let path = match config_path(app, "clusters.json") {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+    if !path.exists() {
+        return Vec::new();
+    }
+    match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<Vec<ClusterContext>>(&content) {
+            Ok(contexts) => contexts,
+            Err(e) => {
+                tracing::error!("Failed to parse cluster contexts: {}", e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read cluster contexts file: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_cluster_contexts(app: &AppHandle, contexts: &[ClusterContext]) {
+    let path = match config_path(app, "clusters.json") {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to resolve cluster contexts path: {}", e);
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(contexts) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                tracing::error!("Failed to write cluster contexts file: {}", e);
+            } else {
+                set_owner_only(&path);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize cluster contexts: {}", e),
+    }
+}
+
 pub fn load_device_cert(app: &AppHandle) -> Option<(Vec<u8>, Vec<u8>)> {
-    let path_resolver = app.path();
-    let cert_path = path_resolver
-        .resolve("device_cert.der", BaseDirectory::AppConfig)
-        .ok()?;
-    let key_path = path_resolver
-        .resolve("device_key.der", BaseDirectory::AppConfig)
-        .ok()?;
+    let cert_path = config_path(app, "device_cert.der").ok()?;
+    let key_path = config_path(app, "device_key.der").ok()?;
 
     if !cert_path.exists() || !key_path.exists() {
         return None;
@@ -276,15 +531,14 @@ pub fn load_device_cert(app: &AppHandle) -> Option<(Vec<u8>, Vec<u8>)> {
 }
 
 pub fn save_device_cert(app: &AppHandle, cert_der: &[u8], key_der: &[u8]) {
-    let path_resolver = app.path();
-    let cert_path = match path_resolver.resolve("device_cert.der", BaseDirectory::AppConfig) {
+    let cert_path = match config_path(app, "device_cert.der") {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Failed to resolve device cert path: {}", e);
             return;
         }
     };
-    let key_path = match path_resolver.resolve("device_key.der", BaseDirectory::AppConfig) {
+    let key_path = match config_path(app, "device_key.der") {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Failed to resolve device key path: {}", e);
@@ -314,9 +568,8 @@ pub fn save_device_cert(app: &AppHandle, cert_der: &[u8], key_der: &[u8]) {
 /// fixed — `device_key.der` in particular is written only at first launch and
 /// never rewritten, so the write-path hardening alone would never reach it.
 pub fn harden_secret_files(app: &AppHandle) {
-    let path_resolver = app.path();
     for name in ["device_key.der", "network_pin"] {
-        if let Ok(path) = path_resolver.resolve(name, BaseDirectory::AppConfig) {
+        if let Ok(path) = config_path(app, name) {
             if path.exists() {
                 set_owner_only(&path);
             }
@@ -325,8 +578,7 @@ pub fn harden_secret_files(app: &AppHandle) {
 }
 
 pub fn load_device_id(app: &AppHandle) -> String {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("device_id", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "device_id") {
         Ok(p) => p,
         Err(_) => return String::new(),
     };
@@ -339,8 +591,7 @@ pub fn load_device_id(app: &AppHandle) -> String {
 }
 
 pub fn save_device_id(app: &AppHandle, id: &str) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("device_id", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "device_id") {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Failed to resolve device_id path: {}", e);
@@ -367,8 +618,7 @@ fn generate_pin() -> String {
 }
 
 pub fn load_network_pin(app: &AppHandle) -> String {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_pin", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_pin") {
         Ok(p) => p,
         Err(_) => return String::from("000000"),
     };
@@ -398,8 +648,7 @@ pub fn load_network_pin(app: &AppHandle) -> String {
 }
 
 pub fn save_network_pin(app: &AppHandle, pin: &str) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("network_pin", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "network_pin") {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Failed to resolve network_pin path: {}", e);
@@ -447,7 +696,7 @@ pub fn establish_network_pin(app: &AppHandle, mode: &str) -> String {
         return load_network_pin(app);
     }
     // Auto mode: delete any stored PIN, go ephemeral.
-    if let Ok(path) = app.path().resolve("network_pin", BaseDirectory::AppConfig) {
+    if let Ok(path) = config_path(app, "network_pin") {
         if path.exists() {
             let _ = fs::remove_file(path);
         }
@@ -457,7 +706,6 @@ pub fn establish_network_pin(app: &AppHandle, mode: &str) -> String {
 
 // Helper to reset network state (Self-Destruct/Kick)
 pub fn reset_network_state(app: &AppHandle) {
-    let path_resolver = app.path();
     // Include the actual filenames used by load/save
     let config_files = [
         "cluster_id",
@@ -465,10 +713,15 @@ pub fn reset_network_state(app: &AppHandle) {
         "network_name",
         "network_pin",
         "known_peers.json",
+        "peer_policies.json",
+        "revocations.json",
+        "invite_tokens.json",
+        "join_restrictions.json",
+        "clusters.json",
     ];
 
     for filename in config_files {
-        match path_resolver.resolve(filename, BaseDirectory::AppConfig) {
+        match config_path(app, filename) {
             Ok(path) => {
                 if path.exists() {
                     let _ = fs::remove_file(path);
@@ -484,8 +737,7 @@ pub fn reset_network_state(app: &AppHandle) {
 /// PIN is handled separately by `establish_network_pin` so its persistence
 /// follows the cluster mode (ephemeral in auto — issue 4).
 pub fn regenerate_network_name(app: &AppHandle) -> String {
-    let path_resolver = app.path();
-    if let Ok(path) = path_resolver.resolve("network_name", BaseDirectory::AppConfig) {
+    if let Ok(path) = config_path(app, "network_name") {
         if path.exists() {
             let _ = fs::remove_file(path);
         }
@@ -539,7 +791,7 @@ pub struct AppSettings {
     pub pairing_debug_logs: bool,
     /// User-controlled pause for the SPAKE pairing listener. When `false`,
     /// inbound TCP pairing connections are dropped immediately at the accept
-    /// loop, alongside the existing `pairing_locked_out` brute-force defence.
+    /// loop, alongside the per-source pairing rate limiter (pairing/ratelimit.rs).
     /// Surfaced in the UI as a header-bar toggle (issue #16).
     #[serde(default = "default_pairing_accept_enabled")]
     pub pairing_accept_enabled: bool,
@@ -551,11 +803,99 @@ pub struct AppSettings {
     /// (browsing/discovery of others stays active). Default-on.
     #[serde(default = "default_true")]
     pub mdns_advertising: bool,
+    /// Port for QUIC and pairing (UDP and TCP). 0 picks any free port.
+    /// Read at startup only.
+    #[serde(default = "default_listen_port")]
+    pub listen_port: u16,
+    /// How many ports above `listen_port` to try, in order, when it is
+    /// taken (another program, or a second instance on this host).
+    #[serde(default = "default_listen_port_range")]
+    pub listen_port_range: u16,
+    /// Interfaces left out of the mDNS record and the address list we
+    /// gossip. Each rule is an interface-name pattern (`*` wildcard,
+    /// case-insensitive) or a CIDR block. Defaults skip container, VM and
+    /// bridge interfaces (`net_util::DEFAULT_INTERFACE_EXCLUDE`).
+    #[serde(default = "default_interface_exclude")]
+    pub interface_exclude: Vec<String>,
+    /// Fallback discovery for networks that drop multicast: broadcast a
+    /// UDP beacon with our mDNS fields and listen for others' (beacon.rs).
+    /// Off by default.
+    #[serde(default)]
+    pub beacon_enabled: bool,
+    /// UDP port the beacon is sent to and received on.
+    #[serde(default = "default_beacon_port")]
+    pub beacon_port: u16,
+    /// Addresses (`ip` or `ip:port`) that get our beacon directly, for
+    /// networks that drop broadcast too.
+    #[serde(default)]
+    pub beacon_targets: Vec<String>,
+    /// CIDR ranges probed every few minutes for devices mDNS can't find.
+    #[serde(default)]
+    pub sweep_ranges: Vec<String>,
+    /// Carry messages and file streams between members that can't reach
+    /// each other directly (relay.rs). Default-on.
+    #[serde(default = "default_true")]
+    pub allow_relay: bool,
+    /// Per-network rules that switch sync and advertising (netpolicy.rs).
+    /// The first rule matching the current network applies.
+    #[serde(default)]
+    pub network_policies: Vec<NetworkPolicy>,
+    /// What the applied rule changed, undone when we leave its network.
+    /// Owned by netpolicy.rs; a settings save from the UI keeps it.
+    #[serde(default)]
+    pub network_policy_active: Option<ActivePolicy>,
+    /// Times when sync pauses or goes manual (quiet.rs).
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietSchedule>,
+    /// Hold notifications while the desktop's Do Not Disturb is on and show
+    /// them as one batch afterwards. Linux only. Default-on.
+    #[serde(default = "default_true")]
+    pub follow_dnd: bool,
+    /// Leave devices whose screen is locked out of clipboard broadcasts
+    /// (status.rs). Off by default.
+    #[serde(default)]
+    pub skip_locked_peers: bool,
+    /// Treat a cut of files as a move: once one device has downloaded every
+    /// file and confirmed it, the originals go (moves.rs). Off by default.
+    #[serde(default)]
+    pub move_cut_files: bool,
+    /// Moved originals go to the Trash rather than being deleted. Linux and
+    /// macOS only; elsewhere they are left in place.
+    #[serde(default = "default_true")]
+    pub trash_moved_files: bool,
     /// Max bytes of re-callable clipboard content (text + images) the History
     /// content store retains, across RAM + disk tiers. File transfers don't
     /// count. Default 200 MB; oldest entries evict first when exceeded.
     #[serde(default = "default_history_store_max_bytes")]
     pub history_store_max_bytes: u64,
+    /// Seconds an ephemeral clipboard item stays on a receiver's clipboard
+    /// before it is cleared (or the previous text restored). Applies to every
+    /// ephemeral send: `ephemeral_by_default`, detected sensitive content and
+    /// the "send ephemeral" shortcut.
+    #[serde(default = "default_ephemeral_ttl_secs")]
+    pub ephemeral_ttl_secs: u64,
+    /// Mark every outgoing clipboard item as ephemeral. Off by default.
+    #[serde(default)]
+    pub ephemeral_by_default: bool,
+    /// Mark outgoing items that look like secrets (private keys, API tokens,
+    /// password-manager copies) as ephemeral. See
+    /// `clipboard::common::looks_sensitive`.
+    #[serde(default = "default_true")]
+    pub ephemeral_sensitive_content: bool,
+    /// Global shortcut for a one-off ephemeral send. With auto-send off it
+    /// broadcasts the current clipboard text immediately; with auto-send on it
+    /// arms the next local copy instead (the current one has already gone out).
+    #[serde(default)]
+    pub shortcut_send_ephemeral: Option<String>,
+    /// Only commit a new pair once the user has confirmed on both devices
+    /// that the short authentication string matches (pairing/sas.rs).
+    /// Defends clusters with weak or widely shared PINs. Off by default.
+    #[serde(default)]
+    pub require_sas_confirmation: bool,
+    /// Which of our clusters copies go to and are accepted from, keyed by
+    /// cluster id. A cluster with no entry does both (clusters.rs).
+    #[serde(default)]
+    pub cluster_routing: HashMap<String, ClusterRoute>,
 }
 
 fn default_pairing_accept_enabled() -> bool {
@@ -566,10 +906,30 @@ fn default_true() -> bool {
     true
 }
 
+pub(crate) fn default_interface_exclude() -> Vec<String> {
+    crate::net_util::DEFAULT_INTERFACE_EXCLUDE.iter().map(|r| r.to_string()).collect()
+}
+
+fn default_listen_port() -> u16 {
+    crate::net_util::DEFAULT_PORT
+}
+
+fn default_listen_port_range() -> u16 {
+    10
+}
+
+fn default_beacon_port() -> u16 {
+    crate::beacon::DEFAULT_BEACON_PORT
+}
+
 fn default_history_store_max_bytes() -> u64 {
     200 * 1024 * 1024
 }
 
+fn default_ephemeral_ttl_secs() -> u64 {
+    30
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -590,14 +950,34 @@ impl Default for AppSettings {
             pairing_accept_enabled: true,
             configure_firewall: true,
             mdns_advertising: true,
+            listen_port: default_listen_port(),
+            listen_port_range: default_listen_port_range(),
+            interface_exclude: default_interface_exclude(),
+            beacon_enabled: false,
+            beacon_port: default_beacon_port(),
+            beacon_targets: Vec::new(),
+            sweep_ranges: Vec::new(),
+            allow_relay: true,
+            network_policies: Vec::new(),
+            network_policy_active: None,
+            quiet_hours: Vec::new(),
+            follow_dnd: true,
+            skip_locked_peers: false,
+            move_cut_files: false,
+            trash_moved_files: true,
             history_store_max_bytes: 200 * 1024 * 1024,
+            ephemeral_ttl_secs: default_ephemeral_ttl_secs(),
+            ephemeral_by_default: false,
+            ephemeral_sensitive_content: true,
+            shortcut_send_ephemeral: None,
+            require_sas_confirmation: false,
+            cluster_routing: HashMap::new(),
         }
     }
 }
 
 pub fn load_settings(app: &AppHandle) -> AppSettings {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("settings.json", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "settings.json") {
         Ok(p) => p,
         Err(_) => return AppSettings::default(),
     };
@@ -613,8 +993,7 @@ pub fn load_settings(app: &AppHandle) -> AppSettings {
 }
 
 pub fn save_settings(app: &AppHandle, settings: &AppSettings) {
-    let path_resolver = app.path();
-    let path = match path_resolver.resolve("settings.json", BaseDirectory::AppConfig) {
+    let path = match config_path(app, "settings.json") {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Failed to resolve settings path: {}", e);
@@ -676,6 +1055,9 @@ mod settings_tests {
         let s: AppSettings = serde_json::from_str(json).unwrap();
         assert!(s.configure_firewall);
         assert!(s.mdns_advertising);
+        assert!(s.ephemeral_sensitive_content);
+        assert!(!s.ephemeral_by_default);
+        assert_eq!(s.ephemeral_ttl_secs, 30);
     }
 
     #[test]
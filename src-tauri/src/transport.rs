@@ -4,6 +4,7 @@ use sha2::{Digest, Sha256};
 use std::error::Error;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 
@@ -21,13 +22,40 @@ pub type FingerprintResolver = Arc<dyn Fn(SocketAddr) -> Vec<Vec<u8>> + Send + S
 /// know which peer is connecting, only that it must be one of ours.
 pub type KnownFingerprintsResolver = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
 
+/// Expands the address a caller wants to reach into every address we know
+/// for the same peer, best first (see `paths::candidates_for`). Without one,
+/// the address is used as given.
+pub type PathResolver = Arc<dyn Fn(SocketAddr) -> Vec<SocketAddr> + Send + Sync>;
+
+/// Told the outcome of every outbound connection attempt: the address tried
+/// and the connection's RTT, or None if it failed.
+pub type PathReporter = Arc<dyn Fn(SocketAddr, Option<Duration>) + Send + Sync>;
+
+/// Consulted when a message can't be delivered directly: returns a member
+/// that can reach the destination and the sealed `Message::Relay` to hand
+/// it (see `relay::route`), or None if there is no such member.
+pub type RelayRouter = Arc<dyn Fn(&Transport, SocketAddr, &[u8]) -> Option<(SocketAddr, Vec<u8>)> + Send + Sync>;
+
+/// How long a connection attempt gets before the next candidate address
+/// joins the race.
+pub const PATH_RACE_STAGGER: Duration = Duration::from_millis(250);
+
+/// The device's current TLS cert + key. Shared behind a mutex by every
+/// `Transport` clone so a rotation is picked up by all of them at once.
+struct LocalIdentity {
+    cert_der: Vec<u8>,
+    key_der: Vec<u8>,
+}
+
 #[derive(Clone)]
 pub struct Transport {
     pub endpoint: Endpoint,
-    local_cert_der: Vec<u8>,
-    local_key_der: Vec<u8>,
+    local_identity: Arc<Mutex<LocalIdentity>>,
     fingerprint_resolver: Arc<Mutex<Option<FingerprintResolver>>>,
     known_fingerprints_resolver: Arc<Mutex<Option<KnownFingerprintsResolver>>>,
+    path_resolver: Arc<Mutex<Option<PathResolver>>>,
+    path_reporter: Arc<Mutex<Option<PathReporter>>>,
+    relay_router: Arc<Mutex<Option<RelayRouter>>>,
 }
 
 impl Transport {
@@ -43,22 +71,63 @@ impl Transport {
             known_fingerprints_resolver.clone(),
         )?;
 
-        let addr = SocketAddr::from(([0, 0, 0, 0], port));
         // No `set_default_client_config` — every outbound connection goes
         // through `connect_with(...)` with a per-peer pinned config. Calling
         // `connect()` without an explicit config will error, which is what
         // we want: there is no safe default for an mTLS-only endpoint.
-        let endpoint = Endpoint::server(server_config, addr)?;
+        let socket = bind_dual_stack(socket2::Type::DGRAM, port)?;
+        let runtime = quinn::default_runtime().ok_or("no async runtime for the QUIC endpoint")?;
+        let endpoint = Endpoint::new(
+            quinn::EndpointConfig::default(),
+            Some(server_config),
+            socket.into(),
+            runtime,
+        )?;
 
         Ok(Self {
             endpoint,
-            local_cert_der: cert_der,
-            local_key_der: key_der,
+            local_identity: Arc::new(Mutex::new(LocalIdentity { cert_der, key_der })),
             fingerprint_resolver: Arc::new(Mutex::new(None)),
             known_fingerprints_resolver,
+            path_resolver: Arc::new(Mutex::new(None)),
+            path_reporter: Arc::new(Mutex::new(None)),
+            relay_router: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Bind on the first of `listen_port_candidates(first, range, reserved)`
+    /// where both the QUIC (UDP) and pairing (TCP) sockets are free, so a
+    /// second instance or another program on our port doesn't stop us
+    /// starting.
+    pub fn bind_in_range(
+        first: u16,
+        range: u16,
+        reserved: u16,
+        cert_der: Vec<u8>,
+        key_der: Vec<u8>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut last_err: Option<Box<dyn Error>> = None;
+        for port in listen_port_candidates(first, range, reserved) {
+            // Port 0 picks a UDP port at random; the pairing listener then
+            // takes its chances on the same TCP number.
+            if port != 0 {
+                if let Err(e) = bind_dual_stack(socket2::Type::STREAM, port) {
+                    tracing::warn!("Port {} unavailable (TCP: {}), trying the next one", port, e);
+                    last_err = Some(e.into());
+                    continue;
+                }
+            }
+            match Self::new(port, cert_der.clone(), key_der.clone()) {
+                Ok(transport) => return Ok(transport),
+                Err(e) => {
+                    tracing::warn!("Port {} unavailable (UDP: {}), trying the next one", port, e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| "no port to listen on".into()))
+    }
+
     /// Install (or replace) the resolver that maps a peer address to its pinned
     /// SHA-256 fingerprint. Call this once known_peers is loaded.
     pub fn set_fingerprint_resolver(&self, resolver: FingerprintResolver) {
@@ -72,9 +141,49 @@ impl Transport {
         *self.known_fingerprints_resolver.lock().unwrap() = Some(resolver);
     }
 
+    /// Install (or replace) the candidate-address lookup and the outcome
+    /// callback used to pick and rank paths to multi-homed peers.
+    pub fn set_path_resolver(&self, resolver: PathResolver, reporter: PathReporter) {
+        *self.path_resolver.lock().unwrap() = Some(resolver);
+        *self.path_reporter.lock().unwrap() = Some(reporter);
+    }
+
+    /// Install (or replace) the fallback used when a peer can't be reached
+    /// directly.
+    pub fn set_relay_router(&self, router: RelayRouter) {
+        *self.relay_router.lock().unwrap() = Some(router);
+    }
+
     /// SHA-256 of the local cert DER, used as the device's public TLS identity.
     pub fn local_fingerprint(&self) -> Vec<u8> {
-        cert_fingerprint(&self.local_cert_der)
+        cert_fingerprint(&self.local_identity.lock().unwrap().cert_der)
+    }
+
+    pub fn local_cert_der(&self) -> Vec<u8> {
+        self.local_identity.lock().unwrap().cert_der.clone()
+    }
+
+    /// Sign `message` with the current device key. Returns the TLS
+    /// `SignatureScheme` code alongside the signature so the verifier knows
+    /// which algorithm to use (see [`verify_cert_signature`]).
+    pub fn sign_with_local_key(&self, message: &[u8]) -> Result<(u16, Vec<u8>), String> {
+        let key_der = self.local_identity.lock().unwrap().key_der.clone();
+        sign_with_key(&key_der, message)
+    }
+
+    /// Switch to a new cert + key without rebinding the socket. New inbound
+    /// handshakes present the new cert immediately; outbound connections pick
+    /// it up on their next `connect_with`. Connections already established
+    /// under the old cert are left alone and drain naturally.
+    pub fn rotate_identity(&self, cert_der: Vec<u8>, key_der: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let server_config = configure_server(
+            cert_der.clone(),
+            key_der.clone(),
+            self.known_fingerprints_resolver.clone(),
+        )?;
+        self.endpoint.set_server_config(Some(server_config));
+        *self.local_identity.lock().unwrap() = LocalIdentity { cert_der, key_der };
+        Ok(())
     }
 
     fn resolve_fingerprint(&self, addr: SocketAddr) -> Vec<Vec<u8>> {
@@ -91,12 +200,11 @@ impl Transport {
         if fps.is_empty() {
             return Err(format!("no pinned fingerprint for {addr}; peer must re-pair").into());
         }
-        configure_client(
-            vec![b"clustercut-transport".to_vec()],
-            fps,
-            self.local_cert_der.clone(),
-            self.local_key_der.clone(),
-        )
+        let (cert_der, key_der) = {
+            let identity = self.local_identity.lock().unwrap();
+            (identity.cert_der.clone(), identity.key_der.clone())
+        };
+        configure_client(vec![b"clustercut-transport".to_vec()], fps, cert_der, key_der)
         .map_err(|e| -> Box<dyn Error + Send + Sync> { e.to_string().into() })
     }
 
@@ -105,19 +213,122 @@ impl Transport {
         if fps.is_empty() {
             return Err(format!("no pinned fingerprint for {addr}; peer must re-pair").into());
         }
-        configure_client(
-            vec![b"clustercut-file".to_vec()],
-            fps,
-            self.local_cert_der.clone(),
-            self.local_key_der.clone(),
-        )
+        let (cert_der, key_der) = {
+            let identity = self.local_identity.lock().unwrap();
+            (identity.cert_der.clone(), identity.key_der.clone())
+        };
+        configure_client(vec![b"clustercut-file".to_vec()], fps, cert_der, key_der)
         .map_err(|e| -> Box<dyn Error + Send + Sync> { e.to_string().into() })
     }
 
+    fn candidates_for(&self, addr: SocketAddr) -> Vec<SocketAddr> {
+        let candidates = self
+            .path_resolver
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|r| r(addr))
+            .unwrap_or_default();
+        if candidates.is_empty() {
+            vec![addr]
+        } else {
+            candidates
+        }
+    }
+
+    fn report_path(&self, addr: SocketAddr, rtt: Option<Duration>) {
+        let reporter = self.path_reporter.lock().unwrap().clone();
+        if let Some(reporter) = reporter {
+            reporter(addr, rtt);
+        }
+    }
+
+    /// Connect to the peer at `addr` over whichever of its candidate
+    /// addresses answers first. Candidates are started best first, each
+    /// `PATH_RACE_STAGGER` after the previous one or as soon as it fails, so
+    /// a healthy preferred path costs nothing extra and a dead one fails over
+    /// without waiting out its timeout. All candidates share `config`: they
+    /// are the same device, pinned to the same fingerprints.
+    async fn connect(
+        &self,
+        addr: SocketAddr,
+        config: ClientConfig,
+    ) -> Result<quinn::Connection, Box<dyn Error + Send + Sync>> {
+        let mut pending = self.candidates_for(addr).into_iter();
+        let mut attempts = tokio::task::JoinSet::new();
+        let mut last_err: Option<Box<dyn Error + Send + Sync>> = None;
+        loop {
+            if let Some(candidate) = pending.next() {
+                match self.endpoint.connect_with(config.clone(), candidate, "clustercut") {
+                    Ok(connecting) => {
+                        attempts.spawn(async move { (candidate, connecting.await) });
+                    }
+                    Err(e) => {
+                        self.report_path(candidate, None);
+                        last_err = Some(e.into());
+                        continue;
+                    }
+                }
+            }
+            if attempts.is_empty() {
+                return Err(last_err.unwrap_or_else(|| format!("no address to reach {addr}").into()));
+            }
+            let finished = if !pending.as_slice().is_empty() {
+                match tokio::time::timeout(PATH_RACE_STAGGER, attempts.join_next()).await {
+                    Ok(finished) => finished,
+                    // Still connecting: start the next candidate alongside.
+                    Err(_) => continue,
+                }
+            } else {
+                attempts.join_next().await
+            };
+            match finished {
+                Some(Ok((candidate, Ok(connection)))) => {
+                    self.report_path(candidate, Some(connection.rtt()));
+                    if candidate != addr {
+                        tracing::debug!("connect: reached {} via {}", addr, candidate);
+                    }
+                    // Dropping the set aborts the attempts still in flight.
+                    return Ok(connection);
+                }
+                Some(Ok((candidate, Err(e)))) => {
+                    tracing::debug!("connect: {} failed: {}", candidate, e);
+                    self.report_path(candidate, None);
+                    last_err = Some(e.into());
+                }
+                Some(Err(e)) => last_err = Some(e.into()),
+                None => {}
+            }
+        }
+    }
+
+    /// Deliver `data` to `addr`, through a relay if the peer can't be
+    /// reached directly and the relay router knows a member that can.
     pub async fn send_message(
         &self,
         addr: SocketAddr,
         data: &[u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let direct_err = match self.send_message_direct(addr, data).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        let router = self.relay_router.lock().unwrap().clone();
+        let Some((relay, wrapped)) = router.and_then(|route| route(self, addr, data)) else {
+            return Err(direct_err);
+        };
+        tracing::info!("send_message: {} unreachable ({}), relaying via {}", addr, direct_err, relay);
+        self.send_message_direct(relay, &wrapped)
+            .await
+            .map_err(|e| format!("{} (relay via {} also failed: {})", direct_err, relay, e).into())
+    }
+
+    /// Deliver `data` to `addr` itself, never through a relay. Relays
+    /// forward with this so a message can't bounce between members.
+    pub async fn send_message_direct(
+        &self,
+        addr: SocketAddr,
+        data: &[u8],
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let config = self.transport_config_for(addr)?;
         self.send_message_with_config(addr, data, config).await
@@ -135,10 +346,7 @@ impl Transport {
             tracing::info!("send_message: connecting to {} for {} byte payload", addr, len);
         }
 
-        let connection = self
-            .endpoint
-            .connect_with(config, addr, "clustercut")?
-            .await?;
+        let connection = self.connect(addr, config).await?;
         if large {
             tracing::info!("send_message: connection established to {}", addr);
         }
@@ -200,18 +408,18 @@ impl Transport {
         addr: SocketAddr,
     ) -> Result<(quinn::Connection, quinn::SendStream), Box<dyn Error + Send + Sync>> {
         let config = self.file_config_for(addr)?;
-        let connection = self
-            .endpoint
-            .connect_with(config, addr, "clustercut")?
-            .await?;
+        let connection = self.connect(addr, config).await?;
         // Use Uni stream for file transfer (Sender -> Receiver)
         let send = connection.open_uni().await?;
         Ok((connection, send))
     }
 
+    /// Accept connections and hand each message, with the fingerprint of the
+    /// certificate its connection authenticated with, to `on_receive_message`,
+    /// and each file stream to `on_receive_file`.
     pub fn start_listening<F, G, H>(&self, on_receive_message: F, on_receive_file: G, on_conn_event: H)
     where
-        F: Fn(Vec<u8>, SocketAddr) + Send + Sync + 'static + Clone,
+        F: Fn(Vec<u8>, SocketAddr, Option<Vec<u8>>) + Send + Sync + 'static + Clone,
         G: Fn(quinn::RecvStream, SocketAddr) + Send + Sync + 'static + Clone,
         H: Fn(&str, SocketAddr, Option<String>) + Send + Sync + 'static + Clone,
     {
@@ -223,11 +431,11 @@ impl Transport {
                 // consumes `conn`. Without this, a failed handshake logs an
                 // anonymous "rejected" line and you have no way to tell
                 // which host on the LAN tried to reach you.
-                let remote_for_log = conn.remote_address();
+                let remote_for_log = canonical_addr(conn.remote_address());
                 let connection = conn.await;
                 match connection {
                     Ok(conn) => {
-                        let remote_addr = conn.remote_address();
+                        let remote_addr = canonical_addr(conn.remote_address());
                         on_conn_event("connect", remote_addr, None);
                         // tracing::info!("Transport established connection with {}", remote_addr);
 
@@ -275,6 +483,7 @@ impl Transport {
                             // Standard Message Handler (clustercut-transport)
                             let on_receive_message = on_receive_message.clone();
                             let on_conn_event = on_conn_event.clone();
+                            let fingerprint = peer_fingerprint(&conn);
                             tauri::async_runtime::spawn(async move {
                                 // tracing::debug!("Handling MESSAGE connection from {}", remote_addr);
                                 loop {
@@ -290,7 +499,7 @@ impl Transport {
                                             match recv.read_to_end(MESSAGE_BYTE_CAP).await {
                                                 Ok(buf) => {
                                                     if !buf.is_empty() {
-                                                        on_receive_message(buf, remote_addr);
+                                                        on_receive_message(buf, remote_addr, fingerprint.clone());
                                                     }
                                                 }
                                                 Err(quinn::ReadToEndError::TooLong) => {
@@ -338,6 +547,66 @@ impl Transport {
     }
 }
 
+/// Ports to listen on, in order of preference: `first` and the `range`
+/// ports above it, then 0 (any free port). `first` 0 means any port.
+/// `reserved` (the beacon port) is never a candidate: the beacon socket sets
+/// `SO_REUSEADDR`, so QUIC could share it and datagrams would be split
+/// between the two.
+pub fn listen_port_candidates(first: u16, range: u16, reserved: u16) -> Vec<u16> {
+    if first == 0 {
+        return vec![0];
+    }
+    let mut ports: Vec<u16> = (first..=first.saturating_add(range)).filter(|&p| p != reserved).collect();
+    ports.push(0);
+    ports
+}
+
+/// Bind a socket on `[::]:port` that also accepts IPv4 (as v4-mapped
+/// addresses), so peers reach us over either family on the same port. Hosts
+/// with IPv6 disabled fall back to `0.0.0.0:port`. `socket2` is needed
+/// because the OS default for `IPV6_V6ONLY` differs (off on Linux, on on
+/// Windows) and std can't change it.
+fn bind_dual_stack(ty: socket2::Type, port: u16) -> std::io::Result<socket2::Socket> {
+    use socket2::{Domain, Socket};
+    let open = |domain: Domain| -> std::io::Result<Socket> {
+        let socket = Socket::new(domain, ty, None)?;
+        // What std's TcpListener::bind does on Unix, so a restart can
+        // rebind while old connections sit in TIME_WAIT.
+        #[cfg(unix)]
+        if ty == socket2::Type::STREAM {
+            socket.set_reuse_address(true)?;
+        }
+        Ok(socket)
+    };
+    let v6 = open(Domain::IPV6).and_then(|socket| {
+        socket.set_only_v6(false)?;
+        socket.bind(&SocketAddr::from((std::net::Ipv6Addr::UNSPECIFIED, port)).into())?;
+        Ok(socket)
+    });
+    match v6 {
+        Ok(socket) => Ok(socket),
+        Err(e) => {
+            tracing::warn!("Dual-stack bind on port {} failed ({}); listening on IPv4 only", port, e);
+            let socket = open(Domain::IPV4)?;
+            socket.bind(&SocketAddr::from(([0, 0, 0, 0], port)).into())?;
+            Ok(socket)
+        }
+    }
+}
+
+/// The address a dual-stack socket reports for a peer, in the form the rest
+/// of the app keys peers by: IPv4-mapped IPv6 (`::ffff:a.b.c.d`) back to
+/// plain IPv4. Native IPv6 addresses keep their scope id.
+pub fn canonical_addr(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(v4) => SocketAddr::new(v4.into(), v6.port()),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
+
 pub fn generate_self_signed_cert() -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
     // Register BOTH protocols
     let cert = generate_simple_self_signed(vec![
@@ -354,6 +623,67 @@ pub fn cert_fingerprint(cert_der: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+/// Fingerprint of the certificate the far end of `conn` presented. Our
+/// verifiers only let pinned certificates through, so this names the
+/// device that sent whatever arrives on the connection, which its address
+/// doesn't: addresses are shared behind NAT and reused.
+fn peer_fingerprint(conn: &quinn::Connection) -> Option<Vec<u8>> {
+    let certs = conn
+        .peer_identity()?
+        .downcast::<Vec<rustls::pki_types::CertificateDer<'static>>>()
+        .ok()?;
+    certs.first().map(|c| cert_fingerprint(c))
+}
+
+/// Schemes we'll sign application-level messages with. rcgen's default key
+/// is ECDSA P-256; the rest cover keys from other generators.
+const APP_SIGNATURE_SCHEMES: &[rustls::SignatureScheme] = &[
+    rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
+    rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
+    rustls::SignatureScheme::ED25519,
+    rustls::SignatureScheme::RSA_PSS_SHA256,
+];
+
+/// Sign `message` with a PKCS#8 device key through the installed rustls
+/// crypto provider. Returns `(SignatureScheme code, signature)`.
+pub fn sign_with_key(key_der: &[u8], message: &[u8]) -> Result<(u16, Vec<u8>), String> {
+    let provider = rustls::crypto::CryptoProvider::get_default()
+        .ok_or("no rustls crypto provider installed")?;
+    let key = rustls::pki_types::PrivateKeyDer::try_from(key_der.to_vec())
+        .map_err(|_| "Invalid private key".to_string())?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|e| e.to_string())?;
+    let signer = signing_key
+        .choose_scheme(APP_SIGNATURE_SCHEMES)
+        .ok_or("no supported signature scheme for device key")?;
+    let signature = signer.sign(message).map_err(|e| e.to_string())?;
+    Ok((u16::from(signer.scheme()), signature))
+}
+
+/// True if `signature` over `message` verifies under the public key in
+/// `cert_der`. Used for application-level proofs of key possession (e.g.
+/// `Message::KeyRotation`) where there's no TLS handshake to lean on.
+/// webpki parses the cert and checks that the key fits the scheme.
+pub fn verify_cert_signature(cert_der: &[u8], scheme: u16, message: &[u8], signature: &[u8]) -> bool {
+    let cert_der = rustls::pki_types::CertificateDer::from(cert_der);
+    let Ok(cert) = webpki::EndEntityCert::try_from(&cert_der) else {
+        return false;
+    };
+    let Some(provider) = rustls::crypto::CryptoProvider::get_default() else {
+        return false;
+    };
+    let scheme = rustls::SignatureScheme::from(scheme);
+    provider
+        .signature_verification_algorithms
+        .mapping
+        .iter()
+        .filter(|(s, _)| *s == scheme)
+        .flat_map(|(_, algs)| algs.iter())
+        .any(|alg| cert.verify_signature(*alg, message, signature).is_ok())
+}
+
 fn configure_server(
     cert_der: Vec<u8>,
     key_der: Vec<u8>,
@@ -700,10 +1030,11 @@ pub fn start_pairing_listener<F>(
 where
     F: Fn(TcpStream, SocketAddr) + Send + Sync + Clone + 'static,
 {
-    let bind_addr = SocketAddr::from(([0, 0, 0, 0], port));
-    let std_listener = std::net::TcpListener::bind(bind_addr)?;
+    let socket = bind_dual_stack(socket2::Type::STREAM, port)?;
+    socket.listen(128)?;
+    let std_listener: std::net::TcpListener = socket.into();
     std_listener.set_nonblocking(true)?;
-    tracing::info!("Pairing TCP listener bound on {}", bind_addr);
+    tracing::info!("Pairing TCP listener bound on {}", std_listener.local_addr()?);
 
     tauri::async_runtime::spawn(async move {
         let listener = match TcpListener::from_std(std_listener) {
@@ -716,6 +1047,7 @@ where
         loop {
             match listener.accept().await {
                 Ok((stream, peer_addr)) => {
+                    let peer_addr = canonical_addr(peer_addr);
                     tracing::debug!("Pairing TCP accept from {}", peer_addr);
                     handler.clone()(stream, peer_addr);
                 }
@@ -729,6 +1061,31 @@ where
     Ok(())
 }
 
+#[cfg(test)]
+mod addr_tests {
+    use super::{canonical_addr, listen_port_candidates};
+    use std::net::SocketAddr;
+
+    #[test]
+    fn listen_ports_fall_back_through_the_range_then_anywhere() {
+        assert_eq!(listen_port_candidates(4654, 2, 0), vec![4654, 4655, 4656, 0]);
+        assert_eq!(listen_port_candidates(4654, 0, 0), vec![4654, 0]);
+        assert_eq!(listen_port_candidates(65534, 5, 0), vec![65534, 65535, 0]);
+        assert_eq!(listen_port_candidates(0, 5, 0), vec![0]);
+        // The beacon port is skipped, even when configured as the first.
+        assert_eq!(listen_port_candidates(4654, 2, 4655), vec![4654, 4656, 0]);
+        assert_eq!(listen_port_candidates(4655, 1, 4655), vec![4656, 0]);
+    }
+
+    #[test]
+    fn mapped_v4_becomes_plain_v4_and_v6_is_untouched() {
+        let mapped: SocketAddr = "[::ffff:192.168.1.20]:4654".parse().unwrap();
+        assert_eq!(canonical_addr(mapped), "192.168.1.20:4654".parse::<SocketAddr>().unwrap());
+        let v6: SocketAddr = "[fe80::1%3]:4654".parse().unwrap();
+        assert_eq!(canonical_addr(v6), v6);
+    }
+}
+
 #[cfg(test)]
 mod fingerprint_tests {
     use super::fingerprint_in_set;
@@ -754,3 +1111,34 @@ mod fingerprint_tests {
         assert!(!fingerprint_in_set(&[0x01, 0x02, 0x03], &[]));
     }
 }
+
+#[cfg(test)]
+mod signature_tests {
+    use super::*;
+
+    fn provider() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    }
+
+    #[test]
+    fn signature_round_trips_through_cert() {
+        provider();
+        let (cert, key) = generate_self_signed_cert().unwrap();
+        let (scheme, sig) = sign_with_key(&key, b"hello").unwrap();
+        assert!(verify_cert_signature(&cert, scheme, b"hello", &sig));
+    }
+
+    #[test]
+    fn signature_rejects_tampering_and_other_certs() {
+        provider();
+        let (cert, key) = generate_self_signed_cert().unwrap();
+        let (other_cert, _) = generate_self_signed_cert().unwrap();
+        let (scheme, sig) = sign_with_key(&key, b"hello").unwrap();
+        assert!(!verify_cert_signature(&cert, scheme, b"hellp", &sig));
+        assert!(!verify_cert_signature(&other_cert, scheme, b"hello", &sig));
+        assert!(!verify_cert_signature(&cert[..cert.len() / 2], scheme, b"hello", &sig));
+        // A scheme that doesn't fit the cert's key type.
+        let ed25519 = u16::from(rustls::SignatureScheme::ED25519);
+        assert!(!verify_cert_signature(&cert, ed25519, b"hello", &sig));
+    }
+}
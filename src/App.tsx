@@ -15,11 +15,11 @@ import { DevicesView } from "./components/DevicesView";
 import { HistoryView } from "./components/HistoryView";
 import { SettingsView } from "./components/SettingsView";
 import { ManualSyncFAB, ManualSyncModal } from "./components/ManualSync";
-import { IncompatibleModal, ConnectionFailedModal, JoinModal, LeaveModal, AddRemoteModal, PortWarningModal } from "./components/Modals";
+import { IncompatibleModal, ConnectionFailedModal, JoinModal, LeaveModal, AddRemoteModal, PortWarningModal, SasModal } from "./components/Modals";
 import { LegacyPeerBanner, PairingLockoutBanner } from "./components/Banners";
 import type {
   Peer, View, NearbyNetwork, ClipboardBlobPreview, ClipboardFormatPreview,
-  HistoryItem, AppSettings,
+  HistoryItem, AppSettings, SasPrompt,
 } from "./types";
 import { blobPreviewFromPreview, formatsFromPayload } from "./lib/protocol";
 
@@ -62,10 +62,11 @@ export default function App() {
   // Those peers are unreachable under v0.3 and need to be re-paired.
   const [legacyPeers, setLegacyPeers] = useState<{ id: string; hostname: string }[]>([]);
 
-  // Pairing-lockout banner: surfaced when the backend's pairing listener
-  // tripped its global AEAD-failure threshold (WIRE-PROTOCOL-0.3.1 §H1).
-  // The listener refuses inbound pairing attempts until the user clicks
-  // "Re-enable pairing", which calls `rearm_pairing` on the backend.
+  // Pairing-lockout banner: surfaced when the pairing rate limiter hit its
+  // global failure ceiling (WIRE-PROTOCOL-0.3.1 §H1, ratelimit.rs). The
+  // listener refuses inbound pairing attempts until the pause lifts by itself
+  // (`pairing-rearmed`) or the user clicks "Re-enable pairing", which calls
+  // `rearm_pairing` on the backend to clear the limiter.
   const [pairingLockedOut, setPairingLockedOut] = useState(false);
 
   // Issue #16: user-controlled pause for inbound pairing. Persists across
@@ -109,6 +110,9 @@ export default function App() {
   // IP[:port] is passed straight to start_pairing as the address override
   // because no mDNS-discovered peer record exists yet.
   const [pairingPeerAddr, setPairingPeerAddr] = useState<string | null>(null);
+  // Set when the join was started from a `clustercut://pair` invite: the
+  // responder's certificate fingerprint, which start_pairing enforces.
+  const [pairingExpectedFp, setPairingExpectedFp] = useState<string | null>(null);
 
   const [leaveOpen, setLeaveOpen] = useState(false);
 
@@ -117,11 +121,13 @@ export default function App() {
   const [manualBusy, setManualBusy] = useState(false);
 
   const [joinError, setJoinError] = useState("");
+  const [sasPrompt, setSasPrompt] = useState<SasPrompt | null>(null);
   const [expandedNetworks, setExpandedNetworks] = useState<Set<string>>(new Set());
 
   /* Port Warning State */
   const [showPortWarning, setShowPortWarning] = useState(false);
   const [currentPort, setCurrentPort] = useState(4654);
+  const [configuredPort, setConfiguredPort] = useState(4654);
 
   // Manual Sync State
   // Manual Sync State
@@ -195,7 +201,10 @@ export default function App() {
       if (urlStr) {
         console.log("Found Deep Link URL:", urlStr);
         logToBackend("Deep Link Detected:", urlStr);
-        if (urlStr.includes("action/show") || urlStr.includes("action/download")) {
+        if (urlStr.startsWith("clustercut://pair")) {
+          startInviteFlow(urlStr);
+          setActiveView("devices");
+        } else if (urlStr.includes("action/show") || urlStr.includes("action/download")) {
           console.log("Action matched! Parsing view/action from URL...");
           logToBackend("Action matched, checking for view/action param.");
 
@@ -432,9 +441,9 @@ export default function App() {
       .then(setLegacyPeers)
       .catch(() => {});
 
-    // 2c. Pairing-lockout banner — initial fetch. The backend retains
-    // the locked-out state across the session (and across a UI reload
-    // within the same process lifetime), so we surface it on mount.
+    // 2c. Pairing-lockout banner — initial fetch. The backend keeps the
+    // pause across a UI reload within the same process lifetime, so we
+    // surface it on mount.
     invoke<boolean>("is_pairing_locked_out")
       .then(setPairingLockedOut)
       .catch(() => {});
@@ -447,9 +456,11 @@ export default function App() {
     // 3. Settings
     fetchSettings();
 
-    // 4. Port Check
-    invoke<number>("get_listening_port").then(port => {
-      if (port !== 4654) {
+    // 4. Port Check: warn when the configured port was taken and we fell
+    // back to another one (0 means "any port", so nothing to warn about).
+    Promise.all([invoke<number>("get_listening_port"), invoke<AppSettings>("get_settings")]).then(([port, s]) => {
+      if (s.listen_port !== 0 && port !== s.listen_port) {
+        setConfiguredPort(s.listen_port);
         setCurrentPort(port);
         setShowPortWarning(true);
       }
@@ -479,6 +490,7 @@ export default function App() {
     // would otherwise race the PIN dialog closed before the user can submit.
     const unlistenPairingSuccess = listen<string>("pairing-success", () => {
       setJoinOpen(false);
+      setSasPrompt(null);
       setJoinBusy(false);
       invoke<string>("get_network_name").then(name => setMyNetworkName(name));
       invoke<string>("get_network_pin").then(pin => setNetworkPin(pin));
@@ -537,6 +549,7 @@ export default function App() {
         blob: blobPreviewFromPreview(p.blob),
         formats: formatsFromPayload(p.formats),
         has_backing: !!p.has_backing,
+        ttl_secs: typeof p.ttl_secs === "number" ? p.ttl_secs : undefined,
       };
 
       // Update Local State but NOT 'lastSentClipboard'
@@ -572,6 +585,7 @@ export default function App() {
         blob: blobPreviewFromPreview(p.blob),
         formats: formatsFromPayload(p.formats),
         has_backing: !!p.has_backing,
+        ttl_secs: typeof p.ttl_secs === "number" ? p.ttl_secs : undefined,
       };
 
       // Update Local Clipboard State
@@ -638,7 +652,13 @@ export default function App() {
       invoke<string>("get_network_pin").then(pin => setNetworkPin(pin));
     });
 
+    // Security code shown on both devices once the PIN has been accepted.
+    const unlistenPairingSas = listen<SasPrompt>("pairing-sas", (event) => {
+      setSasPrompt(event.payload);
+    });
+
     const unlistenPairingFailed = listen<string>("pairing-failed", (event) => {
+      setSasPrompt(null);
       // During a cluster join we try each member in turn; a failed attempt
       // against a member whose PIN doesn't match is expected. `submitJoin`
       // reports the final outcome itself, so ignore per-attempt failures here.
@@ -676,6 +696,7 @@ export default function App() {
       unlistenUpdate.then((f) => f());
       unlistenDelete.then((f) => f());
       unlistenPairingFailed.then((f) => f());
+      unlistenPairingSas.then((f) => f());
       unlistenPairingSuccess.then((f) => f());
       unlistenIncompatible.then((f) => f());
       unlistenNotification.then((f) => f());
@@ -696,6 +717,7 @@ export default function App() {
     setJoinTarget(networkName);
     setPairingPeerId(null);
     setPairingPeerAddr(null);
+    setPairingExpectedFp(null);
     setJoinPin("");
     setJoinError("");
     setJoinBusy(false);
@@ -708,12 +730,33 @@ export default function App() {
     setJoinTarget(addr);
     setPairingPeerId(null);
     setPairingPeerAddr(addr);
+    setPairingExpectedFp(null);
     setJoinPin("");
     setJoinError("");
     setJoinBusy(false);
     setJoinOpen(true);
   };
 
+  // Invite-link variant of the manual flow: the address and expected
+  // fingerprint come from the link, and an embedded token pre-fills the PIN.
+  // The user still confirms in the modal — opening a link never pairs on
+  // its own.
+  const startInviteFlow = async (uri: string) => {
+    try {
+      const invite = await invoke<{ addr: string; fingerprint: string; token: string | null }>("parse_pairing_invite", { uri });
+      setJoinTarget(invite.addr);
+      setPairingPeerId(null);
+      setPairingPeerAddr(invite.addr);
+      setPairingExpectedFp(invite.fingerprint);
+      setJoinPin(invite.token ?? "");
+      setJoinError("");
+      setJoinBusy(false);
+      setJoinOpen(true);
+    } catch (e) {
+      alert("Invalid invite link: " + e);
+    }
+  };
+
   const submitJoin = async () => {
     if (!joinPin) return;
     setJoinBusy(true);
@@ -726,6 +769,7 @@ export default function App() {
           peerId: pairingPeerId ?? "",
           pin: joinPin,
           peerAddr: pairingPeerAddr,
+          expectedFingerprint: pairingExpectedFp,
         });
         // On success the `pairing-success` event closes the modal.
         setTimeout(() => setJoinBusy(false), 5000);
@@ -762,7 +806,7 @@ export default function App() {
     try {
       for (const peer of candidates) {
         try {
-          await invoke("start_pairing", { peerId: peer.id, pin: joinPin, peerAddr: null });
+          await invoke("start_pairing", { peerId: peer.id, pin: joinPin, peerAddr: null, expectedFingerprint: null });
           joined = true;
           break; // matched this member's PIN; `pairing-success` closes the modal
         } catch {
@@ -831,6 +875,13 @@ export default function App() {
   const submitManualPeer = async () => {
     if (!manualIp) return;
     const input = manualIp.trim();
+    // A pasted invite link carries its own address and fingerprint.
+    if (input.startsWith("clustercut://pair")) {
+      setAddManualOpen(false);
+      setManualIp("");
+      startInviteFlow(input);
+      return;
+    }
     // CIDR scans existing peers on a subnet and still relies on pinned
     // fingerprints — it's a re-discovery tool, not a first-pair entry point.
     // A single IP[:port] is the "Add Remote Peer" first-pair case: hand it
@@ -998,9 +1049,9 @@ export default function App() {
       )}
 
       {/* Pairing-lockout banner — fires when the responder's global
-          AEAD-failure threshold trips per WIRE-PROTOCOL-0.3.1 §H1. The
-          pairing TCP listener refuses inbound connections until the user
-          explicitly re-arms via the button below. Red (not amber) because
+          AEAD-failure ceiling trips per WIRE-PROTOCOL-0.3.1 §H1. The
+          pairing TCP listener refuses inbound connections until the pause
+          lifts or the user re-arms via the button below. Red (not amber) because
           the cause is genuinely adversarial (or a misbehaving peer), not
           a benign configuration drift. */}
       {pairingLockedOut && (
@@ -1341,6 +1392,17 @@ export default function App() {
           onClose={() => setJoinOpen(false)}
         />
 
+        <SasModal
+          prompt={sasPrompt}
+          onRespond={(accepted) => {
+            if (sasPrompt) {
+              invoke("confirm_pairing_sas", { session: sasPrompt.session, accepted }).catch(console.error);
+            }
+            setSasPrompt(null);
+          }}
+          onClose={() => setSasPrompt(null)}
+        />
+
         <LeaveModal
           open={leaveOpen}
           onConfirm={confirmLeaveNetwork}
@@ -1368,6 +1430,7 @@ export default function App() {
       <PortWarningModal
         open={showPortWarning}
         currentPort={currentPort}
+        configuredPort={configuredPort}
         onClose={() => setShowPortWarning(false)}
       />
 
@@ -40,7 +40,7 @@ export function PairingLockoutBanner({ onReEnable }: { onReEnable: () => void })
             Pairing paused — too many failed attempts
           </div>
           <div className="mt-1 text-rose-800 dark:text-rose-200">
-            Another device tried to pair with this one too many times with the wrong PIN. Pairing is disabled until you re-enable it. If you didn't expect this, check that no one else on your network is trying to join.
+            Devices on your network tried to pair with this one too many times with the wrong PIN. Pairing resumes automatically in a few minutes, or you can re-enable it now. If you didn't expect this, check that no one else on your network is trying to join.
           </div>
         </div>
         <Button
@@ -1,12 +1,144 @@
-import { useState } from "react";
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import {
   ShieldCheck, Lock, Unlock, AlertTriangle, CheckCircle2,
   ChevronDown, ChevronRight, PlusCircle, Trash2, Wifi,
-  Eye, EyeOff, Copy,
+  Eye, EyeOff, Copy, SlidersHorizontal, Gauge,
 } from "lucide-react";
 import clsx from "clsx";
 import { Badge, SectionHeader, Card, Button, IconButton, Field } from "./ui";
-import type { Peer, NearbyNetwork } from "../types";
+import { DEFAULT_PEER_POLICY } from "../types";
+import type { Peer, NearbyNetwork, PeerPolicy, ContentClass, LinkSummary, ConnectionTest, PeerStatus, DeviceInfo, Backend } from "../types";
+
+const CONTENT_CLASSES: { id: ContentClass; label: string }[] = [
+  { id: "text", label: "Text" },
+  { id: "rich", label: "Rich" },
+  { id: "image", label: "Images" },
+  { id: "files", label: "Files" },
+];
+
+function formatRate(bytesPerSec: number) {
+  const mbit = (bytesPerSec * 8) / 1_000_000;
+  return mbit >= 10 ? `${mbit.toFixed(0)} Mbit/s` : `${mbit.toFixed(1)} Mbit/s`;
+}
+
+/// One line of measured link stats, or null before anything was measured.
+const BACKEND_LABELS: Record<Backend, string> = {
+  plugin: "system clipboard",
+  wlr_data_control: "wlr data-control",
+  gnome_extension: "GNOME extension",
+  degraded: "no clipboard",
+  unknown: "unknown backend",
+};
+
+function deviceLine(device?: DeviceInfo | null) {
+  if (!device) return null;
+  return [
+    device.os_name || device.os,
+    device.desktop,
+    BACKEND_LABELS[device.backend] ?? device.backend,
+    device.flatpak ? "Flatpak" : null,
+    `v${device.app_version}`,
+  ].filter(Boolean).join(" · ");
+}
+
+function linkLine(link?: LinkSummary) {
+  if (!link) return null;
+  const parts: string[] = [];
+  if (link.rtt_avg_ms != null) {
+    parts.push(`${link.rtt_avg_ms} ms${link.jitter_ms != null ? ` ± ${link.jitter_ms}` : ""}`);
+  }
+  if (link.outbound) parts.push(`↑ ${formatRate(link.outbound.bytes_per_sec)}`);
+  if (link.inbound) parts.push(`↓ ${formatRate(link.inbound.bytes_per_sec)}`);
+  return parts.length > 0 ? parts.join(" · ") : null;
+}
+
+/// Badge for a peer's own status; plain "online" until it reports one.
+function statusBadge(status?: PeerStatus | null): { tone: "good" | "warn" | "neutral"; label: string; title?: string } {
+  if (!status) return { tone: "good", label: "online" };
+  if (status.sync_paused) return { tone: "warn", label: "paused", title: "Sync is paused by quiet hours; clipboards sent to it are dropped." };
+  const note = status.manual ? "Auto-receive is off; clipboards land in its History only." : undefined;
+  switch (status.activity) {
+    case "locked":
+      return { tone: "neutral", label: "locked", title: note };
+    case "idle": {
+      const mins = status.idle_secs != null ? Math.floor(status.idle_secs / 60) : null;
+      return { tone: "neutral", label: mins != null ? `idle ${mins}m` : "idle", title: note };
+    }
+    default:
+      return { tone: "good", label: status.manual ? "manual" : "active", title: note };
+  }
+}
+
+function StatusBadge({ status }: { status?: PeerStatus | null }) {
+  const { tone, label, title } = statusBadge(status);
+  return (
+    <span title={title}>
+      <Badge tone={tone}>{label}</Badge>
+    </span>
+  );
+}
+
+function PolicyToggle({ label, checked, onChange }: { label: string; checked: boolean; onChange: (v: boolean) => void }) {
+  return (
+    <label className="flex items-center justify-between gap-3 text-xs text-zinc-700 dark:text-zinc-300">
+      {label}
+      <button
+        onClick={() => onChange(!checked)}
+        className={clsx("relative h-5 w-9 shrink-0 rounded-full transition-colors", checked ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+      >
+        <span className={clsx("block h-3.5 w-3.5 transform rounded-full bg-white shadow-sm transition-transform", checked ? "translate-x-5" : "translate-x-0.5")} />
+      </button>
+    </label>
+  );
+}
+
+function PeerPolicyEditor({ policy, onChange }: { policy: PeerPolicy; onChange: (p: PeerPolicy) => void }) {
+  const allowed = policy.allowed_classes ?? CONTENT_CLASSES.map(c => c.id);
+  const toggleClass = (id: ContentClass) => {
+    const next = allowed.includes(id) ? allowed.filter(c => c !== id) : [...allowed, id];
+    onChange({ ...policy, allowed_classes: next.length === CONTENT_CLASSES.length ? null : next });
+  };
+  return (
+    <div className="mt-3 flex flex-col gap-2 rounded-xl border border-zinc-200 bg-zinc-50 p-3 dark:border-white/10 dark:bg-white/5">
+      <PolicyToggle label="Send my clipboard to this device" checked={policy.send_to} onChange={v => onChange({ ...policy, send_to: v })} />
+      <PolicyToggle label="Accept clipboard from this device" checked={policy.receive_from} onChange={v => onChange({ ...policy, receive_from: v })} />
+      <PolicyToggle label="File transfer" checked={policy.file_transfer} onChange={v => onChange({ ...policy, file_transfer: v })} />
+      <div className="flex flex-wrap items-center gap-2 text-xs text-zinc-700 dark:text-zinc-300">
+        Accept:
+        {CONTENT_CLASSES.map(c => (
+          <button
+            key={c.id}
+            onClick={() => toggleClass(c.id)}
+            className={clsx(
+              "rounded-md px-1.5 py-0.5 text-[10px] font-medium uppercase tracking-wide",
+              allowed.includes(c.id)
+                ? "bg-emerald-100 text-emerald-700 dark:bg-emerald-500/15 dark:text-emerald-200"
+                : "bg-zinc-200 text-zinc-500 line-through dark:bg-zinc-700 dark:text-zinc-400",
+            )}
+          >
+            {c.label}
+          </button>
+        ))}
+      </div>
+      <div className="flex items-center justify-between gap-3 text-xs text-zinc-700 dark:text-zinc-300">
+        Max auto-download (MB)
+        <input
+          type="number"
+          min={0}
+          step={1}
+          placeholder="Global"
+          className="h-7 w-20 rounded-lg border border-zinc-900/10 bg-white px-2 text-xs text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-white/5 dark:text-zinc-50"
+          value={policy.max_auto_download_size === null ? "" : Math.round(policy.max_auto_download_size / (1024 * 1024))}
+          onChange={(e) => {
+            const mb = e.target.value === "" ? null : Math.max(0, parseInt(e.target.value, 10) || 0);
+            onChange({ ...policy, max_auto_download_size: mb === null ? null : mb * 1024 * 1024 });
+          }}
+        />
+      </div>
+    </div>
+  );
+}
 
 function CopyMini({ text }: { text: string }) {
   return (
@@ -42,6 +174,29 @@ function DevicesView({
   onAddManual: () => void;
 }) {
   const [showPin, setShowPin] = useState(false);
+  const [policies, setPolicies] = useState<Record<string, PeerPolicy>>({});
+  const [editingPolicy, setEditingPolicy] = useState<string | null>(null);
+  const [testing, setTesting] = useState<string | null>(null);
+  const [testErrors, setTestErrors] = useState<Record<string, string>>({});
+
+  useEffect(() => {
+    invoke<Record<string, PeerPolicy>>("get_peer_policies").then(setPolicies).catch(console.error);
+  }, []);
+
+  const updatePolicy = (peerId: string, policy: PeerPolicy) => {
+    setPolicies(prev => ({ ...prev, [peerId]: policy }));
+    invoke("set_peer_policy", { peerId, policy }).catch(console.error);
+  };
+
+  // Results arrive through the usual peer-update as `link`; only errors
+  // need keeping here.
+  const testConnection = (peerId: string) => {
+    setTesting(peerId);
+    setTestErrors(prev => ({ ...prev, [peerId]: "" }));
+    invoke<ConnectionTest>("test_connection", { peerId })
+      .catch(e => setTestErrors(prev => ({ ...prev, [peerId]: String(e) })))
+      .finally(() => setTesting(null));
+  };
 
   return (
     <div className="flex h-full flex-col gap-3">
@@ -118,13 +273,11 @@ function DevicesView({
             ) : (
               <div className="mt-2 space-y-2">
                 {peers.map((p) => (
-                  <div
-                    key={p.id}
-                    className="relative flex items-center justify-between gap-3 rounded-2xl border border-zinc-900/10 bg-white/60 p-3 pr-4 dark:border-white/10 dark:bg-white/5"
-                  >
+                  <div key={p.id} className="relative rounded-2xl border border-zinc-900/10 bg-white/60 p-3 pr-4 dark:border-white/10 dark:bg-white/5">
+                  <div className="flex items-center justify-between gap-3">
                     {/* Online Badge - Absolute Top Right with some padding */}
                     <div className="absolute right-2 top-2">
-                      <Badge tone="good">online</Badge>
+                      <StatusBadge status={p.status} />
                     </div>
 
                     <div className="flex items-center gap-3">
@@ -144,16 +297,64 @@ function DevicesView({
                             </span>
                           )}
                         </div>
-                        <div className="text-xs text-zinc-600 dark:text-zinc-400">{p.ip}</div>
+                        <div
+                          className="text-xs text-zinc-600 dark:text-zinc-400"
+                          title={(p.addresses ?? [])
+                            .map(a => `${a.addr}${a.rtt_ms != null ? ` · ${a.rtt_ms} ms` : ""}${a.failures > 0 ? ` · ${a.failures} failed` : ""}`)
+                            .join("\n") || undefined}
+                        >
+                          {p.ip}
+                          {(p.addresses?.length ?? 0) > 1 && ` (+${p.addresses!.length - 1} more)`}
+                          {p.relay_via && ` · via ${peers.find(r => r.id === p.relay_via)?.hostname || p.relay_via}`}
+                        </div>
+                        {deviceLine(p.device) && (
+                          <div
+                            className="text-xs text-zinc-500 dark:text-zinc-400"
+                            title={p.device?.formats.length ? `Takes ${p.device.formats.join(", ")}` : undefined}
+                          >
+                            {deviceLine(p.device)}
+                          </div>
+                        )}
+                        {(p.capability_gaps ?? []).map((gap) => (
+                          <div key={gap} className="text-xs text-amber-600 dark:text-amber-400">{gap}</div>
+                        ))}
+                        {linkLine(p.link) && (
+                          <div
+                            className="text-xs text-zinc-500 dark:text-zinc-400"
+                            title={p.link?.rtt_min_ms != null ? `Best ${p.link.rtt_min_ms} ms over ${p.link.samples} pings` : undefined}
+                          >
+                            {linkLine(p.link)}
+                          </div>
+                        )}
+                        {testErrors[p.id] && (
+                          <div className="text-xs text-rose-600 dark:text-rose-400">{testErrors[p.id]}</div>
+                        )}
                       </div>
                     </div>
 
-                    <div className="mt-4 flex items-center">
+                    <div className="mt-4 flex items-center gap-1">
+                      <IconButton
+                        label={testing === p.id ? "Testing…" : "Test connection"}
+                        onClick={() => testConnection(p.id)}
+                        disabled={testing !== null}
+                      >
+                        <Gauge className={clsx("h-5 w-5 text-zinc-600 dark:text-zinc-300", testing === p.id && "animate-pulse")} />
+                      </IconButton>
+                      <IconButton label="Permissions" onClick={() => setEditingPolicy(id => (id === p.id ? null : p.id))}>
+                        <SlidersHorizontal className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />
+                      </IconButton>
                       <IconButton label="Kick / Ban" onClick={() => onDeletePeer(p.id)}>
                         <Trash2 className="h-5 w-5 text-rose-600" />
                       </IconButton>
                     </div>
                   </div>
+                  {editingPolicy === p.id && (
+                    <PeerPolicyEditor
+                      policy={policies[p.id] ?? DEFAULT_PEER_POLICY}
+                      onChange={(policy) => updatePolicy(p.id, policy)}
+                    />
+                  )}
+                  </div>
                 ))}
               </div>
             )}
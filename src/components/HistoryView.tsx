@@ -147,6 +147,14 @@ export function HistoryView({ items, onClearHistory }: { items: HistoryItem[]; o
                         )}
                       </Badge>
                       <span className="text-xs text-zinc-500 dark:text-zinc-400">{timeAgo(it.ts)}</span>
+                      {it.ttl_secs !== undefined && (
+                        <span
+                          className="inline-flex items-center rounded-md bg-amber-100 px-1.5 py-0.5 text-[10px] font-medium uppercase tracking-wide text-amber-700 dark:bg-amber-500/15 dark:text-amber-200"
+                          title="Ephemeral: cleared from receiving clipboards after this delay and not kept in History"
+                        >
+                          Ephemeral · {it.ttl_secs}s
+                        </span>
+                      )}
                       {it.formats && it.formats.length > 0 && (
                         <span
                           className="inline-flex items-center rounded-md bg-violet-100 px-1.5 py-0.5 text-[10px] font-medium uppercase tracking-wide text-violet-700 dark:bg-violet-500/15 dark:text-violet-200"
@@ -1,6 +1,7 @@
 import clsx from "clsx";
-import { AlertTriangle, PlusCircle } from "lucide-react";
+import { AlertTriangle, PlusCircle, ShieldCheck } from "lucide-react";
 import { Button, Modal } from "./ui";
+import type { SasPrompt } from "../types";
 
 /* --- IncompatibleModal --- */
 
@@ -109,7 +110,7 @@ export function JoinModal({
       open={open}
       onClose={onClose}
       title={`Join "${joinTarget}"`}
-      subtitle="Enter the 6-character Network PIN shown on any device in that cluster."
+      subtitle="Enter the Network PIN shown on any device in that cluster, or an invite token from the device you are joining."
       footer={
         <>
           <Button variant="ghost" onClick={onClose}>
@@ -123,7 +124,7 @@ export function JoinModal({
     >
       <div className="space-y-3">
         <div className="rounded-2xl border border-zinc-900/10 bg-zinc-50 p-4 dark:border-white/10 dark:bg-white/5">
-          <div className="text-xs font-medium text-zinc-600 dark:text-zinc-400">Cluster PIN</div>
+          <div className="text-xs font-medium text-zinc-600 dark:text-zinc-400">Cluster PIN or Invite Token</div>
           <input
             className={clsx(
               "mt-2 h-12 w-full rounded-2xl border bg-white px-4 font-mono text-lg tracking-[0.25em] text-zinc-900 outline-none focus:ring-2 dark:bg-zinc-950 dark:text-zinc-50",
@@ -190,6 +191,58 @@ export function LeaveModal({ open, onConfirm, onClose }: LeaveModalProps) {
   );
 }
 
+/* --- SasModal --- */
+
+interface SasModalProps {
+  prompt: SasPrompt | null;
+  onRespond: (accepted: boolean) => void;
+  onClose: () => void;
+}
+
+export function SasModal({ prompt, onRespond, onClose }: SasModalProps) {
+  if (!prompt) return null;
+  return (
+    <Modal
+      open
+      onClose={prompt.requires_confirmation ? () => onRespond(false) : onClose}
+      title="Security code"
+      subtitle={
+        prompt.requires_confirmation
+          ? `Check that ${prompt.peer} shows the same symbols, in the same order.`
+          : `${prompt.peer} should show the same symbols. If it doesn't, remove it and pair again.`
+      }
+      footer={
+        prompt.requires_confirmation ? (
+          <>
+            <Button variant="danger" onClick={() => onRespond(false)}>
+              They don't match
+            </Button>
+            <Button variant="primary" onClick={() => onRespond(true)} iconLeft={<ShieldCheck className="h-4 w-4" />}>
+              They match
+            </Button>
+          </>
+        ) : (
+          <Button variant="primary" onClick={onClose}>
+            OK
+          </Button>
+        )
+      }
+    >
+      <div className="grid grid-cols-3 gap-3">
+        {prompt.symbols.map((s, i) => (
+          <div
+            key={i}
+            className="flex flex-col items-center gap-1 rounded-2xl border border-zinc-900/10 bg-zinc-50 p-3 dark:border-white/10 dark:bg-zinc-800/50"
+          >
+            <span className="text-3xl">{s.emoji}</span>
+            <span className="text-xs font-medium text-zinc-700 dark:text-zinc-300">{s.word}</span>
+          </div>
+        ))}
+      </div>
+    </Modal>
+  );
+}
+
 /* --- AddRemoteModal --- */
 
 interface AddRemoteModalProps {
@@ -214,7 +267,7 @@ export function AddRemoteModal({
       open={open}
       onClose={onClose}
       title="Add Remote Peer"
-      subtitle="Enter an IP address to pair with a remote peer, or a CIDR range (e.g. 192.168.1.0/24) to rediscover already-paired peers."
+      subtitle="Enter an IPv4 or IPv6 address to pair with a remote peer, or a CIDR range (e.g. 192.168.1.0/24) to rediscover already-paired peers."
       footer={
         <>
           <Button variant="ghost" onClick={onClose}>
@@ -228,17 +281,17 @@ export function AddRemoteModal({
     >
       <div className="space-y-3">
         <div className="rounded-2xl border border-zinc-900/10 bg-zinc-50 p-4 dark:border-white/10 dark:bg-white/5">
-          <div className="text-xs font-medium text-zinc-600 dark:text-zinc-400">IP Address / CIDR</div>
+          <div className="text-xs font-medium text-zinc-600 dark:text-zinc-400">IP Address / CIDR / Invite Link</div>
           <input
             className="mt-2 h-12 w-full rounded-2xl border border-zinc-200 bg-white px-4 text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50"
-            placeholder="e.g. 10.8.0.5 or 192.168.1.0/24"
+            placeholder="e.g. 10.8.0.5, [2001:db8::5]:4654 or 192.168.1.0/24"
             value={manualIp}
             onChange={(e) => onIpChange(e.target.value)}
             autoFocus
             onKeyDown={(e) => e.key === "Enter" && onSubmit()}
           />
           <div className="mt-2 text-xs text-zinc-500">
-            Target must be running ClusterCut on the default port (4654).
+            Add :port if the target doesn't use the default port (4654), e.g. 10.8.0.5:5000 or [2001:db8::5]:5000. You can also paste a clustercut://pair invite link.
           </div>
         </div>
       </div>
@@ -251,10 +304,11 @@ export function AddRemoteModal({
 interface PortWarningModalProps {
   open: boolean;
   currentPort: number;
+  configuredPort: number;
   onClose: () => void;
 }
 
-export function PortWarningModal({ open, currentPort, onClose }: PortWarningModalProps) {
+export function PortWarningModal({ open, currentPort, configuredPort, onClose }: PortWarningModalProps) {
   return (
     <Modal
       open={open}
@@ -271,7 +325,7 @@ export function PortWarningModal({ open, currentPort, onClose }: PortWarningModa
         <div className="flex items-center gap-3 rounded-xl border border-amber-200 bg-amber-50 p-4 text-amber-900 dark:border-amber-900/30 dark:bg-amber-900/10 dark:text-amber-200">
           <AlertTriangle className="h-5 w-5 shrink-0" />
           <div className="text-sm">
-            <span className="font-semibold">Port 4654 is busy.</span>
+            <span className="font-semibold">Port {configuredPort} is busy.</span>
             <p className="mt-1 opacity-90">
               ClusterCut is listening on port <span className="font-mono font-bold">{currentPort}</span> instead.
             </p>
@@ -279,7 +333,7 @@ export function PortWarningModal({ open, currentPort, onClose }: PortWarningModa
         </div>
         <p className="text-sm text-zinc-600 dark:text-zinc-400">
           This usually happens if another instance of ClusterCut is already running.
-          Peer discovery might be affected if other devices expect the standard port.
+          Discovered devices pick up the new port automatically; devices added by address need it entered as <span className="font-mono">address:{currentPort}</span>.
         </p>
       </div>
     </Modal>
@@ -1,7 +1,565 @@
+import { useEffect, useState, type ReactNode } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import clsx from "clsx";
-import { ShieldCheck, Wifi } from "lucide-react";
-import { SectionHeader, Card } from "../ui";
-import type { AppSettings } from "../../types";
+import { Copy, KeyRound, Layers, MapPin, QrCode, RefreshCw, ShieldCheck, Ticket, Trash2, Wifi } from "lucide-react";
+import { SectionHeader, Card, Button } from "../ui";
+import type { AppSettings, ClusterRoute, ClusterView, InviteToken, NetworkIdentity, NetworkMatch, NetworkPolicy } from "../../types";
+
+function CertificateRotation() {
+  const [busy, setBusy] = useState(false);
+  const [result, setResult] = useState<string | null>(null);
+
+  const rotate = async () => {
+    if (!confirm("Generate a new device certificate and hand it to every paired device? Devices that are offline right now will need to re-pair.")) return;
+    setBusy(true);
+    setResult(null);
+    try {
+      const summary = await invoke<{ notified: number; unreachable: string[] }>("rotate_device_cert");
+      setResult(
+        summary.unreachable.length === 0
+          ? `Certificate rotated. ${summary.notified} device(s) updated.`
+          : `Certificate rotated. ${summary.notified} device(s) updated; ${summary.unreachable.join(", ")} did not confirm the new certificate and will need to re-pair.`,
+      );
+    } catch (e) {
+      setResult(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<KeyRound className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Device Certificate"
+        subtitle="This device's TLS identity."
+      />
+      <div className="mt-4 flex flex-col gap-2 px-1">
+        <div className="flex items-center justify-between gap-4">
+          <div className="text-xs text-zinc-500">
+            Replace the certificate without re-pairing. Paired devices are told about the new one, signed with the old key.
+          </div>
+          <Button size="sm" iconLeft={<RefreshCw className="h-4 w-4" />} onClick={rotate} disabled={busy}>
+            {busy ? "Rotating…" : "Rotate"}
+          </Button>
+        </div>
+        {result && <div className="text-[11px] text-zinc-600 dark:text-zinc-400">{result}</div>}
+      </div>
+    </Card>
+  );
+}
+
+function PairingInvite() {
+  const [addr, setAddr] = useState("");
+  const [includeToken, setIncludeToken] = useState(false);
+  const [invite, setInvite] = useState<{ uri: string; qr_png: string } | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const create = async () => {
+    setError(null);
+    try {
+      setInvite(await invoke<{ uri: string; qr_png: string }>("create_pairing_invite", { addr: addr.trim() || null, includeToken }));
+    } catch (e) {
+      setInvite(null);
+      setError(String(e));
+    }
+  };
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<QrCode className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Invite Link"
+        subtitle="Pair another device by link or QR code."
+      />
+      <div className="mt-4 flex flex-col gap-3 px-1">
+        <div className="text-xs text-zinc-500">
+          The link carries this device's address and certificate fingerprint, so the joiner refuses any other device that answers.
+        </div>
+        <div className="flex flex-col gap-1">
+          <label className="text-xs font-medium text-zinc-600 dark:text-zinc-400">Address (optional)</label>
+          <input
+            className="h-10 rounded-xl border border-zinc-900/10 bg-white px-3 font-mono text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50"
+            placeholder="This device's LAN address"
+            value={addr}
+            onChange={(e) => setAddr(e.target.value)}
+          />
+        </div>
+        <div className="flex items-center justify-between">
+          <div>
+            <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Include One-Time Token</div>
+            <div className="text-xs text-zinc-500">The link works once, within an hour, without typing the PIN.</div>
+          </div>
+          <button
+            onClick={() => setIncludeToken(!includeToken)}
+            className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", includeToken ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+          >
+            <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", includeToken ? "translate-x-6" : "translate-x-1")} />
+          </button>
+        </div>
+        <div className="flex justify-end">
+          <Button size="sm" iconLeft={<QrCode className="h-4 w-4" />} onClick={create}>
+            Create Invite
+          </Button>
+        </div>
+        {error && <div className="text-[11px] text-rose-500">{error}</div>}
+        {invite && (
+          <div className="flex flex-col items-center gap-2">
+            <img src={`data:image/png;base64,${invite.qr_png}`} alt="Pairing invite QR code" className="h-48 w-48 rounded-lg" />
+            <div className="flex w-full items-center gap-2">
+              <div className="min-w-0 flex-1 truncate font-mono text-[11px] text-zinc-600 dark:text-zinc-400">{invite.uri}</div>
+              <Button size="sm" variant="ghost" iconLeft={<Copy className="h-4 w-4" />} onClick={() => navigator.clipboard.writeText(invite.uri)}>
+                Copy
+              </Button>
+            </div>
+          </div>
+        )}
+      </div>
+    </Card>
+  );
+}
+
+const TOKEN_TTL_OPTIONS: { label: string; secs: number }[] = [
+  { label: "1 hour", secs: 60 * 60 },
+  { label: "1 day", secs: 24 * 60 * 60 },
+  { label: "7 days", secs: 7 * 24 * 60 * 60 },
+];
+
+function InviteTokens() {
+  const [tokens, setTokens] = useState<InviteToken[]>([]);
+  const [label, setLabel] = useState("");
+  const [ttlSecs, setTtlSecs] = useState(TOKEN_TTL_OPTIONS[0].secs);
+  const [maxUses, setMaxUses] = useState(1);
+  const [receiveOnly, setReceiveOnly] = useState(false);
+  const [noFiles, setNoFiles] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = () => invoke<InviteToken[]>("list_invite_tokens").then(setTokens).catch(console.error);
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const mint = async () => {
+    setError(null);
+    try {
+      await invoke("mint_invite_token", {
+        label: label.trim() || null,
+        ttlSecs,
+        maxUses,
+        restrictions: { receive_only: receiveOnly, no_file_transfer: noFiles },
+      });
+      setLabel("");
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const revoke = async (id: string) => {
+    try {
+      await invoke("revoke_invite_token", { id });
+    } catch (e) {
+      setError(String(e));
+    }
+    refresh();
+  };
+
+  const inputClass =
+    "h-10 rounded-xl border border-zinc-900/10 bg-white px-3 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50";
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<Ticket className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Invite Tokens"
+        subtitle="Codes that work in place of the PIN, for a limited time."
+      />
+      <div className="mt-4 flex flex-col gap-3 px-1">
+        <div className="text-xs text-zinc-500">
+          A token works only when pairing with this device. Share it instead of the cluster PIN; it expires and can be revoked.
+        </div>
+        <div className="grid grid-cols-3 gap-2">
+          <input className={clsx(inputClass, "col-span-3")} placeholder="Label (optional)" value={label} onChange={(e) => setLabel(e.target.value)} />
+          <select className={inputClass} value={ttlSecs} onChange={(e) => setTtlSecs(Number(e.target.value))}>
+            {TOKEN_TTL_OPTIONS.map((o) => (
+              <option key={o.secs} value={o.secs}>
+                {o.label}
+              </option>
+            ))}
+          </select>
+          <input
+            className={inputClass}
+            type="number"
+            min={1}
+            max={100}
+            value={maxUses}
+            onChange={(e) => setMaxUses(Math.max(1, Number(e.target.value) || 1))}
+            title="Number of uses"
+          />
+          <Button size="sm" onClick={mint}>
+            Create Token
+          </Button>
+        </div>
+        <div className="flex gap-4 text-xs text-zinc-600 dark:text-zinc-400">
+          <label className="flex items-center gap-2">
+            <input type="checkbox" checked={receiveOnly} onChange={(e) => setReceiveOnly(e.target.checked)} />
+            Receive only
+          </label>
+          <label className="flex items-center gap-2">
+            <input type="checkbox" checked={noFiles} onChange={(e) => setNoFiles(e.target.checked)} />
+            No file transfer
+          </label>
+        </div>
+        {error && <div className="text-[11px] text-rose-500">{error}</div>}
+        {tokens.map((t) => (
+          <div key={t.id} className={clsx("flex items-center gap-2 rounded-xl border border-zinc-900/10 p-2 dark:border-white/10", !t.active && "opacity-50")}>
+            <div className="min-w-0 flex-1">
+              <div className="truncate font-mono text-xs text-zinc-900 dark:text-zinc-50">{t.code}</div>
+              <div className="text-[11px] text-zinc-500">
+                {t.label ? `${t.label} · ` : ""}
+                {t.uses}/{t.max_uses} used · {t.active ? `expires ${new Date(t.expires_at * 1000).toLocaleString()}` : "inactive"}
+                {t.restrictions.receive_only ? " · receive only" : ""}
+                {t.restrictions.no_file_transfer ? " · no files" : ""}
+              </div>
+            </div>
+            {t.active && (
+              <Button size="sm" variant="ghost" iconLeft={<Copy className="h-4 w-4" />} onClick={() => navigator.clipboard.writeText(t.code)}>
+                Copy
+              </Button>
+            )}
+            <Button size="sm" variant="ghost" onClick={() => revoke(t.id)}>
+              {t.active ? "Revoke" : "Remove"}
+            </Button>
+          </div>
+        ))}
+      </div>
+    </Card>
+  );
+}
+
+function Clusters() {
+  const [clusters, setClusters] = useState<ClusterView[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = () => invoke<ClusterView[]>("list_clusters").then(setClusters).catch(console.error);
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const setRoute = async (clusterId: string, route: ClusterRoute) => {
+    setError(null);
+    try {
+      await invoke("set_cluster_route", { clusterId, route });
+    } catch (e) {
+      setError(String(e));
+    }
+    refresh();
+  };
+
+  const leave = async (c: ClusterView) => {
+    if (!confirm(`Leave "${c.name}"? Its ${c.members} device(s) will be forgotten on this device.`)) return;
+    setError(null);
+    try {
+      await invoke("leave_cluster", { clusterId: c.cluster_id });
+    } catch (e) {
+      setError(String(e));
+    }
+    refresh();
+  };
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<Layers className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Clusters"
+        subtitle="Clusters this device belongs to."
+      />
+      <div className="mt-4 flex flex-col gap-3 px-1">
+        <div className="text-xs text-zinc-500">
+          Pairing with a device from another cluster joins that cluster too, without leaving this one. Choose where copies go and which clusters you accept them from.
+        </div>
+        {error && <div className="text-[11px] text-rose-500">{error}</div>}
+        {clusters.map((c) => (
+          <div key={c.cluster_id} className="flex items-center gap-2 rounded-xl border border-zinc-900/10 p-2 dark:border-white/10">
+            <div className="min-w-0 flex-1">
+              <div className="truncate text-sm font-medium text-zinc-900 dark:text-zinc-50">{c.name}</div>
+              <div className="text-[11px] text-zinc-500">
+                {c.is_primary ? "Primary · " : ""}
+                {c.members} device(s)
+              </div>
+            </div>
+            <label className="flex items-center gap-1 text-xs text-zinc-600 dark:text-zinc-400">
+              <input type="checkbox" checked={c.route.send} onChange={(e) => setRoute(c.cluster_id, { ...c.route, send: e.target.checked })} />
+              Send
+            </label>
+            <label className="flex items-center gap-1 text-xs text-zinc-600 dark:text-zinc-400">
+              <input type="checkbox" checked={c.route.receive} onChange={(e) => setRoute(c.cluster_id, { ...c.route, receive: e.target.checked })} />
+              Receive
+            </label>
+            {!c.is_primary && (
+              <Button size="sm" variant="ghost" onClick={() => leave(c)}>
+                Leave
+              </Button>
+            )}
+          </div>
+        ))}
+      </div>
+    </Card>
+  );
+}
+
+// A list setting edited as one entry per line, saved when the field loses focus.
+function LineList({
+  title,
+  description,
+  placeholder,
+  values,
+  onCommit,
+}: {
+  title: string;
+  description: ReactNode;
+  placeholder?: string;
+  values: string[];
+  onCommit: (values: string[]) => void;
+}) {
+  const [draft, setDraft] = useState(values.join("\n"));
+
+  useEffect(() => {
+    setDraft(values.join("\n"));
+  }, [values]);
+
+  const commit = () => {
+    const next = draft.split("\n").map((r) => r.trim()).filter((r) => r.length > 0);
+    if (next.join("\n") !== values.join("\n")) {
+      onCommit(next);
+    }
+  };
+
+  return (
+    <div className="mt-4 flex flex-col gap-1">
+      <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">{title}</div>
+      <div className="text-xs text-zinc-500">{description}</div>
+      <textarea
+        rows={values.length > 3 ? 5 : 3}
+        spellCheck={false}
+        placeholder={placeholder}
+        className="mt-1 rounded-xl border border-zinc-900/10 bg-white px-3 py-2 font-mono text-xs text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50"
+        value={draft}
+        onChange={(e) => setDraft(e.target.value)}
+        onBlur={commit}
+      />
+    </div>
+  );
+}
+
+// Port settings apply on restart, so show the port actually in use beside them.
+function ListenPort({ settings, setSettings }: { settings: AppSettings; setSettings: (s: AppSettings) => void }) {
+  const [current, setCurrent] = useState<number | null>(null);
+
+  useEffect(() => {
+    invoke<number>("get_listening_port").then(setCurrent).catch(console.error);
+  }, []);
+
+  const inputClass =
+    "h-9 w-28 rounded-xl border border-zinc-900/10 bg-white px-3 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50";
+
+  return (
+    <>
+      <div className="mt-6 flex items-center justify-between gap-4">
+        <div>
+          <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Listen Port</div>
+          <div className="text-xs text-zinc-500">
+            UDP and TCP port for sync and pairing; 0 picks any free port. Takes effect after a restart.
+            {current !== null && (
+              <>
+                {" "}Currently listening on <span className="font-mono">{current}</span>.
+              </>
+            )}
+          </div>
+        </div>
+        <input
+          type="number"
+          min={0}
+          max={65535}
+          className={inputClass}
+          value={settings.listen_port}
+          onChange={(e) => {
+            const port = parseInt(e.target.value, 10);
+            if (port >= 0 && port <= 65535) setSettings({ ...settings, listen_port: port });
+          }}
+        />
+      </div>
+      <div className="mt-4 flex items-center justify-between gap-4">
+        <div>
+          <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Fallback Ports</div>
+          <div className="text-xs text-zinc-500">How many ports above it to try, in order, when it is taken.</div>
+        </div>
+        <input
+          type="number"
+          min={0}
+          max={100}
+          className={inputClass}
+          value={settings.listen_port_range}
+          onChange={(e) => {
+            const range = parseInt(e.target.value, 10);
+            if (range >= 0 && range <= 100) setSettings({ ...settings, listen_port_range: range });
+          }}
+        />
+      </div>
+    </>
+  );
+}
+
+const MATCH_KINDS: { kind: NetworkMatch["kind"]; label: string; placeholder: string }[] = [
+  { kind: "subnet", label: "Subnet", placeholder: "192.168.1.0/24" },
+  { kind: "gateway_mac", label: "Gateway MAC", placeholder: "aa:bb:cc:dd:ee:ff" },
+  { kind: "nm_connection", label: "NetworkManager connection", placeholder: "Home Wi-Fi" },
+];
+
+const POLICY_TOGGLES: { key: "auto_send" | "auto_receive" | "file_transfer" | "advertise"; label: string }[] = [
+  { key: "auto_send", label: "Auto-Send" },
+  { key: "auto_receive", label: "Auto-Receive" },
+  { key: "file_transfer", label: "Files" },
+  { key: "advertise", label: "Advertise" },
+];
+
+// Rules switch the real settings while this device is on a matching network
+// and put them back when it leaves (netpolicy.rs). Text fields commit on blur.
+function NetworkPolicies({ settings, setSettings }: { settings: AppSettings; setSettings: (s: AppSettings) => void }) {
+  const [draft, setDraft] = useState<NetworkPolicy[]>(settings.network_policies);
+  const [identity, setIdentity] = useState<NetworkIdentity | null>(null);
+
+  useEffect(() => {
+    setDraft(settings.network_policies);
+  }, [settings.network_policies]);
+
+  const refreshIdentity = () => invoke<NetworkIdentity>("get_network_identity").then(setIdentity).catch(console.error);
+
+  useEffect(() => {
+    refreshIdentity();
+  }, []);
+
+  const commit = (rules: NetworkPolicy[]) => {
+    setDraft(rules);
+    if (JSON.stringify(rules) !== JSON.stringify(settings.network_policies)) {
+      setSettings({ ...settings, network_policies: rules });
+    }
+  };
+  const update = (i: number, rule: NetworkPolicy) => setDraft(draft.map((r, j) => (j === i ? rule : r)));
+  const add = () =>
+    commit([
+      ...draft,
+      {
+        name: `Network ${draft.length + 1}`,
+        match: { kind: "subnet", value: identity?.addresses.find((a) => !a.includes(":"))?.replace(/\.\d+$/, ".0/24") ?? "" },
+        auto_send: null,
+        auto_receive: null,
+        file_transfer: null,
+        advertise: null,
+      },
+    ]);
+
+  const inputClass =
+    "h-9 rounded-xl border border-zinc-900/10 bg-white px-3 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50";
+  const active = settings.network_policy_active?.rule;
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<MapPin className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Network Rules"
+        subtitle="Change how this device syncs depending on the network."
+      />
+      <div className="mt-4 flex flex-col gap-3 px-1">
+        <div className="text-xs text-zinc-500">
+          The first rule that matches the current network applies; settings go back when you leave it. A setting you change by hand in the meantime is kept.
+        </div>
+        {identity && (
+          <div className="flex items-start justify-between gap-2 rounded-xl bg-zinc-900/5 p-2 text-[11px] text-zinc-600 dark:bg-white/5 dark:text-zinc-400">
+            <div>
+              This network: <span className="font-mono">{identity.addresses.join(", ") || "no addresses"}</span>
+              {identity.gateway_mac && (
+                <>
+                  {" "}· gateway <span className="font-mono">{identity.gateway_mac}</span>
+                </>
+              )}
+              {identity.nm_connection && <> · connection “{identity.nm_connection}”</>}
+              {active && <> · rule “{active}” applied</>}
+            </div>
+            <button className="shrink-0 text-zinc-500 hover:text-zinc-900 dark:hover:text-zinc-50" onClick={refreshIdentity} title="Refresh">
+              <RefreshCw className="h-3.5 w-3.5" />
+            </button>
+          </div>
+        )}
+        {draft.map((rule, i) => (
+          <div
+            key={i}
+            className={clsx(
+              "flex flex-col gap-2 rounded-xl border p-2",
+              rule.name === active ? "border-emerald-500/50" : "border-zinc-900/10 dark:border-white/10"
+            )}
+          >
+            <div className="flex items-center gap-2">
+              <input
+                className={clsx(inputClass, "w-36")}
+                value={rule.name}
+                onChange={(e) => update(i, { ...rule, name: e.target.value })}
+                onBlur={() => commit(draft)}
+              />
+              <select
+                className={inputClass}
+                value={rule.match.kind}
+                onChange={(e) => commit(draft.map((r, j) => (j === i ? { ...r, match: { ...r.match, kind: e.target.value as NetworkMatch["kind"] } } : r)))}
+              >
+                {MATCH_KINDS.map((m) => (
+                  <option key={m.kind} value={m.kind}>
+                    {m.label}
+                  </option>
+                ))}
+              </select>
+              <input
+                className={clsx(inputClass, "min-w-0 flex-1 font-mono text-xs")}
+                placeholder={MATCH_KINDS.find((m) => m.kind === rule.match.kind)?.placeholder}
+                value={rule.match.value}
+                onChange={(e) => update(i, { ...rule, match: { ...rule.match, value: e.target.value } })}
+                onBlur={() => commit(draft)}
+              />
+              <Button size="sm" variant="ghost" onClick={() => commit(draft.filter((_, j) => j !== i))} title="Remove rule">
+                <Trash2 className="h-4 w-4" />
+              </Button>
+            </div>
+            <div className="flex flex-wrap gap-3 text-xs text-zinc-600 dark:text-zinc-400">
+              {POLICY_TOGGLES.map(({ key, label }) => (
+                <label key={key} className="flex items-center gap-1">
+                  {label}
+                  <select
+                    className="rounded-lg border border-zinc-900/10 bg-white px-1 py-0.5 text-xs dark:border-white/10 dark:bg-zinc-950"
+                    value={rule[key] === null ? "" : String(rule[key])}
+                    onChange={(e) => {
+                      const value = e.target.value === "" ? null : e.target.value === "true";
+                      commit(draft.map((r, j) => (j === i ? { ...r, [key]: value } : r)));
+                    }}
+                  >
+                    <option value="">Unchanged</option>
+                    <option value="true">On</option>
+                    <option value="false">Off</option>
+                  </select>
+                </label>
+              ))}
+            </div>
+          </div>
+        ))}
+        <div>
+          <Button size="sm" onClick={add}>
+            Add Rule
+          </Button>
+        </div>
+      </div>
+    </Card>
+  );
+}
 
 export function ClusterSettings({
   settings,
@@ -108,8 +666,112 @@ export function ClusterSettings({
               <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.mdns_advertising ? "translate-x-6" : "translate-x-1")} />
             </button>
           </div>
+          <LineList
+            title="Excluded Interfaces"
+            description={
+              <>
+                Addresses on these interfaces are not advertised. One rule per line: an interface name with <span className="font-mono">*</span> wildcards (e.g. <span className="font-mono">docker*</span>) or a CIDR block (e.g. <span className="font-mono">172.17.0.0/16</span>).
+              </>
+            }
+            values={settings.interface_exclude}
+            onCommit={(interface_exclude) => setSettings({ ...settings, interface_exclude })}
+          />
+
+          <ListenPort settings={settings} setSettings={setSettings} />
+
+          <div className="mt-6 flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Beacon Fallback</div>
+              <div className="text-xs text-zinc-500">For networks that block mDNS. Broadcasts a small UDP beacon and listens for other devices' beacons. Every device needs it on, with the same port.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, beacon_enabled: !settings.beacon_enabled })}
+              className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", settings.beacon_enabled ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.beacon_enabled ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+
+          {settings.beacon_enabled && (
+            <>
+              <div className="mt-4 flex items-center justify-between">
+                <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Beacon Port (UDP)</div>
+                <input
+                  type="number"
+                  min={1}
+                  max={65535}
+                  className="h-9 w-28 rounded-xl border border-zinc-900/10 bg-white px-3 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50"
+                  value={settings.beacon_port}
+                  onChange={(e) => {
+                    const port = parseInt(e.target.value, 10);
+                    if (port >= 1 && port <= 65535) setSettings({ ...settings, beacon_port: port });
+                  }}
+                />
+              </div>
+              <LineList
+                title="Beacon Targets"
+                description="Devices to send the beacon to directly, for networks that also drop broadcast. One IPv4 address (or address:port) per line."
+                placeholder="10.20.0.15"
+                values={settings.beacon_targets}
+                onCommit={(beacon_targets) => setSettings({ ...settings, beacon_targets })}
+              />
+            </>
+          )}
+
+          <LineList
+            title="Sweep Ranges"
+            description="Ranges probed every few minutes for devices that can't be discovered any other way. One CIDR per line; IPv6 ranges up to /112."
+            placeholder="192.168.10.0/24"
+            values={settings.sweep_ranges}
+            onCommit={(sweep_ranges) => setSettings({ ...settings, sweep_ranges })}
+          />
+
+          <div className="mt-6 flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Relay for Other Devices</div>
+              <div className="text-xs text-zinc-500">Pass messages and files between cluster members that can't reach each other directly. They stay signed by the sending device.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, allow_relay: !settings.allow_relay })}
+              className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", settings.allow_relay ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.allow_relay ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+        </div>
+      </Card>
+
+      <NetworkPolicies settings={settings} setSettings={setSettings} />
+
+      <Card className="p-4">
+        <SectionHeader
+          icon={<ShieldCheck className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+          title="Pairing"
+          subtitle="How new devices prove who they are."
+        />
+        <div className="mt-4 px-1">
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Require Security Code</div>
+              <div className="text-xs text-zinc-500">After the PIN is accepted, both devices show six symbols and both users must confirm they match. Protects against someone else who knows the PIN.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, require_sas_confirmation: !settings.require_sas_confirmation })}
+              className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", settings.require_sas_confirmation ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.require_sas_confirmation ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
         </div>
       </Card>
+
+      <Clusters />
+
+      <PairingInvite />
+
+      <InviteTokens />
+
+      <CertificateRotation />
     </div>
   );
 }
@@ -1,6 +1,6 @@
 import { version } from "../../../package.json";
 import clsx from "clsx";
-import { Settings, Monitor, Wifi } from "lucide-react";
+import { Settings, Monitor, Wifi, Timer } from "lucide-react";
 import { SectionHeader, Card } from "../ui";
 import { ShortcutRecorder } from "../ShortcutRecorder";
 import type { AppSettings } from "../../types";
@@ -154,6 +154,122 @@ export function GeneralSettings({
               </div>
             </div>
           )}
+
+          <div className="h-px bg-zinc-900/5 dark:bg-white/5" />
+
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Skip Locked Devices</div>
+              <div className="text-xs text-zinc-500">Don't send clipboard content to devices whose screen is locked.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, skip_locked_peers: !settings.skip_locked_peers })}
+              className={clsx("relative h-6 w-11 rounded-full transition-colors", settings.skip_locked_peers ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.skip_locked_peers ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+
+          <div className="h-px bg-zinc-900/5 dark:bg-white/5" />
+
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Move Cut Files</div>
+              <div className="text-xs text-zinc-500">When you cut files, remove the originals once a device has received all of them intact.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, move_cut_files: !settings.move_cut_files })}
+              className={clsx("relative h-6 w-11 rounded-full transition-colors", settings.move_cut_files ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.move_cut_files ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+
+          {settings.move_cut_files && (
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Trash Moved Files</div>
+                <div className="text-xs text-zinc-500">Put moved originals in the Trash instead of deleting them (Linux and macOS).</div>
+              </div>
+              <button
+                onClick={() => setSettings({ ...settings, trash_moved_files: !settings.trash_moved_files })}
+                className={clsx("relative h-6 w-11 rounded-full transition-colors", settings.trash_moved_files ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+              >
+                <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.trash_moved_files ? "translate-x-6" : "translate-x-1")} />
+              </button>
+            </div>
+          )}
+        </div>
+      </Card>
+
+      {/* Ephemeral Items */}
+      <Card className="p-4">
+        <SectionHeader
+          icon={<Timer className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+          title="Ephemeral Items"
+          subtitle="Self-destructing clipboard items."
+        />
+        <div className="mt-4 px-1 space-y-4">
+          <div className="flex flex-col gap-1">
+            <label className="text-xs font-medium text-zinc-600 dark:text-zinc-400">
+              Self-destruct after (seconds)
+            </label>
+            <input
+              type="number"
+              min={1}
+              step={1}
+              className="h-10 w-40 rounded-xl border border-zinc-900/10 bg-white px-3 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-white/5 dark:text-zinc-50"
+              value={settings.ephemeral_ttl_secs}
+              onChange={(e) => {
+                if (e.target.value === "") return;
+                const secs = Math.max(1, parseInt(e.target.value, 10) || 1);
+                setSettings({ ...settings, ephemeral_ttl_secs: secs });
+              }}
+            />
+            <div className="text-[10px] text-zinc-500">
+              Receivers clear the item (or put back what was there before) after this delay, and never keep it in History.
+            </div>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Protect Sensitive Content</div>
+              <div className="text-xs text-zinc-500">Send private keys, API tokens and password-manager copies as ephemeral automatically.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, ephemeral_sensitive_content: !settings.ephemeral_sensitive_content })}
+              className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", settings.ephemeral_sensitive_content ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.ephemeral_sensitive_content ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Always Ephemeral</div>
+              <div className="text-xs text-zinc-500">Send every text and image as ephemeral. Files are never ephemeral.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, ephemeral_by_default: !settings.ephemeral_by_default })}
+              className={clsx("relative h-6 w-11 shrink-0 rounded-full transition-colors", settings.ephemeral_by_default ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-4 w-4 transform rounded-full bg-white shadow-sm transition-transform", settings.ephemeral_by_default ? "translate-x-6" : "translate-x-1")} />
+            </button>
+          </div>
+
+          <div className="flex flex-col gap-2">
+            <div className="text-sm font-medium text-zinc-900 dark:text-zinc-50">Global Shortcut (Send Ephemeral)</div>
+            <ShortcutRecorder
+              value={settings.shortcut_send_ephemeral}
+              onChange={(val) => setSettings({ ...settings, shortcut_send_ephemeral: val })}
+              placeholder="No shortcut set"
+            />
+            <div className="text-[10px] text-zinc-500">
+              {settings.auto_send
+                ? "Marks your next copy as ephemeral."
+                : "Broadcasts the current clipboard text as ephemeral."}
+            </div>
+          </div>
         </div>
       </Card>
 
@@ -1,7 +1,99 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import clsx from "clsx";
-import { Info } from "lucide-react";
-import { SectionHeader, Card } from "../ui";
-import type { AppSettings } from "../../types";
+import { Info, Moon, Trash2 } from "lucide-react";
+import { SectionHeader, Card, Button } from "../ui";
+import type { AppSettings, QuietMode, QuietSchedule, QuietStatus } from "../../types";
+
+const DAYS = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+function QuietHours({ settings, setSettings }: { settings: AppSettings; setSettings: (s: AppSettings) => void }) {
+  const [status, setStatus] = useState<QuietStatus | null>(null);
+
+  useEffect(() => {
+    invoke<QuietStatus>("get_quiet_status").then(setStatus).catch(console.error);
+    const unlisten = listen<QuietStatus>("quiet-state-changed", (event) => setStatus(event.payload));
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  const schedules = settings.quiet_hours;
+  const update = (i: number, schedule: QuietSchedule) =>
+    setSettings({ ...settings, quiet_hours: schedules.map((s, j) => (j === i ? schedule : s)) });
+  const add = () =>
+    setSettings({ ...settings, quiet_hours: [...schedules, { days: [0, 1, 2, 3, 4, 5, 6], start: "22:00", end: "07:00", mode: "pause" }] });
+
+  const inputClass =
+    "h-9 rounded-xl border border-zinc-900/10 bg-white px-2 text-sm text-zinc-900 outline-none focus:ring-2 focus:ring-emerald-500/40 dark:border-white/10 dark:bg-zinc-950 dark:text-zinc-50";
+
+  return (
+    <Card className="p-4">
+      <SectionHeader
+        icon={<Moon className="h-5 w-5 text-zinc-600 dark:text-zinc-300" />}
+        title="Quiet Hours"
+        subtitle="Hold sync back at set times."
+      />
+      <div className="mt-4 flex flex-col gap-3 px-1">
+        <div className="text-xs text-zinc-500">
+          Manual: nothing is sent or applied automatically, but incoming items still reach History. Pause: incoming items are ignored too. Your Auto-Send and Auto-Receive toggles are left as they are.
+        </div>
+        {status?.mode && (
+          <div className="rounded-xl bg-amber-500/10 p-2 text-[11px] text-amber-700 dark:text-amber-300">
+            Quiet hours are on now ({status.mode === "pause" ? "paused" : "manual"}).
+          </div>
+        )}
+        {schedules.map((schedule, i) => (
+          <div key={i} className="flex flex-col gap-2 rounded-xl border border-zinc-900/10 p-2 dark:border-white/10">
+            <div className="flex items-center gap-2">
+              <input type="time" className={inputClass} value={schedule.start} onChange={(e) => update(i, { ...schedule, start: e.target.value })} />
+              <span className="text-xs text-zinc-500">to</span>
+              <input type="time" className={inputClass} value={schedule.end} onChange={(e) => update(i, { ...schedule, end: e.target.value })} />
+              <select className={inputClass} value={schedule.mode} onChange={(e) => update(i, { ...schedule, mode: e.target.value as QuietMode })}>
+                <option value="manual">Manual</option>
+                <option value="pause">Pause</option>
+              </select>
+              <div className="flex-1" />
+              <Button
+                size="sm"
+                variant="ghost"
+                onClick={() => setSettings({ ...settings, quiet_hours: schedules.filter((_, j) => j !== i) })}
+                title="Remove"
+              >
+                <Trash2 className="h-4 w-4" />
+              </Button>
+            </div>
+            <div className="flex gap-1">
+              {DAYS.map((label, day) => {
+                const on = schedule.days.includes(day);
+                return (
+                  <button
+                    key={day}
+                    className={clsx(
+                      "h-7 w-10 rounded-lg text-xs font-medium transition",
+                      on ? "bg-emerald-500 text-white" : "bg-zinc-900/5 text-zinc-600 dark:bg-white/5 dark:text-zinc-400"
+                    )}
+                    onClick={() =>
+                      update(i, { ...schedule, days: on ? schedule.days.filter((d) => d !== day) : [...schedule.days, day].sort() })
+                    }
+                  >
+                    {label}
+                  </button>
+                );
+              })}
+            </div>
+          </div>
+        ))}
+        <div>
+          <Button size="sm" onClick={add}>
+            Add Quiet Hours
+          </Button>
+        </div>
+      </div>
+    </Card>
+  );
+}
 
 export function NotificationsSettings({
   settings,
@@ -53,8 +145,24 @@ export function NotificationsSettings({
               <span className={clsx("block h-3 w-3 transform rounded-full bg-white shadow-sm transition-transform", settings.notify_large_files ? "translate-x-5" : "translate-x-1")} />
             </button>
           </div>
+
+          {/* Do Not Disturb */}
+          <div className="flex items-center justify-between gap-4">
+            <div>
+              <div className="text-sm text-zinc-700 dark:text-zinc-300">Follow Do Not Disturb</div>
+              <div className="text-xs text-zinc-500">Hold notifications while the desktop is in Do Not Disturb and show them together afterwards. Linux only.</div>
+            </div>
+            <button
+              onClick={() => setSettings({ ...settings, follow_dnd: !settings.follow_dnd })}
+              className={clsx("relative h-5 w-9 shrink-0 rounded-full transition-colors", settings.follow_dnd ? "bg-emerald-500" : "bg-zinc-200 dark:bg-zinc-700")}
+            >
+              <span className={clsx("block h-3 w-3 transform rounded-full bg-white shadow-sm transition-transform", settings.follow_dnd ? "translate-x-5" : "translate-x-1")} />
+            </button>
+          </div>
         </div>
       </Card>
+
+      <QuietHours settings={settings} setSettings={setSettings} />
     </div>
   );
 }
@@ -10,12 +10,112 @@ export interface Peer {
   platform?: string; // Backend doesn't send this yet, will mock or infer
   /// Protocol-compatibility version advertised via mDNS.
   protocol_version?: string | null;
+  /// Every address the peer has been seen at, with our path stats. `ip`/`port`
+  /// is the preferred one.
+  addresses?: PeerAddress[];
+  /// Id of the member relaying our traffic to this peer while direct
+  /// connections fail.
+  relay_via?: string | null;
+  /// What the peer last said it is doing; null until it heartbeats.
+  status?: PeerStatus | null;
+  /// RTT, jitter and throughput we have measured to this peer.
+  link?: LinkSummary;
+  /// What the peer runs; null until it answers our DeviceInfo.
+  device?: DeviceInfo | null;
+  /// Why the peer can't do everything this device can, in words.
+  capability_gaps?: string[];
   /// True when the peer's `protocol_version` meets the minimum required by
   /// this build. Computed by the Rust backend (net_util::is_protocol_compatible)
   /// and injected into every frontend-bound payload; never travels peer-to-peer.
   compatible: boolean;
 }
 
+export interface PeerAddress {
+  addr: string;
+  last_seen: number;
+  last_success?: number | null;
+  rtt_ms?: number | null;
+  failures: number;
+}
+
+/// "unknown" is a state from a newer build.
+export type Activity = "active" | "idle" | "locked" | "unknown";
+
+/// Mirrors status::PeerStatus, from the peer's own heartbeat.
+export interface PeerStatus {
+  activity: Activity;
+  idle_secs?: number | null;
+  /// Quiet hours paused sync: clipboards sent to it are dropped.
+  sync_paused: boolean;
+  /// Auto-receive is off: clipboards land in its History only.
+  manual: boolean;
+}
+
+export interface Throughput {
+  bytes: number;
+  bytes_per_sec: number;
+  /// Unix seconds the transfer finished.
+  at: number;
+}
+
+/// Mirrors link::LinkSummary.
+export interface LinkSummary {
+  rtt_ms?: number | null;
+  rtt_min_ms?: number | null;
+  rtt_avg_ms?: number | null;
+  jitter_ms?: number | null;
+  samples: number;
+  /// Peer to us, from the last sizeable stream it sent.
+  inbound?: Throughput | null;
+  /// Us to the peer, from the last connection test.
+  outbound?: Throughput | null;
+}
+
+/// "unknown" is a backend from a newer build.
+export type Backend = "plugin" | "wlr_data_control" | "gnome_extension" | "degraded" | "unknown";
+
+/// Mirrors device_info::DeviceInfo.
+export interface DeviceInfo {
+  os: string;
+  os_name?: string | null;
+  desktop?: string | null;
+  backend: Backend;
+  app_version: string;
+  formats: string[];
+  flatpak: boolean;
+}
+
+/// Result of the `test_connection` command.
+export interface ConnectionTest {
+  peer_id: string;
+  relay_via?: string | null;
+  quic_rtt_ms: number;
+  bytes_sent: number;
+  elapsed_ms: number;
+  link: LinkSummary;
+}
+
+export type ContentClass = "text" | "rich" | "image" | "files";
+
+/// Local per-peer permissions (stored in peer_policies.json, never shared).
+export interface PeerPolicy {
+  send_to: boolean;
+  receive_from: boolean;
+  file_transfer: boolean;
+  /// Overrides the global max auto-download size; null = use the global one.
+  max_auto_download_size: number | null;
+  /// Content accepted from this peer; null = everything.
+  allowed_classes: ContentClass[] | null;
+}
+
+export const DEFAULT_PEER_POLICY: PeerPolicy = {
+  send_to: true,
+  receive_from: true,
+  file_transfer: true,
+  max_auto_download_size: null,
+  allowed_classes: null,
+};
+
 export type View = "devices" | "history" | "settings";
 
 export type DiagLevel = "minimal" | "detailed" | "debug";
@@ -73,6 +173,7 @@ export type HistoryItem = {
   formats?: ClipboardFormatPreview[];
   sender_id?: string;
   has_backing: boolean; // re-call possible (content still retained)
+  ttl_secs?: number;    // ephemeral item: self-destructs on receivers, never retained
 };
 
 export interface NotificationSettings {
@@ -98,5 +199,117 @@ export interface AppSettings {
   pairing_debug_logs: boolean;
   configure_firewall: boolean;
   mdns_advertising: boolean;
+  listen_port: number;         // 0 = any free port; applied on restart
+  listen_port_range: number;   // ports above listen_port tried when it is taken
+  interface_exclude: string[]; // interface-name patterns or CIDR blocks kept out of our advertisement
+  beacon_enabled: boolean;     // UDP beacon fallback for networks that block mDNS
+  beacon_port: number;
+  beacon_targets: string[];    // unicast beacon destinations (ip or ip:port)
+  sweep_ranges: string[];      // CIDR ranges probed periodically
+  allow_relay: boolean;        // carry traffic between members that can't reach each other
+  network_policies: NetworkPolicy[]; // first rule matching the current network applies
+  network_policy_active?: ActivePolicy | null; // backend-owned; what the applied rule changed
+  quiet_hours: QuietSchedule[]; // times when sync pauses or goes manual
+  follow_dnd: boolean;          // hold notifications during the desktop's Do Not Disturb (Linux)
+  skip_locked_peers: boolean;   // leave locked devices out of clipboard broadcasts
+  move_cut_files: boolean;      // remove cut originals once a device confirms it has them
+  trash_moved_files: boolean;   // moved originals go to the Trash instead of being deleted
   history_store_max_bytes: number; // bytes; History content store budget
+  ephemeral_ttl_secs: number;       // seconds an ephemeral item stays on receivers' clipboards
+  ephemeral_by_default: boolean;
+  ephemeral_sensitive_content: boolean;
+  shortcut_send_ephemeral: string | null;
+  require_sas_confirmation: boolean; // both users must confirm the security code after pairing
+  cluster_routing: Record<string, ClusterRoute>; // keyed by cluster id; missing => send + receive
+}
+
+export type NetworkMatch =
+  | { kind: "subnet"; value: string }       // CIDR containing one of our addresses
+  | { kind: "gateway_mac"; value: string }  // default gateway's MAC
+  | { kind: "nm_connection"; value: string }; // NetworkManager connection id (Linux)
+
+export type PolicyToggle = "auto_send" | "auto_receive" | "file_transfer" | "advertise";
+
+// null = leave the setting alone on this network
+export interface NetworkPolicy {
+  name: string;
+  match: NetworkMatch;
+  auto_send: boolean | null;
+  auto_receive: boolean | null;
+  file_transfer: boolean | null;
+  advertise: boolean | null;
+}
+
+export interface ActivePolicy {
+  rule: string;
+  changes: { setting: PolicyToggle; before: boolean; applied: boolean }[];
+}
+
+// Returned by `get_network_identity`.
+export interface NetworkIdentity {
+  addresses: string[];
+  gateway_mac: string | null;
+  nm_connection: string | null;
+}
+
+export type QuietMode = "manual" | "pause";
+
+// start/end are local "HH:MM"; an end at or before start runs past midnight.
+// days: 0 = Monday … 6 = Sunday (the day the range starts on).
+export interface QuietSchedule {
+  days: number[];
+  start: string;
+  end: string;
+  mode: QuietMode;
+}
+
+// Returned by `get_quiet_status` and sent with `quiet-state-changed`.
+export interface QuietStatus {
+  mode: QuietMode | null;
+  dnd: boolean;
+  held: number;
+}
+
+export interface ClusterRoute {
+  send: boolean;
+  receive: boolean;
+}
+
+export interface ClusterView {
+  cluster_id: string;
+  name: string;
+  is_primary: boolean;
+  members: number;
+  route: ClusterRoute;
+}
+
+export interface SasSymbol {
+  emoji: string;
+  word: string;
+}
+
+// Payload of the `pairing-sas` event.
+export interface SasPrompt {
+  session: string;
+  peer: string;
+  symbols: SasSymbol[];
+  requires_confirmation: boolean;
+}
+
+export interface TokenRestrictions {
+  receive_only: boolean;
+  no_file_transfer: boolean;
+}
+
+// Invite token as listed by `list_invite_tokens`.
+export interface InviteToken {
+  id: string;
+  code: string; // typed or pasted in place of the PIN
+  label: string | null;
+  created_at: number; // unix seconds
+  expires_at: number; // unix seconds
+  max_uses: number;
+  uses: number;
+  restrictions: TokenRestrictions;
+  active: boolean;
 }